@@ -1,4 +1,5 @@
 pub mod nextclade_cli;
+pub mod nextclade_dataset_check_tree;
 pub mod nextclade_dataset_get;
 pub mod nextclade_dataset_list;
 pub mod nextclade_loop;
@@ -1,3 +1,4 @@
+use crate::cli::nextclade_dataset_check_tree::nextclade_dataset_check_tree;
 use crate::cli::nextclade_dataset_get::nextclade_dataset_get;
 use crate::cli::nextclade_dataset_list::nextclade_dataset_list;
 use crate::cli::nextclade_loop::nextclade_run;
@@ -17,6 +18,7 @@ use nextclade::io::fs::add_extension;
 use nextclade::run::params::NextcladeInputParamsOptional;
 use nextclade::schema::schema::{NextcladeSchemaArgs, cli_handle_schema};
 use nextclade::sort::params::NextcladeSeqSortParams;
+use nextclade::tree::tree_ancestral_seqs::AncestralSeqsNodes;
 use nextclade::utils::global_init::{GlobalInitConfig, global_init};
 use nextclade::{getenv, make_error};
 use std::fmt::Debug;
@@ -128,6 +130,11 @@ pub enum NextcladeDatasetCommands {
   ///
   /// For short help type: `nextclade -h`, for extended help type: `nextclade --help`. Each subcommand has its own help, for example: `nextclade run --help`.
   Get(NextcladeDatasetGetArgs),
+
+  /// Check reference tree of a dataset for inconsistencies
+  ///
+  /// For short help type: `nextclade -h`, for extended help type: `nextclade --help`. Each subcommand has its own help, for example: `nextclade run --help`.
+  CheckTree(Box<NextcladeDatasetCheckTreeArgs>),
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -289,25 +296,31 @@ pub enum NextcladeOutputSelection {
   Tree,
   TreeNwk,
   Translations,
+  CdsNuc,
   Gff,
   Tbl,
+  SegmentsSummary,
+  Vcf,
 }
 
 impl NextcladeOutputSelection {
   #[rustfmt::skip]
   pub const fn flag_name(self) -> &'static str {
     match self {
-      Self::All          => "--output-all",
-      Self::Fasta        => "--output-fasta",
-      Self::Json         => "--output-json",
-      Self::Ndjson       => "--output-ndjson",
-      Self::Csv          => "--output-csv",
-      Self::Tsv          => "--output-tsv",
-      Self::Tree         => "--output-tree",
-      Self::TreeNwk      => "--output-tree-nwk",
-      Self::Translations => "--output-translations",
-      Self::Gff          => "--output-annotation-gff",
-      Self::Tbl          => "--output-annotation-tbl",
+      Self::All             => "--output-all",
+      Self::Fasta           => "--output-fasta",
+      Self::Json            => "--output-json",
+      Self::Ndjson          => "--output-ndjson",
+      Self::Csv             => "--output-csv",
+      Self::Tsv             => "--output-tsv",
+      Self::Tree            => "--output-tree",
+      Self::TreeNwk         => "--output-tree-nwk",
+      Self::Translations    => "--output-translations",
+      Self::CdsNuc          => "--output-cds-nuc",
+      Self::Gff             => "--output-annotation-gff",
+      Self::Tbl             => "--output-annotation-tbl",
+      Self::SegmentsSummary => "--output-segments-summary",
+      Self::Vcf             => "--output-vcf",
     }
   }
 
@@ -315,17 +328,20 @@ impl NextcladeOutputSelection {
   #[allow(clippy::missing_const_for_fn)]
   pub fn is_output_set(self, args: &NextcladeRunOutputArgs) -> bool {
     match self {
-      Self::All          => args.output_all.is_some(),
-      Self::Fasta        => args.output_fasta.is_some(),
-      Self::Json         => args.output_json.is_some(),
-      Self::Ndjson       => args.output_ndjson.is_some(),
-      Self::Csv          => args.output_csv.is_some(),
-      Self::Tsv          => args.output_tsv.is_some(),
-      Self::Tree         => args.output_tree.is_some(),
-      Self::TreeNwk      => args.output_tree_nwk.is_some(),
-      Self::Translations => args.output_translations.is_some(),
-      Self::Gff          => args.output_annotation_gff.is_some(),
-      Self::Tbl          => args.output_annotation_tbl.is_some(),
+      Self::All             => args.output_all.is_some(),
+      Self::Fasta           => args.output_fasta.is_some(),
+      Self::Json            => args.output_json.is_some(),
+      Self::Ndjson          => args.output_ndjson.is_some(),
+      Self::Csv             => args.output_csv.is_some(),
+      Self::Tsv             => args.output_tsv.is_some(),
+      Self::Tree            => args.output_tree.is_some(),
+      Self::TreeNwk         => args.output_tree_nwk.is_some(),
+      Self::Translations    => args.output_translations.is_some(),
+      Self::CdsNuc          => args.output_cds_nuc.is_some(),
+      Self::Gff             => args.output_annotation_gff.is_some(),
+      Self::Tbl             => args.output_annotation_tbl.is_some(),
+      Self::SegmentsSummary => args.output_segments_summary.is_some(),
+      Self::Vcf             => args.output_vcf.is_some(),
     }
   }
 }
@@ -349,6 +365,24 @@ pub struct NextcladeRunInputArgs {
   #[clap(hide_long_help = true, hide_short_help = true)]
   pub input_fasta: Option<PathBuf>,
 
+  /// Path to a VCF file with variant calls to be used instead of FASTA input sequences.
+  ///
+  /// Query sequences are reconstructed from the reference sequence of the dataset by applying the variants (SNVs and indels) of each of the samples in the VCF file. The CHROM column should match the reference sequence name (or, for segmented datasets, one of the segment reference names). Each sample produces a sequence per chromosome, named `<sample>` for non-segmented and `<sample>|<chromosome>` for segmented datasets. For sites-only VCF files, without sample columns, a single sequence named after the file is produced.
+  ///
+  /// Sites with missing genotypes (`.`) and with filters other than `PASS` are masked with `N`. See also `--vcf-min-depth`.
+  ///
+  /// Supports the following compression formats: "gz", "bz2", "xz", "zst". Use "-" to read uncompressed data from standard input (stdin).
+  ///
+  /// This flag is mutually exclusive with FASTA inputs.
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::FilePath)]
+  #[clap(conflicts_with = "input_fastas")]
+  pub input_vcf: Option<PathBuf>,
+
+  /// Minimum read depth of VCF records, to be used with `--input-vcf`. Sites with depth (`DP` in FORMAT or INFO column) below this value are masked with `N`.
+  #[clap(long, requires = "input_vcf")]
+  pub vcf_min_depth: Option<usize>,
+
   /// Path to a directory or a zip file containing a dataset.
   ///
   /// See `nextclade dataset --help` on how to obtain datasets.
@@ -386,6 +420,18 @@ pub struct NextcladeRunInputArgs {
   #[clap(value_hint = ValueHint::FilePath)]
   pub input_ref: Option<PathBuf>,
 
+  /// Path to a FASTA file containing additional reference sequences.
+  ///
+  /// If provided, each query sequence is aligned against the closest of the primary and additional references, and the
+  /// alignment is then projected onto the coordinates of the primary reference (`--input-ref`).
+  ///
+  /// Overrides path to the additional references file in the dataset (`--input-dataset`).
+  ///
+  /// Supports the following compression formats: "gz", "bz2", "xz", "zst". Use "-" to read uncompressed data from standard input (stdin).
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::FilePath)]
+  pub input_additional_refs: Option<PathBuf>,
+
   /// Path to Auspice JSON v2 file containing reference tree.
   ///
   /// See https://nextstrain.org/docs/bioinformatics/data-formats.
@@ -397,6 +443,20 @@ pub struct NextcladeRunInputArgs {
   #[clap(value_hint = ValueHint::FilePath)]
   pub input_tree: Option<PathBuf>,
 
+  /// Path to a TSV file containing a table of clade-defining mutations with a parent hierarchy.
+  ///
+  /// Used for clade assignment when there is no reference tree: each sequence is assigned the deepest clade whose
+  /// defining mutations are all present. Required columns are `clade` and `mutations` (comma-separated, relative to
+  /// reference, e.g. `C241T,A23403G`), and the optional `parent` column refers to the parent clade. Other columns are
+  /// reported as clade-like attributes. If a reference tree is also provided, the tree is used for clade assignment.
+  ///
+  /// Overrides path to the clade mutations table in the dataset (`--input-dataset`).
+  ///
+  /// Supports the following compression formats: "gz", "bz2", "xz", "zst". Use "-" to read uncompressed data from standard input (stdin).
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::FilePath)]
+  pub input_clade_mutations: Option<PathBuf>,
+
   /// Path to a JSON file containing configuration and data specific to a pathogen.
   ///
   /// Overrides path to `pathogen.json` in the dataset (`--input-dataset`).
@@ -445,6 +505,29 @@ pub struct NextcladeRunInputArgs {
   #[clap(value_hint = ValueHint::FilePath)]
   pub input_pcr_primers: Option<PathBuf>,
 
+  /// Path to a BED file describing a tiled amplicon scheme (e.g. ARTIC `primer.bed`). This information is used to report
+  /// coverage of each amplicon, amplicon dropouts and mutations in primer binding sites, grouped by primer pool.
+  ///
+  /// Columns are: reference name, 0-based start, end (exclusive), primer name, pool. Primer names are expected to end with
+  /// `_LEFT` or `_RIGHT`, optionally followed by an alternative primer suffix (e.g. `_alt1`). Primer positions should be
+  /// relative to the reference sequence of the dataset. For segmented datasets, the scheme applies to the first segment.
+  ///
+  /// Supports the following compression formats: "gz", "bz2", "xz", "zstd". Use "-" to read uncompressed data from standard input (stdin).
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::FilePath)]
+  pub input_amplicon_scheme: Option<PathBuf>,
+
+  /// Path to a TSV or CSV file with sample metadata, such as collection dates.
+  ///
+  /// The table should contain a column with sequence names (see `--metadata-id-column`). Rows are matched to query
+  /// sequences by the full sequence name, or by the sequence identifier (the part of the name before the first
+  /// whitespace). Collection dates (see `--metadata-date-column`) are used by the "molecular clock" QC rule.
+  ///
+  /// Supports the following compression formats: "gz", "bz2", "xz", "zstd". Use "-" to read uncompressed data from standard input (stdin).
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::FilePath)]
+  pub input_metadata: Option<PathBuf>,
+
   /// Use custom dataset server
   #[clap(long)]
   #[clap(value_hint = ValueHint::Url)]
@@ -566,6 +649,26 @@ pub struct NextcladeRunOutputArgs {
   #[clap(value_hint = ValueHint::AnyPath)]
   pub output_translations: Option<String>,
 
+  /// Template string for path to output fasta files containing in-frame nucleotide alignments of CDSes (codon alignments). A separate file will be generated for every CDS.
+  ///
+  /// Each sequence contains one codon for every amino acid of the corresponding aligned peptide in `--output-translations`: gaps in the peptide correspond to `---`, codons of inserted amino acids are removed and frame-shifted codons are masked. These alignments are suitable for codon-model analyses, e.g. for estimation of selection.
+  ///
+  /// The string should contain template variable `{cds}`, where the CDS name will be substituted.
+  /// Make sure you properly quote and/or escape the curly braces, so that your shell, programming language or pipeline manager does not attempt to substitute the variables.
+  ///
+  /// Takes precedence over paths configured with `--output-all`, `--output-basename` and `--output-selection`.
+  ///
+  /// If the provided file path ends with one of the supported extensions: "gz", "bz2", "xz", "zst", then the file will be written compressed. Use "-" to write the uncompressed to standard output (stdout).
+  ///
+  /// If the required directory tree does not exist, it will be created.
+  ///
+  /// Example for bash shell:
+  ///
+  ///   --output-cds-nuc='output_dir/nextclade.cds_nuc.{cds}.fasta'
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::AnyPath)]
+  pub output_cds_nuc: Option<String>,
+
   /// Path to output Newline-delimited JSON (NDJSON) results file.
   ///
   /// This file format is most suitable for further machine processing of the results. By contrast to plain json, it can be streamed line-by line, so much bigger outputs are feasible.
@@ -682,6 +785,24 @@ pub struct NextcladeRunOutputArgs {
   #[clap(value_hint = ValueHint::AnyPath)]
   pub output_tree_nwk: Option<PathBuf>,
 
+  /// Path to output reconstructed nucleotide sequences of reference tree nodes, in FASTA format.
+  ///
+  /// Sequences are reconstructed by applying the mutations on the path from the root of the reference tree to the node
+  /// to the reference sequence. They are in alignment coordinates, with deletions as gaps and without insertions. Which
+  /// nodes are written is controlled by `--ancestral-seqs-nodes`. This allows to compare a query sequence to its closest
+  /// ancestor in external tools.
+  ///
+  /// If the provided file path ends with one of the supported extensions: "gz", "bz2", "xz", "zst", then the file will be written compressed. Use "-" to write the uncompressed to standard output (stdout).
+  ///
+  /// If the required directory tree does not exist, it will be created.
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::AnyPath)]
+  pub output_ancestral_seqs: Option<PathBuf>,
+
+  /// Which reference tree nodes to write into `--output-ancestral-seqs`.
+  #[clap(long, value_enum, default_value_t = AncestralSeqsNodes::default())]
+  pub ancestral_seqs_nodes: AncestralSeqsNodes,
+
   /// Path to output annotation for query sequences in GFF3 format (EXPERIMENTAL)
   ///
   /// This output contains annotation of genetic features (genes and CDSes) for each query sequence.
@@ -716,6 +837,56 @@ pub struct NextcladeRunOutputArgs {
   #[clap(value_hint = ValueHint::AnyPath)]
   pub output_annotation_tbl: Option<PathBuf>,
 
+  /// Path to output per-sample summary of segmented genome analysis, in TSV format
+  ///
+  /// Only relevant for segmented datasets, i.e. when reference sequence file contains multiple records, one per genome
+  /// segment. Each query sequence is assigned to a segment and the sequences are grouped into samples using sample
+  /// identifier parsed from the sequence name (see `--segment-sample-id-regex`). The summary contains one row per
+  /// sample, with sequence name, clade, coverage and QC status for each of the segments. This allows to spot incomplete
+  /// samples and reassortment between segments.
+  ///
+  /// Takes precedence over paths configured with `--output-all`, `--output-basename` and `--output-selection`.
+  ///
+  /// If the provided file path ends with one of the supported extensions: "gz", "bz2", "xz", "zst", then the file will be written compressed. Use "-" to write the uncompressed to standard output (stdout).
+  ///
+  /// If the required directory tree does not exist, it will be created.
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::AnyPath)]
+  pub output_segments_summary: Option<PathBuf>,
+
+  /// Path to output nucleotide variants in VCF format (Variant Call Format)
+  ///
+  /// The file contains one haploid sample per query sequence, with genotypes derived from nucleotide substitutions, deletions and insertions relative to the reference sequence. Deletions and insertions are anchored to the preceding reference nucleotide. Genotypes of sequences which do not cover a variant site (outside of the alignment, missing or ambiguous nucleotides) are reported as missing (`.`). INFO fields contain names of overlapping CDSes and the corresponding aminoacid changes.
+  ///
+  /// This is useful for annotation with tools such as SnpEff and VEP and for comparison with variants called from reads. Note that all the variants are collected in memory before the file is written.
+  ///
+  /// For file format description see: https://samtools.github.io/hts-specs/VCFv4.2.pdf
+  ///
+  /// Takes precedence over paths configured with `--output-all`, `--output-basename` and `--output-selection`.
+  ///
+  /// If the provided file path ends with one of the supported extensions: "gz", "bz2", "xz", "zst", then the file will be written compressed. Use "-" to write the uncompressed to standard output (stdout).
+  ///
+  /// If the required directory tree does not exist, it will be created.
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::AnyPath)]
+  pub output_vcf: Option<PathBuf>,
+
+  /// Template string for path to output VCF files, one per query sequence. See `--output-vcf` for the description of the contents.
+  ///
+  /// The string should contain template variable `{seq_index}` and/or `{seq_name}`, where the index of the sequence in the input and the sequence name (with characters unsafe in file names replaced by `_`) will be substituted.
+  /// Make sure you properly quote and/or escape the curly braces, so that your shell, programming language or pipeline manager does not attempt to substitute the variables.
+  ///
+  /// If the provided file path ends with one of the supported extensions: "gz", "bz2", "xz", "zst", then the file will be written compressed.
+  ///
+  /// If the required directory tree does not exist, it will be created.
+  ///
+  /// Example for bash shell:
+  ///
+  ///   --output-vcf-per-sequence='output_dir/vcf/{seq_name}.vcf'
+  #[clap(long)]
+  #[clap(value_hint = ValueHint::AnyPath)]
+  pub output_vcf_per_sequence: Option<String>,
+
   /// REMOVED. The argument `--output-insertions` have been removed in favor of `--output-csv` and `--output-tsv`.
   #[clap(long, short = 'I')]
   #[clap(value_hint = ValueHint::AnyPath)]
@@ -827,6 +998,33 @@ pub struct NextcladeSortArgs {
   pub proxy_config: ProxyConfig,
 }
 
+/// Check reference tree of a dataset for inconsistencies, without analyzing any sequences.
+///
+/// Reports all branch mutations whose origin state disagrees with the state of the parent node or which are outside of
+/// the reference sequence, nodes without `clade_membership`, clades which emerge more than once in the tree, duplicate
+/// node names, and node names which collide with the names of the query sequences (if the sequences are provided).
+/// Also prints summary statistics of the tree.
+///
+/// Exits with an error if any issues are found.
+#[derive(Parser, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct NextcladeDatasetCheckTreeArgs {
+  /// Dataset and, optionally, query sequences to check node names against. The sequences are not analyzed.
+  #[clap(flatten)]
+  pub inputs: NextcladeRunInputArgs,
+
+  /// Path to output JSON or YAML file with the report.
+  ///
+  /// The format is chosen based on file extension: ".json" or ".yaml".
+  #[clap(long, short = 'o')]
+  #[clap(value_hint = ValueHint::FilePath)]
+  pub output: Option<PathBuf>,
+
+  /// Print console output in JSON format, rather than human-readable text.
+  #[clap(long)]
+  pub json: bool,
+}
+
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Debug)]
 #[clap(verbatim_doc_comment)]
@@ -884,6 +1082,7 @@ pub fn nextclade_get_output_filenames(run_args: &mut NextcladeRunArgs) -> Result
         output_selection,
         output_fasta,
         output_translations,
+        output_cds_nuc,
         output_ndjson,
         output_json,
         output_csv,
@@ -892,6 +1091,9 @@ pub fn nextclade_get_output_filenames(run_args: &mut NextcladeRunArgs) -> Result
         output_tree_nwk,
         output_annotation_gff,
         output_annotation_tbl,
+        output_segments_summary,
+        output_vcf,
+        output_vcf_per_sequence,
         ..
       },
     ..
@@ -930,6 +1132,18 @@ pub fn nextclade_get_output_filenames(run_args: &mut NextcladeRunArgs) -> Result
       output_translations.get_or_insert(output_translations_template);
     }
 
+    if output_selection.contains(&NextcladeOutputSelection::CdsNuc) {
+      let output_cds_nuc_path = default_output_file_path.with_file_name(format!("{output_basename}.cds_nuc.{{cds}}"));
+      let output_cds_nuc_path = add_extension(output_cds_nuc_path, "fasta");
+
+      let output_cds_nuc_template = output_cds_nuc_path
+        .to_str()
+        .wrap_err_with(|| format!("When converting path to string: {}", output_cds_nuc_path.display()))?
+        .to_owned();
+
+      output_cds_nuc.get_or_insert(output_cds_nuc_template);
+    }
+
     if output_selection.contains(&NextcladeOutputSelection::Ndjson) {
       output_ndjson.get_or_insert(add_extension(&default_output_file_path, "ndjson"));
     }
@@ -961,6 +1175,14 @@ pub fn nextclade_get_output_filenames(run_args: &mut NextcladeRunArgs) -> Result
     if output_selection.contains(&NextcladeOutputSelection::Tbl) {
       output_annotation_tbl.get_or_insert(add_extension(&default_output_file_path, "tbl"));
     }
+
+    if output_selection.contains(&NextcladeOutputSelection::SegmentsSummary) {
+      output_segments_summary.get_or_insert(add_extension(&default_output_file_path, "segments.tsv"));
+    }
+
+    if output_selection.contains(&NextcladeOutputSelection::Vcf) {
+      output_vcf.get_or_insert(add_extension(&default_output_file_path, "vcf"));
+    }
   }
 
   if let Some(output_translations) = output_translations
@@ -981,7 +1203,46 @@ Example for bash shell:
     );
   }
 
-  let all_outputs_are_missing = !NextcladeOutputSelection::iter().any(|sel| sel.is_output_set(&run_args.outputs));
+  if let Some(output_cds_nuc) = output_cds_nuc
+    && !output_cds_nuc.contains("{cds}")
+  {
+    return make_error!(
+      r#"
+Expected `--output-cds-nuc` argument to contain a template string containing template variable {{cds}} (with curly braces), but received:
+
+  {output_cds_nuc}
+
+Make sure the variable is not substituted by your shell, programming language or workflow manager. Apply proper escaping as needed.
+Example for bash shell:
+
+  --output-cds-nuc='output_dir/nextclade.cds_nuc.{{cds}}.fasta'
+
+      "#
+    );
+  }
+
+  if let Some(output_vcf_per_sequence) = output_vcf_per_sequence
+    && !output_vcf_per_sequence.contains("{seq_index}")
+    && !output_vcf_per_sequence.contains("{seq_name}")
+  {
+    return make_error!(
+      r#"
+Expected `--output-vcf-per-sequence` argument to contain a template string containing template variable {{seq_index}} and/or {{seq_name}} (with curly braces), but received:
+
+  {output_vcf_per_sequence}
+
+Make sure the variables are not substituted by your shell, programming language or workflow manager. Apply proper escaping as needed.
+Example for bash shell:
+
+  --output-vcf-per-sequence='output_dir/vcf/{{seq_name}}.vcf'
+
+      "#
+    );
+  }
+
+  let all_outputs_are_missing = !NextcladeOutputSelection::iter().any(|sel| sel.is_output_set(&run_args.outputs))
+    && run_args.outputs.output_vcf_per_sequence.is_none()
+    && run_args.outputs.output_ancestral_seqs.is_none();
 
   if all_outputs_are_missing {
     let flag_list = NextcladeOutputSelection::iter()
@@ -1202,6 +1463,7 @@ pub fn nextclade_parse_cli_args() -> Result<(), Report> {
         nextclade_check_removed_dataset_get_args(&dataset_get_args)?;
         nextclade_dataset_get(&dataset_get_args)
       }
+      NextcladeDatasetCommands::CheckTree(check_tree_args) => nextclade_dataset_check_tree(&check_tree_args),
     },
     NextcladeCommands::Sort(seq_sort_args) => nextclade_seq_sort(&seq_sort_args),
     NextcladeCommands::ReadAnnotation(read_annotation_args) => nextclade_read_annotation(&read_annotation_args),
@@ -0,0 +1,92 @@
+use crate::cli::nextclade_cli::NextcladeDatasetCheckTreeArgs;
+use crate::dataset::dataset_download::nextclade_get_inputs;
+use eyre::{Report, WrapErr};
+use itertools::Itertools;
+use nextclade::alphabet::nuc::to_nuc_seq;
+use nextclade::io::fasta::{FastaReader, FastaRecord};
+use nextclade::io::json::{JsonPretty, json_or_yaml_write, json_stringify};
+use nextclade::make_error;
+use nextclade::tree::tree::AuspiceGraph;
+use nextclade::tree::tree_check::{TreeCheckReport, graph_check};
+
+pub fn nextclade_dataset_check_tree(args: &NextcladeDatasetCheckTreeArgs) -> Result<(), Report> {
+  let inputs = nextclade_get_inputs(&args.inputs, &None)?;
+
+  let Some(tree) = inputs.tree else {
+    return make_error!("The dataset has no reference tree. There is nothing to check.");
+  };
+
+  let ref_seq = to_nuc_seq(&inputs.ref_record.seq).wrap_err("When converting reference sequence")?;
+  let graph = AuspiceGraph::from_auspice_tree(tree).wrap_err("When converting reference tree")?;
+
+  let query_names = if args.inputs.input_fastas.is_empty() {
+    vec![]
+  } else {
+    read_seq_names(&args.inputs.input_fastas).wrap_err("When reading query sequence names")?
+  };
+
+  let report = graph_check(&graph, &ref_seq, &query_names)?;
+
+  if args.json {
+    println!("{}", json_stringify(&report, JsonPretty(true))?);
+  } else {
+    println!("{}", format_report(&report));
+  }
+
+  if let Some(output) = &args.output {
+    json_or_yaml_write(output, &report)?;
+  }
+
+  if !report.issues.is_empty() {
+    return make_error!("Reference tree check found {} issue(s)", report.issues.len());
+  }
+
+  Ok(())
+}
+
+fn read_seq_names(filepaths: &[impl AsRef<std::path::Path>]) -> Result<Vec<String>, Report> {
+  let mut reader = FastaReader::from_paths(filepaths)?;
+  let mut names = vec![];
+  loop {
+    let mut record = FastaRecord::default();
+    reader.read(&mut record)?;
+    if record.is_empty() {
+      break;
+    }
+    names.push(record.seq_name);
+  }
+  Ok(names)
+}
+
+fn format_report(report: &TreeCheckReport) -> String {
+  let stats = &report.stats;
+  let mut lines = vec![
+    "Reference tree summary:".to_owned(),
+    format!("  Nodes: {} ({} leaves)", stats.num_nodes, stats.num_leaves),
+    format!("  Maximum depth: {}", stats.max_depth),
+    format!("  Maximum divergence: {}", stats.max_divergence),
+    format!("  Nucleotide mutations on branches: {}", stats.num_nuc_mutations),
+    format!("  Clades: {}", stats.clades.len()),
+  ];
+  lines.extend(
+    stats
+      .clades
+      .iter()
+      .map(|(clade, num_nodes)| format!("    {clade}: {num_nodes} nodes")),
+  );
+
+  lines.push(String::new());
+  if report.issues.is_empty() {
+    lines.push("No issues found".to_owned());
+  } else {
+    lines.push(format!("Issues ({}):", report.issues.len()));
+    lines.extend(
+      report
+        .issues
+        .iter()
+        .map(|issue| format!("  [{:?}] node '{}': {}", issue.kind, issue.node_name, issue.message)),
+    );
+  }
+
+  lines.iter().join("\n")
+}
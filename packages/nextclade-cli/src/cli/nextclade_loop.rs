@@ -2,18 +2,30 @@ use crate::cli::nextclade_cli::{NextcladeOutputSelection, NextcladeRunArgs};
 use crate::cli::nextclade_ordered_writer::NextcladeOrderedWriter;
 use crate::dataset::dataset_download::nextclade_get_inputs;
 use eyre::{ContextCompat, Report, WrapErr};
-use log::info;
+use itertools::Itertools;
+use log::{info, warn};
+use nextclade::alphabet::nuc::{Nuc, from_nuc_seq};
+use nextclade::analyze::amplicon_scheme::AmpliconScheme;
 use nextclade::analyze::pcr_primers::PcrPrimer;
 use nextclade::gene::gene_map_display::gene_map_to_table_string;
 use nextclade::graph::graph::Graph;
-use nextclade::io::fasta::{FastaReader, FastaRecord};
+use nextclade::io::fasta::{FastaReader, FastaRecord, FastaWriter};
+use nextclade::io::file::open_file_or_stdin;
+use nextclade::io::fs::read_file_to_string;
 use nextclade::io::json::{JsonPretty, json_write};
 use nextclade::io::nextclade_csv_column_config::CsvColumnConfig;
 use nextclade::io::nwk_writer::nwk_write_to_file;
+use nextclade::io::segments_summary::segments_summary_write;
+use nextclade::io::vcf::{VcfReference, vcf_write_per_sequence, vcf_write_to_file};
+use nextclade::io::vcf_reader::{VcfConsensusParams, vcf_read_consensus};
+use nextclade::run::nextclade_segments::{get_gene_map_all_segments, get_segment_names, iter_segments};
 use nextclade::run::nextclade_wasm::{AnalysisInitialData, AnalysisOutput, Nextclade};
+use nextclade::tree::tree::AuspiceGraph;
+use nextclade::tree::tree_ancestral_seqs::{AncestralSeqsNodes, graph_find_ancestral_seq_nodes, graph_node_nuc_seq};
 use nextclade::tree::tree_builder::graph_attach_new_nodes_in_place;
 use nextclade::types::outputs::NextcladeOutputs;
 use nextclade::utils::option::OptionMapRefFallible;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 pub struct NextcladeRecord {
@@ -25,7 +37,7 @@ pub struct NextcladeRecord {
 pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
   info!("Command-line arguments:\n{run_args:#?}");
 
-  let inputs = nextclade_get_inputs(&run_args, &run_args.inputs.cds_selection)?;
+  let mut inputs = nextclade_get_inputs(&run_args.inputs, &run_args.inputs.cds_selection)?;
 
   if inputs.gene_map.is_empty() {
     // If there is no genome annotation, then we cannot emit these output files
@@ -34,11 +46,38 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
       NextcladeOutputSelection::Tbl,
       NextcladeOutputSelection::All,
       NextcladeOutputSelection::Translations,
+      NextcladeOutputSelection::CdsNuc,
     ];
     run_args.outputs.output_selection.retain(|o| !to_remove.contains(o));
     run_args.outputs.output_annotation_gff = None;
     run_args.outputs.output_annotation_tbl = None;
     run_args.outputs.output_translations = None;
+    run_args.outputs.output_cds_nuc = None;
+  }
+
+  if inputs.ref_segments.is_empty() {
+    // If the reference is not segmented, then there is nothing to summarize per sample
+    run_args
+      .outputs
+      .output_selection
+      .retain(|o| o != &NextcladeOutputSelection::SegmentsSummary);
+    run_args.outputs.output_segments_summary = None;
+  } else {
+    // Each of the segments has its own reference tree, and the tree outputs can only describe one tree
+    let outputs = &mut run_args.outputs;
+    if outputs.output_tree.is_some()
+      || outputs.output_tree_nwk.is_some()
+      || outputs.output_graph.is_some()
+      || outputs.output_ancestral_seqs.is_some()
+    {
+      warn!("Tree outputs and ancestral sequences are not supported for segmented datasets. Skipping.");
+    }
+    let to_remove = [NextcladeOutputSelection::Tree, NextcladeOutputSelection::TreeNwk];
+    outputs.output_selection.retain(|o| !to_remove.contains(o));
+    outputs.output_tree = None;
+    outputs.output_tree_nwk = None;
+    outputs.output_graph = None;
+    outputs.output_ancestral_seqs = None;
   }
 
   let primers = run_args
@@ -49,17 +88,43 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
     .wrap_err("When parsing PCR primers input CSV")
     .unwrap_or_default();
 
+  if let Some(input_amplicon_scheme) = &run_args.inputs.input_amplicon_scheme {
+    inputs.amplicon_scheme = Some(AmpliconScheme::from_path(
+      input_amplicon_scheme,
+      inputs.ref_record.seq.len(),
+    )?);
+  }
+
+  if let Some(input_metadata) = &run_args.inputs.input_metadata {
+    inputs.sample_metadata = Some(
+      read_file_to_string(input_metadata)
+        .wrap_err_with(|| format!("When reading sample metadata {}", input_metadata.display()))?,
+    );
+  }
+
   let nextclade = Nextclade::new(inputs, primers, &run_args.params)?;
 
+  let vcf_records = run_args
+    .inputs
+    .input_vcf
+    .as_ref()
+    .map_ref_fallible(|input_vcf| read_vcf_consensus(&nextclade, input_vcf, run_args.inputs.vcf_min_depth))?;
+
   let should_write_tree = run_args.outputs.output_tree.is_some()
     || run_args.outputs.output_tree_nwk.is_some()
     || run_args.outputs.output_graph.is_some();
+  let should_keep_outputs = should_write_tree
+    || run_args.outputs.output_segments_summary.is_some()
+    || run_args.outputs.output_vcf.is_some()
+    || run_args.outputs.output_vcf_per_sequence.is_some()
+    || run_args.outputs.output_ancestral_seqs.is_some();
   let mut outputs = Vec::<NextcladeOutputs>::new();
 
   let csv_column_config = CsvColumnConfig::new(&run_args.outputs.output_columns_selection)?;
 
   info!("Parameters (final):\n{:#?}", &nextclade.params);
-  info!("Genome annotation:\n{}", gene_map_to_table_string(&nextclade.gene_map)?);
+  let gene_map = get_gene_map_all_segments(&nextclade);
+  info!("Genome annotation:\n{}", gene_map_to_table_string(&gene_map)?);
 
   let thread_errors: Arc<Mutex<Vec<Report>>> = Arc::new(Mutex::new(Vec::new()));
 
@@ -69,12 +134,20 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
     let (result_sender, result_receiver) = crossbeam_channel::bounded::<NextcladeRecord>(CHANNEL_SIZE);
 
     let nextclade = &nextclade;
+    let gene_map = &gene_map;
     let outputs = &mut outputs;
     let run_args = &run_args;
 
     let thread_errors_cloned = Arc::clone(&thread_errors);
     s.spawn(move || {
       let result = (|| {
+        if let Some(vcf_records) = vcf_records {
+          for record in vcf_records {
+            fasta_sender.send(record).wrap_err("When sending a FastaRecord")?;
+          }
+          return Ok(());
+        }
+
         let mut reader = FastaReader::from_paths(&run_args.inputs.input_fastas)?;
         loop {
           let mut record = FastaRecord::default();
@@ -136,18 +209,24 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
       let result = (|| {
         let AnalysisInitialData {
           clade_node_attr_key_descs,
+          numeric_node_attr_keys,
           phenotype_attr_descs,
           aa_motif_keys,
+          nuc_motif_keys,
           ref_nodes,
+          metadata_keys,
           ..
         } = nextclade.get_initial_data();
 
         let mut output_writer = NextcladeOrderedWriter::new(
-          &nextclade.gene_map,
+          gene_map,
           &clade_node_attr_key_descs,
+          &numeric_node_attr_keys,
           &phenotype_attr_descs,
           &ref_nodes,
           &aa_motif_keys,
+          &nuc_motif_keys,
+          &metadata_keys,
           &csv_column_config,
           &run_args.outputs,
           &nextclade.params,
@@ -155,13 +234,15 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
         .wrap_err("When creating output writer")?;
 
         if nextclade.params.general.include_reference {
-          output_writer
-            .write_ref(&nextclade.ref_record, &nextclade.ref_translation)
-            .wrap_err("When writing output record for ref sequence")?;
+          for segment in iter_segments(nextclade) {
+            output_writer
+              .write_ref(&segment.ref_record, &segment.ref_translation)
+              .wrap_err("When writing output record for ref sequence")?;
+          }
         }
 
         for record in result_receiver {
-          if should_write_tree && let Ok(AnalysisOutput { analysis_result, .. }) = &record.outputs_or_err {
+          if should_keep_outputs && let Ok(AnalysisOutput { analysis_result, .. }) = &record.outputs_or_err {
             outputs.push(analysis_result.clone());
           }
           output_writer
@@ -183,12 +264,58 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
     return Err(errors.remove(0));
   }
 
+  if let Some(output_segments_summary) = &run_args.outputs.output_segments_summary {
+    let segment_names = get_segment_names(&nextclade);
+    segments_summary_write(output_segments_summary, b'\t', &segment_names, &outputs)
+      .wrap_err("When writing segments summary")?;
+  }
+
+  if run_args.outputs.output_vcf.is_some() || run_args.outputs.output_vcf_per_sequence.is_some() {
+    // Samples are written in the order of the input sequences
+    outputs.sort_by_key(|output| output.index);
+
+    let refs = iter_segments(&nextclade)
+      .map(|segment| VcfReference {
+        name: &segment.ref_record.seq_name,
+        seq: &segment.ref_seq,
+        gene_map: &segment.gene_map,
+      })
+      .collect_vec();
+
+    if let Some(output_vcf) = &run_args.outputs.output_vcf {
+      vcf_write_to_file(output_vcf, &refs, &outputs).wrap_err("When writing VCF")?;
+    }
+
+    if let Some(output_vcf_per_sequence) = &run_args.outputs.output_vcf_per_sequence {
+      vcf_write_per_sequence(output_vcf_per_sequence, &refs, &outputs).wrap_err("When writing per-sequence VCF")?;
+    }
+  }
+
+  if let Some(output_ancestral_seqs) = &run_args.outputs.output_ancestral_seqs {
+    if let Some(graph) = &nextclade.graph {
+      write_ancestral_seqs(
+        output_ancestral_seqs,
+        graph,
+        &nextclade.ref_seq,
+        run_args.outputs.ancestral_seqs_nodes,
+        &outputs,
+      )
+      .wrap_err("When writing ancestral sequences")?;
+    } else {
+      warn!("Ancestral sequences are requested, but the dataset has no reference tree. Skipping.");
+    }
+  }
+
   if should_write_tree {
     let Nextclade {
-      ref_seq, params, graph, ..
+      ref_seq,
+      metadata_keys,
+      params,
+      graph,
+      ..
     } = nextclade;
     if let Some(mut graph) = graph {
-      graph_attach_new_nodes_in_place(&mut graph, outputs, ref_seq.len(), &params.tree_builder)?;
+      graph_attach_new_nodes_in_place(&mut graph, outputs, ref_seq.len(), &metadata_keys, &params.tree_builder)?;
 
       if let Some(output_tree) = run_args.outputs.output_tree {
         let tree = Graph::to_auspice_tree(&graph)?;
@@ -207,3 +334,43 @@ pub fn nextclade_run(mut run_args: NextcladeRunArgs) -> Result<(), Report> {
 
   Ok(())
 }
+
+/// Write reconstructed sequences of the selected reference tree nodes into a FASTA file
+fn write_ancestral_seqs(
+  filepath: &Path,
+  graph: &AuspiceGraph,
+  ref_seq: &[Nuc],
+  selection: AncestralSeqsNodes,
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  let mut writer = FastaWriter::from_path(filepath)?;
+  for key in graph_find_ancestral_seq_nodes(graph, selection, outputs)? {
+    let node = graph.get_node(key)?.payload();
+    let seq = graph_node_nuc_seq(node, ref_seq);
+    writer.write(&node.name, &from_nuc_seq(&seq), false)?;
+  }
+  writer.flush()
+}
+
+/// Reconstruct query sequences from VCF variant calls, relative to reference sequences of the dataset
+fn read_vcf_consensus(
+  nextclade: &Nextclade,
+  input_vcf: &Path,
+  min_depth: Option<usize>,
+) -> Result<Vec<FastaRecord>, Report> {
+  let refs = iter_segments(nextclade)
+    .map(|segment| VcfReference {
+      name: &segment.ref_record.seq_name,
+      seq: &segment.ref_seq,
+      gene_map: &segment.gene_map,
+    })
+    .collect_vec();
+
+  // Name of the single sample of sites-only VCF files, e.g. 'sample' for 'sample.vcf.gz'
+  let file_name = input_vcf.file_name().unwrap_or_default().to_string_lossy();
+  let default_sample_name = file_name.split_once(".vcf").map_or(&*file_name, |(stem, _)| stem);
+
+  let reader = open_file_or_stdin(Some(&input_vcf))?;
+  vcf_read_consensus(reader, &refs, &VcfConsensusParams { min_depth }, default_sample_name)
+    .wrap_err_with(|| format!("When reading VCF file: {}", input_vcf.display()))
+}
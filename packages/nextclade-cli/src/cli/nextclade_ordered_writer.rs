@@ -6,7 +6,7 @@ use log::{info, warn};
 use nextclade::alphabet::nuc::from_nuc_seq;
 use nextclade::analyze::virus_properties::PhenotypeAttrDesc;
 use nextclade::gene::gene_map::GeneMap;
-use nextclade::io::fasta::{FastaPeptideWriter, FastaRecord, FastaWriter};
+use nextclade::io::fasta::{FastaCdsNucWriter, FastaPeptideWriter, FastaRecord, FastaWriter};
 use nextclade::io::genbank_tbl::GenbankTblFileWriter;
 use nextclade::io::gff3_writer::Gff3FileWriter;
 use nextclade::io::ndjson::NdjsonFileWriter;
@@ -26,6 +26,7 @@ use std::collections::HashMap;
 pub struct NextcladeOrderedWriter {
   fasta_writer: Option<FastaWriter>,
   fasta_peptide_writer: Option<FastaPeptideWriter>,
+  fasta_cds_nuc_writer: Option<FastaCdsNucWriter>,
   output_json_writer: Option<ResultsJsonWriter>,
   output_ndjson_writer: Option<NdjsonFileWriter>,
   output_csv_writer: Option<NextcladeResultsCsvFileWriter>,
@@ -41,9 +42,12 @@ impl NextcladeOrderedWriter {
   pub fn new(
     gene_map: &GeneMap,
     clade_node_attr_descs: &[CladeNodeAttrKeyDesc],
+    numeric_node_attr_keys: &[String],
     phenotype_attr_key_desc: &[PhenotypeAttrDesc],
     ref_nodes: &AuspiceRefNodesDesc,
     aa_motifs_keys: &[String],
+    nuc_motifs_keys: &[String],
+    metadata_keys: &[String],
     csv_column_config: &CsvColumnConfig,
     output_params: &NextcladeRunOutputArgs,
     params: &NextcladeInputParams,
@@ -54,6 +58,10 @@ impl NextcladeOrderedWriter {
       .output_translations
       .map_ref_fallible(|output_translations| FastaPeptideWriter::new(gene_map, output_translations))?;
 
+    let fasta_cds_nuc_writer = output_params
+      .output_cds_nuc
+      .map_ref_fallible(|output_cds_nuc| FastaCdsNucWriter::new(gene_map, output_cds_nuc))?;
+
     let output_json_writer = output_params.output_json.map_ref_fallible(|output_json| {
       ResultsJsonWriter::new(output_json, clade_node_attr_descs, phenotype_attr_key_desc, ref_nodes)
     })?;
@@ -70,9 +78,12 @@ impl NextcladeOrderedWriter {
         output_csv,
         b';',
         clade_node_attr_descs,
+        numeric_node_attr_keys,
         &phenotype_attr_keys,
         ref_nodes,
         aa_motifs_keys,
+        nuc_motifs_keys,
+        metadata_keys,
         csv_column_config,
       )
     })?;
@@ -82,9 +93,12 @@ impl NextcladeOrderedWriter {
         output_tsv,
         b'\t',
         clade_node_attr_descs,
+        numeric_node_attr_keys,
         &phenotype_attr_keys,
         ref_nodes,
         aa_motifs_keys,
+        nuc_motifs_keys,
+        metadata_keys,
         csv_column_config,
       )
     })?;
@@ -100,6 +114,7 @@ impl NextcladeOrderedWriter {
     Ok(Self {
       fasta_writer,
       fasta_peptide_writer,
+      fasta_cds_nuc_writer,
       output_json_writer,
       output_ndjson_writer,
       output_csv_writer,
@@ -123,6 +138,9 @@ impl NextcladeOrderedWriter {
       if let Some(fasta_peptide_writer) = &mut self.fasta_peptide_writer {
         fasta_peptide_writer.write(seq_name, cds_tr)?;
       }
+      if let Some(fasta_cds_nuc_writer) = &mut self.fasta_cds_nuc_writer {
+        fasta_cds_nuc_writer.write(seq_name, cds_tr)?;
+      }
       Result::<(), Report>::Ok(())
     })?;
 
@@ -159,6 +177,12 @@ impl NextcladeOrderedWriter {
           }
         }
 
+        if let Some(fasta_cds_nuc_writer) = &mut self.fasta_cds_nuc_writer {
+          for cds_tr in translation.cdses() {
+            fasta_cds_nuc_writer.write(&seq_name, cds_tr)?;
+          }
+        }
+
         for warning in warnings {
           info!("In sequence #{index} '{seq_name}': {}", warning.warning);
         }
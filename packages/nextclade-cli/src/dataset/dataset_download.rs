@@ -1,23 +1,25 @@
-use crate::cli::nextclade_cli::{NextcladeRunArgs, NextcladeRunInputArgs};
+use crate::cli::nextclade_cli::NextcladeRunInputArgs;
 use crate::cli::nextclade_dataset_get::{dataset_file_http_get, dataset_http_get};
 use crate::io::http_client::{HttpClient, ProxyConfig};
 use color_eyre::{Section, SectionExt};
 use eyre::{ContextCompat, Report, WrapErr, eyre};
 use itertools::Itertools;
 use log::{LevelFilter, warn};
+use nextclade::analyze::clade_mutations::CladeMutationsTable;
 use nextclade::analyze::virus_properties::VirusProperties;
 use nextclade::gene::gene_map::{GeneMap, filter_gene_map};
 use nextclade::io::dataset::{Dataset, DatasetsIndexJson};
-use nextclade::io::fasta::{read_one_fasta_from_file, read_one_fasta_from_str};
+use nextclade::io::fasta::{read_many_fasta, read_many_fasta_from_str, read_one_fasta_from_file};
 use nextclade::io::file::create_file_or_stdout;
 use nextclade::io::fs::{ensure_dir, has_extension, read_file_to_string};
+use nextclade::run::nextclade_segments::split_ref_segments;
 use nextclade::run::nextclade_wasm::{NextcladeParams, NextcladeParamsOptional};
 use nextclade::tree::tree::{AuspiceTree, check_ref_seq_mismatch};
 use nextclade::utils::fs::list_files_recursive;
 use nextclade::utils::option::OptionMapRefFallible;
 use nextclade::utils::string::{Indent, format_list, surround_with_quotes};
 use nextclade::{make_error, make_internal_error, o};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read, Seek, Write};
 use std::ops::Deref;
@@ -25,21 +27,20 @@ use std::path::Path;
 use zip::ZipArchive;
 
 pub fn nextclade_get_inputs(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
-  if let Some(dataset_name) = run_args.inputs.dataset_name.as_ref() {
-    dataset_str_download_and_load(run_args, cdses)
-      .wrap_err_with(|| format!("When downloading dataset '{dataset_name}'"))
-  } else if let Some(input_dataset) = run_args.inputs.input_dataset.as_ref() {
+  if let Some(dataset_name) = inputs.dataset_name.as_ref() {
+    dataset_str_download_and_load(inputs, cdses).wrap_err_with(|| format!("When downloading dataset '{dataset_name}'"))
+  } else if let Some(input_dataset) = inputs.input_dataset.as_ref() {
     if input_dataset.is_file() && has_extension(input_dataset, "zip") {
-      dataset_zip_load(run_args, input_dataset, cdses)
+      dataset_zip_load(inputs, input_dataset, cdses)
         .wrap_err_with(|| format!("When loading dataset from {}", input_dataset.display()))
     } else if input_dataset.is_file() && has_extension(input_dataset, "json") {
-      dataset_json_load(run_args, input_dataset, cdses)
+      dataset_json_load(inputs, input_dataset, cdses)
         .wrap_err_with(|| format!("When loading dataset from {}", input_dataset.display()))
     } else if input_dataset.is_dir() {
-      dataset_dir_load(run_args, input_dataset, cdses)
+      dataset_dir_load(inputs, input_dataset, cdses)
         .wrap_err_with(|| format!("When loading dataset from {}", input_dataset.display()))
     } else {
       make_error!(
@@ -49,7 +50,7 @@ pub fn nextclade_get_inputs(
       )
     }
   } else {
-    dataset_individual_files_load(run_args, cdses)
+    dataset_individual_files_load(inputs, cdses)
   }
 }
 
@@ -112,7 +113,7 @@ pub fn read_from_path_or_zip(
 }
 
 pub fn dataset_zip_load(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   dataset_zip: impl AsRef<Path>,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
@@ -121,26 +122,31 @@ pub fn dataset_zip_load(
   let buf_file = BufReader::new(file);
   let mut zip = ZipArchive::new(buf_file)?;
 
-  let virus_properties = read_from_path_or_zip(
-    run_args.inputs.input_pathogen_json.as_ref(),
-    &mut zip,
-    Some(&"pathogen.json"),
-  )?
-  .map_ref_fallible(VirusProperties::from_str)
-  .wrap_err("When reading pathogen JSON from dataset")?
-  .ok_or_else(|| eyre!("Pathogen JSON must always be present in the dataset but not found."))?;
+  let virus_properties = read_from_path_or_zip(inputs.input_pathogen_json.as_ref(), &mut zip, Some(&"pathogen.json"))?
+    .map_ref_fallible(VirusProperties::from_str)
+    .wrap_err("When reading pathogen JSON from dataset")?
+    .ok_or_else(|| eyre!("Pathogen JSON must always be present in the dataset but not found."))?;
 
-  let ref_record = read_from_path_or_zip(
-    run_args.inputs.input_ref.as_ref(),
+  let (ref_record, ref_segments) = read_from_path_or_zip(
+    inputs.input_ref.as_ref(),
     &mut zip,
     virus_properties.files.reference.as_ref(),
   )?
-  .map_ref_fallible(read_one_fasta_from_str)
+  .map_ref_fallible(|content| read_many_fasta_from_str(content).and_then(split_ref_segments))
   .wrap_err("When reading reference sequence from dataset")?
   .ok_or_else(|| eyre!("Reference sequence must always be present in the dataset but not found."))?;
 
+  let additional_refs = read_from_path_or_zip(
+    inputs.input_additional_refs.as_ref(),
+    &mut zip,
+    virus_properties.files.additional_references.as_ref(),
+  )?
+  .map_ref_fallible(read_many_fasta_from_str)
+  .wrap_err("When reading additional reference sequences from dataset")?
+  .unwrap_or_default();
+
   let gene_map = read_from_path_or_zip(
-    run_args.inputs.input_annotation.as_ref(),
+    inputs.input_annotation.as_ref(),
     &mut zip,
     virus_properties.files.genome_annotation.as_ref(),
   )?
@@ -150,13 +156,23 @@ pub fn dataset_zip_load(
   .unwrap_or_default();
 
   let tree = read_from_path_or_zip(
-    run_args.inputs.input_tree.as_ref(),
+    inputs.input_tree.as_ref(),
     &mut zip,
     virus_properties.files.tree_json.as_ref(),
   )?
   .map_ref_fallible(AuspiceTree::from_str)
   .wrap_err("When reading reference tree JSON from dataset")?;
 
+  let segment_trees = read_segment_trees(&virus_properties, |filename| zip_read_str(&mut zip, filename))?;
+
+  let clade_mutations = read_from_path_or_zip(
+    inputs.input_clade_mutations.as_ref(),
+    &mut zip,
+    virus_properties.files.clade_mutations.as_ref(),
+  )?
+  .map_ref_fallible(CladeMutationsTable::from_tsv_str)
+  .wrap_err("When reading clade mutations table from dataset")?;
+
   verify_dataset_files(&virus_properties, zip.file_names());
 
   if let Some(tree) = &tree
@@ -168,27 +184,54 @@ pub fn dataset_zip_load(
   Ok(NextcladeParams {
     dataset_name: dataset_zip.to_str().unwrap().to_owned(),
     ref_record,
+    ref_segments,
+    additional_refs,
     gene_map,
     tree,
+    segment_trees,
+    clade_mutations,
+    amplicon_scheme: None,
+    sample_metadata: None,
     virus_properties,
   })
 }
 
+/// Read reference trees of genome segments declared in pathogen.json, given a function which reads a dataset file
+fn read_segment_trees(
+  virus_properties: &VirusProperties,
+  mut read_file: impl FnMut(&str) -> Result<String, Report>,
+) -> Result<BTreeMap<String, AuspiceTree>, Report> {
+  virus_properties
+    .files
+    .segment_trees
+    .iter()
+    .map(|(segment, filename)| {
+      let tree = read_file(filename)
+        .and_then(AuspiceTree::from_str)
+        .wrap_err_with(|| format!("When reading reference tree of segment '{segment}' from dataset"))?;
+      Ok((segment.clone(), tree))
+    })
+    .collect()
+}
+
 fn verify_dataset_files<'a, T: AsRef<str> + 'a + ?Sized>(
   virus_properties: &VirusProperties,
   files_present: impl Iterator<Item = &'a T> + 'a,
 ) {
   let declared: BTreeSet<&str> = [
     virus_properties.files.reference.as_deref(),
+    virus_properties.files.additional_references.as_deref(),
     virus_properties.files.pathogen_json.as_deref(),
     virus_properties.files.genome_annotation.as_deref(),
     virus_properties.files.tree_json.as_deref(),
+    virus_properties.files.clade_mutations.as_deref(),
     virus_properties.files.examples.as_deref(),
     virus_properties.files.readme.as_deref(),
     virus_properties.files.changelog.as_deref(),
   ]
   .into_iter()
   .flatten()
+  .chain(virus_properties.files.segment_trees.values().map(Deref::deref))
   .chain(virus_properties.files.rest_files.values().map(Deref::deref))
   .collect();
 
@@ -242,7 +285,7 @@ pub fn dataset_dir_download(
 }
 
 pub fn dataset_dir_load(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   dataset_dir: impl AsRef<Path>,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
@@ -250,11 +293,13 @@ pub fn dataset_dir_load(
 
   let NextcladeRunInputArgs {
     input_ref,
+    input_additional_refs,
     input_tree,
+    input_clade_mutations,
     input_pathogen_json,
     input_annotation,
     ..
-  } = &run_args.inputs;
+  } = inputs;
 
   let input_pathogen_json = input_pathogen_json
     .clone()
@@ -273,7 +318,22 @@ pub fn dataset_dir_load(
     })
     .expect("Reference sequence is required but it is neither declared in the dataset's pathogen.json `.files` section, nor provided as a separate file");
 
-  let ref_record = read_one_fasta_from_file(input_ref).wrap_err("When reading reference sequence")?;
+  let (ref_record, ref_segments) = read_many_fasta(&[input_ref])
+    .and_then(split_ref_segments)
+    .wrap_err("When reading reference sequence")?;
+
+  let additional_refs = input_additional_refs
+    .clone()
+    .or_else(|| {
+      virus_properties
+        .files
+        .additional_references
+        .as_ref()
+        .map(|additional_refs| dataset_dir.join(additional_refs))
+    })
+    .map_ref_fallible(|filepath| read_many_fasta(&[filepath]))
+    .wrap_err("When reading additional reference sequences")?
+    .unwrap_or_default();
 
   let gene_map = input_annotation
     .clone()
@@ -301,6 +361,22 @@ pub fn dataset_dir_load(
     .map_ref_fallible(AuspiceTree::from_path)
     .wrap_err("When reading reference tree JSON")?;
 
+  let segment_trees = read_segment_trees(&virus_properties, |filename| {
+    read_file_to_string(dataset_dir.join(filename))
+  })?;
+
+  let clade_mutations = input_clade_mutations
+    .clone()
+    .or_else(|| {
+      virus_properties
+        .files
+        .clade_mutations
+        .as_ref()
+        .map(|clade_mutations| dataset_dir.join(clade_mutations))
+    })
+    .map_ref_fallible(CladeMutationsTable::from_path)
+    .wrap_err("When reading clade mutations table")?;
+
   let dataset_dir_files = list_files_recursive(dataset_dir)?
     .into_iter()
     .map(|p| p.strip_prefix(dataset_dir).unwrap_or(&p).to_owned())
@@ -317,14 +393,20 @@ pub fn dataset_dir_load(
   Ok(NextcladeParams {
     dataset_name: dataset_dir.to_str().unwrap().to_owned(),
     ref_record,
+    ref_segments,
+    additional_refs,
     gene_map,
     tree,
+    segment_trees,
+    clade_mutations,
+    amplicon_scheme: None,
+    sample_metadata: None,
     virus_properties,
   })
 }
 
 pub fn dataset_json_load(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   dataset_json: impl AsRef<Path>,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
@@ -332,11 +414,13 @@ pub fn dataset_json_load(
 
   let NextcladeRunInputArgs {
     input_ref,
+    input_additional_refs,
     input_tree,
+    input_clade_mutations,
     input_pathogen_json,
     input_annotation,
     ..
-  } = &run_args.inputs;
+  } = inputs;
 
   let auspice_json = AuspiceTree::from_path(dataset_json).wrap_err("When reading Auspice JSON v2")?;
 
@@ -349,6 +433,10 @@ pub fn dataset_json_load(
       .map_ref_fallible(read_one_fasta_from_file)
       .wrap_err("When parsing reference sequence")?;
 
+    let additional_refs = input_additional_refs
+      .map_ref_fallible(|filepath| read_many_fasta(&[filepath]))
+      .wrap_err("When parsing additional reference sequences")?;
+
     let tree = input_tree
       .map_ref_fallible(AuspiceTree::from_path)
       .wrap_err("When parsing reference tree Auspice JSON v2")?;
@@ -357,6 +445,10 @@ pub fn dataset_json_load(
       .map_ref_fallible(GeneMap::from_path)
       .wrap_err("When parsing genome annotation")?;
 
+    let clade_mutations = input_clade_mutations
+      .map_ref_fallible(CladeMutationsTable::from_path)
+      .wrap_err("When parsing clade mutations table")?;
+
     if let (Some(tree), Some(ref_record)) = (&tree, &ref_record)
       && let Some(tree_ref) = tree.root_sequence()
     {
@@ -366,8 +458,14 @@ pub fn dataset_json_load(
     NextcladeParamsOptional {
       dataset_name: dataset_json.to_str().map(ToOwned::to_owned),
       ref_record,
+      ref_segments: None,
+      additional_refs,
       gene_map,
       tree,
+      segment_trees: None,
+      clade_mutations,
+      amplicon_scheme: None,
+      sample_metadata: None,
       virus_properties,
     }
   };
@@ -378,14 +476,13 @@ pub fn dataset_json_load(
 }
 
 pub fn dataset_individual_files_load(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
-  match (&run_args.inputs.input_dataset, &run_args.inputs.input_ref) {
+  match (&inputs.input_dataset, &inputs.input_ref) {
     (None, None) => make_error!("When `--input-dataset` is not specified, --input-ref is required"),
     (_, Some(input_ref)) => {
-      let virus_properties = run_args
-        .inputs
+      let virus_properties = inputs
         .input_pathogen_json
         .as_ref()
         .and_then(|input_pathogen_json| read_file_to_string(input_pathogen_json).ok())
@@ -393,10 +490,11 @@ pub fn dataset_individual_files_load(
         .wrap_err("When reading pathogen JSON")?
         .unwrap_or_default();
 
-      let ref_record = read_one_fasta_from_file(input_ref).wrap_err("When reading reference sequence")?;
+      let (ref_record, ref_segments) = read_many_fasta(&[input_ref])
+        .and_then(split_ref_segments)
+        .wrap_err("When reading reference sequence")?;
 
-      let gene_map = run_args
-        .inputs
+      let gene_map = inputs
         .input_annotation
         .as_ref()
         .map_ref_fallible(GeneMap::from_path)
@@ -404,13 +502,25 @@ pub fn dataset_individual_files_load(
         .map(|gen_map| filter_gene_map(gen_map, cdses.as_ref()))
         .unwrap_or_default();
 
-      let tree = run_args
-        .inputs
+      let additional_refs = inputs
+        .input_additional_refs
+        .as_ref()
+        .map_ref_fallible(|filepath| read_many_fasta(&[filepath]))
+        .wrap_err("When reading additional reference sequences")?
+        .unwrap_or_default();
+
+      let tree = inputs
         .input_tree
         .as_ref()
         .map_ref_fallible(AuspiceTree::from_path)
         .wrap_err("When reading reference tree JSON")?;
 
+      let clade_mutations = inputs
+        .input_clade_mutations
+        .as_ref()
+        .map_ref_fallible(CladeMutationsTable::from_path)
+        .wrap_err("When reading clade mutations table")?;
+
       if let Some(tree) = &tree
         && let Some(tree_ref) = tree.root_sequence()
       {
@@ -418,15 +528,20 @@ pub fn dataset_individual_files_load(
       }
 
       Ok(NextcladeParams {
-        dataset_name: run_args
-          .inputs
+        dataset_name: inputs
           .input_pathogen_json
           .as_ref()
           .map(|s| s.to_str().unwrap().to_owned())
           .unwrap_or_default(),
         ref_record,
+        ref_segments,
+        additional_refs,
         gene_map,
         tree,
+        segment_trees: BTreeMap::new(),
+        clade_mutations,
+        amplicon_scheme: None,
+        sample_metadata: None,
         virus_properties,
       })
     }
@@ -449,38 +564,45 @@ pub fn read_from_path_or_url(
 }
 
 pub fn dataset_str_download_and_load(
-  run_args: &NextcladeRunArgs,
+  inputs: &NextcladeRunInputArgs,
   cdses: &Option<Vec<String>>,
 ) -> Result<NextcladeParams, Report> {
   let verbose = log::max_level() > LevelFilter::Info;
-  let http = HttpClient::new(&run_args.inputs.server, &ProxyConfig::default(), verbose)?;
+  let http = HttpClient::new(&inputs.server, &ProxyConfig::default(), verbose)?;
 
-  let name = run_args
-    .inputs
+  let name = inputs
     .dataset_name
     .as_ref()
     .expect("Dataset name is expected, but got 'None'");
 
   let dataset = dataset_http_get(&http, name, None)?;
 
-  let virus_properties = read_from_path_or_url(
+  let virus_properties =
+    read_from_path_or_url(&http, &dataset, &inputs.input_pathogen_json, &Some(o!("pathogen.json")))?
+      .map_ref_fallible(VirusProperties::from_str)
+      .wrap_err("When reading pathogen JSON from dataset")?
+      .ok_or_else(|| {
+        eyre!("Required file not found in dataset: 'pathogen.json'. Please report it to dataset authors.")
+      })?;
+
+  let (ref_record, ref_segments) = read_from_path_or_url(&http, &dataset, &inputs.input_ref, &dataset.files.reference)?
+    .map_ref_fallible(|content| read_many_fasta_from_str(content).and_then(split_ref_segments))?
+    .wrap_err("When reading reference sequence from dataset")?;
+
+  let additional_refs = read_from_path_or_url(
     &http,
     &dataset,
-    &run_args.inputs.input_pathogen_json,
-    &Some(o!("pathogen.json")),
+    &inputs.input_additional_refs,
+    &dataset.files.additional_references,
   )?
-  .map_ref_fallible(VirusProperties::from_str)
-  .wrap_err("When reading pathogen JSON from dataset")?
-  .ok_or_else(|| eyre!("Required file not found in dataset: 'pathogen.json'. Please report it to dataset authors."))?;
-
-  let ref_record = read_from_path_or_url(&http, &dataset, &run_args.inputs.input_ref, &dataset.files.reference)?
-    .map_ref_fallible(read_one_fasta_from_str)?
-    .wrap_err("When reading reference sequence from dataset")?;
+  .map_ref_fallible(read_many_fasta_from_str)
+  .wrap_err("When reading additional reference sequences from dataset")?
+  .unwrap_or_default();
 
   let gene_map = read_from_path_or_url(
     &http,
     &dataset,
-    &run_args.inputs.input_annotation,
+    &inputs.input_annotation,
     &dataset.files.genome_annotation,
   )?
   .map_ref_fallible(GeneMap::from_str)
@@ -488,10 +610,23 @@ pub fn dataset_str_download_and_load(
   .map(|gene_map| filter_gene_map(gene_map, cdses.as_ref()))
   .unwrap_or_default();
 
-  let tree = read_from_path_or_url(&http, &dataset, &run_args.inputs.input_tree, &dataset.files.tree_json)?
+  let tree = read_from_path_or_url(&http, &dataset, &inputs.input_tree, &dataset.files.tree_json)?
     .map_ref_fallible(AuspiceTree::from_str)
     .wrap_err("When reading reference tree from dataset")?;
 
+  let segment_trees = read_segment_trees(&virus_properties, |filename| {
+    dataset_file_http_get(&http, &dataset, filename)
+  })?;
+
+  let clade_mutations = read_from_path_or_url(
+    &http,
+    &dataset,
+    &inputs.input_clade_mutations,
+    &dataset.files.clade_mutations,
+  )?
+  .map_ref_fallible(CladeMutationsTable::from_tsv_str)
+  .wrap_err("When reading clade mutations table from dataset")?;
+
   if let Some(tree) = &tree
     && let Some(tree_ref) = tree.root_sequence()
   {
@@ -501,8 +636,14 @@ pub fn dataset_str_download_and_load(
   Ok(NextcladeParams {
     dataset_name: name.to_owned(),
     ref_record,
+    ref_segments,
+    additional_refs,
     gene_map,
     tree,
+    segment_trees,
+    clade_mutations,
+    amplicon_scheme: None,
+    sample_metadata: None,
     virus_properties,
   })
 }
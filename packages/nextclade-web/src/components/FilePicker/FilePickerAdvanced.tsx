@@ -4,10 +4,24 @@ import { useTranslationSafe as useTranslation } from 'src/helpers/useTranslation
 import { Col, Row } from 'reactstrap'
 import { useRecoilState, useRecoilValue, useResetRecoilState } from 'recoil'
 
-import { geneMapErrorAtom, refSeqErrorAtom, refTreeErrorAtom, virusPropertiesErrorAtom } from 'src/state/error.state'
-import { geneMapInputAtom, refSeqInputAtom, refTreeInputAtom, virusPropertiesInputAtom } from 'src/state/inputs.state'
+import {
+  ampliconSchemeErrorAtom,
+  geneMapErrorAtom,
+  refSeqErrorAtom,
+  refTreeErrorAtom,
+  sampleMetadataErrorAtom,
+  virusPropertiesErrorAtom,
+} from 'src/state/error.state'
+import {
+  ampliconSchemeInputAtom,
+  geneMapInputAtom,
+  refSeqInputAtom,
+  refTreeInputAtom,
+  sampleMetadataInputAtom,
+  virusPropertiesInputAtom,
+} from 'src/state/inputs.state'
 
-import { FileIconFasta, FileIconGff, FileIconJson } from 'src/components/Common/FileIcons'
+import { FileIconFasta, FileIconGff, FileIconJson, FileIconTsv } from 'src/components/Common/FileIcons'
 import { FilePicker } from 'src/components/FilePicker/FilePicker'
 
 export function FilePickerAdvanced() {
@@ -29,9 +43,18 @@ export function FilePickerAdvanced() {
   const virusPropertiesError = useRecoilValue(virusPropertiesErrorAtom)
   const resetVirusProperties = useResetRecoilState(virusPropertiesInputAtom)
 
+  const [ampliconScheme, setAmpliconScheme] = useRecoilState(ampliconSchemeInputAtom)
+  const ampliconSchemeError = useRecoilValue(ampliconSchemeErrorAtom)
+  const resetAmpliconScheme = useResetRecoilState(ampliconSchemeInputAtom)
+
+  const [sampleMetadata, setSampleMetadata] = useRecoilState(sampleMetadataInputAtom)
+  const sampleMetadataError = useRecoilValue(sampleMetadataErrorAtom)
+  const resetSampleMetadata = useResetRecoilState(sampleMetadataInputAtom)
+
   const iconFasta = useMemo(() => <FileIconFasta size={30} />, [])
   const iconGff = useMemo(() => <FileIconGff size={30} />, [])
   const iconJson = useMemo(() => <FileIconJson size={30} />, [])
+  const iconTsv = useMemo(() => <FileIconTsv size={30} />, [])
 
   return (
     <Row noGutters>
@@ -87,6 +110,32 @@ export function FilePickerAdvanced() {
           onRemove={resetGeneMap}
           onInput={setGeneMap}
         />
+
+        <FilePicker
+          className="my-3"
+          compact
+          icon={iconTsv}
+          title={t('Amplicon scheme')}
+          exampleUrl="https://example.com/primer.bed"
+          pasteInstructions={t('Enter amplicon scheme in {{formatName}} format', { formatName: 'BED' })}
+          input={ampliconScheme}
+          error={ampliconSchemeError}
+          onRemove={resetAmpliconScheme}
+          onInput={setAmpliconScheme}
+        />
+
+        <FilePicker
+          className="my-3"
+          compact
+          icon={iconTsv}
+          title={t('Sample metadata')}
+          exampleUrl="https://example.com/metadata.tsv"
+          pasteInstructions={t('Enter sample metadata in {{formatName}} format', { formatName: 'TSV or CSV' })}
+          input={sampleMetadata}
+          error={sampleMetadataError}
+          onRemove={resetSampleMetadata}
+          onInput={setSampleMetadata}
+        />
       </Col>
     </Row>
   )
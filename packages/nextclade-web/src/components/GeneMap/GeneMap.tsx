@@ -205,6 +205,13 @@ export function CdsSegmentView({
               <td>{frame + 1}</td>
             </tr>
 
+            {!isNil(cds.geneticCode) && cds.geneticCode !== 1 && (
+              <tr>
+                <td>{t('Genetic code')}</td>
+                <td>{`transl_table=${cds.geneticCode}`}</td>
+              </tr>
+            )}
+
             {cds.exceptions.length > 0 && (
               <tr>
                 <td>{t('Notes')}</td>
@@ -12,6 +12,10 @@ import { formatQCMissingData } from 'src/helpers/formatQCMissingData'
 import { formatQCMixedSites } from 'src/helpers/formatQCMixedSites'
 import { formatQCFrameShifts } from 'src/helpers/formatQCFrameShifts'
 import { formatQCStopCodons } from 'src/helpers/formatQCStopCodons'
+import { formatQCRecombinants } from 'src/helpers/formatQCRecombinants'
+import { formatQCMutationSignature } from 'src/helpers/formatQCMutationSignature'
+import { formatQCAmpliconDropouts } from 'src/helpers/formatQCAmpliconDropouts'
+import { formatQCMolecularClock } from 'src/helpers/formatQCMolecularClock'
 import { Circle, CircleProps } from 'src/components/Results/Circle'
 
 export const QcList = styled.ul`
@@ -52,6 +56,10 @@ export function ListOfQcIssues({ qc }: ListOfQcIssuesProps) {
     missingData,
     frameShifts,
     stopCodons,
+    recombinants,
+    mutationSignature,
+    ampliconDropouts,
+    molecularClock,
   } = qc
 
   const rules = [
@@ -61,6 +69,10 @@ export function ListOfQcIssues({ qc }: ListOfQcIssuesProps) {
     { name: t('Mutation Clusters'), shortName: 'C', value: snpClusters, message: formatQCSNPClusters(t, snpClusters) }, // prettier-ignore
     { name: t('Frame shifts'), shortName: 'F', value: frameShifts, message: formatQCFrameShifts(t, frameShifts) }, // prettier-ignore
     { name: t('Stop codons'), shortName: 'S', value: stopCodons, message: formatQCStopCodons(t, stopCodons) }, // prettier-ignore
+    { name: t('Recombinants'), shortName: 'R', value: recombinants, message: formatQCRecombinants(t, recombinants) }, // prettier-ignore
+    { name: t('Mutation signature'), shortName: 'G', value: mutationSignature, message: formatQCMutationSignature(t, mutationSignature) }, // prettier-ignore
+    { name: t('Amplicon dropouts'), shortName: 'A', value: ampliconDropouts, message: formatQCAmpliconDropouts(t, ampliconDropouts) }, // prettier-ignore
+    { name: t('Molecular clock'), shortName: 'T', value: molecularClock, message: formatQCMolecularClock(t, molecularClock) }, // prettier-ignore
   ].filter((value) => notUndefined(value))
 
   const issues = rules.map(({ name, shortName, value, message }) => {
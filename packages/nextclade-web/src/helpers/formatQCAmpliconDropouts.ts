@@ -0,0 +1,37 @@
+import type { QcResultAmpliconDropouts } from 'src/types'
+import { notUndefined } from 'src/helpers/notUndefined'
+import type { TFunctionInterface } from 'src/helpers/TFunctionInterface'
+
+export function formatQCAmpliconDropouts<TFunction extends TFunctionInterface>(
+  t: TFunction,
+  qcAmpliconDropouts?: QcResultAmpliconDropouts,
+) {
+  if (!qcAmpliconDropouts) {
+    return undefined
+  }
+
+  const { score, dropouts, totalDropouts, dropoutsIgnored } = qcAmpliconDropouts
+
+  let unexpected: string | undefined
+  if (dropouts.length > 0) {
+    unexpected = t('{{numDropouts}} amplicon dropout(s) detected: {{dropoutsList}}', {
+      numDropouts: totalDropouts,
+      dropoutsList: dropouts.join(', '),
+    })
+  }
+
+  let ignored: string | undefined
+  if (dropoutsIgnored.length > 0) {
+    ignored = t('Ignored {{numIgnored}} known amplicon dropout(s): {{dropoutsIgnoredList}}', {
+      numIgnored: dropoutsIgnored.length,
+      dropoutsIgnoredList: dropoutsIgnored.join(', '),
+    })
+  }
+
+  let scoreStr: string | undefined
+  if (unexpected || ignored) {
+    scoreStr = t('QC score: {{score}}', { score })
+  }
+
+  return [unexpected, ignored, scoreStr].filter(notUndefined).join('. ')
+}
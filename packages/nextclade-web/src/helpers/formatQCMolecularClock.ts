@@ -0,0 +1,31 @@
+import { round } from 'lodash'
+
+import type { DeepReadonly } from 'ts-essentials'
+
+import type { QcResultMolecularClock } from 'src/types'
+import type { TFunctionInterface } from 'src/helpers/TFunctionInterface'
+
+export function formatQCMolecularClock<TFunction extends TFunctionInterface>(
+  t: TFunction,
+  molecularClock?: DeepReadonly<QcResultMolecularClock>,
+) {
+  if (!molecularClock || molecularClock.status === 'good') {
+    return undefined
+  }
+
+  const { score, sampleDate, nodeDate, expectedPrivateMutations, privateMutations } = molecularClock
+
+  return t(
+    'QC score: {{score}}. ' +
+      'Private mutations: {{privateMutations}}, ' +
+      'expected {{expectedPrivateMutations}} between the date of the nearest node ({{nodeDate}}) ' +
+      'and the collection date ({{sampleDate}})',
+    {
+      score: round(score),
+      privateMutations: round(privateMutations, 1),
+      expectedPrivateMutations: round(expectedPrivateMutations, 1),
+      nodeDate: round(nodeDate, 2),
+      sampleDate: round(sampleDate, 2),
+    },
+  )
+}
@@ -0,0 +1,23 @@
+import type { QcResultMutationSignature } from 'src/types'
+import type { TFunctionInterface } from 'src/helpers/TFunctionInterface'
+
+export function formatQCMutationSignature<TFunction extends TFunctionInterface>(
+  t: TFunction,
+  qcMutationSignature?: QcResultMutationSignature,
+) {
+  if (!qcMutationSignature || qcMutationSignature.status === 'good') {
+    return undefined
+  }
+
+  const { score, signature, matchingSubstitutions, totalSubstitutions } = qcMutationSignature
+
+  return t(
+    '{{matchingSubstitutions}} of {{totalSubstitutions}} private substitutions match mutation signature "{{signature}}", which is outside of the expected range. QC score: {{score}}',
+    {
+      matchingSubstitutions,
+      totalSubstitutions,
+      signature,
+      score,
+    },
+  )
+}
@@ -0,0 +1,21 @@
+import type { QcResultRecombinants } from 'src/types'
+import type { TFunctionInterface } from 'src/helpers/TFunctionInterface'
+
+export function formatQCRecombinants<TFunction extends TFunctionInterface>(
+  t: TFunction,
+  qcRecombinants?: QcResultRecombinants,
+) {
+  if (!qcRecombinants || qcRecombinants.status === 'good') {
+    return undefined
+  }
+
+  const { score, totalBreakpoints } = qcRecombinants
+
+  return t(
+    '{{totalBreakpoints}} recombination breakpoint(s) detected: parts of the genome are closer to different clades. QC score: {{score}}',
+    {
+      totalBreakpoints,
+      score,
+    },
+  )
+}
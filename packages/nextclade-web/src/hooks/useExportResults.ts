@@ -11,6 +11,8 @@ import type {
   AaMotifsDesc,
   AuspiceRefNodesDesc,
   CsvColumnConfig,
+  NucMotifsDesc,
+  NumericNodeAttrDesc,
   PhenotypeAttrDesc,
 } from 'src/types'
 import type { CladeNodeAttrDesc } from 'auspice'
@@ -20,11 +22,14 @@ import { saveBase64File, saveFile, saveZip, ZipFileDescription } from 'src/helpe
 import { globalErrorAtom } from 'src/state/error.state'
 import {
   aaMotifsDescsAtom,
+  nucMotifsDescsAtom,
+  metadataKeysAtom,
   allInitialDataAtom,
   analysisResultsAtom,
   cdsesAtom,
   cladeNodeAttrDescsAtom,
   csvColumnConfigAtom,
+  numericNodeAttrDescsAtom,
   phenotypeAttrDescsAtom,
   refNodesAtom,
   treeAtom,
@@ -150,9 +155,12 @@ async function prepareCsvData(
   results: AnalysisResult[],
   errors: AnalysisError[],
   cladeNodeAttrDescs: CladeNodeAttrDesc[] | undefined,
+  numericNodeAttrDescs: NumericNodeAttrDesc[] | undefined,
   phenotypeAttrDescs: PhenotypeAttrDesc[] | undefined,
   refNodes: AuspiceRefNodesDesc | undefined,
   aaMotifsDescs: AaMotifsDesc[] | undefined,
+  nucMotifsDescs: NucMotifsDesc[] | undefined,
+  metadataKeys: string[] | undefined,
   csvColumnConfig: CsvColumnConfig,
   delimiter: string,
   worker: ExportWorker,
@@ -161,9 +169,12 @@ async function prepareCsvData(
     results,
     errors,
     cladeNodeAttrDescs ?? [],
+    numericNodeAttrDescs ?? [],
     phenotypeAttrDescs ?? [],
     refNodes ?? {},
     aaMotifsDescs ?? [],
+    nucMotifsDescs ?? [],
+    metadataKeys ?? [],
     delimiter,
     csvColumnConfig,
   )
@@ -360,9 +371,12 @@ async function prepareAllExportData(
   analysisResults: NextcladeResult[],
   datasetName: string,
   cladeNodeAttrDescs: CladeNodeAttrDesc[] | undefined,
+  numericNodeAttrDescs: NumericNodeAttrDesc[] | undefined,
   phenotypeAttrDescs: PhenotypeAttrDesc[] | undefined,
   refNodes: AuspiceRefNodesDesc | undefined,
   aaMotifsDescs: AaMotifsDesc[] | undefined,
+  nucMotifsDescs: NucMotifsDesc[] | undefined,
+  metadataKeys: string[] | undefined,
   csvColumnConfig: CsvColumnConfig | undefined,
   worker: ExportWorker,
 ) {
@@ -378,9 +392,12 @@ async function prepareAllExportData(
       results,
       errors,
       cladeNodeAttrDescs ?? [],
+      numericNodeAttrDescs ?? [],
       phenotypeAttrDescs ?? [],
       refNodes,
       aaMotifsDescs ?? [],
+      nucMotifsDescs ?? [],
+      metadataKeys ?? [],
       csvColumnConfig,
       ';',
       worker,
@@ -389,9 +406,12 @@ async function prepareAllExportData(
       results,
       errors,
       cladeNodeAttrDescs ?? [],
+      numericNodeAttrDescs ?? [],
       phenotypeAttrDescs ?? [],
       refNodes,
       aaMotifsDescs ?? [],
+      nucMotifsDescs ?? [],
+      metadataKeys ?? [],
       csvColumnConfig,
       '\t',
       worker,
@@ -420,9 +440,12 @@ async function prepareAllExportData(
 export function useExportZip({ datasetName }: { datasetName: string }) {
   const analysisResults = useAtomValue(analysisResultsAtom)
   const cladeNodeAttrDescs = useRecoilValue(cladeNodeAttrDescsAtom({ datasetName }))
+  const numericNodeAttrDescs = useRecoilValue(numericNodeAttrDescsAtom({ datasetName }))
   const phenotypeAttrDescs = useRecoilValue(phenotypeAttrDescsAtom({ datasetName }))
   const refNodes = useRecoilValue(refNodesAtom({ datasetName }))
   const aaMotifsDescs = useRecoilValue(aaMotifsDescsAtom({ datasetName }))
+  const nucMotifsDescs = useRecoilValue(nucMotifsDescsAtom({ datasetName }))
+  const metadataKeys = useRecoilValue(metadataKeysAtom({ datasetName }))
   const csvColumnConfig = useRecoilValue(csvColumnConfigAtom)
   const tree = useRecoilValue(treeAtom(datasetName))
   const treeNwk = useRecoilValue(treeNwkAtom({ datasetName }))
@@ -433,9 +456,12 @@ export function useExportZip({ datasetName }: { datasetName: string }) {
         analysisResults,
         datasetName,
         cladeNodeAttrDescs,
+        numericNodeAttrDescs,
         phenotypeAttrDescs,
         refNodes,
         aaMotifsDescs,
+        nucMotifsDescs,
+        metadataKeys,
         csvColumnConfig,
         worker,
       )
@@ -472,9 +498,12 @@ export function useExportZip({ datasetName }: { datasetName: string }) {
       analysisResults,
       datasetName,
       cladeNodeAttrDescs,
+      numericNodeAttrDescs,
       phenotypeAttrDescs,
       refNodes,
       aaMotifsDescs,
+      nucMotifsDescs,
+      metadataKeys,
       csvColumnConfig,
       tree,
       treeNwk,
@@ -504,9 +533,12 @@ function createCsvExportHook(delimiter: string, mimeType: string) {
   return function csvExportHook({ datasetName }: { datasetName: string }) {
     const analysisResults = useAtomValue(analysisResultsAtom)
     const cladeNodeAttrDescs = useRecoilValue(cladeNodeAttrDescsAtom({ datasetName }))
+    const numericNodeAttrDescs = useRecoilValue(numericNodeAttrDescsAtom({ datasetName }))
     const phenotypeAttrDescs = useRecoilValue(phenotypeAttrDescsAtom({ datasetName }))
     const refNodes = useRecoilValue(refNodesAtom({ datasetName }))
     const aaMotifsDescs = useRecoilValue(aaMotifsDescsAtom({ datasetName }))
+    const nucMotifsDescs = useRecoilValue(nucMotifsDescsAtom({ datasetName }))
+    const metadataKeys = useRecoilValue(metadataKeysAtom({ datasetName }))
     const csvColumnConfig = useRecoilValue(csvColumnConfigAtom)
 
     const exportFn = useCallback(
@@ -522,16 +554,30 @@ function createCsvExportHook(delimiter: string, mimeType: string) {
           results,
           errors,
           cladeNodeAttrDescs,
+          numericNodeAttrDescs,
           phenotypeAttrDescs,
           refNodes,
           aaMotifsDescs,
+          nucMotifsDescs,
+          metadataKeys,
           csvColumnConfig,
           delimiter,
           worker,
         )
         saveFile(csvStr, filename, mimeType)
       },
-      [analysisResults, datasetName, cladeNodeAttrDescs, phenotypeAttrDescs, refNodes, aaMotifsDescs, csvColumnConfig],
+      [
+        analysisResults,
+        datasetName,
+        cladeNodeAttrDescs,
+        numericNodeAttrDescs,
+        phenotypeAttrDescs,
+        refNodes,
+        aaMotifsDescs,
+        nucMotifsDescs,
+        metadataKeys,
+        csvColumnConfig,
+      ],
     )
 
     return useResultsExport(exportFn)
@@ -1,6 +1,6 @@
 import type { AuspiceJsonV2 } from 'auspice'
 import { concurrent } from 'fasy'
-import { isEmpty, isNil, omitBy } from 'lodash'
+import { isEmpty, isNil, mapValues, omitBy } from 'lodash'
 import pProps from 'p-props'
 import { useRouter } from 'next/router'
 import { useRecoilCallback } from 'recoil'
@@ -34,15 +34,19 @@ import {
 } from 'src/state/dataset.state'
 import { globalErrorAtom } from 'src/state/error.state'
 import {
+  ampliconSchemeInputAtom,
   datasetJsonAtom,
   geneMapInputAtom,
   qrySeqInputsStorageAtom,
   refSeqInputAtom,
   refTreeInputAtom,
+  sampleMetadataInputAtom,
   virusPropertiesInputAtom,
 } from 'src/state/inputs.state'
 import {
   aaMotifsDescsAtom,
+  nucMotifsDescsAtom,
+  metadataKeysAtom,
   allGenomeSizesAtom,
   allGenesAtom,
   allCdsesAtom,
@@ -60,6 +64,7 @@ import {
   currentRefNodeNameAtom,
   genesAtom,
   genomeSizeAtom,
+  numericNodeAttrDescsAtom,
   phenotypeAttrDescsAtom,
   refNodesAtom,
   treeAtom,
@@ -118,9 +123,12 @@ export function useRunAnalysis() {
               defaultCds,
               cdsOrderPreference,
               cladeNodeAttrKeyDescs,
+              numericNodeAttrDescs,
               phenotypeAttrDescs,
               refNodes,
               aaMotifsDescs,
+              nucMotifsDescs,
+              metadataKeys,
               csvColumnConfigDefault,
             } = initialData
 
@@ -140,6 +148,7 @@ export function useRunAnalysis() {
             }
 
             set(cladeNodeAttrDescsAtom({ datasetName }), cladeNodeAttrKeyDescs)
+            set(numericNodeAttrDescsAtom({ datasetName }), numericNodeAttrDescs ?? [])
             set(phenotypeAttrDescsAtom({ datasetName }), phenotypeAttrDescs)
             set(refNodesAtom({ datasetName }), refNodes)
 
@@ -152,6 +161,8 @@ export function useRunAnalysis() {
             set(currentRefNodeNameAtom({ datasetName }), defaultSearchName)
 
             set(aaMotifsDescsAtom({ datasetName }), aaMotifsDescs)
+            set(nucMotifsDescsAtom({ datasetName }), nucMotifsDescs)
+            set(metadataKeysAtom({ datasetName }), metadataKeys ?? [])
             set(csvColumnConfigAtom, csvColumnConfigDefault)
           },
           onAnalysisResult(result) {
@@ -189,6 +200,8 @@ export function useRunAnalysis() {
               genomeAnnotation: getPromise(geneMapInputAtom),
               treeJson: getPromise(refTreeInputAtom),
               pathogenJson: getPromise(virusPropertiesInputAtom),
+              ampliconScheme: getPromise(ampliconSchemeInputAtom),
+              sampleMetadata: getPromise(sampleMetadataInputAtom),
             }
 
             const topSuggestedDatasets = await getPromise(topSuggestedDatasetsAtom)
@@ -266,6 +279,8 @@ async function resolveInputsForAuspiceDataset(
     reference: resolveOverride(overrides.reference),
     treeJson: resolveOverride(overrides.treeJson),
     pathogenJson: resolveOverride(overrides.pathogenJson),
+    ampliconScheme: resolveOverride(overrides.ampliconScheme),
+    sampleMetadata: resolveOverride(overrides.sampleMetadata),
   })
   const filteredOverrides = omitBy(resolvedOverrides, isNil)
   return {
@@ -304,6 +319,8 @@ async function getDatasetFiles(datasets: Dataset[]): Promise<NextcladeParamsRawD
       genomeAnnotation: await axiosFetchRawMaybe(dataset.files?.genomeAnnotation),
       reference: await axiosFetchRaw(dataset.files?.reference),
       treeJson: await axiosFetchRawMaybe(dataset.files?.treeJson),
+      segmentTrees: await fetchSegmentTrees(dataset.files?.segmentTrees),
+      cladeMutations: await axiosFetchRawMaybe(dataset.files?.cladeMutations),
       pathogenJson: await axiosFetchRaw(dataset.files?.pathogenJson),
     }
   }, datasets)
@@ -319,10 +336,19 @@ async function getDatasetFilesWithOverrides(
     genomeAnnotation: await resolveOverrideOrDatasetFile(overrides.genomeAnnotation, dataset.files?.genomeAnnotation),
     reference: await resolveOverrideOrDatasetFileRequired(overrides.reference, dataset.files?.reference),
     treeJson: await resolveOverrideOrDatasetFile(overrides.treeJson, dataset.files?.treeJson),
+    segmentTrees: await fetchSegmentTrees(dataset.files?.segmentTrees),
+    cladeMutations: await axiosFetchRawMaybe(dataset.files?.cladeMutations),
+    ampliconScheme: await resolveOverride(overrides.ampliconScheme),
+    sampleMetadata: await resolveOverride(overrides.sampleMetadata),
     pathogenJson: await resolveOverrideOrDatasetFileRequired(overrides.pathogenJson, dataset.files?.pathogenJson),
   }
 }
 
+/** Resolves reference trees of genome segments into strings, keyed by segment name */
+async function fetchSegmentTrees(segmentTrees: Record<string, string> | undefined): Promise<Record<string, string>> {
+  return pProps(mapValues(segmentTrees ?? {}, async (url) => axiosFetchRaw(url)))
+}
+
 async function resolveOverrideOrDatasetFileRequired(
   override: Promise<AlgorithmInput | undefined>,
   datasetFileUrl: string | undefined,
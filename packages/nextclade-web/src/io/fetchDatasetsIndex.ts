@@ -18,12 +18,13 @@ export function isCompatible(dataset: Dataset): boolean {
 }
 
 export function fileUrlsToAbsolute(datasetServerUrl: string, dataset: Dataset): Dataset {
-  const restFilesAbs = mapValues(dataset.files, (file) =>
-    file ? urljoin(datasetServerUrl, dataset.path, dataset.version?.tag ?? '', file) : undefined,
-  ) as DatasetFiles
+  const toAbsolute = (file: string) => urljoin(datasetServerUrl, dataset.path, dataset.version?.tag ?? '', file)
+  const { segmentTrees, ...restFiles } = dataset.files ?? {}
+  const restFilesAbs = mapValues(restFiles, (file) => (typeof file === 'string' ? toAbsolute(file) : undefined))
   const files = {
     ...restFilesAbs,
-  }
+    ...(segmentTrees ? { segmentTrees: mapValues(segmentTrees, toAbsolute) } : {}),
+  } as DatasetFiles
   return { ...dataset, files }
 }
 
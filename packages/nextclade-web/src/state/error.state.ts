@@ -58,6 +58,16 @@ export const primersCsvErrorAtom = atom<string | undefined>({
   default: undefined,
 })
 
+export const ampliconSchemeErrorAtom = atom<string | undefined>({
+  key: 'ampliconSchemeError',
+  default: undefined,
+})
+
+export const sampleMetadataErrorAtom = atom<string | undefined>({
+  key: 'sampleMetadataError',
+  default: undefined,
+})
+
 export const hasInputErrorsAtom = selector({
   key: 'hasInputErrors',
   get({ get }) {
@@ -69,6 +79,8 @@ export const hasInputErrorsAtom = selector({
       get(qcConfigErrorAtom),
       get(virusPropertiesErrorAtom),
       get(primersCsvErrorAtom),
+      get(ampliconSchemeErrorAtom),
+      get(sampleMetadataErrorAtom),
     ].some((error) => !isNil(error))
   },
 })
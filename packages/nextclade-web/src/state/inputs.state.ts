@@ -98,6 +98,16 @@ export const virusPropertiesInputAtom = atom<AlgorithmInput | undefined>({
   default: undefined,
 })
 
+export const ampliconSchemeInputAtom = atom<AlgorithmInput | undefined>({
+  key: 'ampliconSchemeInput',
+  default: undefined,
+})
+
+export const sampleMetadataInputAtom = atom<AlgorithmInput | undefined>({
+  key: 'sampleMetadataInput',
+  default: undefined,
+})
+
 export const hasRequiredInputsAtom = selector({
   key: 'hasRequiredInputs',
   get({ get }) {
@@ -114,9 +124,14 @@ export const datasetJsonAtom = atom<AuspiceTree | undefined>({
 export const inputCustomizationCounterAtom = selector<number>({
   key: 'inputCustomizationCounterAtom',
   get: ({ get }) => {
-    return [get(refSeqInputAtom), get(geneMapInputAtom), get(refTreeInputAtom), get(virusPropertiesInputAtom)].filter(
-      notUndefinedOrNull,
-    ).length
+    return [
+      get(refSeqInputAtom),
+      get(geneMapInputAtom),
+      get(refTreeInputAtom),
+      get(virusPropertiesInputAtom),
+      get(ampliconSchemeInputAtom),
+      get(sampleMetadataInputAtom),
+    ].filter(notUndefinedOrNull).length
   },
 })
 
@@ -129,6 +144,8 @@ export const datasetFilesResetAtom = selector<undefined>({
     reset(geneMapInputAtom)
     reset(refTreeInputAtom)
     reset(virusPropertiesInputAtom)
+    reset(ampliconSchemeInputAtom)
+    reset(sampleMetadataInputAtom)
     reset(datasetJsonAtom)
   },
 })
@@ -13,6 +13,8 @@ import type {
   CsvColumnConfig,
   Gene,
   NextcladeResult,
+  NucMotifsDesc,
+  NumericNodeAttrDesc,
   PhenotypeAttrDesc,
 } from 'src/types'
 import { AlgorithmGlobalStatus, getResultStatus } from 'src/types'
@@ -230,6 +232,21 @@ export const [aaMotifsDescsAtom, allAaMotifsDescsAtom] = multiAtom<AaMotifsDesc[
   key: 'aaMotifsDescsAtom',
 })
 
+export const [nucMotifsDescsAtom, allNucMotifsDescsAtom] = multiAtom<NucMotifsDesc[], { datasetName: string }>({
+  key: 'nucMotifsDescsAtom',
+})
+
+export const [metadataKeysAtom, allMetadataKeysAtom] = multiAtom<string[], { datasetName: string }>({
+  key: 'metadataKeysAtom',
+})
+
+export const [numericNodeAttrDescsAtom, allNumericNodeAttrDescsAtom] = multiAtom<
+  NumericNodeAttrDesc[],
+  { datasetName: string }
+>({
+  key: 'numericNodeAttrDescs',
+})
+
 export const [initialDataAtom, allInitialDataAtom] = multiAtom<AnalysisInitialData, string>({
   key: 'initialDataAtom',
 })
@@ -1,7 +1,7 @@
 use crate::wasm::jserr::jserr;
 use eyre::{Report, WrapErr};
 use itertools::Itertools;
-use nextclade::analyze::virus_properties::{AaMotifsDesc, PhenotypeAttrDesc};
+use nextclade::analyze::virus_properties::{AaMotifsDesc, NucMotifsDesc, PhenotypeAttrDesc};
 use nextclade::io::csv::{CsvVecWriter, VecWriter};
 use nextclade::io::fasta::{FastaReader, FastaRecord, read_one_fasta_from_str};
 use nextclade::io::genbank_tbl::results_to_tbl_string;
@@ -15,7 +15,7 @@ use nextclade::run::nextclade_wasm::{
   AnalysisInitialData, Nextclade, NextcladeParams, NextcladeParamsRaw, NextcladeResult,
 };
 use nextclade::run::params::NextcladeInputParamsOptional;
-use nextclade::tree::tree::{AuspiceRefNodesDesc, CladeNodeAttrKeyDesc};
+use nextclade::tree::tree::{AuspiceRefNodesDesc, CladeNodeAttrKeyDesc, NumericNodeAttrDesc};
 use nextclade::types::outputs::{NextcladeErrorOutputs, NextcladeOutputs};
 use nextclade::utils::encode::base64_encode;
 use nextclade::utils::error::report_to_string;
@@ -226,9 +226,12 @@ impl NextcladeWasm {
     outputs_json_str: &str,
     errors_json_str: &str,
     clade_node_attrs_json_str: &str,
+    numeric_node_attrs_json_str: &str,
     phenotype_attrs_json_str: &str,
     ref_nodes_json_str: &str,
     aa_motifs_keys_json_str: &str,
+    nuc_motifs_keys_json_str: &str,
+    metadata_keys_json_str: &str,
     delimiter: char,
     csv_colum_config_json_str: &str,
   ) -> Result<String, JsError> {
@@ -245,6 +248,11 @@ impl NextcladeWasm {
         .wrap_err("When serializing results into CSV: When parsing clade node attrs JSON internally"),
     )?;
 
+    let numeric_node_attr_descs: Vec<NumericNodeAttrDesc> = jserr(
+      json_parse(numeric_node_attrs_json_str)
+        .wrap_err("When serializing results into CSV: When parsing numeric node attrs JSON internally"),
+    )?;
+
     let phenotype_attrs: Vec<PhenotypeAttrDesc> = jserr(
       json_parse(phenotype_attrs_json_str)
         .wrap_err("When serializing results into CSV: When parsing phenotypes attr keys JSON internally"),
@@ -260,8 +268,20 @@ impl NextcladeWasm {
         .wrap_err("When serializing results into CSV: When parsing AA motifs keys JSON internally"),
     )?;
 
+    let nuc_motifs_descs: Vec<NucMotifsDesc> = jserr(
+      json_parse(nuc_motifs_keys_json_str)
+        .wrap_err("When serializing results into CSV: When parsing nucleotide motifs keys JSON internally"),
+    )?;
+
+    let metadata_keys: Vec<String> = jserr(
+      json_parse(metadata_keys_json_str)
+        .wrap_err("When serializing results into CSV: When parsing sample metadata keys JSON internally"),
+    )?;
+
+    let numeric_node_attr_keys = numeric_node_attr_descs.into_iter().map(|desc| desc.name).collect_vec();
     let phenotype_attr_keys = phenotype_attrs.into_iter().map(|attr| attr.name).collect_vec();
     let aa_motifs_keys = aa_motifs_descs.into_iter().map(|desc| desc.name).collect_vec();
+    let nuc_motifs_keys = nuc_motifs_descs.into_iter().map(|desc| desc.name).collect_vec();
 
     let csv_colum_config: CsvColumnConfig = jserr(
       json_parse(csv_colum_config_json_str)
@@ -272,9 +292,12 @@ impl NextcladeWasm {
       &outputs,
       &errors,
       &clade_node_attr_descs,
+      &numeric_node_attr_keys,
       &phenotype_attr_keys,
       &ref_nodes,
       &aa_motifs_keys,
+      &nuc_motifs_keys,
+      &metadata_keys,
       delimiter as u8,
       &csv_colum_config,
     ))
@@ -5,6 +5,8 @@ import type {
   AnalysisInitialData,
   AnalysisResult,
   AuspiceRefNodesDesc,
+  NucMotifsDesc,
+  NumericNodeAttrDesc,
   PhenotypeAttrDesc,
 } from 'src/types'
 import type { NextcladeWasmWorker } from 'src/workers/nextcladeWasm.worker'
@@ -55,9 +57,12 @@ export class ExportWorker {
     results: AnalysisResult[],
     errors: AnalysisError[],
     cladeNodeAttrs: CladeNodeAttrDesc[],
+    numericNodeAttrs: NumericNodeAttrDesc[],
     phenotypeAttrs: PhenotypeAttrDesc[],
     refNodes: AuspiceRefNodesDesc,
     aaMotifsDescs: AaMotifsDesc[],
+    nucMotifsDescs: NucMotifsDesc[],
+    metadataKeys: string[],
     delimiter: string,
     csvColumnConfig: CsvColumnConfig,
   ) {
@@ -65,9 +70,12 @@ export class ExportWorker {
       results,
       errors,
       cladeNodeAttrs,
+      numericNodeAttrs,
       phenotypeAttrs,
       refNodes,
       aaMotifsDescs,
+      nucMotifsDescs,
+      metadataKeys,
       delimiter,
       csvColumnConfig,
     )
@@ -17,6 +17,8 @@ export interface DatasetFilesOverrides {
   genomeAnnotation: Promise<AlgorithmInput | undefined>
   treeJson: Promise<AlgorithmInput | undefined>
   pathogenJson: Promise<AlgorithmInput | undefined>
+  ampliconScheme: Promise<AlgorithmInput | undefined>
+  sampleMetadata: Promise<AlgorithmInput | undefined>
 }
 
 export interface LaunchAnalysisCallbacks {
@@ -14,6 +14,8 @@ import type {
   FastaRecord,
   NextcladeParamsRaw,
   NextcladeResult,
+  NucMotifsDesc,
+  NumericNodeAttrDesc,
   PhenotypeAttrDesc,
 } from 'src/types'
 import { sanitizeError } from 'src/helpers/sanitizeError'
@@ -153,9 +155,12 @@ export async function serializeResultsCsv(
   results: AnalysisResult[],
   errors: AnalysisError[],
   cladeNodeAttrsJson: CladeNodeAttrDesc[],
+  numericNodeAttrsJson: NumericNodeAttrDesc[],
   phenotypeAttrsJson: PhenotypeAttrDesc[],
   refNodesJson: AuspiceRefNodesDesc,
   aaMotifsDescs: AaMotifsDesc[],
+  nucMotifsDescs: NucMotifsDesc[],
+  metadataKeys: string[],
   delimiter: string,
   csvColumnConfig: CsvColumnConfig,
 ) {
@@ -163,9 +168,12 @@ export async function serializeResultsCsv(
     JSON.stringify(results),
     JSON.stringify(errors),
     JSON.stringify(cladeNodeAttrsJson),
+    JSON.stringify(numericNodeAttrsJson),
     JSON.stringify(phenotypeAttrsJson),
     JSON.stringify(refNodesJson),
     JSON.stringify(aaMotifsDescs),
+    JSON.stringify(nucMotifsDescs),
+    JSON.stringify(metadataKeys),
     delimiter,
     JSON.stringify(csvColumnConfig),
   )
@@ -1,10 +1,14 @@
 use crate::align::backtrace::{AlignmentOutput, backtrace};
 use crate::align::band_2d::Stripe;
 use crate::align::band_2d::{full_matrix, simple_stripes};
+use crate::align::circular::align_circular;
 use crate::align::params::AlignPairwiseParams;
 use crate::align::score_matrix::{ScoreMatrixResult, score_matrix};
 use crate::align::seed_alignment::create_alignment_band;
-use crate::align::seed_match::{CodonSpacedIndex, SeedMatchesResult, get_seed_matches_maybe_reverse_complement};
+use crate::align::seed_match::{
+  CodonSpacedIndex, SeedMatch2, SeedMatchesResult, get_seed_matches_maybe_reverse_complement, get_seed_matches2,
+};
+use crate::align::soft_clip::soft_clip;
 use crate::alphabet::aa::Aa;
 use crate::alphabet::letter::Letter;
 use crate::alphabet::nuc::Nuc;
@@ -53,7 +57,14 @@ pub fn align_nuc(
     trace!(
       "When processing sequence #{index} '{seq_name}': In nucleotide alignment: Band construction: short sequences, using full matrix"
     );
-    return Ok(align_pairwise(qry_seq, ref_seq, gap_open_close, params, &stripes));
+
+    let mut alignment = align_circular(index, seq_name, qry_seq, ref_seq, seed_index, params, |qry_seq, _| {
+      Ok(align_pairwise(qry_seq, ref_seq, gap_open_close, params, &stripes))
+    })?;
+    if params.soft_clip {
+      soft_clip(&mut alignment, gap_open_close, params);
+    }
+    return Ok(alignment);
   }
 
   // otherwise, determine seed matches roughly regularly spaced along the query sequence
@@ -64,6 +75,43 @@ pub fn align_nuc(
   } = get_seed_matches_maybe_reverse_complement(qry_seq, ref_seq, seed_index, params)
     .wrap_err("When calculating seed matches")?;
 
+  // for circular genomes, the query is rotated such that it starts at the origin of the reference
+  let mut alignment = align_circular(
+    index,
+    seq_name,
+    &qry_seq,
+    ref_seq,
+    seed_index,
+    params,
+    |qry_seq, is_rotated| {
+      let seed_matches = if is_rotated {
+        get_seed_matches2(qry_seq, ref_seq, seed_index, params)
+          .wrap_err("When calculating seed matches for the rotated query sequence")?
+      } else {
+        seed_matches
+      };
+      align_nuc_banded(index, seq_name, qry_seq, ref_seq, &seed_matches, gap_open_close, params)
+    },
+  )?;
+  alignment.is_reverse_complement = is_reverse_complement;
+  if params.soft_clip {
+    soft_clip(&mut alignment, gap_open_close, params);
+  }
+  Ok(alignment)
+}
+
+/// align nucleotide sequences using a band around the seed matches, relaxing the band if its boundary is hit
+fn align_nuc_banded(
+  index: usize,
+  seq_name: &str,
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_matches: &[SeedMatch2],
+  gap_open_close: &[i32],
+  params: &AlignPairwiseParams,
+) -> Result<AlignmentOutput<Nuc>, Report> {
+  let qry_len = qry_seq.len();
+  let ref_len = ref_seq.len();
   let mut terminal_bandwidth = params.terminal_bandwidth as isize;
   let mut excess_bandwidth = params.excess_bandwidth as isize;
   let mut minimal_bandwidth = max(1, params.allowed_mismatches as isize);
@@ -71,7 +119,7 @@ pub fn align_nuc(
   let mut attempt = 0;
 
   let (mut stripes, mut band_area) = create_alignment_band(
-    &seed_matches,
+    seed_matches,
     qry_len as isize,
     ref_len as isize,
     terminal_bandwidth,
@@ -101,7 +149,7 @@ pub fn align_nuc(
     };
   }
 
-  let mut alignment = align_pairwise(&qry_seq, ref_seq, gap_open_close, params, &stripes);
+  let mut alignment = align_pairwise(qry_seq, ref_seq, gap_open_close, params, &stripes);
 
   while alignment.hit_boundary && attempt < params.max_alignment_attempts {
     info!(
@@ -116,7 +164,7 @@ pub fn align_nuc(
     attempt += 1;
     // make new band
     (stripes, band_area) = create_alignment_band(
-      &seed_matches,
+      seed_matches,
       qry_len as isize,
       ref_len as isize,
       terminal_bandwidth,
@@ -128,7 +176,7 @@ pub fn align_nuc(
       break;
     }
     // realign
-    alignment = align_pairwise(&qry_seq, ref_seq, gap_open_close, params, &stripes);
+    alignment = align_pairwise(qry_seq, ref_seq, gap_open_close, params, &stripes);
   }
   // report success/failure of broadening of band width
   if alignment.hit_boundary {
@@ -148,7 +196,6 @@ pub fn align_nuc(
       alignment.alignment_score
     );
   }
-  alignment.is_reverse_complement = is_reverse_complement;
   Ok(alignment)
 }
 
@@ -174,8 +221,10 @@ mod tests {
 
   // rstest fixtures are passed by value
   use super::*;
-  use crate::align::gap_open::{GapScoreMap, get_gap_open_close_scores_codon_aware};
+  use crate::align::circular::rotate_circular;
+  use crate::align::gap_open::{GapScoreMap, get_gap_open_close_scores_codon_aware, get_gap_open_close_scores_flat};
   use crate::align::params::GapAlignmentSide;
+  use crate::align::test_utils::random_seq;
   use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
   use crate::gene::gene_map::GeneMap;
   use eyre::Report;
@@ -646,4 +695,48 @@ mod tests {
     assert_eq!(from_nuc_seq(&qry_aln), from_nuc_seq(&result.qry_seq));
     Ok(())
   }
+
+  #[rstest]
+  #[case::short_partial(100, 50, 99, 50, 49)]
+  #[case::long_full(3000, 700, 3000, 2300, 2300)]
+  #[case::long_partial(3000, 2000, 1500, 1000, 500)]
+  fn aligns_circular_query_spanning_origin(
+    #[case] ref_len: usize,
+    #[case] start: usize,
+    #[case] len: usize,
+    #[case] expected_offset: usize,
+    #[case] expected_missing_begin: usize,
+  ) -> Result<(), Report> {
+    let params = AlignPairwiseParams {
+      circular: true,
+      min_length: 50,
+      min_match_length: 10,
+      ..AlignPairwiseParams::default()
+    };
+    let ref_seq = random_seq(ref_len);
+    let qry_seq = rotate_circular(&ref_seq, start)
+      .into_iter()
+      .take(len)
+      .collect::<Vec<_>>();
+    let gap_open_close = get_gap_open_close_scores_flat(&ref_seq, &params);
+
+    let result = align_nuc(
+      0,
+      "",
+      &qry_seq,
+      &ref_seq,
+      &CodonSpacedIndex::from_sequence(&ref_seq),
+      &gap_open_close,
+      &params,
+    )?;
+
+    // The part of the reference between the ends of the query is not sequenced, rather than deleted
+    let expected_missing_end = start;
+    let mut qry_aln = ref_seq.clone();
+    qry_aln[expected_missing_begin..expected_missing_end.max(expected_missing_begin)].fill(Nuc::N);
+    assert_eq!(result.circular_offset, expected_offset);
+    assert_eq!(from_nuc_seq(&result.ref_seq), from_nuc_seq(&ref_seq));
+    assert_eq!(from_nuc_seq(&result.qry_seq), from_nuc_seq(&qry_aln));
+    Ok(())
+  }
 }
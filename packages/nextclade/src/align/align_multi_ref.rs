@@ -0,0 +1,306 @@
+use crate::align::align::align_nuc;
+use crate::align::backtrace::AlignmentOutput;
+use crate::align::gap_open::get_gap_open_close_scores_flat;
+use crate::align::params::AlignPairwiseParams;
+use crate::align::seed_match::{CodonSpacedIndex, SeedMatch2, get_seed_matches_maybe_reverse_complement};
+use crate::alphabet::letter::Letter;
+use crate::alphabet::nuc::{Nuc, to_nuc_seq};
+use crate::io::fasta::FastaRecord;
+use crate::translate::complement::reverse_complement_in_place;
+use eyre::{Report, WrapErr};
+
+/// Additional (non-primary) reference sequence, pre-aligned to the primary reference
+pub struct AdditionalRef {
+  pub name: String,
+  pub seq: Vec<Nuc>,
+  pub seed_index: CodonSpacedIndex,
+  pub gap_open_close: Vec<i32>,
+
+  /// Alignment of this reference (as query) against the primary reference. Used for coordinate liftover.
+  pub to_primary: AlignmentOutput<Nuc>,
+}
+
+impl AdditionalRef {
+  pub fn new(
+    record: &FastaRecord,
+    primary_seq: &[Nuc],
+    primary_seed_index: &CodonSpacedIndex,
+    primary_gap_open_close: &[i32],
+    params: &AlignPairwiseParams,
+  ) -> Result<Self, Report> {
+    let name = record.seq_name.clone();
+
+    let mut seq =
+      to_nuc_seq(&record.seq).wrap_err_with(|| format!("When converting additional reference sequence '{name}'"))?;
+
+    let mut to_primary = align_nuc(
+      record.index,
+      &name,
+      &seq,
+      primary_seq,
+      primary_seed_index,
+      primary_gap_open_close,
+      params,
+    )
+    .wrap_err_with(|| format!("When aligning additional reference sequence '{name}' to the primary reference"))?;
+
+    // Store the additional reference in the orientation of the primary reference, such that alignments against it can
+    // be lifted over onto the primary reference
+    if to_primary.is_reverse_complement {
+      reverse_complement_in_place(&mut seq);
+      to_primary.is_reverse_complement = false;
+    }
+
+    let seed_index = CodonSpacedIndex::from_sequence(&seq);
+    let gap_open_close = get_gap_open_close_scores_flat(&seq, params);
+
+    Ok(Self {
+      name,
+      seq,
+      seed_index,
+      gap_open_close,
+      to_primary,
+    })
+  }
+}
+
+/// Result of the alignment against the best matching of the available references
+pub struct AlignmentMultiRefOutput {
+  /// Alignment in the coordinates of the primary reference
+  pub alignment: AlignmentOutput<Nuc>,
+
+  /// Index of the additional reference used for alignment, or `None` if the primary reference was used
+  pub additional_ref_index: Option<usize>,
+}
+
+/// Align a nucleotide sequence to the closest of the primary and additional references.
+///
+/// The closest reference is the one with the largest seed match coverage of the query. If the closest reference is
+/// one of the additional references, the resulting alignment is lifted over onto the primary reference, such that
+/// all downstream analysis happens in the primary reference coordinates.
+#[allow(clippy::too_many_arguments)]
+pub fn align_nuc_multi_ref(
+  index: usize,
+  seq_name: &str,
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_index: &CodonSpacedIndex,
+  gap_open_close: &[i32],
+  additional_refs: &[AdditionalRef],
+  params: &AlignPairwiseParams,
+) -> Result<AlignmentMultiRefOutput, Report> {
+  let additional_ref_index = choose_closest_ref(qry_seq, ref_seq, seed_index, additional_refs, params);
+
+  let alignment = match additional_ref_index {
+    None => align_nuc(index, seq_name, qry_seq, ref_seq, seed_index, gap_open_close, params)?,
+    Some(i) => {
+      let additional_ref = &additional_refs[i];
+      let alignment = align_nuc(
+        index,
+        seq_name,
+        qry_seq,
+        &additional_ref.seq,
+        &additional_ref.seed_index,
+        &additional_ref.gap_open_close,
+        params,
+      )
+      .wrap_err_with(|| {
+        format!(
+          "When aligning to additional reference sequence '{}'",
+          additional_ref.name
+        )
+      })?;
+      liftover_alignment(&alignment, &additional_ref.to_primary)
+    }
+  };
+
+  Ok(AlignmentMultiRefOutput {
+    alignment,
+    additional_ref_index,
+  })
+}
+
+/// Find the reference with the largest seed match coverage of the query sequence.
+///
+/// Returns index of the closest additional reference, or `None` if the primary reference is the closest (or if
+/// seed matching fails for all references).
+pub fn choose_closest_ref(
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_index: &CodonSpacedIndex,
+  additional_refs: &[AdditionalRef],
+  params: &AlignPairwiseParams,
+) -> Option<usize> {
+  if additional_refs.is_empty() {
+    return None;
+  }
+
+  let primary_coverage = seed_match_coverage(qry_seq, ref_seq, seed_index, params);
+
+  let (best_index, best_coverage) = additional_refs
+    .iter()
+    .map(|additional_ref| seed_match_coverage(qry_seq, &additional_ref.seq, &additional_ref.seed_index, params))
+    .enumerate()
+    .max_by_key(|(i, coverage)| (*coverage, std::cmp::Reverse(*i)))?;
+
+  // Prefer primary reference on ties
+  (best_coverage > primary_coverage).then_some(best_index)
+}
+
+/// Number of query nucleotides covered by seed matches. Zero if seed matching fails.
+pub fn seed_match_coverage(
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_index: &CodonSpacedIndex,
+  params: &AlignPairwiseParams,
+) -> usize {
+  get_seed_matches_maybe_reverse_complement(qry_seq, ref_seq, seed_index, params)
+    .map(|result| result.seed_matches.iter().map(|SeedMatch2 { length, .. }| length).sum())
+    .unwrap_or_default()
+}
+
+/// Convert alignment of a query against an additional reference into the alignment against the primary reference,
+/// given the alignment of the additional reference against the primary reference.
+///
+/// Both alignments share the additional reference as a pivot. The columns are merged such that:
+///  - query insertions relative to the additional reference become insertions relative to the primary reference
+///  - nucleotides present in the primary but absent in the additional reference become deletions in the query
+///  - nucleotides present in the additional but absent in the primary reference become query insertions
+pub fn liftover_alignment(
+  qry_to_additional: &AlignmentOutput<Nuc>,
+  additional_to_primary: &AlignmentOutput<Nuc>,
+) -> AlignmentOutput<Nuc> {
+  // Alignment of the query against the additional reference
+  let q1 = &qry_to_additional.qry_seq;
+  let r1 = &qry_to_additional.ref_seq;
+
+  // Alignment of the additional reference against the primary reference
+  let r2 = &additional_to_primary.qry_seq;
+  let p2 = &additional_to_primary.ref_seq;
+
+  let capacity = q1.len() + p2.len();
+  let mut qry_seq = Vec::with_capacity(capacity);
+  let mut ref_seq = Vec::with_capacity(capacity);
+
+  let mut i = 0; // column in the additional-to-primary alignment
+  let mut j = 0; // column in the query-to-additional alignment
+  while i < p2.len() || j < q1.len() {
+    let (pri, qry) = if j < q1.len() && (r1[j].is_gap() || i >= p2.len()) {
+      // Query insertion relative to the additional reference
+      j += 1;
+      (Nuc::Gap, q1[j - 1])
+    } else if j >= q1.len() || r2[i].is_gap() {
+      // Primary reference nucleotide which is not in the additional reference (or past the end of query alignment)
+      i += 1;
+      (p2[i - 1], Nuc::Gap)
+    } else {
+      // Both columns correspond to the same nucleotide of the additional reference
+      i += 1;
+      j += 1;
+      (p2[i - 1], q1[j - 1])
+    };
+
+    if !(pri.is_gap() && qry.is_gap()) {
+      ref_seq.push(pri);
+      qry_seq.push(qry);
+    }
+  }
+
+  AlignmentOutput {
+    qry_seq,
+    ref_seq,
+    alignment_score: qry_to_additional.alignment_score,
+    is_reverse_complement: qry_to_additional.is_reverse_complement,
+    hit_boundary: qry_to_additional.hit_boundary,
+    circular_offset: qry_to_additional.circular_offset,
+    soft_clipped_left: qry_to_additional.soft_clipped_left.clone(),
+    soft_clipped_right: qry_to_additional.soft_clipped_right.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::align::test_utils::random_seq;
+  use crate::alphabet::nuc::from_nuc_seq;
+  use eyre::Report;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn aln(qry: &str, reff: &str) -> Result<AlignmentOutput<Nuc>, Report> {
+    Ok(AlignmentOutput {
+      qry_seq: to_nuc_seq(qry)?,
+      ref_seq: to_nuc_seq(reff)?,
+      alignment_score: 0,
+      is_reverse_complement: false,
+      hit_boundary: false,
+      circular_offset: 0,
+      soft_clipped_left: vec![],
+      soft_clipped_right: vec![],
+    })
+  }
+
+  #[rstest]
+  fn additional_ref_in_opposite_orientation_is_lifted_over_as_forward() -> Result<(), Report> {
+    let params = AlignPairwiseParams {
+      retry_reverse_complement: true,
+      ..AlignPairwiseParams::default()
+    };
+    let primary = random_seq(300);
+    let primary_seed_index = CodonSpacedIndex::from_sequence(&primary);
+    let primary_gap_open_close = get_gap_open_close_scores_flat(&primary, &params);
+
+    let mut additional = primary.clone();
+    additional[150] = if additional[150] == Nuc::A { Nuc::C } else { Nuc::A };
+    let mut additional_rev = additional.clone();
+    reverse_complement_in_place(&mut additional_rev);
+    let record = FastaRecord {
+      seq_name: "additional".to_owned(),
+      seq: from_nuc_seq(&additional_rev),
+      index: 0,
+    };
+
+    let additional_ref = AdditionalRef::new(&record, &primary, &primary_seed_index, &primary_gap_open_close, &params)?;
+    assert_eq!(from_nuc_seq(&additional_ref.seq), from_nuc_seq(&additional));
+
+    let qry_to_additional = align_nuc(
+      1,
+      "qry",
+      &additional,
+      &additional_ref.seq,
+      &additional_ref.seed_index,
+      &additional_ref.gap_open_close,
+      &params,
+    )?;
+    let actual = liftover_alignment(&qry_to_additional, &additional_ref.to_primary);
+    assert_eq!(
+      (from_nuc_seq(&actual.qry_seq), from_nuc_seq(&actual.ref_seq)),
+      (from_nuc_seq(&additional), from_nuc_seq(&primary))
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::identical("ACGTACGT", "ACGTACGT", "ACGTACGT", "ACGTACGT", "ACGTACGT", "ACGTACGT")]
+  #[case::qry_sub("ACCTACGT", "ACGTACGT", "ACGTACGT", "ACGTACGT", "ACCTACGT", "ACGTACGT")]
+  #[case::qry_ins("ACGTTACGT", "ACGT-ACGT", "ACGTACGT", "ACGTACGT", "ACGTTACGT", "ACGT-ACGT")]
+  #[case::additional_del("ACGTACGT", "ACGTACGT", "ACGT-ACGT", "ACGTGACGT", "ACGT-ACGT", "ACGTGACGT")]
+  #[case::additional_ins("ACGTTACGT", "ACGTTACGT", "ACGTTACGT", "ACGT-ACGT", "ACGTTACGT", "ACGT-ACGT")]
+  #[case::both_del("ACGT-ACGT", "ACGTTACGT", "ACGTTACGT", "ACGT-ACGT", "ACGTACGT", "ACGTACGT")]
+  #[case::terminal("--GTAC--", "ACGTACGT", "-ACGTACGT-", "GACGTACGTG", "---GTAC---", "GACGTACGTG")]
+  fn liftover_alignment_general_case(
+    #[case] q1: &str,
+    #[case] r1: &str,
+    #[case] r2: &str,
+    #[case] p2: &str,
+    #[case] expected_qry: &str,
+    #[case] expected_ref: &str,
+  ) -> Result<(), Report> {
+    let actual = liftover_alignment(&aln(q1, r1)?, &aln(r2, p2)?);
+    assert_eq!(
+      (from_nuc_seq(&actual.qry_seq), from_nuc_seq(&actual.ref_seq)),
+      (expected_qry.to_owned(), expected_ref.to_owned())
+    );
+    Ok(())
+  }
+}
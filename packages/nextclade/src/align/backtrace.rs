@@ -10,6 +10,18 @@ pub struct AlignmentOutput<T> {
   pub alignment_score: i32,
   pub is_reverse_complement: bool,
   pub hit_boundary: bool,
+
+  /// Position in the original query sequence which was rotated to the start of the circular genome before alignment
+  #[serde(default)]
+  pub circular_offset: usize,
+
+  /// Query letters which were soft-clipped from the beginning of the alignment
+  #[serde(default)]
+  pub soft_clipped_left: Vec<T>,
+
+  /// Query letters which were soft-clipped from the end of the alignment
+  #[serde(default)]
+  pub soft_clipped_right: Vec<T>,
 }
 
 pub fn backtrace<T: Letter<T>>(
@@ -92,6 +104,9 @@ pub fn backtrace<T: Letter<T>>(
     alignment_score: scores[(num_rows - 1, num_cols - 1)],
     is_reverse_complement: false,
     hit_boundary,
+    circular_offset: 0,
+    soft_clipped_left: vec![],
+    soft_clipped_right: vec![],
   }
 }
 
@@ -138,6 +153,9 @@ mod tests {
       alignment_score: 18,
       is_reverse_complement: false,
       hit_boundary: false,
+      circular_offset: 0,
+      soft_clipped_left: vec![],
+      soft_clipped_right: vec![],
     };
 
     let output = backtrace(&qry_seq, &ref_seq, &scores, &paths);
@@ -0,0 +1,195 @@
+use crate::align::backtrace::AlignmentOutput;
+use crate::align::params::AlignPairwiseParams;
+use crate::align::seed_match::{CodonSpacedIndex, SeedMatch2};
+use crate::alphabet::letter::Letter;
+use crate::alphabet::nuc::Nuc;
+use eyre::Report;
+use log::trace;
+use std::collections::BTreeMap;
+
+/// Align a query sequence to a circular reference.
+///
+/// If the genome is circular and the query wraps around the origin of the reference, the query is rotated such that it
+/// starts at the origin, the rotated query is aligned with `align` and the rotation is recorded in the alignment.
+/// Otherwise the query is aligned as is. Besides the query to align, `align` receives whether the query was rotated,
+/// such that seed matches found for the original query can be recalculated.
+pub fn align_circular<F>(
+  index: usize,
+  seq_name: &str,
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_index: &CodonSpacedIndex,
+  params: &AlignPairwiseParams,
+  align: F,
+) -> Result<AlignmentOutput<Nuc>, Report>
+where
+  F: FnOnce(&[Nuc], bool) -> Result<AlignmentOutput<Nuc>, Report>,
+{
+  let circular_offset = params
+    .circular
+    .then(|| find_circular_offset(qry_seq, ref_seq, seed_index, params))
+    .flatten();
+
+  let Some(offset) = circular_offset else {
+    return align(qry_seq, false);
+  };
+
+  trace!(
+    "When processing sequence #{index} '{seq_name}': In nucleotide alignment: Circular genome: rotating query sequence by {offset} nucleotides"
+  );
+  let rotated = rotate_circular(qry_seq, offset);
+  let mut alignment = align(&rotated, true)?;
+  alignment.circular_offset = offset;
+  mark_circular_junction_missing(&mut alignment, qry_seq.len() - offset);
+  Ok(alignment)
+}
+
+/// Find position in the query sequence which corresponds to the origin (first nucleotide) of a circular reference.
+///
+/// If the query starts at a different position of the circular genome than the reference, then seed matches fall onto
+/// two diagonals of the alignment matrix, which are `ref_len` apart: the part of the query before the origin matches
+/// the end of the reference, and the part of the query after the origin matches the beginning of the reference.
+///
+/// Returns `None` if no such pair of diagonals is found, i.e. if the query does not wrap around the origin.
+pub fn find_circular_offset(
+  qry_seq: &[Nuc],
+  ref_seq: &[Nuc],
+  seed_index: &CodonSpacedIndex,
+  params: &AlignPairwiseParams,
+) -> Option<usize> {
+  let qry_len = qry_seq.len() as isize;
+  let ref_len = ref_seq.len() as isize;
+
+  // Diagonals of matches separated by indels are not exactly the same
+  let tolerance = params.terminal_bandwidth.max(1) as isize;
+
+  let matches = seed_index.extended_matches(qry_seq, ref_seq, params);
+  let diagonal = |seed: &SeedMatch2| seed.qry_pos as isize - seed.ref_pos as isize;
+
+  // Total length of seed matches on each of the diagonals
+  let mut diagonals = BTreeMap::<isize, usize>::new();
+  for seed in &matches {
+    *diagonals.entry(diagonal(seed)).or_default() += seed.length;
+  }
+
+  let (&main, _) = diagonals.iter().max_by_key(|(_, length)| **length)?;
+  let wrapped = if main > 0 { main - ref_len } else { main + ref_len };
+
+  let wrapped_length: usize = diagonals
+    .range((wrapped - tolerance)..=(wrapped + tolerance))
+    .map(|(_, length)| length)
+    .sum();
+  if wrapped_length < params.min_match_length {
+    return None;
+  }
+
+  // Diagonal of the part of the query after the origin. The match closest to the origin of the reference gives the
+  // most precise estimate of the origin position in the query.
+  let after_origin = main.max(wrapped);
+  let offset = matches
+    .iter()
+    .filter(|seed| (diagonal(seed) - after_origin).abs() <= tolerance)
+    .min_by_key(|seed| seed.ref_pos)
+    .map_or(after_origin, diagonal);
+
+  (offset > 0 && offset < qry_len).then_some(offset as usize)
+}
+
+/// Rotate sequence such that the nucleotide at `offset` becomes the first one
+pub fn rotate_circular(seq: &[Nuc], offset: usize) -> Vec<Nuc> {
+  let mut rotated = seq.to_vec();
+  rotated.rotate_left(offset % seq.len().max(1));
+  rotated
+}
+
+/// Mark the part of the reference which is not covered by a rotated partial query as missing.
+///
+/// If a partial query of a circular genome spans the origin, then after rotation its two ends meet at `junction`
+/// (position in the rotated query). The reference letters between the ends are not sequenced, but they are aligned to
+/// gaps, which would be reported as a deletion. These gaps are replaced with unknown letters instead.
+pub fn mark_circular_junction_missing(alignment: &mut AlignmentOutput<Nuc>, junction: usize) {
+  if junction == 0 {
+    return;
+  }
+
+  let mut n_letters = 0;
+  for qry in &mut alignment.qry_seq {
+    if qry.is_gap() {
+      if n_letters == junction {
+        *qry = Nuc::N;
+      }
+    } else {
+      n_letters += 1;
+      if n_letters > junction {
+        break;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::align::test_utils::random_seq;
+  use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
+  use eyre::Report;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::not_rotated(0, 3000, None)]
+  #[case::rotated(700, 3000, Some(2300))]
+  #[case::rotated_near_end(2800, 3000, Some(200))]
+  #[case::partial_not_wrapped(500, 2000, None)]
+  #[case::partial_wrapped(2000, 1500, Some(1000))]
+  fn finds_circular_offset(
+    #[case] start: usize,
+    #[case] len: usize,
+    #[case] expected: Option<usize>,
+  ) -> Result<(), Report> {
+    let ref_seq = random_seq(3000);
+    let qry_seq = rotate_circular(&ref_seq, start)
+      .into_iter()
+      .take(len)
+      .collect::<Vec<_>>();
+    let seed_index = CodonSpacedIndex::from_sequence(&ref_seq);
+    let actual = find_circular_offset(&qry_seq, &ref_seq, &seed_index, &AlignPairwiseParams::default());
+    assert_eq!(actual, expected);
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::junction_gap("ACGT---ACGT", 4, "ACGTNNNACGT")]
+  #[case::no_gap_at_junction("ACG-TACGT", 4, "ACG-TACGT")]
+  #[case::terminal_gaps_kept("--ACGT---ACGT--", 4, "--ACGTNNNACGT--")]
+  #[case::no_junction("ACGT---ACGT", 0, "ACGT---ACGT")]
+  fn marks_circular_junction_missing(
+    #[case] qry_aln: &str,
+    #[case] junction: usize,
+    #[case] expected: &str,
+  ) -> Result<(), Report> {
+    let mut alignment = AlignmentOutput {
+      qry_seq: to_nuc_seq(qry_aln)?,
+      ref_seq: vec![Nuc::A; qry_aln.len()],
+      alignment_score: 0,
+      is_reverse_complement: false,
+      hit_boundary: false,
+      circular_offset: 0,
+      soft_clipped_left: vec![],
+      soft_clipped_right: vec![],
+    };
+    mark_circular_junction_missing(&mut alignment, junction);
+    assert_eq!(from_nuc_seq(&alignment.qry_seq), expected);
+    Ok(())
+  }
+
+  #[rstest]
+  fn rotates_back_to_reference() -> Result<(), Report> {
+    let ref_seq = random_seq(3000);
+    let qry_seq = rotate_circular(&ref_seq, 700);
+    let seed_index = CodonSpacedIndex::from_sequence(&ref_seq);
+    let offset = find_circular_offset(&qry_seq, &ref_seq, &seed_index, &AlignPairwiseParams::default());
+    assert_eq!(offset.map(|offset| rotate_circular(&qry_seq, offset)), Some(ref_seq));
+    Ok(())
+  }
+}
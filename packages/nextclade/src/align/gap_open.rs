@@ -55,6 +55,7 @@ mod tests {
   use crate::gene::gene::GeneStrand::{Forward, Reverse};
   use crate::gene::gene::{Gene, GeneStrand};
   use crate::gene::phase::Phase;
+  use crate::translate::genetic_code::GeneticCode;
   use eyre::Report;
   use indexmap::indexmap;
   use itertools::Itertools;
@@ -107,6 +108,8 @@ mod tests {
           proteins: vec![],
           exceptions: vec![],
           attributes: indexmap! {},
+          genetic_code: GeneticCode::default(),
+          rna_editing: vec![],
           compat_is_gene: false,
           color: None,
         })
@@ -1,6 +1,8 @@
 pub mod align;
+pub mod align_multi_ref;
 pub mod backtrace;
 pub mod band_2d;
+pub mod circular;
 pub mod gap_open;
 pub mod insertions_strip;
 pub mod params;
@@ -10,3 +12,7 @@ pub mod score_matrix_aa;
 pub mod score_matrix_nuc;
 pub mod seed_alignment;
 pub mod seed_match;
+pub mod soft_clip;
+
+#[cfg(test)]
+pub mod test_utils;
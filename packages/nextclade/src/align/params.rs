@@ -92,6 +92,20 @@ pub struct AlignPairwiseParams {
   #[clap(num_args=0..=1, default_missing_value = "true")]
   pub retry_reverse_complement: bool,
 
+  /// Treat the genome as circular. If a query sequence starts at a different position of the circular genome than the reference sequence, it is rotated to start at the origin of the reference before alignment. The applied rotation offset is reported in the `circularOffset` output column. Note that the aligned sequence and the query coordinates in the outputs refer to the rotated query sequence.
+  #[clap(long)]
+  #[clap(num_args=0..=1, default_missing_value = "true")]
+  pub circular: bool,
+
+  /// Local alignment mode: soft-clip non-homologous ends of query sequences, e.g. adapters, vector or host contamination. The clipped fragments are reported in the `softClips.left` and `softClips.right` output columns instead of as insertions, and the reference positions they were aligned to are counted as missing rather than mutated. See `--min-soft-clip-score`.
+  #[clap(long)]
+  #[clap(num_args=0..=1, default_missing_value = "true")]
+  pub soft_clip: bool,
+
+  /// In local alignment mode (`--soft-clip`), minimum clipping score of a query end for it to be soft-clipped. Starting from the end, matches score +1, mismatches -2, and insertions and deletions -2 for the first and -1 for each following position. The end is clipped up to the lowest point of the cumulative score, if it is at or below minus this value.
+  #[clap(long)]
+  pub min_soft_clip_score: usize,
+
   /// If this flag is present, the amino acid sequences will be truncated at the first stop codon, if mutations or sequencing errors cause premature stop codons to be present. No amino acid mutations in the truncated region will be recorded.
   #[clap(long)]
   #[clap(num_args=0..=1, default_missing_value = "true")]
@@ -187,6 +201,9 @@ impl Default for AlignPairwiseParams {
       score_match: 3,
       max_band_area: 500_000_000, // requires around 500Mb for paths, 2GB for the scores
       retry_reverse_complement: false,
+      circular: false,
+      soft_clip: false,
+      min_soft_clip_score: 10,
       no_translate_past_stop: false,
       left_terminal_gaps_free: true,
       right_terminal_gaps_free: true,
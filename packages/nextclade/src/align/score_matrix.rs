@@ -202,11 +202,67 @@ pub fn score_matrix<T: Letter<T>>(
   ScoreMatrixResult { scores, paths }
 }
 
+/// Compute score of a given pairwise alignment, using the same scoring scheme as the alignment algorithm
+pub fn score_alignment<T: Letter<T>>(
+  qry_seq: &[T],
+  ref_seq: &[T],
+  gap_open_close: &[i32],
+  params: &AlignPairwiseParams,
+) -> i32 {
+  let qry_len = qry_seq.iter().filter(|c| !c.is_gap()).count();
+  let ref_len = ref_seq.iter().filter(|c| !c.is_gap()).count();
+
+  let mut score = 0;
+  let mut qry_pos = 0;
+  let mut ref_pos = 0;
+  let mut prev_qry_gap = false;
+  let mut prev_ref_gap = false;
+  for (&qry, &reff) in qry_seq.iter().zip(ref_seq) {
+    if qry.is_gap() {
+      let is_free =
+        (qry_pos == 0 && params.left_terminal_gaps_free) || (qry_pos == qry_len && params.right_terminal_gaps_free);
+      if !is_free {
+        score -= if prev_qry_gap {
+          params.penalty_gap_extend
+        } else {
+          gap_open_close[ref_pos]
+        };
+      }
+      ref_pos += 1;
+    } else if reff.is_gap() {
+      let is_free =
+        (ref_pos == 0 && params.left_terminal_gaps_free) || (ref_pos == ref_len && params.right_terminal_gaps_free);
+      if !is_free {
+        score -= if prev_ref_gap {
+          params.penalty_gap_extend
+        } else {
+          gap_open_close[ref_pos]
+        };
+      }
+      qry_pos += 1;
+    } else {
+      score += if qry.is_unknown() || reff.is_unknown() {
+        params.score_match - 1
+      } else if T::lookup_match_score(qry, reff) > 0 {
+        params.score_match
+      } else {
+        -params.penalty_mismatch
+      };
+      qry_pos += 1;
+      ref_pos += 1;
+    }
+    prev_qry_gap = qry.is_gap();
+    prev_ref_gap = reff.is_gap();
+  }
+  score
+}
+
 #[cfg(test)]
 mod tests {
   #![allow(clippy::needless_pass_by_value)] // rstest fixtures are passed by value
   use super::*;
-  use crate::align::band_2d::simple_stripes;
+  use crate::align::backtrace::backtrace;
+  use crate::align::band_2d::{full_matrix, simple_stripes};
   use crate::align::gap_open::{GapScoreMap, get_gap_open_close_scores_codon_aware};
 
   use crate::alphabet::nuc::{Nuc, to_nuc_seq};
@@ -346,4 +402,29 @@ mod tests {
 
     Ok(())
   }
+
+  #[rstest]
+  #[case::identical("ACGTACGTACGT", "ACGTACGTACGT")]
+  #[case::mismatches("ACGTACCTACGA", "ACGTACGTACGT")]
+  #[case::terminal_gaps("GTACGTAC", "ACGTACGTACGT")]
+  #[case::deletion("ACGTACGTTTTGGGACGTA", "ACGTACGTTTTCCCGGGACGTA")]
+  #[case::insertion("ACGTACGTTTTCCCGGGACGTA", "ACGTACGTTTTGGGACGTA")]
+  #[case::unknown("ACGTNNNTACGT", "ACGTACGTACGT")]
+  fn scores_alignment_same_as_alignment_algorithm(
+    ctx: Context,
+    #[case] qry: &str,
+    #[case] reff: &str,
+  ) -> Result<(), Report> {
+    let qry_seq = to_nuc_seq(qry)?;
+    let ref_seq = to_nuc_seq(reff)?;
+    let stripes = full_matrix(ref_seq.len(), qry_seq.len());
+    let ScoreMatrixResult { scores, paths } =
+      score_matrix(&qry_seq, &ref_seq, &ctx.gap_open_close, &stripes, &ctx.params);
+    let alignment = backtrace(&qry_seq, &ref_seq, &scores, &paths);
+
+    let score = score_alignment(&alignment.qry_seq, &alignment.ref_seq, &ctx.gap_open_close, &ctx.params);
+
+    assert_eq!(score, alignment.alignment_score);
+    Ok(())
+  }
 }
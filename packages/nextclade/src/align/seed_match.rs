@@ -273,7 +273,7 @@ impl CodonSpacedIndex {
   }
 
   /// Returns extended matches for given query sequence in natural coordinates
-  fn extended_matches(&self, qry_seq: &[Nuc], ref_seq: &[Nuc], config: &AlignPairwiseParams) -> Vec<SeedMatch2> {
+  pub fn extended_matches(&self, qry_seq: &[Nuc], ref_seq: &[Nuc], config: &AlignPairwiseParams) -> Vec<SeedMatch2> {
     let index_matches = self.index_matches(qry_seq, config);
 
     // matches is dict for Offset -> IntervalSet
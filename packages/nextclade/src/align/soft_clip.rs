@@ -0,0 +1,261 @@
+use crate::align::backtrace::AlignmentOutput;
+use crate::align::params::AlignPairwiseParams;
+use crate::align::score_matrix::score_alignment;
+use crate::alphabet::letter::{Letter, serde_deserialize_seq, serde_serialize_seq};
+use crate::alphabet::nuc::Nuc;
+use serde::{Deserialize, Serialize};
+
+/// Query sequence fragments which were soft-clipped from the ends of the alignment
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct NucSoftClips {
+  /// Clipped fragment at the beginning (5' end) of the query sequence
+  #[schemars(with = "String")]
+  #[serde(serialize_with = "serde_serialize_seq")]
+  #[serde(deserialize_with = "serde_deserialize_seq")]
+  pub left: Vec<Nuc>,
+
+  /// Clipped fragment at the end (3' end) of the query sequence
+  #[schemars(with = "String")]
+  #[serde(serialize_with = "serde_serialize_seq")]
+  #[serde(deserialize_with = "serde_deserialize_seq")]
+  pub right: Vec<Nuc>,
+}
+
+impl NucSoftClips {
+  pub fn from_alignment(alignment: &AlignmentOutput<Nuc>) -> Self {
+    Self {
+      left: alignment.soft_clipped_left.clone(),
+      right: alignment.soft_clipped_right.clone(),
+    }
+  }
+}
+
+/// Soft-clip non-homologous ends of the query sequence from an alignment.
+///
+/// Aligned columns are scored +1 for matching and -2 for mismatching letters, and insertions and deletions score -2
+/// for the first and -1 for each of the following columns (unknown query letters score 0). The terminal region which
+/// ends at the minimum of the cumulative score is considered non-homologous, if the score drops to
+/// `-params.min_soft_clip_score` or below. In the clipped region, the query letters aligned to the reference are
+/// replaced with unknown letters, such that they count as missing rather than mutated, and insertions are removed. The
+/// clipped query letters are reported separately. The alignment score is recomputed for the remaining, homologous part
+/// of the alignment.
+pub fn soft_clip<T: Letter<T>>(
+  alignment: &mut AlignmentOutput<T>,
+  gap_open_close: &[i32],
+  params: &AlignPairwiseParams,
+) {
+  let min_score = params.min_soft_clip_score;
+  let n_cols = alignment.qry_seq.len();
+
+  let left = find_clip(
+    (0..n_cols).map(|i| (alignment.qry_seq[i], alignment.ref_seq[i])),
+    min_score,
+  );
+  let right = find_clip(
+    (left..n_cols)
+      .rev()
+      .map(|i| (alignment.qry_seq[i], alignment.ref_seq[i])),
+    min_score,
+  );
+
+  if left == 0 && right == 0 {
+    return;
+  }
+
+  // Terminal gaps of the query are outside of its sequenced part and stay gaps
+  let qry_begin = alignment.qry_seq.iter().position(|c| !c.is_gap()).unwrap_or_default();
+  let qry_end = alignment.qry_seq.iter().rposition(|c| !c.is_gap()).unwrap_or_default();
+
+  let mut qry_seq = Vec::with_capacity(n_cols);
+  let mut ref_seq = Vec::with_capacity(n_cols);
+  for i in 0..n_cols {
+    let (qry, reff) = (alignment.qry_seq[i], alignment.ref_seq[i]);
+    let is_clipped = i < left || i >= n_cols - right;
+    if !is_clipped {
+      qry_seq.push(qry);
+      ref_seq.push(reff);
+      continue;
+    }
+
+    if !qry.is_gap() {
+      if i < left {
+        alignment.soft_clipped_left.push(qry);
+      } else {
+        alignment.soft_clipped_right.push(qry);
+      }
+    }
+
+    // Insertions in the clipped region are dropped, the rest of the region is marked as missing
+    if !reff.is_gap() {
+      ref_seq.push(reff);
+      qry_seq.push(if i < qry_begin || i > qry_end {
+        T::GAP
+      } else {
+        T::UNKNOWN
+      });
+    }
+  }
+
+  // Columns which remained after clipping the ends. Of the clipped columns, only the ones of the reference remain.
+  let n_clipped_left = alignment.ref_seq[..left].iter().filter(|c| !c.is_gap()).count();
+  let kept = n_clipped_left..(n_clipped_left + n_cols - left - right);
+  alignment.alignment_score = score_alignment(
+    &qry_seq[kept.clone()],
+    &ref_seq[kept],
+    &gap_open_close[n_clipped_left..],
+    params,
+  );
+
+  alignment.qry_seq = qry_seq;
+  alignment.ref_seq = ref_seq;
+}
+
+/// Find number of terminal alignment columns to clip, given the columns ordered starting from the end being clipped
+fn find_clip<T: Letter<T>>(columns: impl Iterator<Item = (T, T)>, min_score: usize) -> usize {
+  let mut score = 0_isize;
+  let mut min = 0_isize;
+  let mut clip = 0;
+  let mut started = false;
+  let mut prev_gap = false;
+
+  for (i, (qry, reff)) in columns.enumerate() {
+    // Skip terminal gaps in the query, these are not sequenced rather than clipped
+    if !started && qry.is_gap() {
+      continue;
+    }
+    started = true;
+
+    let is_gap = qry.is_gap() || reff.is_gap();
+    score += if is_gap {
+      // Affine penalty for indels
+      if prev_gap { -1 } else { -2 }
+    } else if qry.is_unknown() {
+      0
+    } else if T::lookup_match_score(qry, reff) > 0 {
+      1
+    } else {
+      -2
+    };
+    prev_gap = is_gap;
+
+    if score < min {
+      min = score;
+      clip = i + 1;
+    }
+  }
+
+  if min <= -(min_score as isize) { clip } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::align::gap_open::get_gap_open_close_scores_flat;
+  use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
+  use eyre::Report;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn aln(qry: &str, reff: &str) -> Result<AlignmentOutput<Nuc>, Report> {
+    Ok(AlignmentOutput {
+      qry_seq: to_nuc_seq(qry)?,
+      ref_seq: to_nuc_seq(reff)?,
+      alignment_score: 0,
+      is_reverse_complement: false,
+      hit_boundary: false,
+      circular_offset: 0,
+      soft_clipped_left: vec![],
+      soft_clipped_right: vec![],
+    })
+  }
+
+  #[rstest]
+  #[case::no_clip(
+    "ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "",
+    "",
+    0
+  )]
+  #[case::terminal_mismatch_kept(
+    "TCGTACGTACGTACGA",
+    "ACGTACGTACGTACGT",
+    "TCGTACGTACGTACGA",
+    "ACGTACGTACGTACGT",
+    "",
+    "",
+    0
+  )]
+  #[case::left_mismatches(
+    "TTTTTTTGTACGTACGT",
+    "ACGCACCGTACGTACGT",
+    "NNNNNNNGTACGTACGT",
+    "ACGCACCGTACGTACGT",
+    "TTTTTTT",
+    "",
+    30
+  )]
+  #[case::left_insertion(
+    "GGGGGGACGTACGTACGTACGT",
+    "------ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "GGGGGG",
+    "",
+    48
+  )]
+  #[case::right_mismatches_and_insertion(
+    "---ACGTACGTACGTACTTTTTTTT",
+    "ACGACGTACGTACGTACGTCGA---",
+    "---ACGTACGTACGTACNNNNN",
+    "ACGACGTACGTACGTACGTCGA",
+    "",
+    "TTTTTTTT",
+    42
+  )]
+  #[case::both_ends(
+    "CCCCCCACGTACGTACGTACGTGGGGGG",
+    "------ACGTACGTACGTACGT------",
+    "ACGTACGTACGTACGT",
+    "ACGTACGTACGTACGT",
+    "CCCCCC",
+    "GGGGGG",
+    48
+  )]
+  fn soft_clips_non_homologous_ends(
+    #[case] qry: &str,
+    #[case] reff: &str,
+    #[case] expected_qry: &str,
+    #[case] expected_ref: &str,
+    #[case] expected_left: &str,
+    #[case] expected_right: &str,
+    #[case] expected_score: i32,
+  ) -> Result<(), Report> {
+    let params = AlignPairwiseParams {
+      min_soft_clip_score: 5,
+      ..AlignPairwiseParams::default()
+    };
+    let mut alignment = aln(qry, reff)?;
+    let gap_open_close = get_gap_open_close_scores_flat(&alignment.ref_seq, &params);
+    soft_clip(&mut alignment, &gap_open_close, &params);
+    assert_eq!(
+      (
+        from_nuc_seq(&alignment.qry_seq),
+        from_nuc_seq(&alignment.ref_seq),
+        from_nuc_seq(&alignment.soft_clipped_left),
+        from_nuc_seq(&alignment.soft_clipped_right),
+        alignment.alignment_score,
+      ),
+      (
+        expected_qry.to_owned(),
+        expected_ref.to_owned(),
+        expected_left.to_owned(),
+        expected_right.to_owned(),
+        expected_score,
+      )
+    );
+    Ok(())
+  }
+}
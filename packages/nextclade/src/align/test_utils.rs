@@ -0,0 +1,14 @@
+use crate::alphabet::nuc::Nuc;
+
+/// Deterministic pseudo-random nucleotide sequence (linear congruential generator)
+pub fn random_seq(len: usize) -> Vec<Nuc> {
+  let mut state: u64 = 42;
+  std::iter::repeat_with(|| {
+    state = state
+      .wrapping_mul(6_364_136_223_846_793_005)
+      .wrapping_add(1_442_695_040_888_963_407);
+    [Nuc::A, Nuc::C, Nuc::G, Nuc::T][(state >> 33) as usize % 4]
+  })
+  .take(len)
+  .collect()
+}
@@ -0,0 +1,209 @@
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::amplicon_scheme::{Amplicon, AmpliconScheme};
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::coord::range::NucRefGlobalRange;
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Coverage of one amplicon of the amplicon scheme in a query sequence
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmpliconCoverage {
+  /// Amplicon name
+  pub name: String,
+  /// Name of the primer pool of the amplicon
+  pub pool: String,
+  /// Region of the reference between the primers of the amplicon
+  pub insert: NucRefGlobalRange,
+  /// Fraction of the insert which is aligned and not missing (N)
+  pub coverage: f64,
+  /// Fraction of the unique (not overlapping with other amplicons) part of the insert which is aligned and not missing
+  pub unique_coverage: f64,
+  /// Whether the amplicon is considered dropped out: coverage of its unique part is below the threshold
+  pub is_dropout: bool,
+}
+
+/// Change in a primer binding site of the amplicon scheme due to mutations
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmpliconPrimerChange {
+  /// Primer name
+  pub primer: String,
+  /// Amplicon name
+  pub amplicon: String,
+  /// Primer binding site in the reference sequence
+  pub range: NucRefGlobalRange,
+  /// Substitutions in the primer binding site
+  pub substitutions: Vec<NucSub>,
+}
+
+/// Per-amplicon coverage and primer binding site mutations of a query sequence
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmpliconsResult {
+  /// Coverage of each of the amplicons, ordered by position
+  pub amplicons: Vec<AmpliconCoverage>,
+  /// Names of amplicons considered dropped out
+  pub dropouts: Vec<String>,
+  /// Number of amplicons considered dropped out
+  pub total_dropouts: usize,
+  /// Primers with substitutions in their binding sites, grouped by primer pool
+  pub primer_changes: BTreeMap<String, Vec<AmpliconPrimerChange>>,
+  /// Total number of substitutions in primer binding sites
+  pub total_primer_changes: usize,
+}
+
+/// Calculate coverage of amplicons of the amplicon scheme and find mutations in primer binding sites.
+///
+/// A position is considered covered if it is inside of the alignment range and the query does not have `N` there.
+/// Deletions are considered covered. An amplicon is considered dropped out if the coverage of the part of its insert,
+/// which does not overlap with other amplicons, is below `min_coverage`.
+pub fn find_amplicon_dropouts(
+  scheme: &AmpliconScheme,
+  qry_seq_stripped: &[Nuc],
+  alignment_range: &NucRefGlobalRange,
+  substitutions: &[NucSub],
+  min_coverage: f64,
+) -> AmpliconsResult {
+  let is_covered = |pos: NucRefGlobalPosition| {
+    alignment_range.contains(pos) && qry_seq_stripped.get(pos.as_usize()).is_some_and(|nuc| *nuc != Nuc::N)
+  };
+
+  let amplicons = scheme
+    .amplicons
+    .iter()
+    .map(|amplicon| {
+      let coverage = calculate_coverage(std::slice::from_ref(&amplicon.insert), is_covered);
+      let unique_coverage = if amplicon.unique_ranges.is_empty() {
+        coverage
+      } else {
+        calculate_coverage(&amplicon.unique_ranges, is_covered)
+      };
+      AmpliconCoverage {
+        name: amplicon.name.clone(),
+        pool: amplicon.pool.clone(),
+        insert: amplicon.insert.clone(),
+        coverage,
+        unique_coverage,
+        is_dropout: unique_coverage < min_coverage,
+      }
+    })
+    .collect_vec();
+
+  let dropouts = amplicons
+    .iter()
+    .filter(|amplicon| amplicon.is_dropout)
+    .map(|amplicon| amplicon.name.clone())
+    .collect_vec();
+  let total_dropouts = dropouts.len();
+
+  let primer_changes = scheme
+    .amplicons
+    .iter()
+    .flat_map(|amplicon| find_primer_changes(amplicon, substitutions))
+    .into_group_map()
+    .into_iter()
+    .collect::<BTreeMap<_, _>>();
+  let total_primer_changes = primer_changes
+    .values()
+    .flatten()
+    .map(|change| change.substitutions.len())
+    .sum();
+
+  AmpliconsResult {
+    amplicons,
+    dropouts,
+    total_dropouts,
+    primer_changes,
+    total_primer_changes,
+  }
+}
+
+fn calculate_coverage(ranges: &[NucRefGlobalRange], is_covered: impl Fn(NucRefGlobalPosition) -> bool) -> f64 {
+  let total: usize = ranges.iter().map(NucRefGlobalRange::len).sum();
+  if total == 0 {
+    return 1.0;
+  }
+  let covered = ranges
+    .iter()
+    .flat_map(NucRefGlobalRange::iter)
+    .filter(|pos| is_covered(*pos))
+    .count();
+  covered as f64 / total as f64
+}
+
+fn find_primer_changes(amplicon: &Amplicon, substitutions: &[NucSub]) -> Vec<(String, AmpliconPrimerChange)> {
+  amplicon
+    .primers
+    .iter()
+    .filter_map(|primer| {
+      let substitutions = substitutions
+        .iter()
+        .filter(|sub| primer.range.contains(sub.pos))
+        .cloned()
+        .collect_vec();
+      (!substitutions.is_empty()).then(|| {
+        let change = AmpliconPrimerChange {
+          primer: primer.name.clone(),
+          amplicon: amplicon.name.clone(),
+          range: primer.range.clone(),
+          substitutions,
+        };
+        (amplicon.pool.clone(), change)
+      })
+    })
+    .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use eyre::Report;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  fn finds_dropouts_and_primer_changes() -> Result<(), Report> {
+    let bed = [
+      "ref\t0\t2\tA_1_LEFT\t1\t+",
+      "ref\t10\t12\tA_1_RIGHT\t1\t-",
+      "ref\t8\t10\tA_2_LEFT\t2\t+",
+      "ref\t18\t20\tA_2_RIGHT\t2\t-",
+    ]
+    .join("\n");
+    let scheme = AmpliconScheme::from_bed_str(&bed, 20)?;
+
+    //                            01234567890123456789
+    let qry_seq = to_nuc_seq("AAAAAAAAAANNNNNNNNCA")?;
+    let alignment_range = NucRefGlobalRange::from_usize(0, 20);
+    let substitutions = vec![NucSub {
+      pos: NucRefGlobalPosition::new(18),
+      ref_nuc: Nuc::A,
+      qry_nuc: Nuc::C,
+    }];
+
+    let result = find_amplicon_dropouts(&scheme, &qry_seq, &alignment_range, &substitutions, 0.5);
+
+    let coverage = result
+      .amplicons
+      .iter()
+      .map(|a| (a.name.as_str(), a.coverage, a.is_dropout))
+      .collect_vec();
+    assert_eq!(coverage, vec![("A_1", 1.0, false), ("A_2", 0.0, true)]);
+    assert_eq!(result.dropouts, vec!["A_2".to_owned()]);
+    assert_eq!(
+      result
+        .primer_changes
+        .iter()
+        .map(|(pool, changes)| (pool.as_str(), changes[0].primer.as_str()))
+        .collect_vec(),
+      vec![("2", "A_2_RIGHT")]
+    );
+    assert_eq!(result.total_primer_changes, 1);
+    Ok(())
+  }
+}
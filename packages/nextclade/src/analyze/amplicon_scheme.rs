@@ -0,0 +1,280 @@
+use crate::coord::range::{NucRefGlobalRange, Range};
+use crate::io::fs::read_file_to_string;
+use crate::make_error;
+use eyre::{Report, WrapErr, eyre};
+use itertools::Itertools;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Side of the amplicon the primer binds to
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AmpliconPrimerSide {
+  /// Forward primer, at the 5' end of the amplicon
+  Left,
+  /// Reverse primer, at the 3' end of the amplicon
+  Right,
+}
+
+/// Primer of a tiled amplicon scheme, as listed in a primer BED file (e.g. ARTIC `primer.bed`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmpliconPrimer {
+  /// Primer name (e.g. "SARS-CoV-2_1_LEFT" or "SARS-CoV-2_1_LEFT_alt1")
+  pub name: String,
+  /// Side of the amplicon the primer binds to
+  pub side: AmpliconPrimerSide,
+  /// Primer binding site in the reference sequence
+  pub range: NucRefGlobalRange,
+}
+
+/// Amplicon of a tiled amplicon scheme, delimited by its left and right primers
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Amplicon {
+  /// Amplicon name: primer name without the side and alternative suffixes (e.g. "SARS-CoV-2_1")
+  pub name: String,
+  /// Name of the primer pool the amplicon is amplified in (e.g. "1")
+  pub pool: String,
+  /// Region of the reference covered by the amplicon, including primers
+  pub range: NucRefGlobalRange,
+  /// Region of the reference between the primers of the amplicon
+  pub insert: NucRefGlobalRange,
+  /// Parts of the insert not overlapping with inserts of other amplicons. A dropout of the amplicon is detected in these
+  /// regions, because overlapping parts can be covered by the neighbouring amplicons.
+  pub unique_ranges: Vec<NucRefGlobalRange>,
+  /// Primers of the amplicon (including alternative primers)
+  pub primers: Vec<AmpliconPrimer>,
+}
+
+/// Tiled amplicon scheme (e.g. ARTIC), read from a primer BED file
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AmpliconScheme {
+  pub amplicons: Vec<Amplicon>,
+}
+
+impl AmpliconScheme {
+  /// Parse primer BED file (ARTIC format).
+  ///
+  /// Columns are: chromosome, 0-based start, end (exclusive), primer name, pool, and optionally strand and primer
+  /// sequence. Primer names are expected to end with `_LEFT` or `_RIGHT`, optionally followed by an alternative primer
+  /// suffix (e.g. `_alt1` or `_1`). Chromosome names are not checked: primer positions are relative to the reference.
+  pub fn from_bed_str(s: &str, ref_seq_len: usize) -> Result<Self, Report> {
+    let mut primers = BTreeMap::<(String, String), Vec<AmpliconPrimer>>::new();
+
+    for (line_index, line) in s.lines().enumerate() {
+      let line = line.trim_end_matches('\r');
+      if line.trim().is_empty() || line.starts_with('#') || line.starts_with("track") || line.starts_with("browser") {
+        continue;
+      }
+
+      let (amplicon, pool, primer) = parse_bed_line(line, ref_seq_len)
+        .wrap_err_with(|| eyre!("When parsing primer BED line {}: '{line}'", line_index + 1))?;
+      primers.entry((amplicon, pool)).or_default().push(primer);
+    }
+
+    let amplicons = primers
+      .into_iter()
+      .map(|((name, pool), primers)| make_amplicon(name, pool, primers))
+      .collect::<Result<Vec<_>, Report>>()?;
+
+    let mut amplicons = amplicons
+      .into_iter()
+      .sorted_by_key(|amplicon| (amplicon.range.begin, amplicon.range.end))
+      .collect_vec();
+
+    let inserts = amplicons.iter().map(|amplicon| amplicon.insert.clone()).collect_vec();
+    for (i, amplicon) in amplicons.iter_mut().enumerate() {
+      let others = inserts
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != i)
+        .map(|(_, insert)| insert.clone())
+        .collect_vec();
+      amplicon.unique_ranges = subtract_ranges(&amplicon.insert, &others);
+    }
+
+    Ok(Self { amplicons })
+  }
+
+  pub fn from_path(filepath: impl AsRef<Path>, ref_seq_len: usize) -> Result<Self, Report> {
+    let filepath = filepath.as_ref();
+
+    let data = read_file_to_string(filepath)
+      .wrap_err_with(|| format!("When reading amplicon scheme file {}", filepath.display()))?;
+
+    Self::from_bed_str(&data, ref_seq_len)
+      .wrap_err_with(|| format!("When parsing amplicon scheme file {}", filepath.display()))
+  }
+}
+
+fn parse_bed_line(line: &str, ref_seq_len: usize) -> Result<(String, String, AmpliconPrimer), Report> {
+  static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<amplicon>.+?)_(?P<side>LEFT|RIGHT)(_.+)?$").expect("Invalid regular expression")
+  });
+
+  let columns = line.split('\t').map(str::trim).collect_vec();
+  if columns.len() < 5 {
+    return make_error!(
+      "Expected at least 5 tab-separated columns (chrom, start, end, name, pool), but found {}",
+      columns.len()
+    );
+  }
+
+  let parse_pos = |s: &str| {
+    s.parse::<usize>()
+      .wrap_err_with(|| eyre!("Expected a non-negative integer position, but found '{s}'"))
+  };
+  let begin = parse_pos(columns[1])?;
+  let end = parse_pos(columns[2])?;
+  if begin >= end || end > ref_seq_len {
+    return make_error!(
+      "Primer range {begin}-{end} is empty or outside of the reference sequence of length {ref_seq_len}. This might mean that the amplicon scheme is not compatible with the reference sequence used."
+    );
+  }
+
+  let name = columns[3];
+  let Some(captures) = RE.captures(name) else {
+    return make_error!(
+      "Primer name '{name}' does not end with '_LEFT' or '_RIGHT' (optionally followed by an alternative primer suffix, e.g. '_alt1')"
+    );
+  };
+  let side = if captures["side"].eq_ignore_ascii_case("LEFT") {
+    AmpliconPrimerSide::Left
+  } else {
+    AmpliconPrimerSide::Right
+  };
+
+  let primer = AmpliconPrimer {
+    name: name.to_owned(),
+    side,
+    range: NucRefGlobalRange::from_usize(begin, end),
+  };
+
+  Ok((captures["amplicon"].to_owned(), columns[4].to_owned(), primer))
+}
+
+fn make_amplicon(name: String, pool: String, primers: Vec<AmpliconPrimer>) -> Result<Amplicon, Report> {
+  let (left, right): (Vec<_>, Vec<_>) = primers
+    .iter()
+    .partition(|primer| primer.side == AmpliconPrimerSide::Left);
+
+  let (Some(left_begin), Some(left_end), Some(right_begin), Some(right_end)) = (
+    left.iter().map(|p| p.range.begin).min(),
+    left.iter().map(|p| p.range.end).max(),
+    right.iter().map(|p| p.range.begin).min(),
+    right.iter().map(|p| p.range.end).max(),
+  ) else {
+    return make_error!("Amplicon '{name}' (pool '{pool}') should have both left and right primers");
+  };
+
+  if left_end > right_begin {
+    return make_error!(
+      "Primers of amplicon '{name}' (pool '{pool}') overlap: left primers end after right primers begin"
+    );
+  }
+
+  Ok(Amplicon {
+    name,
+    pool,
+    range: Range::new(left_begin, right_end),
+    insert: Range::new(left_end, right_begin),
+    unique_ranges: vec![],
+    primers,
+  })
+}
+
+/// Subtract a set of ranges from a given range
+fn subtract_ranges(range: &NucRefGlobalRange, others: &[NucRefGlobalRange]) -> Vec<NucRefGlobalRange> {
+  let mut remaining = vec![range.clone()];
+  for other in others {
+    remaining = remaining
+      .into_iter()
+      .flat_map(|r| {
+        if other.end <= r.begin || other.begin >= r.end {
+          vec![r]
+        } else {
+          [(r.begin, other.begin), (other.end, r.end)]
+            .into_iter()
+            .filter(|(begin, end)| begin < end)
+            .map(|(begin, end)| Range::new(begin, end))
+            .collect_vec()
+        }
+      })
+      .collect_vec();
+  }
+  remaining
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::coord::position::PositionLike;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  const BED: &str = "\
+MN908947.3\t30\t54\tnCoV-2019_1_LEFT\t1\t+
+MN908947.3\t385\t410\tnCoV-2019_1_RIGHT\t1\t-
+MN908947.3\t320\t342\tnCoV-2019_2_LEFT\t2\t+
+MN908947.3\t704\t726\tnCoV-2019_2_RIGHT\t2\t-
+MN908947.3\t690\t712\tnCoV-2019_2_RIGHT_alt1\t2\t-
+MN908947.3\t642\t664\tnCoV-2019_3_LEFT\t1\t+
+MN908947.3\t1004\t1028\tnCoV-2019_3_RIGHT\t1\t-
+";
+
+  fn ranges(ranges: &[NucRefGlobalRange]) -> Vec<(usize, usize)> {
+    ranges
+      .iter()
+      .map(|r| (r.begin.as_usize(), r.end.as_usize()))
+      .collect_vec()
+  }
+
+  #[rstest]
+  fn parses_artic_bed() -> Result<(), Report> {
+    let scheme = AmpliconScheme::from_bed_str(BED, 30000)?;
+
+    let actual = scheme
+      .amplicons
+      .iter()
+      .map(|a| {
+        (
+          a.name.as_str(),
+          a.pool.as_str(),
+          ranges(&[a.range.clone(), a.insert.clone()]),
+          ranges(&a.unique_ranges),
+          a.primers.len(),
+        )
+      })
+      .collect_vec();
+
+    assert_eq!(
+      actual,
+      vec![
+        ("nCoV-2019_1", "1", vec![(30, 410), (54, 385)], vec![(54, 342)], 2),
+        ("nCoV-2019_2", "2", vec![(320, 726), (342, 690)], vec![(385, 664)], 3),
+        ("nCoV-2019_3", "1", vec![(642, 1028), (664, 1004)], vec![(690, 1004)], 2),
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_amplicon_without_right_primer() {
+    let bed = "ref\t30\t54\tnCoV-2019_1_LEFT\t1\t+\n";
+    let report = AmpliconScheme::from_bed_str(bed, 30000).unwrap_err();
+    assert!(report.to_string().contains("should have both left and right primers"));
+  }
+
+  #[rstest]
+  fn rejects_primer_outside_of_reference() {
+    let bed = "ref\t30\t54\tnCoV-2019_1_LEFT\t1\t+\n";
+    let report = AmpliconScheme::from_bed_str(bed, 40).unwrap_err();
+    assert!(format!("{report:?}").contains("outside of the reference sequence of length 40"));
+  }
+}
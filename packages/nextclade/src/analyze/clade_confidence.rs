@@ -0,0 +1,289 @@
+use crate::analyze::is_sequenced::is_nuc_sequenced;
+use crate::analyze::letter_ranges::NucRange;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::range::NucRefGlobalRange;
+use crate::graph::node::GraphNodeKey;
+use crate::graph::search::graph_find_backwards_last;
+use crate::o;
+use crate::run::params_general::CladeLowConfidenceFallback;
+use crate::tree::tree::{AuspiceGraph, AuspiceGraphNodePayload, CladeNodeAttrKeyDesc};
+use eyre::Report;
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Value reported for a clade-like attribute when none of the candidate values has sufficient confidence
+pub const CLADE_UNASSIGNED: &str = "unassigned";
+
+/// Confidence of assignment of a clade-like attribute, based on whether the sites defining its value are sequenced
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CladeAttrConfidence {
+  /// Value of the attribute on the nearest reference tree node
+  pub value: String,
+  /// Name of the node where the value is introduced: the earliest node with this value on the path from the nearest
+  /// node to the root
+  pub founder_node_name: String,
+  /// Fraction of mutations on the branch leading to the founder node, which are at positions sequenced in the query.
+  /// Equals 1 if there are no mutations on the branch.
+  pub confidence: f64,
+  /// Number of mutations on the branch leading to the founder node
+  pub total_defining: usize,
+  /// Mutations on the branch leading to the founder node, which are at positions not sequenced in the query
+  pub unsequenced: Vec<NucSub>,
+  /// Value reported instead of the assigned value, because the confidence is below the threshold
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub fallback: Option<String>,
+}
+
+pub struct FindCladeConfidenceParams<'a> {
+  pub missing: &'a [NucRange],
+  pub alignment_range: &'a NucRefGlobalRange,
+  pub min_confidence: f64,
+  pub fallback: CladeLowConfidenceFallback,
+}
+
+/// Calculate confidence of assignment of clade and of each clade-like attribute of the nearest node.
+///
+/// Walks the path from the nearest node to the root. For each attribute, the branch defining its value is the branch
+/// leading to the earliest node of the contiguous run of nodes with this value. If confidence is below the threshold,
+/// a fallback value is chosen: either the closest ancestral value with sufficient confidence, or "unassigned".
+/// Returns results keyed by attribute name, with key "clade" for the clade.
+pub fn find_clade_confidence(
+  graph: &AuspiceGraph,
+  nearest_node_id: GraphNodeKey,
+  clade_node_attr_descs: &[CladeNodeAttrKeyDesc],
+  params: &FindCladeConfidenceParams,
+) -> Result<BTreeMap<String, CladeAttrConfidence>, Report> {
+  let path = graph_path_to_root(graph, nearest_node_id)?;
+
+  let clade = find_attr_confidence(&path, AuspiceGraphNodePayload::clade, params).map(|result| (o!("clade"), result));
+
+  let attrs = clade_node_attr_descs.iter().filter_map(|desc| {
+    find_attr_confidence(
+      &path,
+      |node| node.get_clade_node_attr(&desc.name).map(ToOwned::to_owned),
+      params,
+    )
+    .map(|result| (desc.name.clone(), result))
+  });
+
+  Ok(clade.into_iter().chain(attrs).collect())
+}
+
+/// Nodes on the path from the given node to the root, including both
+fn graph_path_to_root(graph: &AuspiceGraph, node_key: GraphNodeKey) -> Result<Vec<&AuspiceGraphNodePayload>, Report> {
+  let mut keys = vec![node_key];
+  graph_find_backwards_last(graph, node_key, |node| {
+    keys.push(node.key());
+    None::<()>
+  })?;
+  keys.into_iter().map(|key| Ok(graph.get_node(key)?.payload())).collect()
+}
+
+fn find_attr_confidence(
+  path: &[&AuspiceGraphNodePayload],
+  get_value: impl Fn(&AuspiceGraphNodePayload) -> Option<String>,
+  params: &FindCladeConfidenceParams,
+) -> Option<CladeAttrConfidence> {
+  let values = path.iter().map(|node| get_value(node)).collect_vec();
+
+  let mut result = attr_confidence_from(path, &values, 0, params)?;
+  if result.confidence >= params.min_confidence {
+    return Some(result);
+  }
+
+  let fallback = match params.fallback {
+    CladeLowConfidenceFallback::Unassigned => None,
+    CladeLowConfidenceFallback::Parent => {
+      // Move to the ancestral values, one contiguous run at a time, until the confidence is sufficient
+      let mut start = founder_index(&values, 0) + 1;
+      let mut fallback = None;
+      while let Some(candidate) = attr_confidence_from(path, &values, start, params) {
+        if candidate.confidence >= params.min_confidence {
+          fallback = Some(candidate.value);
+          break;
+        }
+        start = founder_index(&values, start) + 1;
+      }
+      fallback
+    }
+  };
+
+  result.fallback = Some(fallback.unwrap_or_else(|| o!(CLADE_UNASSIGNED)));
+  Some(result)
+}
+
+/// Index of the earliest node of the contiguous run of nodes with the same value as the node at `start`
+fn founder_index(values: &[Option<String>], start: usize) -> usize {
+  values[start..]
+    .iter()
+    .take_while(|value| *value == &values[start])
+    .count()
+    + start
+    - 1
+}
+
+fn attr_confidence_from(
+  path: &[&AuspiceGraphNodePayload],
+  values: &[Option<String>],
+  start: usize,
+  params: &FindCladeConfidenceParams,
+) -> Option<CladeAttrConfidence> {
+  let value = values.get(start)?.as_ref()?;
+  let founder = path[founder_index(values, start)];
+
+  let defining = &founder.tmp.private_mutations.nuc_muts;
+  let unsequenced = defining
+    .iter()
+    .filter(|sub| !is_nuc_sequenced(sub.pos, params.missing, params.alignment_range))
+    .cloned()
+    .collect_vec();
+
+  let total_defining = defining.len();
+  let confidence = if total_defining == 0 {
+    1.0
+  } else {
+    (total_defining - unsequenced.len()) as f64 / total_defining as f64
+  };
+
+  Some(CladeAttrConfidence {
+    value: value.clone(),
+    founder_node_name: founder.name.clone(),
+    confidence,
+    total_defining,
+    unsequenced,
+    fallback: None,
+  })
+}
+
+/// Replace clade and clade-like attribute values by their fallback values, where confidence is below the threshold
+pub fn apply_clade_confidence_fallbacks(
+  clade: Option<String>,
+  clade_node_attrs: BTreeMap<String, String>,
+  clade_confidence: &BTreeMap<String, CladeAttrConfidence>,
+) -> (Option<String>, BTreeMap<String, String>) {
+  let fallback = |key: &str| clade_confidence.get(key)?.fallback.clone();
+
+  let clade = fallback("clade").or(clade);
+  let clade_node_attrs = clade_node_attrs
+    .into_iter()
+    .map(|(key, value)| {
+      let value = fallback(&key).unwrap_or(value);
+      (key, value)
+    })
+    .collect();
+
+  (clade, clade_node_attrs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::Nuc;
+  use crate::analyze::find_private_nuc_mutations::BranchMutations;
+  use crate::coord::position::NucRefGlobalPosition;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+  use std::str::FromStr;
+
+  // Path from the nearest node to the root: (node name, clade, branch mutations)
+  const PATH: &[(&str, &str, &[&str])] = &[
+    ("nearest", "B.1", &[]),
+    ("b1_founder", "B.1", &["C101T", "G201A"]),
+    ("b_founder", "B", &["A11G"]),
+    ("root", "A", &[]),
+  ];
+
+  fn make_path() -> Vec<AuspiceGraphNodePayload> {
+    PATH
+      .iter()
+      .map(|(name, _, muts)| {
+        let mut node = AuspiceGraphNodePayload {
+          name: o!(*name),
+          ..AuspiceGraphNodePayload::default()
+        };
+        node.tmp.private_mutations = BranchMutations {
+          nuc_muts: muts.iter().map(|m| NucSub::from_str(m).unwrap()).collect(),
+          aa_muts: BTreeMap::new(),
+        };
+        node
+      })
+      .collect()
+  }
+
+  fn clade_of(node: &AuspiceGraphNodePayload) -> Option<String> {
+    PATH
+      .iter()
+      .find(|(name, ..)| *name == node.name)
+      .map(|(_, clade, _)| o!(*clade))
+  }
+
+  fn missing(begin: usize, end: usize) -> Vec<NucRange> {
+    vec![NucRange {
+      range: NucRefGlobalRange::from_usize(begin, end),
+      letter: Nuc::N,
+    }]
+  }
+
+  #[rstest]
+  #[case::sequenced(0.0, CladeLowConfidenceFallback::Parent, 0.5, None)]
+  #[case::sufficient(0.5, CladeLowConfidenceFallback::Parent, 0.5, None)]
+  #[case::parent(0.9, CladeLowConfidenceFallback::Parent, 0.5, Some("B"))]
+  #[case::unassigned(0.9, CladeLowConfidenceFallback::Unassigned, 0.5, Some("unassigned"))]
+  fn finds_confidence_and_fallback(
+    #[case] min_confidence: f64,
+    #[case] fallback: CladeLowConfidenceFallback,
+    #[case] expected_confidence: f64,
+    #[case] expected_fallback: Option<&str>,
+  ) {
+    let nodes = make_path();
+    let path = nodes.iter().collect_vec();
+    // Position 101 (0-based 100) is missing
+    let missing = missing(90, 110);
+    let params = FindCladeConfidenceParams {
+      missing: &missing,
+      alignment_range: &NucRefGlobalRange::from_usize(0, 1000),
+      min_confidence,
+      fallback,
+    };
+
+    let result = find_attr_confidence(&path, clade_of, &params).unwrap();
+
+    assert_eq!(result.value, "B.1");
+    assert_eq!(result.founder_node_name, "b1_founder");
+    assert_eq!(result.total_defining, 2);
+    assert_eq!(
+      result.unsequenced.iter().map(|sub| sub.pos).collect_vec(),
+      vec![NucRefGlobalPosition::new(100)]
+    );
+    assert_eq!(OrderedFloat(result.confidence), OrderedFloat(expected_confidence));
+    assert_eq!(result.fallback.as_deref(), expected_fallback);
+  }
+
+  #[rstest]
+  fn falls_back_to_closest_supported_ancestor() {
+    let nodes = make_path();
+    let path = nodes.iter().collect_vec();
+    // Sequence covers only the beginning of the genome: mutations of "B.1" are not sequenced, but the mutation of "B" is
+    let params = FindCladeConfidenceParams {
+      missing: &[],
+      alignment_range: &NucRefGlobalRange::from_usize(0, 50),
+      min_confidence: 1.0,
+      fallback: CladeLowConfidenceFallback::Parent,
+    };
+    let result = find_attr_confidence(&path, clade_of, &params).unwrap();
+    assert_eq!(OrderedFloat(result.confidence), OrderedFloat(0.0));
+    assert_eq!(result.fallback.as_deref(), Some("B"));
+
+    let params = FindCladeConfidenceParams {
+      alignment_range: &NucRefGlobalRange::from_usize(0, 5),
+      ..params
+    };
+    let result = find_attr_confidence(&path, clade_of, &params).unwrap();
+    // Root clade "A" has no defining mutations, so it is always supported
+    assert_eq!(result.fallback.as_deref(), Some("A"));
+  }
+}
@@ -0,0 +1,495 @@
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::letter_ranges::NucRange;
+use crate::analyze::nuc_del::NucDelRange;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::coord::range::NucRefGlobalRange;
+use crate::gene::genotype::Genotype;
+use crate::io::fs::read_file_to_string;
+use crate::make_error;
+use crate::tree::tree::CladeNodeAttrKeyDesc;
+use csv::ReaderBuilder as CsvReaderBuilder;
+use eyre::{Report, WrapErr, eyre};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Clade of a clade-defining mutations table
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CladeMutationsEntry {
+  /// Clade name
+  pub clade: String,
+  /// Name of the parent clade. Root clades have no parent.
+  pub parent: Option<String>,
+  /// Mutations defining the clade, relative to reference, in addition to mutations of its ancestors
+  pub mutations: Vec<Genotype<Nuc>>,
+  /// Those of the clade's own mutations which are written with the reference character (e.g. "C241T"). The reference
+  /// character is checked against the reference sequence.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub mutations_with_ref: Vec<NucSub>,
+  /// Mutations defining the clade, including mutations inherited from its ancestors. Where the clade and its ancestor
+  /// define the same position, the clade's own mutation is used.
+  pub all_mutations: Vec<Genotype<Nuc>>,
+  /// Number of ancestors of the clade
+  pub depth: usize,
+  /// Values of additional columns of the table. Empty values are inherited from the parent clade.
+  pub attrs: BTreeMap<String, String>,
+}
+
+/// Table of clade-defining mutations with a parent hierarchy. Used to assign clades when there is no reference tree.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CladeMutationsTable {
+  /// Clades, ordered such that parents come before their children
+  pub clades: Vec<CladeMutationsEntry>,
+  /// Names of additional columns of the table, in the order of appearance
+  pub attr_keys: Vec<String>,
+}
+
+impl CladeMutationsTable {
+  /// Parse clade-defining mutations table in TSV format.
+  ///
+  /// Required columns are `clade` and `mutations`, and the optional column `parent` refers to the parent clade. Mutations
+  /// are separated by commas or spaces, and are written relative to reference with 1-based positions, with or without the
+  /// reference character (e.g. "C241T" or "241T"). Deletions are written with a gap ("-") as the query character. Any
+  /// other columns are reported as clade-like attributes of the assigned clade.
+  pub fn from_tsv_str(s: impl AsRef<str>) -> Result<Self, Report> {
+    let mut reader = CsvReaderBuilder::new()
+      .delimiter(b'\t')
+      .has_headers(true)
+      .comment(Some(b'#'))
+      .trim(csv::Trim::All)
+      .from_reader(s.as_ref().as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let find_column = |name: &str| headers.iter().position(|header| header == name);
+    let Some(clade_column) = find_column("clade") else {
+      return make_error!("Required column 'clade' not found");
+    };
+    let Some(mutations_column) = find_column("mutations") else {
+      return make_error!("Required column 'mutations' not found");
+    };
+    let parent_column = find_column("parent");
+    let attr_columns = headers
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| ![Some(clade_column), Some(mutations_column), parent_column].contains(&Some(*i)))
+      .map(|(i, header)| (i, header.to_owned()))
+      .collect_vec();
+
+    let mut rows = Vec::<CladeMutationsEntry>::new();
+    for (row_index, record) in reader.records().enumerate() {
+      let record = record?;
+      let field = |i: usize| record.get(i).filter(|value| !value.is_empty());
+
+      let Some(clade) = field(clade_column) else {
+        return make_error!("Clade name is empty in row {}", row_index + 1);
+      };
+
+      let (mutations, mutations_with_ref): (Vec<_>, Vec<_>) = field(mutations_column)
+        .unwrap_or_default()
+        .split([',', ' '])
+        .filter(|mutation| !mutation.is_empty())
+        .map(parse_mutation)
+        .collect::<Result<Vec<_>, Report>>()
+        .wrap_err_with(|| eyre!("When parsing mutations of clade '{clade}'"))?
+        .into_iter()
+        .unzip();
+
+      let entry = CladeMutationsEntry {
+        clade: clade.to_owned(),
+        parent: parent_column.and_then(field).map(ToOwned::to_owned),
+        mutations,
+        mutations_with_ref: mutations_with_ref.into_iter().flatten().collect(),
+        all_mutations: vec![],
+        depth: 0,
+        attrs: attr_columns
+          .iter()
+          .filter_map(|(i, key)| field(*i).map(|value| (key.clone(), value.to_owned())))
+          .collect(),
+      };
+
+      if rows.iter().any(|row| row.clade == clade) {
+        return make_error!("Clade '{clade}' is listed more than once");
+      }
+      rows.push(entry);
+    }
+
+    let clades = resolve_hierarchy(&rows)?;
+    let attr_keys = attr_columns.into_iter().map(|(_, key)| key).collect_vec();
+    Ok(Self { clades, attr_keys })
+  }
+
+  pub fn from_path(filepath: impl AsRef<Path>) -> Result<Self, Report> {
+    let filepath = filepath.as_ref();
+
+    let data = read_file_to_string(filepath)
+      .wrap_err_with(|| format!("When reading clade mutations table {}", filepath.display()))?;
+
+    Self::from_tsv_str(&data).wrap_err_with(|| format!("When parsing clade mutations table {}", filepath.display()))
+  }
+
+  /// Check that all mutations are inside of the reference sequence and that their reference characters, where given,
+  /// match the reference sequence
+  pub fn validate(&self, ref_seq: &[Nuc]) -> Result<(), Report> {
+    let ref_seq_len = ref_seq.len();
+    for entry in &self.clades {
+      if let Some(mutation) = entry
+        .mutations
+        .iter()
+        .find(|mutation| mutation.pos.as_usize() >= ref_seq_len)
+      {
+        return make_error!(
+          "Mutation '{mutation}' of clade '{}' is outside of the reference sequence of length {ref_seq_len}. This might mean that the clade mutations table is not compatible with the reference sequence used.",
+          entry.clade
+        );
+      }
+
+      if let Some(mutation) = entry
+        .mutations_with_ref
+        .iter()
+        .find(|mutation| ref_seq.get(mutation.pos.as_usize()) != Some(&mutation.ref_nuc))
+      {
+        return make_error!(
+          "Mutation '{mutation}' of clade '{}' has reference character '{}', but the reference sequence has '{}' at position {}. This might mean that the clade mutations table is not compatible with the reference sequence used.",
+          entry.clade,
+          mutation.ref_nuc,
+          ref_seq[mutation.pos.as_usize()],
+          mutation.pos.as_usize() + 1
+        );
+      }
+    }
+    Ok(())
+  }
+
+  /// Descriptions of additional columns of the table, reported as clade-like attributes
+  pub fn attr_descs(&self) -> Vec<CladeNodeAttrKeyDesc> {
+    self
+      .attr_keys
+      .iter()
+      .map(|key| CladeNodeAttrKeyDesc {
+        name: key.clone(),
+        display_name: key.clone(),
+        description: None,
+        hide_in_web: false,
+        skip_as_reference: false,
+        lineage_hierarchy: None,
+        other: serde_json::Value::default(),
+      })
+      .collect_vec()
+  }
+}
+
+/// Parse mutation, with (e.g. "C241T") or without (e.g. "241T") the reference character. The mutation with the
+/// reference character is returned too, if it is given.
+fn parse_mutation(s: &str) -> Result<(Genotype<Nuc>, Option<NucSub>), Report> {
+  match NucSub::from_str(s) {
+    Ok(sub) => Ok((sub.genotype(), Some(sub))),
+    Err(_) => Ok((Genotype::<Nuc>::from_str(s)?, None)),
+  }
+}
+
+/// Order clades such that parents come before their children, and fill fields inherited from ancestors
+fn resolve_hierarchy(rows: &[CladeMutationsEntry]) -> Result<Vec<CladeMutationsEntry>, Report> {
+  let by_name: BTreeMap<&str, &CladeMutationsEntry> = rows.iter().map(|row| (row.clade.as_str(), row)).collect();
+
+  let mut lineages = vec![];
+  for entry in rows {
+    let mut lineage = vec![entry];
+    let mut visited = BTreeSet::from([entry.clade.as_str()]);
+    while let Some(parent) = &lineage[lineage.len() - 1].parent {
+      let Some(parent_entry) = by_name.get(parent.as_str()) else {
+        return make_error!("Parent clade '{parent}' of clade '{}' is not listed", entry.clade);
+      };
+      if !visited.insert(parent.as_str()) {
+        return make_error!("Parent hierarchy of clade '{}' contains a cycle", entry.clade);
+      }
+      lineage.push(*parent_entry);
+    }
+    lineages.push(lineage);
+  }
+
+  Ok(
+    lineages
+      .into_iter()
+      .map(|lineage| {
+        let mut all_mutations = BTreeMap::<NucRefGlobalPosition, Genotype<Nuc>>::new();
+        let mut attrs = BTreeMap::<String, String>::new();
+        // Walk from the root, so that descendants override their ancestors
+        for ancestor in lineage.iter().rev() {
+          all_mutations.extend(
+            ancestor
+              .mutations
+              .iter()
+              .map(|mutation| (mutation.pos, mutation.clone())),
+          );
+          attrs.extend(ancestor.attrs.clone());
+        }
+        CladeMutationsEntry {
+          all_mutations: all_mutations.into_values().collect_vec(),
+          depth: lineage.len() - 1,
+          attrs,
+          ..lineage[0].clone()
+        }
+      })
+      .sorted_by_key(|entry| entry.depth)
+      .collect_vec(),
+  )
+}
+
+/// Result of clade assignment using a clade-defining mutations table
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CladeMutationsResult {
+  /// Deepest clade whose defining mutations are all present in the query, except for the positions which are missing.
+  /// Absent if none of the root clades is supported.
+  pub clade: Option<String>,
+  /// Fraction of defining mutations of the assigned clade (including inherited mutations) found in the query
+  pub confidence: f64,
+  /// Number of defining mutations of the assigned clade (including inherited mutations) found in the query
+  pub total_matched: usize,
+  /// Number of defining mutations of the assigned clade (including inherited mutations)
+  pub total_defining: usize,
+  /// Defining mutations of the assigned clade which could not be checked, because their positions are missing or not
+  /// sequenced in the query
+  pub unmatched: Vec<Genotype<Nuc>>,
+}
+
+/// Assigned clade along with its clade-like attributes
+pub struct CladeMutationsAssignment {
+  pub result: CladeMutationsResult,
+  pub attrs: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SiteMatch {
+  Matched,
+  Missing,
+  Mismatched,
+}
+
+/// Assign the deepest clade of the clade-defining mutations table supported by the query sequence.
+///
+/// A clade is supported if its parent is supported, none of its defining mutations (including inherited) contradict the
+/// query, and at least one of its own mutations is found in the query (unless it has none). Positions which are
+/// missing, not sequenced or ambiguous neither support nor contradict a clade. Ties between clades of the same depth are
+/// resolved in favor of more matched mutations, then fewer unmatched mutations, then the order in the table.
+pub fn find_clade_by_mutations(
+  table: &CladeMutationsTable,
+  ref_seq: &[Nuc],
+  substitutions: &[NucSub],
+  deletions: &[NucDelRange],
+  missing: &[NucRange],
+  alignment_range: &NucRefGlobalRange,
+) -> CladeMutationsAssignment {
+  let qry_nuc = |pos: NucRefGlobalPosition| -> Option<Nuc> {
+    if !alignment_range.contains(pos) || missing.iter().any(|range| range.contains_pos(pos)) {
+      return None;
+    }
+    if let Some(sub) = substitutions.iter().find(|sub| sub.pos == pos) {
+      return (sub.qry_nuc.is_acgt()).then_some(sub.qry_nuc);
+    }
+    if deletions.iter().any(|del| del.range().contains(pos)) {
+      return Some(Nuc::Gap);
+    }
+    ref_seq.get(pos.as_usize()).copied()
+  };
+
+  let site_match = |mutation: &Genotype<Nuc>| match qry_nuc(mutation.pos) {
+    None => SiteMatch::Missing,
+    Some(nuc) if nuc == mutation.qry => SiteMatch::Matched,
+    Some(_) => SiteMatch::Mismatched,
+  };
+
+  // Clades are ordered parents first, so the parent is always checked before its children
+  let mut supported = BTreeSet::<&str>::new();
+  for entry in &table.clades {
+    let parent_supported = entry
+      .parent
+      .as_ref()
+      .is_none_or(|parent| supported.contains(parent.as_str()));
+    let no_mismatches = entry
+      .all_mutations
+      .iter()
+      .all(|mutation| site_match(mutation) != SiteMatch::Mismatched);
+    let own_matched = entry.mutations.is_empty()
+      || entry
+        .mutations
+        .iter()
+        .any(|mutation| site_match(mutation) == SiteMatch::Matched);
+    if parent_supported && no_mismatches && own_matched {
+      supported.insert(&entry.clade);
+    }
+  }
+
+  let best = table
+    .clades
+    .iter()
+    .filter(|entry| supported.contains(entry.clade.as_str()))
+    .map(|entry| {
+      let (matched, unmatched): (Vec<_>, Vec<_>) = entry
+        .all_mutations
+        .iter()
+        .cloned()
+        .partition(|mutation| site_match(mutation) == SiteMatch::Matched);
+      (entry, matched.len(), unmatched)
+    })
+    .min_by_key(|(entry, total_matched, unmatched)| (Reverse(entry.depth), Reverse(*total_matched), unmatched.len()));
+
+  match best {
+    None => CladeMutationsAssignment {
+      result: CladeMutationsResult::default(),
+      attrs: BTreeMap::new(),
+    },
+    Some((entry, total_matched, unmatched)) => {
+      let total_defining = entry.all_mutations.len();
+      let confidence = if total_defining == 0 {
+        1.0
+      } else {
+        total_matched as f64 / total_defining as f64
+      };
+      CladeMutationsAssignment {
+        result: CladeMutationsResult {
+          clade: Some(entry.clade.clone()),
+          confidence,
+          total_matched,
+          total_defining,
+          unmatched,
+        },
+        attrs: entry.attrs.clone(),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::o;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  const TABLE: &str = "\
+clade\tparent\tmutations\tlineage
+A\t\t\tA
+B\tA\tC2T,G4A\t
+B.1\tB\t6T\tB.1
+B.2\tB\tA8G,C9T\tB.2
+C\tA\tC2G\tC
+";
+
+  fn sub(s: &str) -> NucSub {
+    NucSub::from_str(s).unwrap()
+  }
+
+  fn assign(qry_subs: &[&str], missing: &[NucRange]) -> CladeMutationsAssignment {
+    let table = CladeMutationsTable::from_tsv_str(TABLE).unwrap();
+    //                         123456789
+    let ref_seq = to_nuc_seq("ACTGAAGAC").unwrap();
+    let substitutions = qry_subs.iter().map(|s| sub(s)).collect_vec();
+    let alignment_range = NucRefGlobalRange::from_usize(0, 9);
+    find_clade_by_mutations(&table, &ref_seq, &substitutions, &[], missing, &alignment_range)
+  }
+
+  #[rstest]
+  fn parses_table_with_hierarchy() -> Result<(), Report> {
+    let table = CladeMutationsTable::from_tsv_str(TABLE)?;
+    let actual = table
+      .clades
+      .iter()
+      .map(|entry| {
+        (
+          entry.clade.as_str(),
+          entry.depth,
+          entry.all_mutations.iter().map(ToString::to_string).join(","),
+          entry.attrs["lineage"].as_str(),
+        )
+      })
+      .collect_vec();
+    assert_eq!(
+      actual,
+      vec![
+        ("A", 0, o!(""), "A"),
+        ("B", 1, o!("2T,4A"), "A"),
+        ("C", 1, o!("2G"), "C"),
+        ("B.1", 2, o!("2T,4A,6T"), "B.1"),
+        ("B.2", 2, o!("2T,4A,8G,9T"), "B.2"),
+      ]
+    );
+    assert_eq!(table.attr_keys, vec![o!("lineage")]);
+    Ok(())
+  }
+
+  #[rstest]
+  fn assigns_deepest_supported_clade() {
+    let assignment = assign(&["C2T", "G4A", "A8G", "C9T"], &[]);
+    assert_eq!(assignment.result.clade.as_deref(), Some("B.2"));
+    assert_eq!(OrderedFloat(assignment.result.confidence), OrderedFloat(1.0));
+    assert_eq!(assignment.attrs["lineage"], "B.2");
+  }
+
+  #[rstest]
+  fn does_not_assign_clade_with_contradicting_mutations() {
+    // 8G is present, but 9 is the reference nucleotide
+    let assignment = assign(&["C2T", "G4A", "A8G"], &[]);
+    assert_eq!(assignment.result.clade.as_deref(), Some("B"));
+  }
+
+  #[rstest]
+  fn reports_missing_sites_as_unmatched() {
+    let missing = vec![NucRange {
+      range: NucRefGlobalRange::from_usize(3, 4),
+      letter: Nuc::N,
+    }];
+    let assignment = assign(&["C2T", "A8G", "C9T"], &missing);
+    assert_eq!(assignment.result.clade.as_deref(), Some("B.2"));
+    assert_eq!(assignment.result.total_matched, 3);
+    assert_eq!(assignment.result.total_defining, 4);
+    assert_eq!(OrderedFloat(assignment.result.confidence), OrderedFloat(0.75));
+    assert_eq!(
+      assignment
+        .result
+        .unmatched
+        .iter()
+        .map(ToString::to_string)
+        .collect_vec(),
+      vec![o!("4A")]
+    );
+  }
+
+  #[rstest]
+  fn validates_reference_characters() -> Result<(), Report> {
+    let table = CladeMutationsTable::from_tsv_str(TABLE)?;
+    table.validate(&to_nuc_seq("ACTGAAGAC")?)?;
+
+    let report = table.validate(&to_nuc_seq("AGTGAAGAC")?).unwrap_err();
+    assert!(report.to_string().contains(
+      "Mutation 'C2T' of clade 'B' has reference character 'C', but the reference sequence has 'G' at position 2"
+    ));
+
+    let report = table.validate(&to_nuc_seq("ACTGAAG")?).unwrap_err();
+    assert!(
+      report
+        .to_string()
+        .contains("Mutation '8G' of clade 'B.2' is outside of the reference sequence")
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_unknown_parent() {
+    let report = CladeMutationsTable::from_tsv_str("clade\tparent\tmutations\nB\tA\t2T\n").unwrap_err();
+    assert!(
+      report
+        .to_string()
+        .contains("Parent clade 'A' of clade 'B' is not listed")
+    );
+  }
+}
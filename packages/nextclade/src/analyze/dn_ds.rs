@@ -0,0 +1,218 @@
+use crate::alphabet::aa::Aa;
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::nuc_sub_effects::{NucSubEffect, NucSubEffectType};
+use crate::gene::gene_map::GeneMap;
+use crate::translate::genetic_code::GeneticCode;
+use crate::translate::translate_genes::Translation;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Number of synonymous and nonsynonymous sites in the reference sequence of a CDS (Nei-Gojobori method)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SynonymousSites {
+  pub synonymous: f64,
+  pub nonsynonymous: f64,
+}
+
+/// Counts synonymous and nonsynonymous sites of a CDS. Every position of a codon contributes the fraction of the
+/// possible single-nucleotide changes which are synonymous to synonymous sites, and the rest to nonsynonymous sites.
+/// Stop codons and codons with ambiguous nucleotides are not counted.
+pub fn synonymous_sites(cds_seq: &[Nuc], genetic_code: &GeneticCode) -> SynonymousSites {
+  const ACGT: [Nuc; 4] = [Nuc::A, Nuc::C, Nuc::G, Nuc::T];
+
+  let mut sites = SynonymousSites::default();
+  for codon in cds_seq.chunks_exact(3) {
+    let Ok(codon) = <[Nuc; 3]>::try_from(codon) else {
+      continue;
+    };
+    if !codon.iter().all(|nuc| nuc.is_acgt()) {
+      continue;
+    }
+
+    let aa = genetic_code.decode(&codon);
+    if aa == Aa::Stop {
+      continue;
+    }
+
+    for phase in 0..3 {
+      let n_synonymous = ACGT
+        .iter()
+        .filter(|&&nuc| nuc != codon[phase])
+        .filter(|&&nuc| {
+          let mut mutated = codon;
+          mutated[phase] = nuc;
+          genetic_code.decode(&mutated) == aa
+        })
+        .count();
+
+      sites.synonymous += n_synonymous as f64 / 3.0;
+      sites.nonsynonymous += (3 - n_synonymous) as f64 / 3.0;
+    }
+  }
+  sites
+}
+
+/// Calculates synonymous and nonsynonymous sites of all CDSes in the reference sequence
+pub fn synonymous_sites_for_ref(
+  gene_map: &GeneMap,
+  ref_translation: &Translation,
+) -> BTreeMap<String, SynonymousSites> {
+  gene_map
+    .iter_cdses()
+    .filter_map(|cds| {
+      let ref_cds_seq = &ref_translation.get_cds(&cds.name).ok()?.nuc_seq;
+      Some((cds.name.clone(), synonymous_sites(ref_cds_seq, &cds.genetic_code)))
+    })
+    .collect()
+}
+
+/// Counts of synonymous and nonsynonymous substitutions in a CDS and the resulting dN/dS estimate
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DnDs {
+  /// Number of synonymous substitutions
+  pub synonymous: usize,
+  /// Number of nonsynonymous substitutions (including changes of stop and start codons)
+  pub nonsynonymous: usize,
+  /// Number of nonsynonymous substitutions per nonsynonymous site, with Jukes-Cantor correction
+  pub dn: Option<f64>,
+  /// Number of synonymous substitutions per synonymous site, with Jukes-Cantor correction
+  pub ds: Option<f64>,
+  /// Ratio of dN to dS. Absent if there are no synonymous substitutions.
+  pub dn_ds: Option<f64>,
+}
+
+impl DnDs {
+  pub fn new(synonymous: usize, nonsynonymous: usize, sites: &SynonymousSites) -> Self {
+    let dn = jukes_cantor(nonsynonymous as f64, sites.nonsynonymous);
+    let ds = jukes_cantor(synonymous as f64, sites.synonymous);
+    let dn_ds = match (dn, ds) {
+      (Some(dn), Some(ds)) if ds > 0.0 => Some(dn / ds),
+      _ => None,
+    };
+    Self {
+      synonymous,
+      nonsynonymous,
+      dn,
+      ds,
+      dn_ds,
+    }
+  }
+
+  /// Counts synonymous and nonsynonymous substitutions among the effects in a given CDS
+  pub fn from_effects(cds_name: &str, effects: &[NucSubEffect], sites: &SynonymousSites) -> Self {
+    let (mut synonymous, mut nonsynonymous) = (0, 0);
+    for effect in effects {
+      if effect.cds_name.as_deref() != Some(cds_name) {
+        continue;
+      }
+      if effect.effect == NucSubEffectType::Synonymous {
+        synonymous += 1;
+      } else if effect.effect.is_nonsynonymous() {
+        nonsynonymous += 1;
+      }
+    }
+    Self::new(synonymous, nonsynonymous, sites)
+  }
+}
+
+/// Jukes-Cantor corrected number of substitutions per site. Undefined when there are no sites or the proportion of
+/// substituted sites is too high for the correction.
+fn jukes_cantor(n_subs: f64, n_sites: f64) -> Option<f64> {
+  if n_sites <= 0.0 {
+    return None;
+  }
+  let p = n_subs / n_sites;
+  let arg = 1.0 - 4.0 * p / 3.0;
+  (arg > 0.0).then(|| -0.75 * arg.ln())
+}
+
+/// Synonymous and nonsynonymous changes in a CDS and dN/dS estimates
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CdsDnDs {
+  pub cds_name: String,
+  /// Sites in the reference sequence of the CDS
+  pub sites: SynonymousSites,
+  /// Substitutions relative to the reference sequence
+  pub relative_to_reference: DnDs,
+  /// Private substitutions, relative to the nearest node on the reference tree
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub relative_to_nearest_node: Option<DnDs>,
+}
+
+/// Calculates dN/dS estimates for every CDS, relative to the reference and, if available, to the nearest tree node
+pub fn calculate_dn_ds(
+  gene_map: &GeneMap,
+  ref_cds_sites: &BTreeMap<String, SynonymousSites>,
+  nuc_sub_effects: &[NucSubEffect],
+  private_nuc_sub_effects: Option<&[NucSubEffect]>,
+) -> Vec<CdsDnDs> {
+  gene_map
+    .iter_cdses()
+    .filter_map(|cds| {
+      let sites = ref_cds_sites.get(&cds.name)?;
+      Some(CdsDnDs {
+        cds_name: cds.name.clone(),
+        sites: sites.clone(),
+        relative_to_reference: DnDs::from_effects(&cds.name, nuc_sub_effects, sites),
+        relative_to_nearest_node: private_nuc_sub_effects.map(|effects| DnDs::from_effects(&cds.name, effects, sites)),
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use eyre::Report;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::four_fold_degenerate("GCT", 1.0, 2.0)]
+  #[case::met("ATG", 0.0, 3.0)]
+  #[case::stop_skipped("TAA", 0.0, 0.0)]
+  #[case::ambiguous_skipped("GCN", 0.0, 0.0)]
+  #[case::two_codons("GCTATG", 1.0, 5.0)]
+  fn counts_synonymous_sites(
+    #[case] seq: &str,
+    #[case] synonymous: f64,
+    #[case] nonsynonymous: f64,
+  ) -> Result<(), Report> {
+    let sites = synonymous_sites(&to_nuc_seq(seq)?, &GeneticCode::standard());
+    assert_eq!(
+      sites,
+      SynonymousSites {
+        synonymous,
+        nonsynonymous
+      }
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn calculates_dn_ds() {
+    let sites = SynonymousSites {
+      synonymous: 100.0,
+      nonsynonymous: 300.0,
+    };
+
+    let dn = -0.75 * (1.0 - 4.0 * 0.01 / 3.0_f64).ln();
+    let ds = -0.75 * (1.0 - 4.0 * 0.02 / 3.0_f64).ln();
+    assert_eq!(
+      DnDs::new(2, 3, &sites),
+      DnDs {
+        synonymous: 2,
+        nonsynonymous: 3,
+        dn: Some(dn),
+        ds: Some(ds),
+        dn_ds: Some(dn / ds),
+      }
+    );
+
+    assert_eq!(DnDs::new(0, 3, &sites).dn_ds, None);
+  }
+}
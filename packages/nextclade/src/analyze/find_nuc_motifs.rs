@@ -0,0 +1,247 @@
+use crate::alphabet::letter::Letter;
+use crate::alphabet::nuc::{Nuc, from_nuc_seq};
+use crate::analyze::find_nuc_motifs_changes::NucMotifsMap;
+use crate::analyze::virus_properties::{NucMotifsDesc, NucMotifsSequence};
+use crate::coord::coord_map::make_ref_to_aln_map;
+use crate::coord::coord_map_global::CoordMapGlobal;
+use crate::coord::position::{GlobalSpace, NucRefGlobalPosition, PositionLike};
+use crate::coord::range::{NucRefGlobalRange, intersect_or_none};
+use eyre::{Report, WrapErr, eyre};
+use itertools::Itertools;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Single regex match of a named nucleotide motif in a query sequence (e.g. CpG dinucleotide, restriction site).
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct NucMotif {
+  /// Motif type name as defined in `nucMotifs` configuration
+  pub name: String,
+  /// 0-based position of the motif match start, in reference coordinates
+  pub position: NucRefGlobalPosition,
+  /// Matched nucleotide sequence string
+  pub seq: String,
+}
+
+/// Nucleotide motif description with regexes compiled, ready for searching.
+///
+/// Compiling regexes is expensive, so this is done once, when preparing the analysis, rather than for every sequence.
+#[derive(Debug, Clone)]
+pub struct NucMotifsMatcher {
+  pub name: String,
+  pub sequence: NucMotifsSequence,
+  pub ranges: Vec<NucRefGlobalRange>,
+  pub regexes: Vec<Regex>,
+}
+
+impl NucMotifsMatcher {
+  pub fn new(desc: &NucMotifsDesc) -> Result<Self, Report> {
+    let regexes = desc
+      .motifs
+      .iter()
+      .map(|motif| {
+        RegexBuilder::new(motif)
+          .case_insensitive(true)
+          .build()
+          .wrap_err_with(|| eyre!("When compiling motif RegEx '{motif}'"))
+      })
+      .collect::<Result<Vec<Regex>, Report>>()
+      .wrap_err_with(|| eyre!("When preparing nucleotide motifs '{}'", desc.name))?;
+
+    Ok(Self {
+      name: desc.name.clone(),
+      sequence: desc.sequence,
+      ranges: desc.ranges.clone(),
+      regexes,
+    })
+  }
+
+  pub fn from_descs(descs: &[NucMotifsDesc]) -> Result<Vec<Self>, Report> {
+    descs.iter().map(Self::new).collect()
+  }
+
+  /// Whether a position of the reference is inside of the search ranges
+  fn is_in_ranges(&self, pos: NucRefGlobalPosition) -> bool {
+    self.ranges.is_empty() || self.ranges.iter().any(|range| range.contains(pos))
+  }
+}
+
+/// Find nucleotide motifs in a query sequence, given a list of precompiled regexes (with restriction by ranges).
+///
+/// Depending on configuration, motifs are searched either in the aligned query sequence (with insertions stripped) or
+/// in the unaligned query sequence (with gaps removed). In both cases positions are reported in reference coordinates
+/// and motifs outside of the alignment range are ignored.
+pub fn find_nuc_motifs(
+  matchers: &[NucMotifsMatcher],
+  qry_seq_stripped: &[Nuc],
+  qry_seq_unstripped: &[Nuc],
+  coord_map_global: &CoordMapGlobal,
+  alignment_range: &NucRefGlobalRange,
+) -> NucMotifsMap {
+  matchers
+    .iter()
+    .map(|matcher| {
+      let motifs = match matcher.sequence {
+        NucMotifsSequence::Aligned => find_in_aligned(matcher, qry_seq_stripped, alignment_range),
+        NucMotifsSequence::Unaligned => {
+          find_in_unaligned(matcher, qry_seq_unstripped, coord_map_global, alignment_range)
+        }
+      };
+      (matcher.name.clone(), motifs.into_iter().sorted().collect_vec())
+    })
+    .collect()
+}
+
+fn find_in_aligned(
+  matcher: &NucMotifsMatcher,
+  qry_seq_stripped: &[Nuc],
+  alignment_range: &NucRefGlobalRange,
+) -> Vec<NucMotif> {
+  // If no ranges specified, search the whole sequence
+  let whole = [NucRefGlobalRange::from_usize(0, qry_seq_stripped.len())];
+  let ranges = if matcher.ranges.is_empty() {
+    &whole[..]
+  } else {
+    &matcher.ranges[..]
+  };
+
+  ranges
+    .iter()
+    // NOTE: motifs outside of alignment range (e.g. in partial sequences) are ignored, same as for aa motifs
+    .filter_map(|range| intersect_or_none(alignment_range, range))
+    .filter(|range| range.end.as_usize() <= qry_seq_stripped.len())
+    .flat_map(|range| {
+      let seq = from_nuc_seq(&qry_seq_stripped[range.to_std()]);
+      matcher
+        .regexes
+        .iter()
+        .flat_map(|re| {
+          re.find_iter(&seq)
+            .map(|m| NucMotif {
+              name: matcher.name.clone(),
+              position: range.begin + m.start() as isize,
+              seq: m.as_str().to_owned(),
+            })
+            .collect_vec()
+        })
+        .collect_vec()
+    })
+    .collect_vec()
+}
+
+fn find_in_unaligned(
+  matcher: &NucMotifsMatcher,
+  qry_seq_unstripped: &[Nuc],
+  coord_map_global: &CoordMapGlobal,
+  alignment_range: &NucRefGlobalRange,
+) -> Vec<NucMotif> {
+  let seq = qry_seq_unstripped
+    .iter()
+    .copied()
+    .filter(|nuc| !nuc.is_gap())
+    .collect_vec();
+  let seq = from_nuc_seq(&seq);
+
+  // Position of each of the query nucleotides in the alignment
+  let qry_to_aln = make_ref_to_aln_map::<GlobalSpace>(qry_seq_unstripped);
+
+  matcher
+    .regexes
+    .iter()
+    .flat_map(|re| re.find_iter(&seq).collect_vec())
+    .filter_map(|m| {
+      let position = coord_map_global.aln_to_ref_position(qry_to_aln[m.start()]);
+      (alignment_range.contains(position) && matcher.is_in_ranges(position)).then(|| NucMotif {
+        name: matcher.name.clone(),
+        position,
+        seq: m.as_str().to_owned(),
+      })
+    })
+    .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::o;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn matcher(motifs: &[&str], sequence: NucMotifsSequence, ranges: Vec<NucRefGlobalRange>) -> NucMotifsMatcher {
+    NucMotifsMatcher::new(&NucMotifsDesc {
+      name: o!("motif"),
+      motifs: motifs.iter().map(|&motif| motif.to_owned()).collect_vec(),
+      sequence,
+      ranges,
+      ..NucMotifsDesc::default()
+    })
+    .unwrap()
+  }
+
+  fn found(motifs: &NucMotifsMap) -> Vec<(isize, String)> {
+    motifs["motif"]
+      .iter()
+      .map(|motif| (motif.position.as_isize(), motif.seq.clone()))
+      .collect_vec()
+  }
+
+  #[rstest]
+  fn finds_motifs_in_aligned_sequence() -> Result<(), Report> {
+    let ref_seq_unstripped = to_nuc_seq("ACGTT-CGACG")?;
+    let qry_seq_unstripped = to_nuc_seq("ACGTTCCG-CG")?;
+    //                                0123456789
+    let qry_seq_stripped = to_nuc_seq("ACGTTCG-CG")?;
+    let coord_map = CoordMapGlobal::new(&ref_seq_unstripped, &qry_seq_unstripped);
+    let alignment_range = NucRefGlobalRange::from_usize(0, 10);
+
+    let matchers = [matcher(&["cg"], NucMotifsSequence::Aligned, vec![])];
+    let motifs = find_nuc_motifs(
+      &matchers,
+      &qry_seq_stripped,
+      &qry_seq_unstripped,
+      &coord_map,
+      &alignment_range,
+    );
+
+    assert_eq!(found(&motifs), vec![(1, o!("CG")), (5, o!("CG")), (8, o!("CG"))]);
+    Ok(())
+  }
+
+  #[rstest]
+  fn finds_motifs_in_unaligned_sequence() -> Result<(), Report> {
+    let ref_seq_unstripped = to_nuc_seq("ACGTT-CGACG")?;
+    let qry_seq_unstripped = to_nuc_seq("ACGTTCCG-CG")?;
+    let qry_seq_stripped = to_nuc_seq("ACGTTCG-CG")?;
+    let coord_map = CoordMapGlobal::new(&ref_seq_unstripped, &qry_seq_unstripped);
+    let alignment_range = NucRefGlobalRange::from_usize(0, 10);
+
+    // Motif spanning the deletion is only found in unaligned sequence
+    let matchers = [matcher(
+      &["GCG"],
+      NucMotifsSequence::Unaligned,
+      vec![NucRefGlobalRange::from_usize(2, 10)],
+    )];
+    let motifs = find_nuc_motifs(
+      &matchers,
+      &qry_seq_stripped,
+      &qry_seq_unstripped,
+      &coord_map,
+      &alignment_range,
+    );
+
+    assert_eq!(found(&motifs), vec![(6, o!("GCG"))]);
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_invalid_regex() {
+    let report = NucMotifsMatcher::new(&NucMotifsDesc {
+      name: o!("bad"),
+      motifs: vec![o!("C(G")],
+      ..NucMotifsDesc::default()
+    })
+    .unwrap_err();
+    assert!(format!("{report:?}").contains("When compiling motif RegEx 'C(G'"));
+  }
+}
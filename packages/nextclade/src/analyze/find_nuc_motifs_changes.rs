@@ -0,0 +1,186 @@
+use crate::alphabet::letter::Letter;
+use crate::alphabet::nuc::{Nuc, from_nuc_seq};
+use crate::analyze::find_nuc_motifs::NucMotif;
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::coord::range::NucRefGlobalRange;
+use crate::utils::zip_map::zip_by_key;
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Deref;
+
+pub type NucMotifsMap = BTreeMap<String, Vec<NucMotif>>;
+pub type NucMotifsChangesMap = BTreeMap<String, NucMotifChanges>;
+
+/// Diff of nucleotide motif matches between reference and query for one named motif type (e.g. CpG dinucleotides).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NucMotifChanges {
+  /// Motif matches present in both reference and query
+  pub preserved: Vec<NucMotifMutation>,
+  /// Motif matches present in query but absent in reference
+  pub gained: Vec<NucMotifMutation>,
+  /// Motif matches present in reference but absent in query
+  pub lost: Vec<NucMotifMutation>,
+  /// Motif matches present in reference where the query has ambiguous nucleotides (e.g. N)
+  pub ambiguous: Vec<NucMotifMutation>,
+  /// Total count of gained plus preserved motifs
+  pub total: usize,
+}
+
+/// A motif change record with both reference and query nucleotide sequence fragments at the same position.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema, Ord, PartialOrd, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NucMotifMutation {
+  /// Motif type name as defined in `nucMotifs` configuration
+  pub name: String,
+  /// 0-based position of the motif match start, in reference coordinates
+  pub position: NucRefGlobalPosition,
+  /// Nucleotide sequence at this position in the reference
+  pub ref_seq: String,
+  /// Nucleotide sequence at this position in the query
+  pub qry_seq: String,
+}
+
+/// Find changes between nucleotide motifs in reference and in query sequence.
+///
+/// Motifs are matched by position only. Reference motifs outside of the alignment range of the query are not
+/// considered lost.
+pub fn find_nuc_motifs_changes(
+  nuc_motifs_ref: &NucMotifsMap,
+  nuc_motifs_qry: &NucMotifsMap,
+  ref_seq: &[Nuc],
+  qry_seq_stripped: &[Nuc],
+  alignment_range: &NucRefGlobalRange,
+) -> NucMotifsChangesMap {
+  zip_by_key(nuc_motifs_ref, nuc_motifs_qry)
+    .map(|(name, motifs_ref, motifs_qry)| {
+      let motifs_ref = motifs_ref.map(Deref::deref).unwrap_or_default();
+      let motifs_qry = motifs_qry.map(Deref::deref).unwrap_or_default();
+      let changes = find_nuc_motifs_changes_one(motifs_ref, motifs_qry, ref_seq, qry_seq_stripped, alignment_range);
+      (name.clone(), changes)
+    })
+    .collect()
+}
+
+fn find_nuc_motifs_changes_one(
+  motifs_ref: &[NucMotif],
+  motifs_qry: &[NucMotif],
+  ref_seq: &[Nuc],
+  qry_seq_stripped: &[Nuc],
+  alignment_range: &NucRefGlobalRange,
+) -> NucMotifChanges {
+  // We want to find added and removed positions, so motifs are keyed by position, disregarding `.seq`
+  let motifs_ref: BTreeMap<NucRefGlobalPosition, &NucMotif> = motifs_ref.iter().map(|m| (m.position, m)).collect();
+  let motifs_qry: BTreeMap<NucRefGlobalPosition, &NucMotif> = motifs_qry.iter().map(|m| (m.position, m)).collect();
+
+  // Gained motifs: not present in ref, present in qry
+  let gained = motifs_qry
+    .iter()
+    .filter(|(pos, _)| !motifs_ref.contains_key(pos))
+    .map(|(_, motif)| NucMotifMutation {
+      name: motif.name.clone(),
+      position: motif.position,
+      ref_seq: get_fragment(ref_seq, motif.position, motif.seq.len()),
+      qry_seq: motif.seq.clone(),
+    })
+    .collect_vec();
+
+  // Lost motifs: present in ref, not present in query.
+  // Ambiguous motifs: present in ref, contain ambiguous nucleotides in query.
+  let (lost, ambiguous): (Vec<NucMotifMutation>, Vec<NucMotifMutation>) = motifs_ref
+    .iter()
+    .filter(|(pos, _)| !motifs_qry.contains_key(pos))
+    .filter(|(_, motif)| {
+      let end = motif.position + motif.seq.len() as isize;
+      alignment_range.contains(motif.position) && end <= alignment_range.end
+    })
+    .partition_map(|(_, motif)| {
+      let begin = motif.position.as_usize();
+      let qry_nucs = qry_seq_stripped.get(begin..begin + motif.seq.len()).unwrap_or_default();
+      let motif_change = NucMotifMutation {
+        name: motif.name.clone(),
+        position: motif.position,
+        ref_seq: motif.seq.clone(),
+        qry_seq: from_nuc_seq(qry_nucs),
+      };
+      if qry_nucs.iter().any(|nuc| !nuc.is_acgt() && !nuc.is_gap()) {
+        Either::Right(motif_change)
+      } else {
+        Either::Left(motif_change)
+      }
+    });
+
+  // Preserved motifs: present in ref and qry
+  let preserved = motifs_ref
+    .iter()
+    .filter_map(|(pos, motif_ref)| {
+      motifs_qry.get(pos).map(|motif_qry| NucMotifMutation {
+        name: motif_ref.name.clone(),
+        position: motif_ref.position,
+        ref_seq: motif_ref.seq.clone(),
+        qry_seq: motif_qry.seq.clone(),
+      })
+    })
+    .collect_vec();
+
+  let total = gained.len() + preserved.len();
+
+  NucMotifChanges {
+    preserved,
+    gained,
+    lost,
+    ambiguous,
+    total,
+  }
+}
+
+fn get_fragment(seq: &[Nuc], pos: NucRefGlobalPosition, len: usize) -> String {
+  let begin = pos.as_usize();
+  seq.get(begin..begin + len).map(from_nuc_seq).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::o;
+  use eyre::Report;
+  use maplit::btreemap;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn motif(position: isize, seq: &str) -> NucMotif {
+    NucMotif {
+      name: o!("CpG"),
+      position: NucRefGlobalPosition::new(position),
+      seq: seq.to_owned(),
+    }
+  }
+
+  #[rstest]
+  fn finds_nuc_motifs_changes() -> Result<(), Report> {
+    //                        0123456789
+    let ref_seq = to_nuc_seq("ACGTTCGACG")?;
+    let qry_seq = to_nuc_seq("ACGCGTNACA")?;
+    let motifs_ref = btreemap! { o!("CpG") => vec![motif(1, "CG"), motif(5, "CG"), motif(8, "CG")] };
+    let motifs_qry = btreemap! { o!("CpG") => vec![motif(1, "CG"), motif(3, "CG")] };
+    let alignment_range = NucRefGlobalRange::from_usize(0, 10);
+
+    let changes = &find_nuc_motifs_changes(&motifs_ref, &motifs_qry, &ref_seq, &qry_seq, &alignment_range)["CpG"];
+
+    let positions = |motifs: &[NucMotifMutation]| {
+      motifs
+        .iter()
+        .map(|m| (m.position.as_isize(), m.ref_seq.clone(), m.qry_seq.clone()))
+        .collect_vec()
+    };
+
+    assert_eq!(positions(&changes.preserved), vec![(1, o!("CG"), o!("CG"))]);
+    assert_eq!(positions(&changes.gained), vec![(3, o!("TT"), o!("CG"))]);
+    assert_eq!(positions(&changes.lost), vec![(8, o!("CG"), o!("CA"))]);
+    assert_eq!(positions(&changes.ambiguous), vec![(5, o!("CG"), o!("TN"))]);
+    assert_eq!(changes.total, 2);
+    Ok(())
+  }
+}
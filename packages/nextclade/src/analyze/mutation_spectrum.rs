@@ -0,0 +1,253 @@
+use crate::alphabet::nuc::{Nuc, from_nuc, is_nuc_match, to_nuc, to_nuc_seq};
+use crate::analyze::nuc_sub::NucSub;
+use crate::analyze::virus_properties::MutationSignatureDesc;
+use crate::coord::position::PositionLike;
+use crate::make_error;
+use eyre::{Report, WrapErr};
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+const ACGT: [Nuc; 4] = [Nuc::A, Nuc::C, Nuc::G, Nuc::T];
+
+/// Nucleotide substitution counts of a query sequence, by substitution class and by mutation signature
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationSpectrum {
+  /// Number of substitutions in each of the 12 classes of substitutions between unambiguous nucleotides (e.g. "C>T")
+  pub spectrum: BTreeMap<String, usize>,
+  /// Total number of substitutions between unambiguous nucleotides
+  pub total_substitutions: usize,
+  /// Number of substitutions matching each of the mutation signatures defined in the dataset
+  pub signatures: Vec<MutationSignatureCount>,
+}
+
+/// Number of substitutions matching one of the mutation signatures
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationSignatureCount {
+  /// Name of the mutation signature, as defined in `mutationSignatures` of the pathogen config
+  pub name: String,
+  /// Number of substitutions matching any of the contexts of the signature
+  pub count: usize,
+  /// Fraction of all substitutions between unambiguous nucleotides matching the signature (0 if there are none)
+  pub fraction: f64,
+}
+
+/// Mutation spectra of a query sequence, relative to the reference and for private mutations
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationSpectrumResult {
+  /// Spectrum of substitutions relative to the reference sequence
+  pub substitutions: MutationSpectrum,
+  /// Spectrum of private substitutions, relative to the nearest reference tree node. Absent if there is no reference tree.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub private_substitutions: Option<MutationSpectrum>,
+}
+
+/// Substitution in its sequence context, e.g. `T[C>T]`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MutationContext {
+  pub left: Vec<Nuc>,
+  pub ref_nuc: Nuc,
+  pub qry_nuc: Nuc,
+  pub right: Vec<Nuc>,
+}
+
+impl MutationContext {
+  pub fn parse(context: &str) -> Result<Self, Report> {
+    static RE: LazyLock<Regex> = LazyLock::new(|| {
+      Regex::new(r"^(?P<left>[A-Z]*)\[(?P<ref>[A-Z])>(?P<qry>[A-Z])\](?P<right>[A-Z]*)$")
+        .expect("Invalid regular expression")
+    });
+
+    let context_upper = context.trim().to_uppercase();
+    let Some(captures) = RE.captures(&context_upper) else {
+      return make_error!(
+        "Expected mutation context in format '<5p flank>[<ref>><qry>]<3p flank>' (e.g. 'T[C>T]'), but got: '{context}'"
+      );
+    };
+
+    let letter = |name: &str| to_nuc(captures[name].chars().next().unwrap_or_default());
+    Ok(Self {
+      left: to_nuc_seq(&captures["left"])?,
+      ref_nuc: letter("ref")?,
+      qry_nuc: letter("qry")?,
+      right: to_nuc_seq(&captures["right"])?,
+    })
+  }
+
+  /// Whether a substitution matches this context, with flanking nucleotides taken from the reference sequence
+  pub fn matches(&self, sub: &NucSub, ref_seq: &[Nuc]) -> bool {
+    let pos = sub.pos.as_usize();
+    if pos < self.left.len() {
+      return false;
+    }
+    let (Some(left), Some(right)) = (
+      ref_seq.get(pos - self.left.len()..pos),
+      ref_seq.get(pos + 1..pos + 1 + self.right.len()),
+    ) else {
+      return false;
+    };
+
+    is_nuc_match(self.ref_nuc, sub.ref_nuc)
+      && is_nuc_match(self.qry_nuc, sub.qry_nuc)
+      && self.left.iter().zip(left).all(|(&p, &n)| is_nuc_match(p, n))
+      && self.right.iter().zip(right).all(|(&p, &n)| is_nuc_match(p, n))
+  }
+}
+
+/// Mutation signature with parsed contexts
+struct MutationSignature<'a> {
+  name: &'a str,
+  contexts: Vec<MutationContext>,
+}
+
+/// Count substitutions by class (e.g. `C>T`) and by mutation signatures of the dataset.
+///
+/// Only substitutions between unambiguous nucleotides are counted. The sequence context of each substitution is taken
+/// from the reference sequence around the substitution position.
+pub fn find_mutation_spectrum(
+  signature_descs: &[MutationSignatureDesc],
+  substitutions: &[NucSub],
+  ref_seq: &[Nuc],
+) -> Result<MutationSpectrum, Report> {
+  let signatures = signature_descs
+    .iter()
+    .map(|desc| {
+      let contexts = desc
+        .contexts
+        .iter()
+        .map(|context| MutationContext::parse(context))
+        .collect::<Result<Vec<_>, Report>>()
+        .wrap_err_with(|| format!("When parsing contexts of mutation signature '{}'", desc.name))?;
+      Ok(MutationSignature {
+        name: &desc.name,
+        contexts,
+      })
+    })
+    .collect::<Result<Vec<_>, Report>>()?;
+
+  let substitutions = substitutions
+    .iter()
+    .filter(|sub| sub.ref_nuc.is_acgt() && sub.qry_nuc.is_acgt() && sub.ref_nuc != sub.qry_nuc)
+    .collect_vec();
+
+  let total_substitutions = substitutions.len();
+
+  let mut spectrum: BTreeMap<String, usize> = ACGT
+    .iter()
+    .cartesian_product(ACGT.iter())
+    .filter(|(r, q)| r != q)
+    .map(|(&r, &q)| (format_sub_class(r, q), 0))
+    .collect();
+  for sub in &substitutions {
+    *spectrum.entry(format_sub_class(sub.ref_nuc, sub.qry_nuc)).or_default() += 1;
+  }
+
+  let signatures = signatures
+    .iter()
+    .map(|signature| {
+      let count = substitutions
+        .iter()
+        .filter(|sub| signature.contexts.iter().any(|context| context.matches(sub, ref_seq)))
+        .count();
+      let fraction = if total_substitutions == 0 {
+        0.0
+      } else {
+        count as f64 / total_substitutions as f64
+      };
+      MutationSignatureCount {
+        name: signature.name.to_owned(),
+        count,
+        fraction,
+      }
+    })
+    .collect_vec();
+
+  Ok(MutationSpectrum {
+    spectrum,
+    total_substitutions,
+    signatures,
+  })
+}
+
+fn format_sub_class(ref_nuc: Nuc, qry_nuc: Nuc) -> String {
+  format!("{}>{}", from_nuc(ref_nuc), from_nuc(qry_nuc))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::coord::position::NucRefGlobalPosition;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn sub(pos: isize, ref_nuc: Nuc, qry_nuc: Nuc) -> NucSub {
+    NucSub {
+      pos: NucRefGlobalPosition::new(pos),
+      ref_nuc,
+      qry_nuc,
+    }
+  }
+
+  #[rstest]
+  #[case::dinucleotide_5p("T[C>T]", true)]
+  #[case::dinucleotide_3p("[C>T]C", true)]
+  #[case::trinucleotide("T[C>T]C", true)]
+  #[case::ambiguous_flank("W[C>T]S", true)]
+  #[case::mismatched_flank("G[C>T]", false)]
+  #[case::mismatched_qry("T[C>A]", false)]
+  fn matches_contexts(#[case] context: &str, #[case] expected: bool) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ATCCA")?;
+    let context = MutationContext::parse(context)?;
+    assert_eq!(context.matches(&sub(2, Nuc::C, Nuc::T), &ref_seq), expected);
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_invalid_context() {
+    let report = MutationContext::parse("TC>TT").unwrap_err();
+    assert!(report.to_string().contains("Expected mutation context in format"));
+  }
+
+  #[rstest]
+  fn counts_spectrum_and_signatures() -> Result<(), Report> {
+    //                        0123456789
+    let ref_seq = to_nuc_seq("ATCGAAGATC")?;
+    let substitutions = vec![
+      sub(2, Nuc::C, Nuc::T), // T[C>T]
+      sub(6, Nuc::G, Nuc::A), // [G>A]A
+      sub(9, Nuc::C, Nuc::T), // T[C>T] at the end
+      sub(3, Nuc::G, Nuc::A), // [G>A]A
+      sub(0, Nuc::A, Nuc::G),
+      sub(4, Nuc::A, Nuc::N),
+    ];
+    let descs = vec![MutationSignatureDesc {
+      name: "APOBEC3".to_owned(),
+      contexts: vec!["T[C>T]".to_owned(), "[G>A]A".to_owned()],
+      ..MutationSignatureDesc::default()
+    }];
+
+    let actual = find_mutation_spectrum(&descs, &substitutions, &ref_seq)?;
+
+    let nonzero = actual.spectrum.iter().filter(|(_, count)| **count > 0).collect_vec();
+    assert_eq!(actual.spectrum.len(), 12);
+    assert_eq!(
+      nonzero,
+      vec![
+        (&"A>G".to_owned(), &1),
+        (&"C>T".to_owned(), &2),
+        (&"G>A".to_owned(), &2)
+      ]
+    );
+    assert_eq!(actual.total_substitutions, 5);
+    assert_eq!(
+      (actual.signatures[0].name.as_str(), actual.signatures[0].count),
+      ("APOBEC3", 4)
+    );
+    Ok(())
+  }
+}
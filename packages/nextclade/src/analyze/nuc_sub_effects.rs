@@ -0,0 +1,458 @@
+use crate::alphabet::aa::Aa;
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::coord_map_cds_to_global::global_ref_pos_to_local;
+use crate::coord::position::{AaRefPosition, NucRefGlobalPosition, PositionLike};
+use crate::gene::cds::Cds;
+use crate::gene::gene::GeneStrand;
+use crate::gene::gene_map::GeneMap;
+use crate::translate::complement::complement;
+use crate::translate::translate_genes::Translation;
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Effect of a nucleotide substitution on the protein encoded by a CDS
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum NucSubEffectType {
+  /// Codon encodes the same amino acid
+  Synonymous,
+  /// Codon encodes a different amino acid
+  Nonsynonymous,
+  /// Codon becomes a stop codon
+  StopGained,
+  /// Stop codon becomes a sense codon
+  StopLost,
+  /// Start codon of the CDS no longer encodes methionine
+  StartLost,
+  /// Effect cannot be determined, because the codon contains ambiguous nucleotides
+  Ambiguous,
+  /// Codon contains more than one substitution, so the effect of each substitution cannot be told apart
+  MultipleHits,
+  /// Substitution is outside of any CDS
+  Noncoding,
+}
+
+impl NucSubEffectType {
+  /// Whether the substitution changes the encoded protein. Changes involving stop and start codons are counted as
+  /// nonsynonymous. Multiple hits in a codon are neither synonymous nor nonsynonymous.
+  pub const fn is_nonsynonymous(self) -> bool {
+    matches!(
+      self,
+      Self::Nonsynonymous | Self::StopGained | Self::StopLost | Self::StartLost
+    )
+  }
+}
+
+impl Display for NucSubEffectType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let s = match self {
+      Self::Synonymous => "synonymous",
+      Self::Nonsynonymous => "nonsynonymous",
+      Self::StopGained => "stopGained",
+      Self::StopLost => "stopLost",
+      Self::StartLost => "startLost",
+      Self::Ambiguous => "ambiguous",
+      Self::MultipleHits => "multipleHits",
+      Self::Noncoding => "noncoding",
+    };
+    write!(f, "{s}")
+  }
+}
+
+/// Effect of a nucleotide substitution in one of the CDSes overlapping it (or outside of any CDS)
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NucSubEffect {
+  /// 0-based position in the reference sequence
+  pub pos: NucRefGlobalPosition,
+  /// Nucleotide before the substitution
+  pub ref_nuc: Nuc,
+  /// Nucleotide after the substitution
+  pub qry_nuc: Nuc,
+  /// Effect of the substitution
+  pub effect: NucSubEffectType,
+  /// Name of the affected CDS (absent for noncoding substitutions)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cds_name: Option<String>,
+  /// 0-based position of the affected codon in the CDS
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub codon: Option<AaRefPosition>,
+  /// Amino acid encoded by the codon before the substitution (before all substitutions in the codon for multiple hits)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ref_aa: Option<Aa>,
+  /// Amino acid encoded by the codon after the substitution (after all substitutions in the codon for multiple hits)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub qry_aa: Option<Aa>,
+}
+
+/// Classifies nucleotide substitutions by their effect on every overlapping CDS.
+///
+/// Each substitution is considered in the codon context of the query sequence: the codon after the substitution is the
+/// codon of the query (the reference with all query substitutions `qry_subs` applied) and the codon before it is the
+/// same codon with the substitutions `subs` reverted. This way, the same rule applies to substitutions relative to the
+/// reference and to the substitutions relative to a tree node, where the codon before the substitution might differ
+/// from the reference. Substitutions sharing a codon with other substitutions are reported as multiple hits.
+/// Substitutions outside of any CDS are reported once, as noncoding.
+pub fn find_nuc_sub_effects(
+  subs: &[NucSub],
+  qry_subs: &[NucSub],
+  gene_map: &GeneMap,
+  ref_translation: &Translation,
+) -> Vec<NucSubEffect> {
+  if gene_map.is_empty() {
+    return vec![];
+  }
+
+  let cds_codon_changes = gene_map
+    .iter_cdses()
+    .filter_map(|cds| {
+      let ref_cds_seq = &ref_translation.get_cds(&cds.name).ok()?.nuc_seq;
+      Some((cds, find_codon_changes(subs, qry_subs, cds, ref_cds_seq)))
+    })
+    .collect_vec();
+
+  subs
+    .iter()
+    .flat_map(|sub| {
+      let effects = cds_codon_changes
+        .iter()
+        .flat_map(|(cds, codon_changes)| nuc_sub_effects_for_cds(sub, cds, codon_changes))
+        .collect_vec();
+
+      if effects.is_empty() {
+        vec![NucSubEffect {
+          pos: sub.pos,
+          ref_nuc: sub.ref_nuc,
+          qry_nuc: sub.qry_nuc,
+          effect: NucSubEffectType::Noncoding,
+          cds_name: None,
+          codon: None,
+          ref_aa: None,
+          qry_aa: None,
+        }]
+      } else {
+        effects
+      }
+    })
+    .collect()
+}
+
+/// Codon of a CDS before and after a set of substitutions
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CodonChange {
+  /// Codon before the substitutions
+  pub ref_codon: [Nuc; 3],
+  /// Codon after the substitutions
+  pub qry_codon: [Nuc; 3],
+  /// Number of substitutions in the codon
+  pub n_hits: usize,
+}
+
+/// Finds codons of a CDS affected by the substitutions `subs`, given all substitutions of the query `qry_subs` and the
+/// reference nucleotide sequence of the CDS
+pub fn find_codon_changes(
+  subs: &[NucSub],
+  qry_subs: &[NucSub],
+  cds: &Cds,
+  ref_cds_seq: &[Nuc],
+) -> BTreeMap<AaRefPosition, CodonChange> {
+  let mut codon_changes = BTreeMap::new();
+  for sub in subs {
+    for (codon, _) in codon_locations(cds, sub.pos) {
+      let codon_begin = codon.as_usize() * 3;
+      let Some(ref_codon) = ref_cds_seq
+        .get(codon_begin..codon_begin + 3)
+        .and_then(|codon| <[Nuc; 3]>::try_from(codon).ok())
+      else {
+        continue;
+      };
+      codon_changes
+        .entry(codon)
+        .or_insert(CodonChange {
+          ref_codon,
+          qry_codon: ref_codon,
+          n_hits: 0,
+        })
+        .n_hits += 1;
+    }
+  }
+
+  // Fill the changed codons with the nucleotides of the query, then revert the substitutions to get the codons before
+  for sub in qry_subs {
+    for (codon, phase) in codon_locations(cds, sub.pos) {
+      if let Some(change) = codon_changes.get_mut(&codon) {
+        let qry_nuc = nuc_on_strand(cds, sub.pos, sub.qry_nuc);
+        change.ref_codon[phase] = qry_nuc;
+        change.qry_codon[phase] = qry_nuc;
+      }
+    }
+  }
+  for sub in subs {
+    for (codon, phase) in codon_locations(cds, sub.pos) {
+      if let Some(change) = codon_changes.get_mut(&codon) {
+        change.ref_codon[phase] = nuc_on_strand(cds, sub.pos, sub.ref_nuc);
+        change.qry_codon[phase] = nuc_on_strand(cds, sub.pos, sub.qry_nuc);
+      }
+    }
+  }
+
+  codon_changes
+}
+
+/// Classifies a nucleotide substitution by its effect on one CDS, given the changed codons of the CDS.
+/// Returns one effect for every codon of the CDS containing the position (e.g. more than one with ribosomal slippage).
+pub fn nuc_sub_effects_for_cds(
+  sub: &NucSub,
+  cds: &Cds,
+  codon_changes: &BTreeMap<AaRefPosition, CodonChange>,
+) -> Vec<NucSubEffect> {
+  codon_locations(cds, sub.pos)
+    .into_iter()
+    .filter_map(|(codon, _)| {
+      let CodonChange {
+        ref_codon,
+        qry_codon,
+        n_hits,
+      } = codon_changes.get(&codon)?;
+
+      let ref_aa = cds.genetic_code.decode(ref_codon);
+      let qry_aa = cds.genetic_code.decode(qry_codon);
+
+      let effect = if *n_hits > 1 {
+        NucSubEffectType::MultipleHits
+      } else {
+        classify_codon_change(codon, ref_aa, qry_aa)
+      };
+
+      Some(NucSubEffect {
+        pos: sub.pos,
+        ref_nuc: sub.ref_nuc,
+        qry_nuc: sub.qry_nuc,
+        effect,
+        cds_name: Some(cds.name.clone()),
+        codon: Some(codon),
+        ref_aa: Some(ref_aa),
+        qry_aa: Some(qry_aa),
+      })
+    })
+    .collect()
+}
+
+/// Codons of a CDS containing a given reference position, with the position inside of the codon
+fn codon_locations(cds: &Cds, pos: NucRefGlobalPosition) -> Vec<(AaRefPosition, usize)> {
+  global_ref_pos_to_local(cds, pos)
+    .into_iter()
+    .map(|local| (AaRefPosition::new(local.as_isize() / 3), local.as_usize() % 3))
+    .collect()
+}
+
+/// Nucleotide as read on the strand of the CDS segment containing the position
+fn nuc_on_strand(cds: &Cds, pos: NucRefGlobalPosition, nuc: Nuc) -> Nuc {
+  let strand = cds
+    .segments
+    .iter()
+    .find(|segment| segment.range.contains(pos))
+    .map_or(GeneStrand::Forward, |segment| segment.strand);
+  match strand {
+    GeneStrand::Forward => nuc,
+    GeneStrand::Reverse => complement(nuc),
+  }
+}
+
+fn classify_codon_change(codon: AaRefPosition, ref_aa: Aa, qry_aa: Aa) -> NucSubEffectType {
+  if ref_aa == Aa::X || qry_aa == Aa::X {
+    NucSubEffectType::Ambiguous
+  } else if ref_aa == qry_aa {
+    NucSubEffectType::Synonymous
+  } else if codon.as_usize() == 0 && ref_aa == Aa::M {
+    NucSubEffectType::StartLost
+  } else if qry_aa.is_stop() {
+    NucSubEffectType::StopGained
+  } else if ref_aa.is_stop() {
+    NucSubEffectType::StopLost
+  } else {
+    NucSubEffectType::Nonsynonymous
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::{to_nuc, to_nuc_seq};
+  use crate::coord::range::{NucRefGlobalRange, Range};
+  use crate::gene::cds_segment::{CdsSegment, Truncation, WrappingPart};
+  use crate::gene::frame::Frame;
+  use crate::gene::phase::Phase;
+  use crate::translate::extract::extract_cds_from_ref;
+  use crate::translate::genetic_code::GeneticCode;
+  use eyre::Report;
+  use indexmap::indexmap;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn create_cds(begin: isize, end: isize, strand: GeneStrand) -> Cds {
+    let range = NucRefGlobalRange::from_isize(begin, end);
+    Cds {
+      id: "C".to_owned(),
+      name: "C".to_owned(),
+      product: "C".to_owned(),
+      segments: vec![CdsSegment {
+        index: 0,
+        id: "C".to_owned(),
+        name: "C".to_owned(),
+        range: range.clone(),
+        range_local: Range::from_usize(0, range.len()),
+        landmark: None,
+        wrapping_part: WrappingPart::NonWrapping,
+        strand,
+        frame: Frame::from_begin(range.begin).unwrap(),
+        phase: Phase::from_begin(Range::from_usize(0, 0).begin).unwrap(),
+        truncation: Truncation::default(),
+        exceptions: vec![],
+        attributes: indexmap! {},
+        source_record: None,
+        compat_is_gene: false,
+        color: None,
+        gff_seqid: None,
+        gff_source: None,
+        gff_feature_type: None,
+      }],
+      proteins: vec![],
+      exceptions: vec![],
+      attributes: indexmap! {},
+      genetic_code: GeneticCode::default(),
+      rna_editing: vec![],
+      compat_is_gene: false,
+      color: None,
+    }
+  }
+
+  //                   ATG AAA TGG CAA TAA
+  const REF_SEQ: &str = "CCATGAAATGGCAATAACC";
+
+  #[rstest]
+  #[case::synonymous(7, 'A', 'G', NucSubEffectType::Synonymous)]
+  #[case::nonsynonymous(5, 'A', 'C', NucSubEffectType::Nonsynonymous)]
+  #[case::stop_gained(11, 'C', 'T', NucSubEffectType::StopGained)]
+  #[case::stop_lost(15, 'A', 'C', NucSubEffectType::StopLost)]
+  #[case::start_lost(4, 'G', 'A', NucSubEffectType::StartLost)]
+  #[case::ambiguous(5, 'A', 'N', NucSubEffectType::Ambiguous)]
+  #[case::noncoding(0, 'C', 'T', NucSubEffectType::Noncoding)]
+  fn classifies_substitutions_forward(
+    #[case] pos: isize,
+    #[case] ref_nuc: char,
+    #[case] qry_nuc: char,
+    #[case] expected: NucSubEffectType,
+  ) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let cds = create_cds(2, 17, GeneStrand::Forward);
+    let ref_cds_seq = extract_cds_from_ref(&ref_seq, &cds);
+    let sub = NucSub {
+      pos: pos.into(),
+      ref_nuc: to_nuc(ref_nuc)?,
+      qry_nuc: to_nuc(qry_nuc)?,
+    };
+    let subs = [sub.clone()];
+    let codon_changes = find_codon_changes(&subs, &subs, &cds, &ref_cds_seq);
+    let actual = nuc_sub_effects_for_cds(&sub, &cds, &codon_changes)
+      .first()
+      .map_or(NucSubEffectType::Noncoding, |effect| effect.effect);
+    assert_eq!(actual, expected);
+    Ok(())
+  }
+
+  #[rstest]
+  fn classifies_substitution_on_reverse_strand() -> Result<(), Report> {
+    // Reverse complement of "ATGAAATGGCAATAA"
+    let ref_seq = to_nuc_seq("TTATTGCCATTTCAT")?;
+    let cds = create_cds(0, 15, GeneStrand::Reverse);
+    let ref_cds_seq = extract_cds_from_ref(&ref_seq, &cds);
+
+    // Third position of codon 'TGG' (W): 'G' -> 'A' on the CDS strand makes a stop codon 'TGA'
+    let sub = NucSub {
+      pos: 6.into(),
+      ref_nuc: Nuc::C,
+      qry_nuc: Nuc::T,
+    };
+
+    let subs = [sub.clone()];
+    let codon_changes = find_codon_changes(&subs, &subs, &cds, &ref_cds_seq);
+    let effects = nuc_sub_effects_for_cds(&sub, &cds, &codon_changes);
+    assert_eq!(
+      effects,
+      vec![NucSubEffect {
+        pos: 6.into(),
+        ref_nuc: Nuc::C,
+        qry_nuc: Nuc::T,
+        effect: NucSubEffectType::StopGained,
+        cds_name: Some("C".to_owned()),
+        codon: Some(2.into()),
+        ref_aa: Some(Aa::W),
+        qry_aa: Some(Aa::Stop),
+      }]
+    );
+    Ok(())
+  }
+
+  fn sub(pos: isize, ref_nuc: Nuc, qry_nuc: Nuc) -> NucSub {
+    NucSub {
+      pos: pos.into(),
+      ref_nuc,
+      qry_nuc,
+    }
+  }
+
+  #[rstest]
+  fn reports_multiple_hits_in_codon() -> Result<(), Report> {
+    //                        CTT (L) -> TTA (L)
+    let ref_seq = to_nuc_seq("ATGCTTTAA")?;
+    let cds = create_cds(0, 9, GeneStrand::Forward);
+    let ref_cds_seq = extract_cds_from_ref(&ref_seq, &cds);
+    let subs = vec![sub(3, Nuc::C, Nuc::T), sub(5, Nuc::T, Nuc::A)];
+    let codon_changes = find_codon_changes(&subs, &subs, &cds, &ref_cds_seq);
+
+    let effects = subs
+      .iter()
+      .flat_map(|sub| nuc_sub_effects_for_cds(sub, &cds, &codon_changes))
+      .map(|effect| (effect.effect, effect.ref_aa, effect.qry_aa))
+      .collect_vec();
+
+    assert_eq!(
+      effects,
+      vec![(NucSubEffectType::MultipleHits, Some(Aa::L), Some(Aa::L)); 2]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn classifies_private_substitution_in_codon_of_node() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let cds = create_cds(2, 17, GeneStrand::Forward);
+    let ref_cds_seq = extract_cds_from_ref(&ref_seq, &cds);
+
+    // The node has codon 'TGA' (stop) instead of 'TGG' (W) of the reference. The private substitution makes it 'TCA'.
+    let qry_subs = vec![sub(9, Nuc::G, Nuc::C), sub(10, Nuc::G, Nuc::A)];
+    let private_subs = vec![sub(9, Nuc::G, Nuc::C)];
+    let codon_changes = find_codon_changes(&private_subs, &qry_subs, &cds, &ref_cds_seq);
+
+    let effects = nuc_sub_effects_for_cds(&private_subs[0], &cds, &codon_changes);
+    assert_eq!(
+      effects,
+      vec![NucSubEffect {
+        pos: 9.into(),
+        ref_nuc: Nuc::G,
+        qry_nuc: Nuc::C,
+        effect: NucSubEffectType::StopLost,
+        cds_name: Some("C".to_owned()),
+        codon: Some(2.into()),
+        ref_aa: Some(Aa::Stop),
+        qry_aa: Some(Aa::S),
+      }]
+    );
+    Ok(())
+  }
+}
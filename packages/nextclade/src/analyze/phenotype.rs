@@ -1,20 +1,73 @@
-use crate::analyze::aa_sub::AaSub;
-use crate::analyze::virus_properties::{PhenotypeAttrDesc, PhenotypeData, VirusProperties};
+use crate::alphabet::aa::Aa;
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::aa_del::AaDel;
+use crate::analyze::aa_sub::{AaGenotype, AaSub};
+use crate::analyze::nuc_del::NucDelRange;
+use crate::analyze::nuc_sub::NucSub;
+use crate::analyze::virus_properties::{
+  PhenotypeAttrDesc, PhenotypeData, PhenotypeModel, PhenotypeModelAdditive, PhenotypeModelEscape,
+  PhenotypeModelNucAdditive, VirusProperties,
+};
+use crate::gene::genotype::Genotype;
 use itertools::Itertools;
 
-pub fn calculate_phenotype(phenotype_data: &PhenotypeData, aa_substitutions: &[AaSub]) -> f64 {
-  let aa_substitutions = aa_substitutions
+/// Mutations of a query sequence, relative to reference, which phenotype scores are calculated from
+pub struct PhenotypeMutations<'a> {
+  pub aa_substitutions: &'a [AaSub],
+  pub aa_deletions: &'a [AaDel],
+  pub substitutions: &'a [NucSub],
+  pub deletions: &'a [NucDelRange],
+}
+
+impl PhenotypeMutations<'_> {
+  /// Amino acid substitutions and deletions as genotypes. Deletions have gap as the query amino acid.
+  fn aa_genotypes(&self) -> Vec<AaGenotype> {
+    let subs = self.aa_substitutions.iter().map(AaSub::genotype);
+    let dels = self.aa_deletions.iter().map(|del| AaGenotype {
+      cds_name: del.cds_name.clone(),
+      pos: del.pos,
+      qry: Some(Aa::Gap),
+    });
+    subs.chain(dels).collect_vec()
+  }
+
+  /// Nucleotide substitutions and deleted positions as genotypes. Deletions have gap as the query nucleotide.
+  fn nuc_genotypes(&self) -> Vec<Genotype<Nuc>> {
+    let subs = self.substitutions.iter().map(NucSub::genotype);
+    let dels = self
+      .deletions
+      .iter()
+      .flat_map(|del| del.range().iter())
+      .map(|pos| Genotype { pos, qry: Nuc::Gap });
+    subs.chain(dels).collect_vec()
+  }
+}
+
+pub fn calculate_phenotype(phenotype_data: &PhenotypeData, mutations: &PhenotypeMutations) -> f64 {
+  match &phenotype_data.model {
+    PhenotypeModel::Escape(model) => calculate_phenotype_escape(model, mutations),
+    PhenotypeModel::Additive(model) => calculate_phenotype_additive(model, mutations),
+    PhenotypeModel::NucAdditive(model) => calculate_phenotype_nuc_additive(model, mutations),
+  }
+}
+
+/// Escape-style model considers amino acid substitutions only. Deletions are not scored, for compatibility with
+/// existing datasets.
+fn calculate_phenotype_escape(model: &PhenotypeModelEscape, mutations: &PhenotypeMutations) -> f64 {
+  let aa_genotypes = mutations
+    .aa_substitutions
     .iter()
-    .filter(|sub| sub.cds_name == phenotype_data.cds && phenotype_data.aa_range.contains(sub.pos))
+    .map(AaSub::genotype)
+    .filter(|genotype| genotype.cds_name == model.cds && model.aa_range.contains(genotype.pos))
     .collect_vec();
 
-  let phenotype: f64 = phenotype_data
+  let phenotype: f64 = model
     .data
     .iter()
     .map(|phenotype_data| {
-      let phenotype_for_antibody: f64 = aa_substitutions
+      let phenotype_for_antibody: f64 = aa_genotypes
         .iter()
-        .map(|AaSub { pos, qry_aa: qry, .. }| phenotype_data.get_coeff(*pos, *qry))
+        .filter_map(|AaGenotype { pos, qry, .. }| qry.map(|qry| phenotype_data.get_coeff(*pos, qry)))
         .sum();
       *phenotype_data.weight * (-phenotype_for_antibody).exp()
     })
@@ -23,6 +76,61 @@ pub fn calculate_phenotype(phenotype_data: &PhenotypeData, aa_substitutions: &[A
   -phenotype.ln()
 }
 
+fn calculate_phenotype_additive(model: &PhenotypeModelAdditive, mutations: &PhenotypeMutations) -> f64 {
+  let aa_genotypes = mutations.aa_genotypes();
+
+  let mutations_score: f64 = model
+    .cdses
+    .iter()
+    .flat_map(|cds_coeffs| {
+      aa_genotypes
+        .iter()
+        .filter(|genotype| genotype.cds_name == cds_coeffs.cds)
+        .filter_map(|AaGenotype { pos, qry, .. }| {
+          let coeff = cds_coeffs.locations.get(pos)?;
+          qry.map(|qry| coeff.get_coeff(qry))
+        })
+    })
+    .sum();
+
+  let epistasis_score: f64 = model
+    .epistasis
+    .iter()
+    .filter(|term| {
+      term
+        .mutations
+        .iter()
+        .all(|mutation| aa_genotypes.iter().any(|genotype| mutation.matches(genotype)))
+    })
+    .map(|term| *term.coeff)
+    .sum();
+
+  *model.intercept + mutations_score + epistasis_score
+}
+
+fn calculate_phenotype_nuc_additive(model: &PhenotypeModelNucAdditive, mutations: &PhenotypeMutations) -> f64 {
+  let nuc_genotypes = mutations.nuc_genotypes();
+
+  let mutations_score: f64 = nuc_genotypes
+    .iter()
+    .filter_map(|Genotype { pos, qry }| model.locations.get(pos).map(|coeff| coeff.get_coeff(*qry)))
+    .sum();
+
+  let epistasis_score: f64 = model
+    .epistasis
+    .iter()
+    .filter(|term| {
+      term
+        .mutations
+        .iter()
+        .all(|mutation| nuc_genotypes.iter().any(|genotype| mutation.matches(genotype)))
+    })
+    .map(|term| *term.coeff)
+    .sum();
+
+  *model.intercept + mutations_score + epistasis_score
+}
+
 pub fn get_phenotype_attr_descs(virus_properties: &VirusProperties) -> Vec<PhenotypeAttrDesc> {
   virus_properties
     .phenotype_data
@@ -45,3 +153,126 @@ pub fn get_phenotype_attr_keys(virus_properties: &VirusProperties) -> Vec<String
     .map(|ph| ph.name.clone())
     .collect_vec()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::io::json::json_parse;
+  use eyre::Report;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+  use std::str::FromStr;
+
+  fn aa_sub(s: &str) -> AaSub {
+    AaSub::from_str(s).unwrap()
+  }
+
+  fn aa_del(cds: &str, pos: usize) -> AaDel {
+    AaDel::from_raw(cds, pos, 'A').unwrap()
+  }
+
+  #[rstest]
+  fn calculates_escape_model_by_default() -> Result<(), Report> {
+    let phenotype_data: PhenotypeData = json_parse(
+      r#"{
+        "name": "escape",
+        "nameFriendly": "Escape",
+        "description": "",
+        "cds": "S",
+        "aaRange": { "begin": 0, "end": 100 },
+        "data": [{ "name": "ab", "weight": 1.0, "locations": { "9": 0.5, "19": { "K": 0.25, "-": 0.125 } } }]
+      }"#,
+    )?;
+    assert!(matches!(phenotype_data.model, PhenotypeModel::Escape(_)));
+
+    let aa_substitutions = [aa_sub("S:E10K"), aa_sub("S:E20K"), aa_sub("ORF1a:E10K")];
+    let aa_deletions = [aa_del("S", 19)];
+    let mutations = PhenotypeMutations {
+      aa_substitutions: &aa_substitutions,
+      aa_deletions: &aa_deletions,
+      substitutions: &[],
+      deletions: &[],
+    };
+
+    // Deletions are not scored by the escape model, even if they have a coefficient
+    let expected = -(-0.75_f64).exp().ln();
+    assert_eq!(
+      OrderedFloat(calculate_phenotype(&phenotype_data, &mutations)),
+      OrderedFloat(expected)
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn calculates_additive_model_with_deletions_and_epistasis() -> Result<(), Report> {
+    let phenotype_data: PhenotypeData = json_parse(
+      r#"{
+        "name": "resistance",
+        "nameFriendly": "Resistance",
+        "description": "",
+        "model": "additive",
+        "intercept": 1.0,
+        "cdses": [
+          { "cds": "PA", "locations": { "37": { "T": 2.0, "default": 0.5 } } },
+          { "cds": "NA", "locations": { "274": 4.0, "275": { "-": 8.0 }, "276": 16.0 } }
+        ],
+        "epistasis": [
+          { "mutations": [{ "cds": "PA", "pos": 37, "qry": "T" }, { "cds": "NA", "pos": 274, "qry": "Y" }], "coeff": 32.0 },
+          { "mutations": [{ "cds": "PA", "pos": 37, "qry": "T" }, { "cds": "NA", "pos": 277 }], "coeff": 64.0 },
+          { "mutations": [{ "cds": "PA", "pos": 37 }], "coeff": 128.0 }
+        ]
+      }"#,
+    )?;
+
+    let aa_substitutions = [aa_sub("PA:I38T"), aa_sub("NA:H275Y"), aa_sub("S:E484K")];
+    // Deletion at a position with coefficient for any amino acid does not contribute
+    let aa_deletions = [aa_del("NA", 275), aa_del("NA", 276)];
+    let mutations = PhenotypeMutations {
+      aa_substitutions: &aa_substitutions,
+      aa_deletions: &aa_deletions,
+      substitutions: &[],
+      deletions: &[],
+    };
+
+    assert_eq!(
+      OrderedFloat(calculate_phenotype(&phenotype_data, &mutations)),
+      OrderedFloat(1.0 + 2.0 + 4.0 + 8.0 + 32.0 + 128.0)
+    );
+    assert_eq!(phenotype_data.model.cds_names(), vec!["PA".to_owned(), "NA".to_owned()]);
+    Ok(())
+  }
+
+  #[rstest]
+  fn calculates_nuc_additive_model() -> Result<(), Report> {
+    let phenotype_data: PhenotypeData = json_parse(
+      r#"{
+        "name": "nuc",
+        "nameFriendly": "Nuc",
+        "description": "",
+        "model": "nucAdditive",
+        "locations": { "9": { "A": 1.0 }, "19": 2.0, "21": { "-": 4.0 } },
+        "epistasis": [
+          { "mutations": [{ "pos": 9, "qry": "A" }, { "pos": 21, "qry": "-" }], "coeff": 8.0 },
+          { "mutations": [{ "pos": 9 }, { "pos": 19 }], "coeff": 16.0 },
+          { "mutations": [{ "pos": 9 }, { "pos": 20 }], "coeff": 32.0 }
+        ]
+      }"#,
+    )?;
+
+    let substitutions = [NucSub::from_str("C10A")?, NucSub::from_str("C20G")?];
+    let deletions = [NucDelRange::new(21.into(), 23.into())];
+    let mutations = PhenotypeMutations {
+      aa_substitutions: &[],
+      aa_deletions: &[],
+      substitutions: &substitutions,
+      deletions: &deletions,
+    };
+
+    assert_eq!(
+      OrderedFloat(calculate_phenotype(&phenotype_data, &mutations)),
+      OrderedFloat(1.0 + 2.0 + 4.0 + 8.0 + 16.0)
+    );
+    Ok(())
+  }
+}
@@ -1,27 +1,33 @@
 use crate::align::params::AlignPairwiseParamsOptional;
 use crate::alphabet::aa::Aa;
+use crate::alphabet::letter::Letter;
 use crate::alphabet::nuc::Nuc;
 use crate::analyze::aa_changes_find_for_cds::AaChangesParamsOptional;
 use crate::analyze::aa_sub::AaGenotype;
 use crate::coord::position::AaRefPosition;
 use crate::coord::position::NucRefGlobalPosition;
-use crate::coord::range::AaRefRange;
+use crate::coord::range::{AaRefRange, NucRefGlobalRange};
 use crate::gene::genotype::Genotype;
+use crate::gene::rna_editing::RnaEditingSite;
 use crate::io::dataset::{DatasetCompatibility, DatasetFiles, DatasetMeta, DatasetVersion};
 use crate::io::fs::read_file_to_string;
 use crate::io::json::json_parse;
 use crate::io::schema_version::{SchemaVersion, SchemaVersionParams};
 use crate::qc::qc_config::QcConfig;
 use crate::run::params_general::NextcladeGeneralParamsOptional;
+use crate::translate::genetic_code::GeneticCode;
 use crate::tree::params::TreeBuilderParamsOptional;
 use crate::utils::any::AnyType;
 use crate::{o, vec_of_owned};
 use eyre::{Report, WrapErr};
+use itertools::Itertools;
 use maplit::btreemap;
 use ordered_float::OrderedFloat;
 use schemars;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, SubschemaValidation};
 use semver::Version;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::BTreeMap;
 use std::path::Path;
 use validator::Validate;
@@ -87,6 +93,17 @@ pub struct VirusProperties {
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub cds_order_preference: Vec<String>,
 
+  /// Genetic codes (NCBI translation table numbers, as in the GFF3 `transl_table` attribute) of CDSes, by CDS name.
+  /// Overrides the genetic code set in the genome annotation. CDSes not listed here use the standard code, unless set
+  /// in the genome annotation.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub genetic_codes: BTreeMap<String, GeneticCode>,
+
+  /// RNA editing sites of CDSes, by CDS name: non-templated nucleotides inserted into the transcript (e.g. in
+  /// paramyxovirus P/V/W genes). Applied in addition to the sites declared in the genome annotation.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub rna_editing: BTreeMap<String, Vec<RnaEditingSite>>,
+
   /// Mapping from nucleotide and amino acid mutations to human-readable labels (e.g. clade-defining mutations).
   #[serde(default)]
   pub mut_labels: LabelledMutationsConfig,
@@ -118,6 +135,14 @@ pub struct VirusProperties {
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub aa_motifs: Vec<AaMotifsDesc>,
 
+  /// Nucleotide motifs to search for in query sequences, such as dinucleotides, transcription regulatory sequences or restriction sites
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub nuc_motifs: Vec<NucMotifsDesc>,
+
+  /// Mutation signatures: sets of nucleotide substitutions in their sequence context (e.g. APOBEC3 editing), to be counted in query sequences.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub mutation_signatures: Vec<MutationSignatureDesc>,
+
   /// Available dataset versions. Populated from the dataset index, not from pathogen.json directly.
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub versions: Vec<DatasetVersion>,
@@ -231,6 +256,10 @@ pub struct PhenotypeDataIgnore {
 }
 
 /// Coefficient for a phenotype data entry. Can be a single value for all amino acids at a position, or a mapping from amino acids to their specific values.
+///
+/// In additive models, deletions (`-`) only contribute to the score if their coefficient is listed explicitly: neither
+/// the value for all amino acids nor the "default" value applies to deletions. The escape model does not score
+/// deletions.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[serde(untagged)]
@@ -243,12 +272,14 @@ pub enum PhenotypeCoeff {
 }
 
 impl PhenotypeCoeff {
-  pub fn get_coeff(&self, aa: Aa) -> f64 {
+  pub fn get_coeff<L: Letter<L>>(&self, letter: L) -> f64 {
     match self {
+      PhenotypeCoeff::ByPosition(_) if letter.is_gap() => None,
       PhenotypeCoeff::ByPosition(coeff) => Some(coeff.0),
-      PhenotypeCoeff::ByPositionAndAa(aa_coeff_map) => aa_coeff_map
-        .get(&aa.to_string())
-        .or_else(|| aa_coeff_map.get("default"))
+      PhenotypeCoeff::ByPositionAndAa(coeff_map) if letter.is_gap() => coeff_map.get("-").map(|c| c.0),
+      PhenotypeCoeff::ByPositionAndAa(coeff_map) => coeff_map
+        .get(&letter.to_string())
+        .or_else(|| coeff_map.get("default"))
         .map(|c| c.0),
       PhenotypeCoeff::Other(_) => None,
     }
@@ -266,7 +297,7 @@ pub struct PhenotypeDataEntry {
   pub name: String,
   /// Relative weight of this entry when combining multiple entries into a final phenotype score.
   pub weight: OrderedFloat<f64>,
-  /// Per-position coefficients mapping amino acid reference positions to their contribution to the score.
+  /// Per-position coefficients mapping 0-based amino acid reference positions to their contribution to the score.
   pub locations: BTreeMap<AaRefPosition, PhenotypeCoeff>,
 }
 
@@ -292,9 +323,187 @@ impl PhenotypeDataEntry {
   }
 }
 
-/// Describes a phenotype, such as receptor binding, immune escape, etc.
+/// Escape-style phenotype model: `-ln(sum_i(w_i * exp(-sum_j(c_ij))))`, where `w_i` are the weights of the data
+/// entries and `c_ij` are the coefficients of the amino acid mutations in a single CDS range.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeModelEscape {
+  /// CDS on which this phenotype is evaluated (e.g. "S", "HA1").
+  pub cds: String,
+  /// Amino acid range within the CDS where phenotype-relevant mutations are considered.
+  pub aa_range: AaRefRange,
+  /// Per-position coefficient tables for computing the phenotype score.
+  pub data: Vec<PhenotypeDataEntry>,
+}
+
+/// Per-position coefficients of the amino acid mutations in one CDS
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeCdsCoeffs {
+  /// Name of the CDS
+  pub cds: String,
+  /// Per-position coefficients mapping 0-based amino acid reference positions to their contribution to the score.
+  pub locations: BTreeMap<AaRefPosition, PhenotypeCoeff>,
+}
+
+/// Amino acid mutation in an epistasis term
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeAaMutation {
+  /// Name of the CDS
+  pub cds: String,
+  /// 0-based amino acid reference position, same as in `locations`
+  pub pos: AaRefPosition,
+  /// Query amino acid, or absent to match any change at this position
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub qry: Option<Aa>,
+}
+
+impl PhenotypeAaMutation {
+  pub fn matches(&self, genotype: &AaGenotype) -> bool {
+    let AaGenotype { cds_name, pos, qry } = genotype;
+    &self.cds == cds_name && self.pos == *pos && self.qry.is_none_or(|aa| Some(aa) == *qry)
+  }
+}
+
+/// Nucleotide mutation in an epistasis term
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeNucMutation {
+  /// 0-based nucleotide reference position, same as in `locations`
+  pub pos: NucRefGlobalPosition,
+  /// Query nucleotide, or absent to match any change at this position
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub qry: Option<Nuc>,
+}
+
+impl PhenotypeNucMutation {
+  pub fn matches(&self, genotype: &Genotype<Nuc>) -> bool {
+    self.pos == genotype.pos && self.qry.is_none_or(|qry| qry == genotype.qry)
+  }
+}
+
+/// Interaction term, which contributes to the score only if all of the listed mutations are present in the query
+/// (e.g. a pair of mutations with an epistatic effect).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeEpistasisTerm<M> {
+  /// Mutations which should all be present, e.g. `[{ "cds": "S", "pos": 483, "qry": "K" }]` for amino acids or
+  /// `[{ "pos": 23011, "qry": "A" }]` for nucleotides
+  pub mutations: Vec<M>,
+  /// Contribution to the score when all of the mutations are present
+  pub coeff: OrderedFloat<f64>,
+}
+
+/// Additive linear phenotype model: `intercept + sum_j(c_j) + sum_k(e_k)`, where `c_j` are the coefficients of the
+/// amino acid mutations, possibly in several CDSes, and `e_k` are the coefficients of the epistasis terms present.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenotypeModelAdditive {
+  /// Constant term of the score
+  #[serde(default)]
+  pub intercept: OrderedFloat<f64>,
+  /// Coefficients of amino acid mutations, per CDS
+  pub cdses: Vec<PhenotypeCdsCoeffs>,
+  /// Interaction terms between amino acid mutations
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub epistasis: Vec<PhenotypeEpistasisTerm<PhenotypeAaMutation>>,
+}
+
+/// Additive linear phenotype model over nucleotide mutations: `intercept + sum_j(c_j) + sum_k(e_k)`, where `c_j` are
+/// the coefficients of the nucleotide mutations and `e_k` are the coefficients of the epistasis terms present.
 #[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
+pub struct PhenotypeModelNucAdditive {
+  /// Constant term of the score
+  #[serde(default)]
+  pub intercept: OrderedFloat<f64>,
+  /// Per-position coefficients mapping 0-based nucleotide reference positions to their contribution to the score.
+  pub locations: BTreeMap<NucRefGlobalPosition, PhenotypeCoeff>,
+  /// Interaction terms between nucleotide mutations
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub epistasis: Vec<PhenotypeEpistasisTerm<PhenotypeNucMutation>>,
+}
+
+/// Model used to calculate a phenotype score from mutations, selected by the `model` field. If the field is omitted,
+/// the escape-style model is used.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "model")]
+pub enum PhenotypeModel {
+  /// Escape-style model over amino acid mutations in a single CDS range (default)
+  Escape(PhenotypeModelEscape),
+  /// Additive linear model over amino acid mutations in one or several CDSes, with optional epistasis terms
+  Additive(PhenotypeModelAdditive),
+  /// Additive linear model over nucleotide mutations, with optional epistasis terms
+  NucAdditive(PhenotypeModelNucAdditive),
+}
+
+// NOTE: the schema is written by hand, because the derived schema of an internally tagged enum requires the tag, but
+// the `model` field is optional for the escape-style model.
+impl schemars::JsonSchema for PhenotypeModel {
+  fn schema_name() -> String {
+    "PhenotypeModel".to_owned()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    let variants = vec![
+      phenotype_model_variant_schema::<PhenotypeModelEscape>(generator, "escape", false),
+      phenotype_model_variant_schema::<PhenotypeModelAdditive>(generator, "additive", true),
+      phenotype_model_variant_schema::<PhenotypeModelNucAdditive>(generator, "nucAdditive", true),
+    ];
+    SchemaObject {
+      instance_type: Some(InstanceType::Object.into()),
+      subschemas: Some(Box::new(SubschemaValidation {
+        one_of: Some(variants),
+        ..SubschemaValidation::default()
+      })),
+      ..SchemaObject::default()
+    }
+    .into()
+  }
+}
+
+/// Schema of the model parameters, extended with the `model` field which selects this model
+fn phenotype_model_variant_schema<T: schemars::JsonSchema>(
+  generator: &mut SchemaGenerator,
+  model: &str,
+  is_model_required: bool,
+) -> Schema {
+  let tag = SchemaObject {
+    instance_type: Some(InstanceType::String.into()),
+    enum_values: Some(vec![serde_json::Value::from(model)]),
+    ..SchemaObject::default()
+  };
+  let mut schema = T::json_schema(generator).into_object();
+  let object = schema.object();
+  object.properties.insert(o!("model"), tag.into());
+  if is_model_required {
+    object.required.insert(o!("model"));
+  }
+  schema.into()
+}
+
+impl Default for PhenotypeModel {
+  fn default() -> Self {
+    Self::Escape(PhenotypeModelEscape::default())
+  }
+}
+
+impl PhenotypeModel {
+  /// Names of the CDSes on which the phenotype is evaluated. Empty for nucleotide-based models.
+  pub fn cds_names(&self) -> Vec<String> {
+    match self {
+      PhenotypeModel::Escape(model) => vec![model.cds.clone()],
+      PhenotypeModel::Additive(model) => model.cdses.iter().map(|cds| cds.cds.clone()).unique().collect(),
+      PhenotypeModel::NucAdditive(_) => vec![],
+    }
+  }
+}
+
+/// Describes a phenotype, such as receptor binding, immune escape, etc.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
 #[schemars(example = "PhenotypeData::example")]
 pub struct PhenotypeData {
   /// Machine-readable identifier for this phenotype (e.g. "ace2_binding", "immune_escape").
@@ -303,15 +512,54 @@ pub struct PhenotypeData {
   pub name_friendly: String,
   /// Free-text description of what this phenotype measures.
   pub description: String,
-  /// CDS on which this phenotype is evaluated (e.g. "S", "HA1").
-  pub cds: String,
-  /// Amino acid range within the CDS where phenotype-relevant mutations are considered.
-  pub aa_range: AaRefRange,
   /// Clades to exclude from phenotype scoring.
   #[serde(default)]
   pub ignore: PhenotypeDataIgnore,
-  /// Per-position coefficient tables for computing the phenotype score.
-  pub data: Vec<PhenotypeDataEntry>,
+  /// Model used to calculate the phenotype score, with its parameters.
+  #[serde(flatten)]
+  pub model: PhenotypeModel,
+}
+
+impl<'de> Deserialize<'de> for PhenotypeData {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct PhenotypeDataCommon {
+      name: String,
+      name_friendly: String,
+      description: String,
+      #[serde(default)]
+      ignore: PhenotypeDataIgnore,
+      #[serde(default)]
+      model: Option<String>,
+    }
+
+    // NOTE: model parameters are deserialized from a JSON value, rather than using serde's internally tagged enum,
+    // because the latter does not support integer map keys (positions in `locations`). The `model` field is optional
+    // for compatibility with pathogen configs written before other models were introduced.
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let common: PhenotypeDataCommon = serde_json::from_value(value.clone()).map_err(serde::de::Error::custom)?;
+    let model = match common.model.as_deref().unwrap_or("escape") {
+      "escape" => serde_json::from_value(value).map(PhenotypeModel::Escape),
+      "additive" => serde_json::from_value(value).map(PhenotypeModel::Additive),
+      "nucAdditive" => serde_json::from_value(value).map(PhenotypeModel::NucAdditive),
+      unknown => {
+        return Err(serde::de::Error::custom(format!(
+          "Unknown phenotype model '{unknown}' in phenotype '{}'. Expected one of: 'escape', 'additive', 'nucAdditive'",
+          common.name
+        )));
+      }
+    }
+    .map_err(serde::de::Error::custom)?;
+
+    Ok(Self {
+      name: common.name,
+      name_friendly: common.name_friendly,
+      description: common.description,
+      ignore: common.ignore,
+      model,
+    })
+  }
 }
 
 impl PhenotypeData {
@@ -320,12 +568,14 @@ impl PhenotypeData {
       name: o!("receptor_binding"),
       name_friendly: o!("Receptor Binding"),
       description: o!("Predicts receptor binding specificity and affinity"),
-      cds: o!("HA1"),
-      aa_range: AaRefRange::new(AaRefPosition::from(120), AaRefPosition::from(260)),
       ignore: PhenotypeDataIgnore {
         clades: vec_of_owned!["3C.2A", "3C.3A"],
       },
-      data: vec![PhenotypeDataEntry::example()],
+      model: PhenotypeModel::Escape(PhenotypeModelEscape {
+        cds: o!("HA1"),
+        aa_range: AaRefRange::new(AaRefPosition::from(120), AaRefPosition::from(260)),
+        data: vec![PhenotypeDataEntry::example()],
+      }),
     }
   }
 }
@@ -385,6 +635,90 @@ impl AaMotifsDesc {
   }
 }
 
+/// Describes motifs in nucleotide sequences, such as CpG dinucleotides, transcription regulatory sequences, primer
+/// binding sites or restriction sites
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(example = "NucMotifsDesc::example")]
+pub struct NucMotifsDesc {
+  /// Machine-readable identifier for this motif type (e.g. "CpG").
+  pub name: String,
+  /// Abbreviated name for compact display (e.g. "CpG").
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub name_short: String,
+  /// Human-readable display name (e.g. "CpG dinucleotides").
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub name_friendly: String,
+  /// Free-text description of what the motif represents.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub description: String,
+  /// Regular expressions defining the nucleotide motifs to detect (e.g. "CG" or "GAATTC"). Sequences are searched in upper case.
+  pub motifs: Vec<String>,
+
+  /// Which version of the query sequence to search: `aligned` (insertions removed, deletions shown as gaps) or `unaligned` (gaps removed, insertions kept).
+  #[serde(default)]
+  pub sequence: NucMotifsSequence,
+
+  /// Nucleotide ranges of the reference sequence (0-based, semi-open) in which to search for motifs. If empty, the entire sequence is searched.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub ranges: Vec<NucRefGlobalRange>,
+}
+
+impl NucMotifsDesc {
+  pub fn example() -> Self {
+    Self {
+      name: o!("CpG"),
+      name_short: o!("CpG"),
+      name_friendly: o!("CpG dinucleotides"),
+      description: o!("Cytosine followed by guanine"),
+      motifs: vec_of_owned!["CG"],
+      sequence: NucMotifsSequence::Aligned,
+      ranges: vec![],
+    }
+  }
+}
+
+/// Version of the query sequence in which nucleotide motifs are searched
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum NucMotifsSequence {
+  /// Query aligned to the reference, with insertions removed and deletions shown as gaps
+  #[default]
+  Aligned,
+  /// Query without gaps, with insertions kept. Motifs spanning insertions and deletions can be found.
+  Unaligned,
+}
+
+/// Describes a mutation signature: a set of nucleotide substitutions in their sequence context
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(example = "MutationSignatureDesc::example")]
+pub struct MutationSignatureDesc {
+  /// Machine-readable identifier for this signature (e.g. "APOBEC3").
+  pub name: String,
+  /// Human-readable display name (e.g. "APOBEC3 editing").
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub name_friendly: String,
+  /// Free-text description of what the signature represents.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub description: String,
+  /// Substitutions in their reference sequence context, written as `<5' flank>[<ref>><qry>]<3' flank>`, e.g. "T[C>T]" or "[G>A]A" for dinucleotide and "T[C>T]W" for trinucleotide contexts. Flanks may contain IUPAC ambiguity codes.
+  pub contexts: Vec<String>,
+}
+
+impl MutationSignatureDesc {
+  pub fn example() -> Self {
+    Self {
+      name: o!("APOBEC3"),
+      name_friendly: o!("APOBEC3 editing"),
+      description: o!(
+        "Substitutions characteristic of APOBEC3 cytidine deaminase editing (TC>TT and its reverse complement GA>AA)"
+      ),
+      contexts: vec_of_owned!["T[C>T]", "[G>A]A"],
+    }
+  }
+}
+
 /// Describes a range of amino acids in a CDS for counting motifs
 #[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -414,6 +748,8 @@ impl VirusProperties {
       files: DatasetFiles::default(),
       default_cds: Some(o!("HA1")),
       cds_order_preference: vec_of_owned!["HA1", "HA2"],
+      genetic_codes: btreemap! {},
+      rna_editing: btreemap! {},
       mut_labels: LabelledMutationsConfig::example(),
       qc: Some(QcConfig::example()),
       general_params: None,
@@ -422,6 +758,8 @@ impl VirusProperties {
       aa_changes_params: None,
       phenotype_data: Some(vec![PhenotypeData::example()]),
       aa_motifs: vec![AaMotifsDesc::example()],
+      nuc_motifs: vec![NucMotifsDesc::example()],
+      mutation_signatures: vec![MutationSignatureDesc::example()],
       versions: vec![],
       version: None,
       compatibility: None,
@@ -80,6 +80,7 @@ mod coord_map_tests {
   use crate::gene::frame::Frame;
   use crate::gene::gene::GeneStrand::{Forward, Reverse};
   use crate::gene::phase::Phase;
+  use crate::translate::genetic_code::GeneticCode;
   use indexmap::indexmap;
   use pretty_assertions::assert_eq;
   use rstest::rstest;
@@ -127,6 +128,8 @@ mod coord_map_tests {
       proteins: vec![],
       exceptions: vec![],
       attributes: indexmap! {},
+      genetic_code: GeneticCode::default(),
+      rna_editing: vec![],
       compat_is_gene: false,
       color: None,
     }
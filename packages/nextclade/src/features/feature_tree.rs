@@ -68,6 +68,9 @@ fn read_gff3_feature_tree_str(content: impl AsRef<str>) -> Result<Vec<SequenceRe
       // the `end` once more.
       let end = if tail.len() == 1 { tail[0].0 } else { content.len() };
       begins.push(end);
+    } else if begins.len() > 1 && tail.is_empty() {
+      // Multiple sequence regions and no "tail": the last region extends until the end of the file
+      begins.push(content.len());
     }
 
     // Iterate over pairs of adjacent indices, which give us ranges. The last "tail" range is conveniently excluded.
@@ -6,6 +6,7 @@ use crate::gene::frame::Frame;
 use crate::gene::gene::Gene;
 use crate::gene::phase::Phase;
 use crate::io::json::{JsonPretty, json_stringify};
+use crate::translate::genetic_code::GeneticCode;
 use crate::tree::tree::{AuspiceGenomeAnnotationCds, AuspiceGenomeAnnotations, Segments, StartEnd};
 use crate::utils::iter::single_unique_value;
 use eyre::Report;
@@ -41,6 +42,8 @@ pub fn convert_auspice_annotations_to_genes(anns: &AuspiceGenomeAnnotations) ->
         proteins: vec![],
         exceptions: vec![],
         attributes: IndexMap::default(),
+        genetic_code: GeneticCode::default(),
+        rna_editing: vec![],
         compat_is_gene: true,
         color: ann.color.clone(),
       };
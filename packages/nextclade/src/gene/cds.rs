@@ -7,6 +7,8 @@ use crate::gene::frame::Frame;
 use crate::gene::gene::GeneStrand;
 use crate::gene::phase::Phase;
 use crate::gene::protein::{Protein, ProteinSegment};
+use crate::gene::rna_editing::{RnaEditingSite, cds_apply_rna_editing, rna_editing_from_attributes};
+use crate::translate::genetic_code::GeneticCode;
 use crate::utils::iter::single_unique_value;
 use crate::{make_error, make_internal_error};
 use eyre::{Report, WrapErr, eyre};
@@ -35,6 +37,14 @@ pub struct Cds {
   pub exceptions: Vec<String>,
   /// Additional GFF3 attributes as key-value pairs, merged from all segments.
   pub attributes: IndexMap<String, Vec<String>>,
+  /// Genetic code used for translation: NCBI translation table from the GFF3 `transl_table` attribute or from
+  /// `geneticCodes` in pathogen.json. Defaults to the standard code.
+  #[serde(default)]
+  pub genetic_code: GeneticCode,
+  /// RNA editing sites applied to this CDS, from the GFF3 `rna_editing` attribute or from `rnaEditing` in
+  /// pathogen.json. Inserted nucleotides are represented by repeated segments.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub rna_editing: Vec<RnaEditingSite>,
   /// True when this CDS was synthesized from a gene record that had no child CDS.
   pub compat_is_gene: bool,
   /// Display color for the genome annotation viewer.
@@ -120,7 +130,13 @@ impl Cds {
       .unique()
       .collect_vec();
 
-    Ok(Self {
+    let genetic_code = GeneticCode::from_attributes(&attributes)
+      .wrap_err_with(|| format!("When reading genetic code of CDS '{}'", feature_group.name))?;
+
+    let rna_editing = rna_editing_from_attributes(&attributes)
+      .wrap_err_with(|| format!("When reading RNA editing sites of CDS '{}'", feature_group.name))?;
+
+    let mut cds = Self {
       id: feature_group.id.clone(),
       name: feature_group.name.clone(),
       product: feature_group.product.clone(),
@@ -128,9 +144,18 @@ impl Cds {
       proteins,
       exceptions,
       attributes,
+      genetic_code,
+      rna_editing: vec![],
       compat_is_gene: false,
       color: None,
-    })
+    };
+
+    for site in &rna_editing {
+      cds_apply_rna_editing(&mut cds, site)
+        .wrap_err_with(|| format!("When applying RNA editing to CDS '{}'", feature_group.name))?;
+    }
+
+    Ok(cds)
   }
 
   /// HACK: COMPATIBILITY: if there are no CDS records, we pretend that each gene record imply a CDS with one segment and one protein
@@ -189,7 +214,10 @@ impl Cds {
     let segments = vec![cds_segment];
     let segments = split_circular_cds_segments(&segments)?;
 
-    Ok(Self {
+    let rna_editing = rna_editing_from_attributes(&feature.attributes)
+      .wrap_err_with(|| format!("When reading RNA editing sites of gene '{}'", feature.name))?;
+
+    let mut cds = Self {
       id: format!("cds-from-gene-{}", feature.id),
       name: feature.name.clone(),
       product: feature.product.clone(),
@@ -197,9 +225,19 @@ impl Cds {
       proteins: vec![protein],
       exceptions: feature.exceptions.clone(),
       attributes: feature.attributes.clone(),
+      genetic_code: GeneticCode::from_attributes(&feature.attributes)
+        .wrap_err_with(|| format!("When reading genetic code of gene '{}'", feature.name))?,
+      rna_editing: vec![],
       compat_is_gene: true,
       color: None,
-    })
+    };
+
+    for site in &rna_editing {
+      cds_apply_rna_editing(&mut cds, site)
+        .wrap_err_with(|| format!("When applying RNA editing to gene '{}'", feature.name))?;
+    }
+
+    Ok(cds)
   }
 
   pub fn name_and_type(&self) -> String {
@@ -5,8 +5,10 @@ use crate::gene::auspice_annotations::convert_auspice_annotations_to_genes;
 use crate::gene::cds::Cds;
 use crate::gene::cds_segment::CdsSegment;
 use crate::gene::gene::{Gene, find_cdses};
+use crate::gene::rna_editing::{RNA_EDITING_ATTRIBUTE, RNA_EDITING_EXCEPTION, RnaEditingSite, cds_apply_rna_editing};
 use crate::io::file::open_file_or_stdin;
 use crate::io::yaml::yaml_parse;
+use crate::translate::genetic_code::{GeneticCode, TRANSL_TABLE_ATTRIBUTE};
 use crate::tree::tree::AuspiceGenomeAnnotations;
 use crate::utils::collections::take_exactly_one;
 use crate::utils::error::report_to_string;
@@ -17,6 +19,7 @@ use itertools::Itertools;
 use log::warn;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 type GeneMapParserFn = Box<dyn Fn(&str) -> Result<GeneMap, Report>>;
@@ -78,7 +81,7 @@ impl GeneMap {
     for (name, parser) in &parsers {
       match parser(content) {
         Ok(map) => {
-          map.validate()?;
+          map.validate_unedited()?;
           return Ok(map);
         }
         Err(err) => {
@@ -119,6 +122,28 @@ impl GeneMap {
     self.genes.len()
   }
 
+  /// Sequence region ids (GFF3 column 1) of genes, in order of appearance
+  pub fn seqids(&self) -> Vec<&str> {
+    self
+      .genes
+      .iter()
+      .filter_map(|gene| gene.gff_seqid.as_deref())
+      .unique()
+      .collect_vec()
+  }
+
+  /// Genes belonging to a given sequence region (GFF3 column 1)
+  pub fn filter_by_seqid(&self, seqid: &str) -> Self {
+    Self::from_genes(
+      self
+        .genes
+        .iter()
+        .filter(|gene| gene.gff_seqid.as_deref() == Some(seqid))
+        .cloned()
+        .collect_vec(),
+    )
+  }
+
   #[must_use]
   pub fn contains(&self, name: &str) -> bool {
     self.genes.iter().any(|gene| gene.name == name)
@@ -169,19 +194,84 @@ impl GeneMap {
     self.genes.iter().flat_map(|gene| gene.cdses.iter())
   }
 
+  /// Set genetic codes of CDSes by CDS name (e.g. from pathogen.json). The `transl_table` attribute is updated
+  /// accordingly, such that the genetic code is reflected in the output genome annotations.
+  pub fn set_genetic_codes(&mut self, genetic_codes: &BTreeMap<String, GeneticCode>) {
+    for cds in self.iter_cdses_mut() {
+      let Some(genetic_code) = genetic_codes.get(&cds.name) else {
+        continue;
+      };
+      cds.genetic_code = genetic_code.clone();
+      let value = vec![genetic_code.to_string()];
+      cds.attributes.insert(TRANSL_TABLE_ATTRIBUTE.to_owned(), value.clone());
+      for segment in &mut cds.segments {
+        segment
+          .attributes
+          .insert(TRANSL_TABLE_ATTRIBUTE.to_owned(), value.clone());
+      }
+    }
+  }
+
+  /// Apply RNA editing sites to CDSes by CDS name (e.g. from pathogen.json)
+  pub fn set_rna_editing(&mut self, rna_editing: &BTreeMap<String, Vec<RnaEditingSite>>) -> Result<(), Report> {
+    for cds in self.iter_cdses_mut() {
+      let Some(sites) = rna_editing.get(&cds.name) else {
+        continue;
+      };
+      for site in sites {
+        cds_apply_rna_editing(cds, site)
+          .wrap_err_with(|| format!("When applying RNA editing to CDS '{}'", cds.name))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Validate genome annotation as parsed, before RNA editing sites from pathogen.json are applied. Length of CDSes with
+  /// the "RNA editing" exception, but without editing sites, is only checked once the sites are applied (see `validate()`).
+  pub fn validate_unedited(&self) -> Result<(), Report> {
+    self.validate_impl(true)
+  }
+
   pub fn validate(&self) -> Result<(), Report> {
+    self.validate_impl(false)
+  }
+
+  fn validate_impl(&self, allow_unedited: bool) -> Result<(), Report> {
     self.iter_cdses().try_for_each(|cds| {
-      cds.len().is_multiple_of(3).then_some(()).ok_or_else(|| {
-        let segment_lengths = cds.segments.iter().map(CdsSegment::len).join("+");
-        let n_segments = cds.segments.len();
-        eyre!(
+      if cds.len().is_multiple_of(3) {
+        return Ok(());
+      }
+
+      let segment_lengths = cds.segments.iter().map(CdsSegment::len).join("+");
+      let n_segments = cds.segments.len();
+      let has_rna_editing_exception = cds
+        .exceptions
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case(RNA_EDITING_EXCEPTION));
+
+      if !has_rna_editing_exception {
+        return make_error!(
           "Length of a CDS is expected to be divisible by 3, but the length of CDS '{}' is {} \
           (it consists of {n_segments} fragment(s) of length(s) {segment_lengths}). \
           This is likely a mistake in genome annotation.",
           cds.name,
           cds.len()
-        )
-      })
+        );
+      }
+
+      if allow_unedited && cds.rna_editing.is_empty() {
+        return Ok(());
+      }
+
+      make_error!(
+        "Length of a CDS is expected to be divisible by 3, but the length of CDS '{}' with the '{RNA_EDITING_EXCEPTION}' \
+        exception is {} after applying {} RNA editing site(s) (it consists of {n_segments} fragment(s) of length(s) \
+        {segment_lengths}). Please make sure that the RNA editing sites of this CDS are provided, either in the \
+        '{RNA_EDITING_ATTRIBUTE}' attribute of the genome annotation or in the 'rnaEditing' field of pathogen.json.",
+        cds.name,
+        cds.len(),
+        cds.rna_editing.len()
+      )
     })?;
 
     let gene_name_dupes = self
@@ -247,11 +337,36 @@ fn get_requested_cdses_not_in_genemap(all_cdses: &[Cds], cdses: &[String]) -> St
 }
 
 pub fn convert_feature_tree_to_gene_map(feature_tree: &FeatureTree) -> Result<GeneMap, Report> {
+  if feature_tree.seq_regions.len() > 1 {
+    return convert_seq_regions_to_gene_map_segmented(&feature_tree.seq_regions);
+  }
   let seq_region = take_exactly_one(&feature_tree.seq_regions)
-    .wrap_err_with(|| eyre!("Only feature trees with exactly one sequence region are supported. Please keep exactly one sequence region in genome annotation."))?;
+    .wrap_err_with(|| eyre!("Genome annotation is expected to contain at least one sequence region."))?;
   convert_seq_region_to_gene_map(seq_region)
 }
 
+/// Converts multiple sequence regions (one per genome segment) into a single gene map. Each gene remembers the
+/// sequence region it belongs to in `gff_seqid`, such that the gene map can be later split into segments.
+fn convert_seq_regions_to_gene_map_segmented(seq_regions: &[SequenceRegion]) -> Result<GeneMap, Report> {
+  let mut genes = vec![];
+  for seq_region in seq_regions {
+    let region_genes = find_genes(&seq_region.children)
+      .wrap_err_with(|| eyre!("When processing sequence region '{}'", seq_region.id))?;
+    genes.extend(region_genes.into_iter().map(|mut gene| {
+      gene.gff_seqid.get_or_insert_with(|| seq_region.id.clone());
+      gene
+    }));
+  }
+
+  if genes.is_empty() {
+    return make_error!(
+      "Genome annotation: unable to find any genes or CDSes. Please make sure the genome annotation is correct."
+    );
+  }
+
+  Ok(GeneMap::from_genes(genes))
+}
+
 fn convert_seq_region_to_gene_map(seq_region: &SequenceRegion) -> Result<GeneMap, Report> {
   let genes = find_genes(&seq_region.children)?;
 
@@ -371,4 +486,35 @@ MN908947	GenBank	CDS	27894	28259	.	+	.	Name=N;Parent=9
 
     Ok(())
   }
+
+  #[rstest]
+  fn genome_annotation_validates_rna_editing_cds_length_after_editing() -> Result<(), Report> {
+    let mut gene_map = GeneMap::from_str(
+      r#"##gff-version 3
+##sequence-region MN908947 1 100
+MN908947	GenBank	gene	11	20	.	+	.	Name=P;ID=1
+MN908947	GenBank	CDS	11	20	.	+	.	Name=P;Parent=1;exception=RNA editing
+
+"#,
+    )?;
+
+    assert_eq!(
+      "Length of a CDS is expected to be divisible by 3, but the length of CDS 'P' with the 'RNA editing' exception is 10 \
+      after applying 0 RNA editing site(s) (it consists of 1 fragment(s) of length(s) 10). Please make sure that the RNA \
+      editing sites of this CDS are provided, either in the 'rna_editing' attribute of the genome annotation or in the \
+      'rnaEditing' field of pathogen.json.",
+      report_to_string(&gene_map.validate().unwrap_err()),
+    );
+
+    gene_map.set_rna_editing(&BTreeMap::from([(
+      "P".to_owned(),
+      vec![RnaEditingSite {
+        pos: 14.into(),
+        count: 2,
+      }],
+    )]))?;
+    gene_map.validate()?;
+
+    Ok(())
+  }
 }
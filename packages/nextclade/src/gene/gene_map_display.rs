@@ -131,7 +131,8 @@ fn write_cds<W: Write>(w: &mut W, max_name_len: usize, cds: &Cds) -> Result<(),
   let name = truncate_right(&cds.name_and_type(), max_name_len, "...");
   let nuc_len = cds.len();
   let codon_len = format_codon_length(nuc_len);
-  let exceptions = cds.exceptions.join(", ");
+  let genetic_code = (!cds.genetic_code.is_standard()).then(|| format!("transl_table={}", cds.genetic_code));
+  let exceptions = cds.exceptions.iter().cloned().chain(genetic_code).join(", ");
   writeln!(
     w,
     "{indent}{:max_name_len$} │   │   │   │     │         │         │ {nuc_len:>7} │ {codon_len:>11} │ {exceptions}",
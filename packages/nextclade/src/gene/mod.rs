@@ -8,3 +8,4 @@ pub mod gene_map_display;
 pub mod genotype;
 pub mod phase;
 pub mod protein;
+pub mod rna_editing;
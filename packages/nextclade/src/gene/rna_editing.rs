@@ -0,0 +1,265 @@
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::coord::range::{NucRefGlobalRange, Range};
+use crate::gene::cds::Cds;
+use crate::gene::frame::Frame;
+use crate::gene::gene::GeneStrand;
+use crate::gene::phase::Phase;
+use crate::make_error;
+use eyre::{Report, WrapErr};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::iter::{once, repeat_n};
+
+/// Name of the GFF3 attribute which declares RNA editing sites of a CDS. The value is `<pos>:<count>`, where `pos` is
+/// the 1-based position of the edited nucleotide and `count` is the number of inserted nucleotides.
+pub const RNA_EDITING_ATTRIBUTE: &str = "rna_editing";
+
+/// Name of the exception which marks CDSes with RNA editing
+pub const RNA_EDITING_EXCEPTION: &str = "RNA editing";
+
+/// RNA editing site: non-templated nucleotides inserted into the transcript of a CDS, e.g. by polymerase stuttering
+/// in paramyxovirus P/V/W or Ebola virus GP genes. The inserted nucleotides repeat the nucleotide at the editing site.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RnaEditingSite {
+  /// Position of the reference nucleotide after which the nucleotides are inserted in the transcript. Stored 0-based,
+  /// but written and read as 1-based, same as in the GFF3 attribute.
+  #[serde(serialize_with = "serde_serialize_one_based_pos")]
+  #[serde(deserialize_with = "serde_deserialize_one_based_pos")]
+  #[schemars(with = "isize")]
+  pub pos: NucRefGlobalPosition,
+
+  /// Number of inserted nucleotides
+  pub count: usize,
+}
+
+impl RnaEditingSite {
+  /// Parse editing site from GFF3 attribute value `<pos>:<count>` (1-based position)
+  pub fn from_gff_attribute(value: &str) -> Result<Self, Report> {
+    let parse = || -> Result<Self, Report> {
+      let Some((pos, count)) = value.split_once(':') else {
+        return make_error!("Expected format '<position>:<count>'");
+      };
+      let pos = one_based_to_pos(pos.trim().parse::<isize>()?)?;
+      let count = count.trim().parse::<usize>()?;
+      Ok(Self { pos, count })
+    };
+    parse().wrap_err_with(|| format!("When parsing RNA editing site '{value}'"))
+  }
+}
+
+fn one_based_to_pos(pos: isize) -> Result<NucRefGlobalPosition, Report> {
+  if pos < 1 {
+    return make_error!("Expected 1-based position, but found {pos}");
+  }
+  Ok(NucRefGlobalPosition::new(pos - 1))
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serde_serialize_one_based_pos<S: Serializer>(pos: &NucRefGlobalPosition, s: S) -> Result<S::Ok, S::Error> {
+  s.serialize_i64(pos.as_isize() as i64 + 1)
+}
+
+fn serde_deserialize_one_based_pos<'de, D: Deserializer<'de>>(d: D) -> Result<NucRefGlobalPosition, D::Error> {
+  let pos = isize::deserialize(d)?;
+  one_based_to_pos(pos).map_err(serde::de::Error::custom)
+}
+
+/// Read RNA editing sites from the GFF3 attributes of a CDS
+pub fn rna_editing_from_attributes(attributes: &IndexMap<String, Vec<String>>) -> Result<Vec<RnaEditingSite>, Report> {
+  attributes
+    .get(RNA_EDITING_ATTRIBUTE)
+    .into_iter()
+    .flatten()
+    .flat_map(|value| value.split(','))
+    .map(RnaEditingSite::from_gff_attribute)
+    .collect()
+}
+
+/// Apply RNA editing site to a CDS.
+///
+/// Similarly to programmed ribosomal slippage, the edit is represented by CDS segments which repeat the nucleotide at
+/// the editing site. This way, the extracted CDS sequences, both of the reference and of the query, contain the
+/// inserted nucleotides and are translated into the edited product, and the edit does not appear as a frame shift.
+pub fn cds_apply_rna_editing(cds: &mut Cds, site: &RnaEditingSite) -> Result<(), Report> {
+  let pos = site.pos;
+  let Some(index) = cds.segments.iter().position(|segment| segment.range.contains(pos)) else {
+    return make_error!(
+      "RNA editing site at position {} is outside of CDS '{}'",
+      pos.as_usize() + 1,
+      cds.name
+    );
+  };
+
+  if site.count > 0 {
+    let segment = &cds.segments[index];
+    let NucRefGlobalRange { begin, end } = segment.range;
+
+    // Parts of the segment before and after the inserted nucleotides, in the direction of transcription
+    let (first, last) = match segment.strand {
+      GeneStrand::Forward => (Range::new(begin, pos + 1), Range::new(pos, end)),
+      GeneStrand::Reverse => (Range::new(pos, end), Range::new(begin, pos + 1)),
+    };
+    let repeated = Range::new(pos, pos + 1);
+
+    let segments = once(first)
+      .chain(repeat_n(repeated, site.count - 1))
+      .chain(once(last))
+      .map(|range| {
+        let mut segment = segment.clone();
+        segment.range = range;
+        segment
+      })
+      .collect_vec();
+
+    cds.segments.splice(index..=index, segments);
+    update_local_ranges(cds)?;
+  }
+
+  if !cds
+    .exceptions
+    .iter()
+    .any(|e| e.eq_ignore_ascii_case(RNA_EDITING_EXCEPTION))
+  {
+    cds.exceptions.push(RNA_EDITING_EXCEPTION.to_owned());
+  }
+
+  // The edit is now encoded in the segments. Don't keep the attribute, such that output annotations are not edited again
+  // when read back.
+  cds.attributes.shift_remove(RNA_EDITING_ATTRIBUTE);
+  for segment in &mut cds.segments {
+    segment.attributes.shift_remove(RNA_EDITING_ATTRIBUTE);
+  }
+
+  cds.rna_editing.push(site.clone());
+
+  Ok(())
+}
+
+fn update_local_ranges(cds: &mut Cds) -> Result<(), Report> {
+  let mut begin = 0;
+  for segment in &mut cds.segments {
+    segment.range_local = Range::from_usize(begin, begin + segment.len());
+    segment.phase = Phase::from_begin(segment.range_local.begin)?;
+    segment.frame = Frame::from_begin(segment.range.begin)?;
+    begin += segment.len();
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
+  use crate::gene::cds_segment::{CdsSegment, Truncation, WrappingPart};
+  use crate::translate::extract::extract_cds_from_ref;
+  use crate::translate::genetic_code::GeneticCode;
+  use indexmap::indexmap;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn create_cds(begin: isize, end: isize, strand: GeneStrand) -> Cds {
+    let range = NucRefGlobalRange::from_isize(begin, end);
+    Cds {
+      id: "V".to_owned(),
+      name: "V".to_owned(),
+      product: "V".to_owned(),
+      segments: vec![CdsSegment {
+        index: 0,
+        id: "V".to_owned(),
+        name: "V".to_owned(),
+        range: range.clone(),
+        range_local: Range::from_usize(0, range.len()),
+        landmark: None,
+        wrapping_part: WrappingPart::NonWrapping,
+        strand,
+        frame: Frame::from_begin(range.begin).unwrap(),
+        phase: Phase::from_begin(Range::from_usize(0, 0).begin).unwrap(),
+        truncation: Truncation::default(),
+        exceptions: vec![],
+        attributes: indexmap! { RNA_EDITING_ATTRIBUTE.to_owned() => vec!["6:1".to_owned()] },
+        source_record: None,
+        compat_is_gene: false,
+        color: None,
+        gff_seqid: None,
+        gff_source: None,
+        gff_feature_type: None,
+      }],
+      proteins: vec![],
+      exceptions: vec![],
+      attributes: indexmap! {},
+      genetic_code: GeneticCode::default(),
+      rna_editing: vec![],
+      compat_is_gene: false,
+      color: None,
+    }
+  }
+
+  #[rstest]
+  fn parses_gff_attribute() -> Result<(), Report> {
+    let attributes = indexmap! { RNA_EDITING_ATTRIBUTE.to_owned() => vec!["6:1".to_owned(), "10:2".to_owned()] };
+    assert_eq!(
+      rna_editing_from_attributes(&attributes)?,
+      vec![
+        RnaEditingSite {
+          pos: NucRefGlobalPosition::new(5),
+          count: 1
+        },
+        RnaEditingSite {
+          pos: NucRefGlobalPosition::new(9),
+          count: 2
+        },
+      ]
+    );
+    drop(RnaEditingSite::from_gff_attribute("6").unwrap_err());
+    Ok(())
+  }
+
+  #[rstest]
+  fn reads_and_writes_one_based_position_in_json() -> Result<(), Report> {
+    let site: RnaEditingSite = serde_json::from_str(r#"{"pos": 6, "count": 1}"#)?;
+    assert_eq!(
+      site,
+      RnaEditingSite {
+        pos: NucRefGlobalPosition::new(5),
+        count: 1
+      }
+    );
+    assert_eq!(serde_json::to_string(&site)?, r#"{"pos":6,"count":1}"#);
+    drop(serde_json::from_str::<RnaEditingSite>(r#"{"pos": 0, "count": 1}"#).unwrap_err());
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::forward_one(GeneStrand::Forward, 5, 1, "ATGAAGGGGTTTA")]
+  #[case::forward_two(GeneStrand::Forward, 5, 2, "ATGAAGGGGGTTTA")]
+  #[case::forward_none(GeneStrand::Forward, 5, 0, "ATGAAGGGTTTA")]
+  #[case::reverse_one(GeneStrand::Reverse, 5, 1, "TAAACCCCTTCAT")]
+  fn inserts_edited_nucleotides(
+    #[case] strand: GeneStrand,
+    #[case] pos: isize,
+    #[case] count: usize,
+    #[case] expected: &str,
+  ) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ATGAAGGGTTTA")?;
+    let mut cds = create_cds(0, 12, strand);
+    let site = RnaEditingSite {
+      pos: NucRefGlobalPosition::new(pos),
+      count,
+    };
+    cds_apply_rna_editing(&mut cds, &site)?;
+    let actual = from_nuc_seq(&extract_cds_from_ref(&ref_seq, &cds));
+    assert_eq!(actual, expected.to_owned());
+    assert_eq!(cds.len(), 12 + count);
+    assert_eq!(cds.exceptions, vec![RNA_EDITING_EXCEPTION.to_owned()]);
+    assert!(
+      cds
+        .segments
+        .iter()
+        .all(|s| !s.attributes.contains_key(RNA_EDITING_ATTRIBUTE))
+    );
+    Ok(())
+  }
+}
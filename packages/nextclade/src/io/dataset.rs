@@ -543,6 +543,12 @@ pub struct DatasetFiles {
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub reference: Option<String>,
 
+  /// Additional reference sequences FASTA file (e.g. "additional_references.fasta"). If present, each query sequence
+  /// is aligned against the closest of the primary and additional references, and the alignment is then projected
+  /// onto the coordinates of the primary reference.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub additional_references: Option<String>,
+
   /// Pathogen configuration file (e.g. "pathogen.json")
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub pathogen_json: Option<String>,
@@ -555,6 +561,17 @@ pub struct DatasetFiles {
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub tree_json: Option<String>,
 
+  /// Reference phylogenetic trees of the individual segments of a segmented genome, in Auspice JSON format, keyed by
+  /// the names of the reference sequence records (e.g. `{ "HA": "tree_HA.json" }`). Used instead of `treeJson` when
+  /// the reference sequence file contains multiple records.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub segment_trees: BTreeMap<String, String>,
+
+  /// Table of clade-defining mutations with a parent hierarchy, in TSV format (e.g. "clade_mutations.tsv"). Used for
+  /// clade assignment when the dataset has no reference tree.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub clade_mutations: Option<String>,
+
   /// Example query sequences for testing (e.g. "sequences.fasta")
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub examples: Option<String>,
@@ -584,9 +601,12 @@ impl DatasetFiles {
   pub fn example() -> Self {
     Self {
       reference: Some(o!("reference.fasta")),
+      additional_references: None,
       pathogen_json: Some(o!("pathogen.json")),
       genome_annotation: Some(o!("genome_annotation.gff3")),
       tree_json: Some(o!("tree.json")),
+      segment_trees: BTreeMap::new(),
+      clade_mutations: None,
       examples: Some(o!("sequences.fasta")),
       readme: Some(o!("README.md")),
       changelog: Some(o!("CHANGELOG.md")),
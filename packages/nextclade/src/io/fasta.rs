@@ -1,4 +1,5 @@
 use crate::alphabet::aa::from_aa_seq;
+use crate::alphabet::nuc::from_nuc_seq;
 use crate::constants::REVERSE_COMPLEMENT_SUFFIX;
 use crate::gene::gene_map::GeneMap;
 use crate::io::compression::Decompressor;
@@ -158,6 +159,23 @@ pub fn read_many_fasta<P: AsRef<Path>>(filepaths: &[P]) -> Result<Vec<FastaRecor
   Ok(fasta_records)
 }
 
+pub fn read_many_fasta_from_str(contents: impl AsRef<str>) -> Result<Vec<FastaRecord>, Report> {
+  let contents = contents.as_ref();
+  let mut reader = FastaReader::from_str(&contents)?;
+  let mut fasta_records = Vec::<FastaRecord>::new();
+
+  loop {
+    let mut record = FastaRecord::default();
+    reader.read(&mut record)?;
+    if record.is_empty() {
+      break;
+    }
+    fasta_records.push(record);
+  }
+
+  Ok(fasta_records)
+}
+
 pub fn read_one_fasta_from_file(filepath: impl AsRef<Path>) -> Result<FastaRecord, Report> {
   let filepath = filepath.as_ref();
   let reader = FastaReader::from_path(filepath)?;
@@ -234,26 +252,7 @@ pub struct FastaPeptideWriter {
 
 impl FastaPeptideWriter {
   pub fn new(gene_map: &GeneMap, output_translations: impl AsRef<str>) -> Result<Self, Report> {
-    let output_translations = output_translations.as_ref();
-
-    let mut tt = TinyTemplate::new();
-    tt.add_template("output_translations", output_translations)
-      .wrap_err_with(|| format!("When parsing template: {output_translations}"))?;
-
-    let writers = gene_map
-      .iter_cdses()
-      .map(|cds| -> Result<_, Report> {
-        let template_context = OutputTranslationsTemplateContext { cds: &cds.name };
-        let rendered_path = tt
-          .render("output_translations", &template_context)
-          .wrap_err_with(|| format!("When rendering output translations path template: '{output_translations}', using context: {template_context:?}"))?;
-        let out_gene_fasta_path = PathBuf::from_str(&rendered_path).wrap_err_with(|| format!("Invalid output translations path: '{rendered_path}'"))?;
-        trace!("Creating fasta writer to file '{}'", out_gene_fasta_path.display());
-        let writer = FastaWriter::from_path(&out_gene_fasta_path)?;
-        Ok((cds.name.clone(), writer))
-      })
-      .collect::<Result<FastaPeptideWritersMap, Report>>()?;
-
+    let writers = create_cds_fasta_writers(gene_map, output_translations.as_ref())?;
     Ok(Self { writers })
   }
 
@@ -265,6 +264,47 @@ impl FastaPeptideWriter {
   }
 }
 
+/// Writes in-frame nucleotide codon alignments of CDSes, each into a separate fasta file
+pub struct FastaCdsNucWriter {
+  writers: FastaPeptideWritersMap,
+}
+
+impl FastaCdsNucWriter {
+  pub fn new(gene_map: &GeneMap, output_cds_nuc: impl AsRef<str>) -> Result<Self, Report> {
+    let writers = create_cds_fasta_writers(gene_map, output_cds_nuc.as_ref())?;
+    Ok(Self { writers })
+  }
+
+  pub fn write(&mut self, seq_name: &str, translation: &CdsTranslation) -> Result<(), Report> {
+    match self.writers.get_mut(&translation.name) {
+      None => make_internal_error!("Fasta file writer not found for CDS '{}'", &translation.name),
+      Some(writer) => writer.write(seq_name, &from_nuc_seq(&translation.nuc_seq), false),
+    }
+  }
+}
+
+/// Creates a fasta writer for every CDS, with file paths rendered from a template containing `{cds}` variable
+fn create_cds_fasta_writers(gene_map: &GeneMap, template: &str) -> Result<FastaPeptideWritersMap, Report> {
+  let mut tt = TinyTemplate::new();
+  tt.add_template("output_cds", template)
+    .wrap_err_with(|| format!("When parsing template: {template}"))?;
+
+  gene_map
+    .iter_cdses()
+    .map(|cds| -> Result<_, Report> {
+      let template_context = OutputTranslationsTemplateContext { cds: &cds.name };
+      let rendered_path = tt.render("output_cds", &template_context).wrap_err_with(|| {
+        format!("When rendering output path template: '{template}', using context: {template_context:?}")
+      })?;
+      let out_gene_fasta_path =
+        PathBuf::from_str(&rendered_path).wrap_err_with(|| format!("Invalid output path: '{rendered_path}'"))?;
+      trace!("Creating fasta writer to file '{}'", out_gene_fasta_path.display());
+      let writer = FastaWriter::from_path(&out_gene_fasta_path)?;
+      Ok((cds.name.clone(), writer))
+    })
+    .collect()
+}
+
 pub fn parse_fasta_header(header: &str) -> (String, String) {
   header.split_once(' ').map_or_else(
     || (header.to_owned(), String::new()),
@@ -19,6 +19,10 @@ pub mod nextclade_csv_row;
 pub mod nwk_writer;
 pub mod parse_pos;
 pub mod results_json;
+pub mod sample_metadata;
 pub mod schema_version;
+pub mod segments_summary;
+pub mod vcf;
+pub mod vcf_reader;
 pub mod xlsx;
 pub mod yaml;
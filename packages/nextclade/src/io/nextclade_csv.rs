@@ -12,9 +12,12 @@ use std::path::Path;
 
 pub fn prepare_headers(
   custom_node_attr_descs: &[CladeNodeAttrKeyDesc],
+  numeric_node_attr_keys: &[String],
   phenotype_attr_keys: &[String],
   ref_nodes: &AuspiceRefNodesDesc,
   aa_motifs_keys: &[String],
+  nuc_motifs_keys: &[String],
+  metadata_keys: &[String],
   column_config: &CsvColumnConfig,
 ) -> Vec<String> {
   // Get names of enabled columns
@@ -44,9 +47,20 @@ pub fn prepare_headers(
       .unwrap_or_else(|| headers.len().saturating_sub(1))
       .clamp(0, headers.len());
 
-    custom_node_attr_descs.iter().rev().for_each(|desc| {
+    for desc in custom_node_attr_descs.iter().rev() {
       insert_after(&mut headers, insert_custom_cols_at_index, desc.name.clone());
       insert_custom_cols_at_index += 1;
+      if desc.lineage_hierarchy.is_some() {
+        for col in &lineage_cols(&desc.name) {
+          insert_after(&mut headers, insert_custom_cols_at_index, col.to_owned());
+          insert_custom_cols_at_index += 1;
+        }
+      }
+    }
+
+    numeric_node_attr_keys.iter().rev().for_each(|key| {
+      insert_after(&mut headers, insert_custom_cols_at_index, key.clone());
+      insert_custom_cols_at_index += 1;
     });
 
     phenotype_attr_keys.iter().rev().for_each(|key| {
@@ -58,6 +72,21 @@ pub fn prepare_headers(
       insert_after(&mut headers, insert_custom_cols_at_index, key.clone());
       insert_custom_cols_at_index += 1;
     });
+
+    for key in nuc_motifs_keys {
+      for col in &nuc_motif_cols(key) {
+        insert_after(&mut headers, insert_custom_cols_at_index, col.to_owned());
+        insert_custom_cols_at_index += 1;
+      }
+    }
+
+    // Sample metadata columns go next to the sequence name
+    if let Some(mut insert_metadata_cols_at_index) = headers.iter().position(|header| header == "seqName") {
+      for key in metadata_keys {
+        insert_after(&mut headers, insert_metadata_cols_at_index, metadata_col(key));
+        insert_metadata_cols_at_index += 1;
+      }
+    }
   }
 
   if column_config.include_rel_muts {
@@ -130,6 +159,15 @@ fn clade_founder_cols(name: impl AsRef<str>) -> [String; 5] {
   ]
 }
 
+fn metadata_col(name: impl AsRef<str>) -> String {
+  format!("metadata.{}", name.as_ref())
+}
+
+fn lineage_cols(name: impl AsRef<str>) -> [String; 2] {
+  let name = name.as_ref();
+  [format!("{name}.expanded"), format!("{name}.ancestors")]
+}
+
 fn rel_mut_cols(desc: &AuspiceRefNodeSearchDesc) -> [String; 5] {
   let name = desc.display_name_or_name();
   [
@@ -141,6 +179,16 @@ fn rel_mut_cols(desc: &AuspiceRefNodeSearchDesc) -> [String; 5] {
   ]
 }
 
+fn nuc_motif_cols(name: impl AsRef<str>) -> [String; 4] {
+  let name = name.as_ref();
+  [
+    format!("nucMotifs['{name}'].found"),
+    format!("nucMotifs['{name}'].total"),
+    format!("nucMotifs['{name}'].gained"),
+    format!("nucMotifs['{name}'].lost"),
+  ]
+}
+
 fn insert_after<T>(v: &mut Vec<T>, index: usize, val: T) {
   if index >= v.len() {
     v.push(val);
@@ -193,16 +241,22 @@ impl NextcladeResultsCsvFileWriter {
     filepath: impl AsRef<Path>,
     delimiter: u8,
     clade_node_attr_descs: &[CladeNodeAttrKeyDesc],
+    numeric_node_attr_keys: &[String],
     phenotype_attr_keys: &[String],
     ref_nodes: &AuspiceRefNodesDesc,
     aa_motifs_keys: &[String],
+    nuc_motifs_keys: &[String],
+    metadata_keys: &[String],
     column_config: &CsvColumnConfig,
   ) -> Result<Self, Report> {
     let headers: Vec<String> = prepare_headers(
       clade_node_attr_descs,
+      numeric_node_attr_keys,
       phenotype_attr_keys,
       ref_nodes,
       aa_motifs_keys,
+      nuc_motifs_keys,
+      metadata_keys,
       column_config,
     );
     let csv_writer = CsvVecFileWriter::new(filepath, delimiter, &headers)?;
@@ -224,9 +278,12 @@ pub fn results_to_csv_string(
   outputs: &[NextcladeOutputs],
   errors: &[NextcladeErrorOutputs],
   clade_node_attr_descs: &[CladeNodeAttrKeyDesc],
+  numeric_node_attr_keys: &[String],
   phenotype_attr_keys: &[String],
   ref_nodes: &AuspiceRefNodesDesc,
   aa_motifs_keys: &[String],
+  nuc_motifs_keys: &[String],
+  metadata_keys: &[String],
   delimiter: u8,
   column_config: &CsvColumnConfig,
 ) -> Result<String, Report> {
@@ -235,9 +292,12 @@ pub fn results_to_csv_string(
   {
     let headers: Vec<String> = prepare_headers(
       clade_node_attr_descs,
+      numeric_node_attr_keys,
       phenotype_attr_keys,
       ref_nodes,
       aa_motifs_keys,
+      nuc_motifs_keys,
+      metadata_keys,
       column_config,
     );
     let csv_writer = CsvVecWriter::new(&mut buf, delimiter, &headers)?;
@@ -285,7 +345,16 @@ mod tests {
       include_clade_founder_muts: false,
     };
 
-    let headers = prepare_headers(&[], &[], &AuspiceRefNodesDesc::default(), &[], &column_config);
+    let headers = prepare_headers(
+      &[],
+      &[],
+      &[],
+      &AuspiceRefNodesDesc::default(),
+      &[],
+      &[],
+      &[],
+      &column_config,
+    );
 
     // Verify headers are in canonical order as defined in CSV_COLUMN_CONFIG_MAP_DEFAULT
     let expected_order = vec![
@@ -300,6 +369,39 @@ mod tests {
     assert_eq!(headers, expected_order);
   }
 
+  #[test]
+  fn test_prepare_headers_metadata_after_seq_name() {
+    let column_config = CsvColumnConfig {
+      categories: indexmap! {
+        CsvColumnCategory::General => indexmap! {
+          o!("index") => true,
+          o!("seqName") => true,
+          o!("clade") => true,
+        },
+      },
+      individual: vec![],
+      include_dynamic: true,
+      include_rel_muts: false,
+      include_clade_founder_muts: false,
+    };
+
+    let headers = prepare_headers(
+      &[],
+      &[],
+      &[],
+      &AuspiceRefNodesDesc::default(),
+      &[],
+      &[],
+      &[o!("date"), o!("country")],
+      &column_config,
+    );
+
+    assert_eq!(
+      headers,
+      vec!["index", "seqName", "metadata.date", "metadata.country", "clade"]
+    );
+  }
+
   #[test]
   fn test_sort_headers_by_canonical_order() {
     let headers = vec![
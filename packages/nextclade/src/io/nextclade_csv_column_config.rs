@@ -118,6 +118,11 @@ pub static CSV_COLUMN_CONFIG_MAP_DEFAULT: LazyLock<CsvColumnConfigMap> = LazyLoc
       o!("index") => true,
       o!("seqName") => true,
       o!("clade") => true,
+      o!("cladeConfidence") => true,
+      o!("cladeConfidence.unsequenced") => true,
+      o!("cladeByMutations.clade") => true,
+      o!("cladeByMutations.confidence") => true,
+      o!("cladeByMutations.unmatched") => true,
       o!("qc.overallScore") => true,
       o!("qc.overallStatus") => true,
       o!("totalSubstitutions") => true,
@@ -130,12 +135,20 @@ pub static CSV_COLUMN_CONFIG_MAP_DEFAULT: LazyLock<CsvColumnConfigMap> = LazyLoc
       o!("totalAminoacidDeletions") => true,
       o!("totalAminoacidInsertions") => true,
       o!("totalUnknownAa") => true,
+      o!("totalSynonymousSubstitutions") => true,
+      o!("totalNonsynonymousSubstitutions") => true,
       o!("alignmentScore") => true,
       o!("alignmentStart") => true,
       o!("alignmentEnd") => true,
       o!("coverage") => true,
       o!("cdsCoverage") => true,
       o!("isReverseComplement") => true,
+      o!("circularOffset") => true,
+      o!("softClips.left") => true,
+      o!("softClips.right") => true,
+      o!("alignmentRefName") => true,
+      o!("segment") => true,
+      o!("sampleId") => true,
     },
     CsvColumnCategory::RefMuts => indexmap! {
       o!("substitutions") => true,
@@ -145,6 +158,9 @@ pub static CSV_COLUMN_CONFIG_MAP_DEFAULT: LazyLock<CsvColumnConfigMap> = LazyLoc
       o!("aaSubstitutions") => true,
       o!("aaDeletions") => true,
       o!("aaInsertions") => true,
+      o!("dnDs") => true,
+      o!("mutationSpectrum") => true,
+      o!("mutationSignatures") => true,
     },
     CsvColumnCategory::PrivMuts => indexmap! {
       o!("privateNucMutations.reversionSubstitutions") => true,
@@ -154,6 +170,9 @@ pub static CSV_COLUMN_CONFIG_MAP_DEFAULT: LazyLock<CsvColumnConfigMap> = LazyLoc
       o!("privateNucMutations.totalLabeledSubstitutions") => true,
       o!("privateNucMutations.totalUnlabeledSubstitutions") => true,
       o!("privateNucMutations.totalPrivateSubstitutions") => true,
+      o!("privateDnDs") => true,
+      o!("privateMutationSpectrum") => true,
+      o!("privateMutationSignatures") => true,
     },
     CsvColumnCategory::PrivAaMuts => indexmap! {
       o!("privateAaMutations.reversionSubstitutions") => true,
@@ -197,10 +216,37 @@ pub static CSV_COLUMN_CONFIG_MAP_DEFAULT: LazyLock<CsvColumnConfigMap> = LazyLoc
       o!("qc.stopCodons.totalStopCodons") => true,
       o!("qc.stopCodons.score") => true,
       o!("qc.stopCodons.status") => true,
+      o!("qc.recombinants.breakpoints") => true,
+      o!("qc.recombinants.donors") => true,
+      o!("qc.recombinants.totalBreakpoints") => true,
+      o!("qc.recombinants.score") => true,
+      o!("qc.recombinants.status") => true,
+      o!("qc.mutationSignature.signature") => true,
+      o!("qc.mutationSignature.totalSubstitutions") => true,
+      o!("qc.mutationSignature.matchingSubstitutions") => true,
+      o!("qc.mutationSignature.fraction") => true,
+      o!("qc.mutationSignature.score") => true,
+      o!("qc.mutationSignature.status") => true,
+      o!("qc.ampliconDropouts.dropouts") => true,
+      o!("qc.ampliconDropouts.totalDropouts") => true,
+      o!("qc.ampliconDropouts.dropoutsIgnored") => true,
+      o!("qc.ampliconDropouts.score") => true,
+      o!("qc.ampliconDropouts.status") => true,
+      o!("qc.molecularClock.sampleDate") => true,
+      o!("qc.molecularClock.nodeDate") => true,
+      o!("qc.molecularClock.expectedPrivateMutations") => true,
+      o!("qc.molecularClock.privateMutations") => true,
+      o!("qc.molecularClock.deviation") => true,
+      o!("qc.molecularClock.score") => true,
+      o!("qc.molecularClock.status") => true,
     },
     CsvColumnCategory::Primers => indexmap! {
       o!("totalPcrPrimerChanges") => true,
       o!("pcrPrimerChanges") => true,
+      o!("amplicons.totalDropouts") => true,
+      o!("amplicons.dropouts") => true,
+      o!("amplicons.totalPrimerChanges") => true,
+      o!("amplicons.primerChanges") => true,
     },
     CsvColumnCategory::ErrsWarns => indexmap! {
       o!("failedCdses") => true,
@@ -3,9 +3,14 @@ use crate::alphabet::aa::from_aa_seq;
 use crate::alphabet::nuc::{Nuc, from_nuc, from_nuc_seq};
 use crate::analyze::aa_del::AaDel;
 use crate::analyze::aa_sub::{AaSub, AaSubLabeled};
+use crate::analyze::amplicon_dropouts::AmpliconPrimerChange;
+use crate::analyze::dn_ds::{CdsDnDs, DnDs};
 use crate::analyze::find_aa_motifs::AaMotif;
 use crate::analyze::find_clade_founder::CladeNodeAttrFounderInfo;
+use crate::analyze::find_nuc_motifs::NucMotif;
+use crate::analyze::find_nuc_motifs_changes::NucMotifMutation;
 use crate::analyze::letter_ranges::{CdsAaRange, NucRange};
+use crate::analyze::mutation_spectrum::MutationSpectrum;
 use crate::analyze::nuc_del::NucDelRange;
 use crate::analyze::nuc_sub::{NucSub, NucSubLabeled};
 use crate::analyze::pcr_primer_changes::PcrPrimerChange;
@@ -14,6 +19,7 @@ use crate::o;
 use crate::qc::qc_config::StopCodonLocation;
 use crate::qc::qc_rule_snp_clusters::ClusteredSnp;
 use crate::translate::frame_shifts_translate::FrameShift;
+use crate::tree::tree_find_recombination::{RecombinationBreakpoint, RecombinationDonor};
 use crate::types::outputs::{NextcladeOutputs, PeptideWarning, PhenotypeValue};
 use crate::utils::num::is_int;
 use eyre::Report;
@@ -59,11 +65,16 @@ impl NextcladeResultsCsvRow {
       total_aminoacid_insertions,
       unknown_aa_ranges,
       total_unknown_aa,
+      dn_ds,
+      mutation_spectrum,
       alignment_range,
       alignment_score,
       pcr_primer_changes,
       total_pcr_primer_changes,
+      amplicons,
       clade,
+      clade_by_mutations,
+      clade_confidence,
       private_nuc_mutations,
       private_aa_mutations,
       missing_cdses,
@@ -73,15 +84,26 @@ impl NextcladeResultsCsvRow {
       phenotype_values,
       qc,
       custom_node_attributes,
+      clade_node_attr_lineages,
+      numeric_node_attributes,
+      metadata,
       is_reverse_complement,
+      circular_offset,
+      soft_clips,
+      alignment_ref_name,
+      segment,
+      sample_id,
       warnings,
       aa_motifs,
+      nuc_motifs,
+      nuc_motifs_changes,
       ref_nodes,
       ref_node_search_results,
       relative_nuc_mutations,
       relative_aa_mutations,
       clade_founder_info,
       clade_node_attr_founder_info,
+      recombination,
       ..
     } = nextclade_outputs;
 
@@ -89,6 +111,22 @@ impl NextcladeResultsCsvRow {
       .iter()
       .try_for_each(|(key, val)| self.add_entry(key, &val))?;
 
+    clade_node_attr_lineages.iter().try_for_each(|(key, lineage)| {
+      self.add_entry(format!("{key}.expanded"), &lineage.expanded)?;
+      self.add_entry(
+        format!("{key}.ancestors"),
+        &lineage.ancestors.join(ARRAY_ITEM_DELIMITER),
+      )
+    })?;
+
+    numeric_node_attributes
+      .iter()
+      .try_for_each(|(key, val)| self.add_entry(key, val))?;
+
+    metadata
+      .iter()
+      .try_for_each(|(key, val)| self.add_entry(format!("metadata.{key}"), val))?;
+
     if let Some(phenotype_values) = phenotype_values {
       phenotype_values
         .iter()
@@ -99,6 +137,20 @@ impl NextcladeResultsCsvRow {
       .iter()
       .try_for_each(|(name, motifs)| self.add_entry(name, &format_aa_motifs(motifs)))?;
 
+    nuc_motifs_changes.iter().try_for_each(|(name, changes)| {
+      let found = nuc_motifs.get(name).map(Vec::as_slice).unwrap_or_default();
+      self.add_entry(format!("nucMotifs['{name}'].found"), &format_nuc_motifs(found))?;
+      self.add_entry(format!("nucMotifs['{name}'].total"), &changes.total)?;
+      self.add_entry(
+        format!("nucMotifs['{name}'].gained"),
+        &format_nuc_motif_mutations(&changes.gained, |m| &m.qry_seq),
+      )?;
+      self.add_entry(
+        format!("nucMotifs['{name}'].lost"),
+        &format_nuc_motif_mutations(&changes.lost, |m| &m.ref_seq),
+      )
+    })?;
+
     if let Some(info) = clade_founder_info {
       self.add_clade_founder_cols("clade", info)?;
     }
@@ -175,6 +227,30 @@ impl NextcladeResultsCsvRow {
     self.add_entry("seqName", seq_name)?;
 
     self.add_entry("clade", &clade.as_deref().unwrap_or_default())?;
+    self.add_entry_maybe(
+      "cladeConfidence",
+      clade_confidence.get("clade").map(|c| c.confidence.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "cladeConfidence.unsequenced",
+      clade_confidence
+        .get("clade")
+        .map(|c| format_nuc_substitutions(&c.unsequenced, ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "cladeByMutations.clade",
+      clade_by_mutations.as_ref().map(|c| c.clade.clone().unwrap_or_default()),
+    )?;
+    self.add_entry_maybe(
+      "cladeByMutations.confidence",
+      clade_by_mutations.as_ref().map(|c| c.confidence.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "cladeByMutations.unmatched",
+      clade_by_mutations
+        .as_ref()
+        .map(|c| c.unmatched.iter().map(ToString::to_string).join(ARRAY_ITEM_DELIMITER)),
+    )?;
     self.add_entry("qc.overallScore", &format_qc_score(qc.overall_score))?;
     self.add_entry("qc.overallStatus", &qc.overall_status.to_string())?;
     self.add_entry("totalSubstitutions", &total_substitutions.to_string())?;
@@ -296,6 +372,47 @@ impl NextcladeResultsCsvRow {
       "aaInsertions",
       &format_aa_insertions(aa_insertions, ARRAY_ITEM_DELIMITER),
     )?;
+    self.add_entry(
+      "totalSynonymousSubstitutions",
+      &dn_ds.iter().map(|d| d.relative_to_reference.synonymous).sum::<usize>(),
+    )?;
+    self.add_entry(
+      "totalNonsynonymousSubstitutions",
+      &dn_ds
+        .iter()
+        .map(|d| d.relative_to_reference.nonsynonymous)
+        .sum::<usize>(),
+    )?;
+    self.add_entry(
+      "dnDs",
+      &format_dn_ds(dn_ds, |d| Some(&d.relative_to_reference), ARRAY_ITEM_DELIMITER),
+    )?;
+    self.add_entry(
+      "privateDnDs",
+      &format_dn_ds(dn_ds, |d| d.relative_to_nearest_node.as_ref(), ARRAY_ITEM_DELIMITER),
+    )?;
+    self.add_entry(
+      "mutationSpectrum",
+      &format_mutation_spectrum(&mutation_spectrum.substitutions, ARRAY_ITEM_DELIMITER),
+    )?;
+    self.add_entry(
+      "mutationSignatures",
+      &format_mutation_signatures(&mutation_spectrum.substitutions, ARRAY_ITEM_DELIMITER),
+    )?;
+    self.add_entry_maybe(
+      "privateMutationSpectrum",
+      mutation_spectrum
+        .private_substitutions
+        .as_ref()
+        .map(|spectrum| format_mutation_spectrum(spectrum, ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "privateMutationSignatures",
+      mutation_spectrum
+        .private_substitutions
+        .as_ref()
+        .map(|spectrum| format_mutation_signatures(spectrum, ARRAY_ITEM_DELIMITER)),
+    )?;
     self.add_entry(
       "unknownAaRanges",
       &format_unknown_aa_ranges(unknown_aa_ranges, ARRAY_ITEM_DELIMITER),
@@ -306,6 +423,24 @@ impl NextcladeResultsCsvRow {
       "pcrPrimerChanges",
       &format_pcr_primer_changes(pcr_primer_changes, ARRAY_ITEM_DELIMITER),
     )?;
+    self.add_entry_maybe(
+      "amplicons.totalDropouts",
+      amplicons.as_ref().map(|a| a.total_dropouts.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "amplicons.dropouts",
+      amplicons.as_ref().map(|a| a.dropouts.join(ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "amplicons.totalPrimerChanges",
+      amplicons.as_ref().map(|a| a.total_primer_changes.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "amplicons.primerChanges",
+      amplicons
+        .as_ref()
+        .map(|a| format_amplicon_primer_changes(&a.primer_changes, ARRAY_ITEM_DELIMITER)),
+    )?;
     self.add_entry("alignmentScore", &alignment_score)?;
     self.add_entry("alignmentStart", &(alignment_range.begin + 1).to_string())?;
     self.add_entry("alignmentEnd", &alignment_range.end.to_string())?;
@@ -429,7 +564,127 @@ impl NextcladeResultsCsvRow {
       "qc.stopCodons.status",
       qc.stop_codons.as_ref().map(|sc| sc.status.to_string()),
     )?;
+    self.add_entry_maybe(
+      "qc.recombinants.breakpoints",
+      recombination
+        .as_ref()
+        .map(|r| format_recombination_breakpoints(&r.breakpoints, ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "qc.recombinants.donors",
+      recombination
+        .as_ref()
+        .map(|r| format_recombination_donors(&r.donors, ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "qc.recombinants.totalBreakpoints",
+      qc.recombinants.as_ref().map(|rc| rc.total_breakpoints.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.recombinants.score",
+      qc.recombinants.as_ref().map(|rc| format_qc_score(rc.score)),
+    )?;
+    self.add_entry_maybe(
+      "qc.recombinants.status",
+      qc.recombinants.as_ref().map(|rc| rc.status.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.signature",
+      qc.mutation_signature.as_ref().map(|ms| ms.signature.clone()),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.totalSubstitutions",
+      qc.mutation_signature
+        .as_ref()
+        .map(|ms| ms.total_substitutions.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.matchingSubstitutions",
+      qc.mutation_signature
+        .as_ref()
+        .map(|ms| ms.matching_substitutions.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.fraction",
+      qc.mutation_signature.as_ref().map(|ms| format_qc_score(ms.fraction)),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.score",
+      qc.mutation_signature.as_ref().map(|ms| format_qc_score(ms.score)),
+    )?;
+    self.add_entry_maybe(
+      "qc.mutationSignature.status",
+      qc.mutation_signature.as_ref().map(|ms| ms.status.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.ampliconDropouts.dropouts",
+      qc.amplicon_dropouts
+        .as_ref()
+        .map(|ad| ad.dropouts.join(ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "qc.ampliconDropouts.totalDropouts",
+      qc.amplicon_dropouts.as_ref().map(|ad| ad.total_dropouts.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.ampliconDropouts.dropoutsIgnored",
+      qc.amplicon_dropouts
+        .as_ref()
+        .map(|ad| ad.dropouts_ignored.join(ARRAY_ITEM_DELIMITER)),
+    )?;
+    self.add_entry_maybe(
+      "qc.ampliconDropouts.score",
+      qc.amplicon_dropouts.as_ref().map(|ad| format_qc_score(ad.score)),
+    )?;
+    self.add_entry_maybe(
+      "qc.ampliconDropouts.status",
+      qc.amplicon_dropouts.as_ref().map(|ad| ad.status.to_string()),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.sampleDate",
+      qc.molecular_clock.as_ref().map(|mc| format_qc_score(mc.sample_date)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.nodeDate",
+      qc.molecular_clock.as_ref().map(|mc| format_qc_score(mc.node_date)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.expectedPrivateMutations",
+      qc.molecular_clock
+        .as_ref()
+        .map(|mc| format_qc_score(mc.expected_private_mutations)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.privateMutations",
+      qc.molecular_clock
+        .as_ref()
+        .map(|mc| format_qc_score(mc.private_mutations)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.deviation",
+      qc.molecular_clock.as_ref().map(|mc| format_qc_score(mc.deviation)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.score",
+      qc.molecular_clock.as_ref().map(|mc| format_qc_score(mc.score)),
+    )?;
+    self.add_entry_maybe(
+      "qc.molecularClock.status",
+      qc.molecular_clock.as_ref().map(|mc| mc.status.to_string()),
+    )?;
     self.add_entry("isReverseComplement", &is_reverse_complement.to_string())?;
+    self.add_entry_maybe("circularOffset", circular_offset.as_ref())?;
+    self.add_entry_maybe(
+      "softClips.left",
+      soft_clips.as_ref().map(|soft_clips| from_nuc_seq(&soft_clips.left)),
+    )?;
+    self.add_entry_maybe(
+      "softClips.right",
+      soft_clips.as_ref().map(|soft_clips| from_nuc_seq(&soft_clips.right)),
+    )?;
+    self.add_entry_maybe("alignmentRefName", alignment_ref_name.as_ref())?;
+    self.add_entry_maybe("segment", segment.as_ref())?;
+    self.add_entry_maybe("sampleId", sample_id.as_ref())?;
     self.add_entry("failedCdses", &format_failed_cdses(missing_cdses, ARRAY_ITEM_DELIMITER))?;
     self.add_entry(
       "warnings",
@@ -602,6 +857,23 @@ pub fn format_pcr_primer_changes(pcr_primer_changes: &[PcrPrimerChange], delimit
     .join(delimiter)
 }
 
+#[inline]
+pub fn format_amplicon_primer_changes(
+  primer_changes: &BTreeMap<String, Vec<AmpliconPrimerChange>>,
+  delimiter: &str,
+) -> String {
+  primer_changes
+    .iter()
+    .flat_map(|(pool, changes)| {
+      changes.iter().map(move |change| {
+        let primer = &change.primer;
+        let subs = format_nuc_substitutions_minimal(&change.substitutions, ";");
+        format!("{pool}:{primer}:{subs}")
+      })
+    })
+    .join(delimiter)
+}
+
 #[inline]
 pub fn format_aa_substitutions(aa_subs: &[AaSub], delimiter: &str) -> String {
   aa_subs.iter().map(ToString::to_string).join(delimiter)
@@ -686,6 +958,68 @@ pub fn format_stop_codons(stop_codons: &[StopCodonLocation], delimiter: &str) ->
     .join(delimiter)
 }
 
+#[inline]
+pub fn format_recombination_breakpoints(breakpoints: &[RecombinationBreakpoint], delimiter: &str) -> String {
+  breakpoints
+    .iter()
+    .map(|breakpoint| {
+      let range = breakpoint.range.to_string();
+      let left = breakpoint.left_clade.as_deref().unwrap_or_default();
+      let right = breakpoint.right_clade.as_deref().unwrap_or_default();
+      format!("{range}:{left}>{right}")
+    })
+    .join(delimiter)
+}
+
+#[inline]
+pub fn format_recombination_donors(donors: &[RecombinationDonor], delimiter: &str) -> String {
+  donors
+    .iter()
+    .map(|donor| {
+      let range = donor.range.to_string();
+      let clade = donor.clade.as_deref().unwrap_or(&donor.node_name);
+      format!("{range}:{clade}")
+    })
+    .join(delimiter)
+}
+
+#[inline]
+pub fn format_dn_ds<'a>(
+  dn_ds: &'a [CdsDnDs],
+  get: impl Fn(&'a CdsDnDs) -> Option<&'a DnDs>,
+  delimiter: &str,
+) -> String {
+  dn_ds
+    .iter()
+    .filter_map(|d| {
+      let value = get(d)?.dn_ds?;
+      Some(format!("{}:{value}", d.cds_name))
+    })
+    .join(delimiter)
+}
+
+#[inline]
+pub fn format_mutation_spectrum(spectrum: &MutationSpectrum, delimiter: &str) -> String {
+  spectrum
+    .spectrum
+    .iter()
+    .filter(|(_, count)| **count > 0)
+    .map(|(class, count)| format!("{class}:{count}"))
+    .join(delimiter)
+}
+
+#[inline]
+pub fn format_mutation_signatures(spectrum: &MutationSpectrum, delimiter: &str) -> String {
+  spectrum
+    .signatures
+    .iter()
+    .map(|signature| {
+      let fraction = format_qc_score(signature.fraction);
+      format!("{}:{}:{fraction}", signature.name, signature.count)
+    })
+    .join(delimiter)
+}
+
 #[inline]
 pub fn format_cds_coverage(cds_coverage: &BTreeMap<String, f64>, delimiter: &str) -> String {
   cds_coverage
@@ -719,6 +1053,22 @@ pub fn format_escape(escape: &[PhenotypeValue]) -> String {
     .join(";")
 }
 
+#[inline]
+fn format_nuc_motifs(motifs: &[NucMotif]) -> String {
+  motifs
+    .iter()
+    .map(|NucMotif { position, seq, .. }| format!("{}:{seq}", position + 1))
+    .join(";")
+}
+
+#[inline]
+fn format_nuc_motif_mutations(motifs: &[NucMotifMutation], get_seq: impl Fn(&NucMotifMutation) -> &String) -> String {
+  motifs
+    .iter()
+    .map(|motif| format!("{}:{}", motif.position + 1, get_seq(motif)))
+    .join(";")
+}
+
 #[inline]
 fn format_aa_motifs(motifs: &[AaMotif]) -> String {
   motifs
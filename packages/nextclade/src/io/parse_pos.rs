@@ -1,3 +1,4 @@
+use crate::make_error;
 use crate::utils::error::to_eyre_error;
 use eyre::{Report, WrapErr};
 
@@ -7,5 +8,8 @@ use eyre::{Report, WrapErr};
 /// more common to see 1-based indexing. We perform the conversion here.
 pub fn parse_pos(s: &str) -> Result<isize, Report> {
   let pos = to_eyre_error(s.parse::<usize>()).wrap_err_with(|| format!("Unable to parse position: '{s}'"))?;
+  if pos == 0 {
+    return make_error!("Unable to parse position: '{s}'. Positions are 1-based and cannot be 0");
+  }
   Ok((pos - 1) as isize)
 }
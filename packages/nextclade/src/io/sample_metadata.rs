@@ -0,0 +1,232 @@
+use crate::io::fs::read_file_to_string;
+use crate::make_error;
+use crate::utils::datetime::parse_num_date;
+use csv::ReaderBuilder as CsvReaderBuilder;
+use eyre::{Report, WrapErr};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Names of the columns which are tried, in order, as the column containing sequence names, when it is not specified
+const DEFAULT_ID_COLUMNS: &[&str] = &["seqName", "strain", "name", "accession"];
+
+/// Metadata values of one sample, by column name
+pub type SampleMetadataEntry = BTreeMap<String, String>;
+
+/// Table of sample metadata (e.g. collection dates), with one row per sequence name
+#[derive(Clone, Debug, Default)]
+pub struct SampleMetadata {
+  /// Name of the column containing sequence names
+  pub id_column: String,
+  /// Names of the other columns, in the order of appearance
+  pub columns: Vec<String>,
+  /// Metadata values by sequence name. Empty values are omitted.
+  pub entries: BTreeMap<String, SampleMetadataEntry>,
+}
+
+impl SampleMetadata {
+  /// Parse sample metadata table in TSV or CSV format.
+  ///
+  /// The delimiter is deduced from the header line: tab if it contains tabs, and comma otherwise. Sequence names are
+  /// taken from the column `id_column`, or, if it is empty, from the first of the columns `seqName`, `strain`, `name`
+  /// and `accession` which is present, or from the first column.
+  pub fn from_str(s: impl AsRef<str>, id_column: &str) -> Result<Self, Report> {
+    let s = s.as_ref();
+    let header_line = s.lines().next().unwrap_or_default();
+    let delimiter = if header_line.contains('\t') { b'\t' } else { b',' };
+
+    let mut reader = CsvReaderBuilder::new()
+      .delimiter(delimiter)
+      .has_headers(true)
+      .trim(csv::Trim::All)
+      .from_reader(s.as_bytes());
+
+    let headers = reader.headers()?.iter().map(ToOwned::to_owned).collect_vec();
+    if headers.is_empty() {
+      return make_error!("Sample metadata table has no columns");
+    }
+
+    let id_column_index = if id_column.is_empty() {
+      DEFAULT_ID_COLUMNS
+        .iter()
+        .find_map(|name| headers.iter().position(|header| header == name))
+        .unwrap_or(0)
+    } else {
+      match headers.iter().position(|header| header == id_column) {
+        Some(index) => index,
+        None => {
+          return make_error!(
+            "Column '{id_column}' not found. Available columns: {}",
+            headers.join(", ")
+          );
+        }
+      }
+    };
+
+    let mut entries = BTreeMap::new();
+    for (row_index, record) in reader.records().enumerate() {
+      let record = record?;
+      let Some(name) = record.get(id_column_index).filter(|name| !name.is_empty()) else {
+        return make_error!("Sequence name is empty in row {}", row_index + 1);
+      };
+
+      let entry: SampleMetadataEntry = headers
+        .iter()
+        .zip(record.iter())
+        .enumerate()
+        .filter(|(i, (_, value))| *i != id_column_index && !value.is_empty())
+        .map(|(_, (header, value))| (header.clone(), value.to_owned()))
+        .collect();
+
+      if entries.insert(name.to_owned(), entry).is_some() {
+        return make_error!("Sequence name '{name}' is listed more than once");
+      }
+    }
+
+    let id_column = headers[id_column_index].clone();
+    let columns = headers
+      .into_iter()
+      .enumerate()
+      .filter(|(i, _)| *i != id_column_index)
+      .map(|(_, header)| header)
+      .collect_vec();
+
+    Ok(Self {
+      id_column,
+      columns,
+      entries,
+    })
+  }
+
+  pub fn from_path(filepath: impl AsRef<Path>, id_column: &str) -> Result<Self, Report> {
+    let filepath = filepath.as_ref();
+
+    let data =
+      read_file_to_string(filepath).wrap_err_with(|| format!("When reading sample metadata {}", filepath.display()))?;
+
+    Self::from_str(&data, id_column).wrap_err_with(|| format!("When parsing sample metadata {}", filepath.display()))
+  }
+
+  /// Names of the columns to include into the outputs: the given columns, or all columns if none are given
+  pub fn selected_columns(&self, columns: &[String]) -> Result<Vec<String>, Report> {
+    if columns.is_empty() {
+      return Ok(self.columns.clone());
+    }
+    for column in columns {
+      if !self.columns.contains(column) {
+        return make_error!(
+          "Column '{column}' is requested for outputs, but is not found in sample metadata. Available columns: {}",
+          self.columns.join(", ")
+        );
+      }
+    }
+    Ok(columns.to_vec())
+  }
+
+  /// Find metadata of a sequence by its name. If there is no entry for the full name, the sequence identifier (the part
+  /// of the name before the first whitespace) is tried.
+  pub fn get(&self, seq_name: &str) -> Option<&SampleMetadataEntry> {
+    self.entries.get(seq_name).or_else(|| {
+      let seq_id = seq_name.split_whitespace().next()?;
+      self.entries.get(seq_id)
+    })
+  }
+}
+
+/// Metadata values of the given columns, or of all columns if none are given
+pub fn sample_metadata_select(entry: &SampleMetadataEntry, columns: &[String]) -> SampleMetadataEntry {
+  entry
+    .iter()
+    .filter(|(key, _)| columns.is_empty() || columns.contains(key))
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect()
+}
+
+/// Collection date of the sample as a decimal year, if the metadata contains a valid date in the given column
+pub fn sample_metadata_num_date(entry: &SampleMetadataEntry, date_column: &str) -> Option<f64> {
+  let date = entry.get(date_column)?;
+  parse_num_date(date)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::o;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  fn parses_tsv_with_default_id_column() -> Result<(), Report> {
+    let metadata = SampleMetadata::from_str("date\tstrain\tcountry\n2021-03-15\tA/1\tFrance\n\tB/2\tSpain\n", "")?;
+    assert_eq!(metadata.id_column, "strain");
+    assert_eq!(metadata.columns, vec![o!("date"), o!("country")]);
+    assert_eq!(
+      metadata.get("A/1 some description"),
+      Some(&BTreeMap::from([
+        (o!("country"), o!("France")),
+        (o!("date"), o!("2021-03-15"))
+      ]))
+    );
+    assert_eq!(
+      metadata.get("B/2"),
+      Some(&BTreeMap::from([(o!("country"), o!("Spain"))]))
+    );
+    assert_eq!(metadata.get("C/3"), None);
+    Ok(())
+  }
+
+  #[rstest]
+  fn parses_csv_with_explicit_id_column() -> Result<(), Report> {
+    let metadata = SampleMetadata::from_str("id,sample,date\n1,s1,2020\n", "sample")?;
+    assert_eq!(metadata.columns, vec![o!("id"), o!("date")]);
+    assert_eq!(
+      metadata
+        .get("s1")
+        .and_then(|entry| sample_metadata_num_date(entry, "date"))
+        .map(OrderedFloat),
+      Some(OrderedFloat(2020.5))
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn selects_columns() -> Result<(), Report> {
+    let metadata = SampleMetadata::from_str("strain\tdate\tcountry\nA/1\t2021\tFrance\n", "")?;
+    let columns = metadata.selected_columns(&[o!("country")])?;
+    assert_eq!(columns, vec![o!("country")]);
+    assert_eq!(metadata.selected_columns(&[])?, vec![o!("date"), o!("country")]);
+    assert_eq!(
+      metadata.get("A/1").map(|entry| sample_metadata_select(entry, &columns)),
+      Some(BTreeMap::from([(o!("country"), o!("France"))]))
+    );
+
+    let err = metadata.selected_columns(&[o!("host")]).unwrap_err();
+    assert!(format!("{err:#}").contains("Column 'host' is requested"), "{err:#}");
+    Ok(())
+  }
+
+  #[rstest]
+  #[case("id\tdate\ns1\t2020\ns1\t2021\n", "", "Sequence name 's1' is listed more than once")]
+  #[case("id\tdate\ns1\t2020\n", "strain", "Column 'strain' not found")]
+  fn rejects_malformed_tables(#[case] data: &str, #[case] id_column: &str, #[case] expected: &str) {
+    let err = SampleMetadata::from_str(data, id_column).unwrap_err();
+    assert!(format!("{err:#}").contains(expected), "{err:#}");
+  }
+
+  #[rstest]
+  #[case("2021-01-01", Some(2021.0 + 0.5 / 365.0))]
+  #[case("2020-12-31", Some(2020.0 + 365.5 / 366.0))]
+  // Middle of February: between the middles of the days 32 and 59
+  #[case("2021-02", Some(2021.0 + 45.0 / 365.0))]
+  #[case("2021-02-XX", Some(2021.0 + 45.0 / 365.0))]
+  #[case("2021-XX-XX", Some(2021.5))]
+  #[case("2021.25", Some(2021.25))]
+  #[case("2021-02-30", None)]
+  #[case("unknown", None)]
+  #[case("", None)]
+  fn parses_dates(#[case] date: &str, #[case] expected: Option<f64>) {
+    let round = |num_date: f64| OrderedFloat((num_date * 1e9).round() / 1e9);
+    assert_eq!(parse_num_date(date).map(round), expected.map(round));
+  }
+}
@@ -0,0 +1,146 @@
+use crate::io::csv::{CsvVecFileWriter, VecWriter};
+use crate::types::outputs::NextcladeOutputs;
+use eyre::Report;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::path::Path;
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter};
+
+/// Columns of the segments summary, repeated for each of the segments
+#[derive(Clone, Copy, Debug, AsRefStr, EnumIter)]
+enum SegmentColumn {
+  #[strum(serialize = "seqName")]
+  SeqName,
+  #[strum(serialize = "clade")]
+  Clade,
+  #[strum(serialize = "coverage")]
+  Coverage,
+  #[strum(serialize = "totalSubstitutions")]
+  TotalSubstitutions,
+  #[strum(serialize = "qc.overallStatus")]
+  QcOverallStatus,
+}
+
+impl SegmentColumn {
+  fn format(self, output: &NextcladeOutputs) -> String {
+    match self {
+      Self::SeqName => output.seq_name.clone(),
+      Self::Clade => output.clade.clone().unwrap_or_default(),
+      Self::Coverage => output.coverage.to_string(),
+      Self::TotalSubstitutions => output.total_substitutions.to_string(),
+      Self::QcOverallStatus => output.qc.overall_status.to_string(),
+    }
+  }
+}
+
+/// Per-sample summary of a segmented genome analysis. Each row corresponds to one sample, with columns for each
+/// of the segments. This makes it easy to spot incomplete samples as well as reassortment between segments.
+pub struct SegmentsSummaryRow {
+  pub sample_id: String,
+  pub segments: BTreeMap<String, Vec<NextcladeOutputs>>,
+}
+
+pub fn get_segments_summary_headers(segment_names: &[String]) -> Vec<String> {
+  let general = ["sampleId", "totalSegments", "missingSegments"].map(ToOwned::to_owned);
+  let per_segment = segment_names
+    .iter()
+    .flat_map(|segment| SegmentColumn::iter().map(move |column| format!("{segment}.{}", column.as_ref())));
+  general.into_iter().chain(per_segment).collect_vec()
+}
+
+/// Group results by sample. Results without sample identifier (non-segmented) are skipped.
+pub fn get_segments_summary(outputs: &[NextcladeOutputs]) -> Vec<SegmentsSummaryRow> {
+  let mut samples = BTreeMap::<&str, BTreeMap<String, Vec<NextcladeOutputs>>>::new();
+  for output in outputs {
+    if let (Some(sample_id), Some(segment)) = (&output.sample_id, &output.segment) {
+      samples
+        .entry(sample_id)
+        .or_default()
+        .entry(segment.clone())
+        .or_default()
+        .push(output.clone());
+    }
+  }
+
+  samples
+    .into_iter()
+    .map(|(sample_id, segments)| SegmentsSummaryRow {
+      sample_id: sample_id.to_owned(),
+      segments,
+    })
+    .collect_vec()
+}
+
+fn format_segment_column(outputs: &[NextcladeOutputs], column: SegmentColumn) -> String {
+  outputs
+    .iter()
+    .sorted_by_key(|output| output.index)
+    .map(|output| column.format(output))
+    .join(",")
+}
+
+pub fn format_segments_summary_row(row: &SegmentsSummaryRow, segment_names: &[String]) -> Vec<String> {
+  let missing_segments = segment_names
+    .iter()
+    .filter(|segment| !row.segments.contains_key(*segment))
+    .join(",");
+
+  let general = [row.sample_id.clone(), row.segments.len().to_string(), missing_segments];
+
+  let per_segment = segment_names.iter().flat_map(|segment| {
+    let outputs = row.segments.get(segment).map(Vec::as_slice).unwrap_or_default();
+    SegmentColumn::iter().map(move |column| format_segment_column(outputs, column))
+  });
+
+  general.into_iter().chain(per_segment).collect_vec()
+}
+
+/// Write per-sample summary of segmented genome analysis into a CSV or TSV file
+pub fn segments_summary_write(
+  filepath: impl AsRef<Path>,
+  delimiter: u8,
+  segment_names: &[String],
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  let headers = get_segments_summary_headers(segment_names);
+  let mut writer = CsvVecFileWriter::new(filepath, delimiter, &headers)?;
+  for row in get_segments_summary(outputs) {
+    writer.write(format_segments_summary_row(&row, segment_names))?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::o;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  fn formats_segment_columns() {
+    let segment_names = vec![o!("HA"), o!("NA")];
+    let output = |seq_name: &str, segment: &str, clade: &str| NextcladeOutputs {
+      seq_name: seq_name.to_owned(),
+      sample_id: Some(o!("s1")),
+      segment: Some(segment.to_owned()),
+      clade: Some(clade.to_owned()),
+      ..NextcladeOutputs::default()
+    };
+    let outputs = [output("s1|HA", "HA", "2.3.4.4b"), output("s1|NA", "NA", "N1.A")];
+
+    let headers = get_segments_summary_headers(&segment_names);
+    let rows = get_segments_summary(&outputs)
+      .iter()
+      .map(|row| format_segments_summary_row(row, &segment_names))
+      .collect_vec();
+
+    let value = |column: &str| {
+      let i = headers.iter().position(|header| header == column).unwrap();
+      rows[0][i].clone()
+    };
+    assert_eq!(headers.len(), rows[0].len());
+    assert_eq!((value("HA.clade"), value("NA.clade")), (o!("2.3.4.4b"), o!("N1.A")));
+  }
+}
@@ -0,0 +1,689 @@
+use crate::alphabet::nuc::{Nuc, from_nuc};
+use crate::analyze::aa_sub::AaSub;
+use crate::analyze::nuc_del::NucDelRange;
+use crate::coord::position::PositionLike;
+use crate::coord::range::{NucRefGlobalRange, have_intersection};
+use crate::gene::gene_map::GeneMap;
+use crate::io::fasta::parse_fasta_header;
+use crate::io::file::create_file_or_stdout;
+use crate::types::outputs::NextcladeOutputs;
+use crate::utils::info::this_package_version_str;
+use crate::{make_error, make_internal_error, make_internal_report};
+use eyre::{Report, WrapErr};
+use itertools::{Itertools, chain};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::Path;
+
+/// Reference sequence (or one segment of a segmented reference) the variants are called against
+pub struct VcfReference<'a> {
+  /// Name of the reference sequence record. Sequence id is used as chromosome name.
+  pub name: &'a str,
+  pub seq: &'a [Nuc],
+  pub gene_map: &'a GeneMap,
+}
+
+impl VcfReference<'_> {
+  pub fn chrom(&self) -> String {
+    parse_fasta_header(self.name).0
+  }
+}
+
+/// Variant found in one of the query sequences, normalized to VCF representation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VcfCall {
+  /// 0-based position of the first nucleotide of the reference allele
+  pub pos: usize,
+  pub ref_allele: String,
+  pub alt_allele: String,
+  /// Amino acid changes overlapping the variant
+  pub aa_changes: Vec<AaSub>,
+}
+
+impl VcfCall {
+  fn range(&self) -> NucRefGlobalRange {
+    NucRefGlobalRange::from_usize(self.pos, self.pos + self.ref_allele.len())
+  }
+}
+
+/// Convert nucleotide substitutions, deletions and insertions of a query sequence into VCF variant calls.
+///
+/// Deletions are anchored to the preceding reference nucleotide (or to the following one, if the deletion starts at
+/// the beginning of the reference), as required by VCF. Insertions are anchored to the reference nucleotide after
+/// which they occur. Variants overlapping each other, including their anchors, are merged into one call, such that
+/// each position of the sequence is described by at most one call (e.g. a substitution `T>C` and an insertion `GG`
+/// after it become `T>CGG`). Calls are then normalized, same as by `bcftools norm`.
+pub fn find_vcf_calls(output: &NextcladeOutputs, ref_seq: &[Nuc]) -> Vec<VcfCall> {
+  let qry_nucs: BTreeMap<usize, Nuc> = output
+    .substitutions
+    .iter()
+    .map(|sub| (sub.pos.as_usize(), sub.qry_nuc))
+    .collect();
+
+  let deleted: BTreeSet<usize> = output
+    .deletions
+    .iter()
+    .flat_map(|del| del.range().iter())
+    .map(|pos| pos.as_usize())
+    .collect();
+
+  let insertions: BTreeMap<i32, &[Nuc]> = output
+    .insertions
+    .iter()
+    .map(|ins| (ins.pos, ins.ins.as_slice()))
+    .collect();
+
+  // Reference ranges of the variants, including the anchor nucleotides of indels
+  let substitution_ranges = qry_nucs.keys().map(|&pos| (pos, pos + 1));
+  let deletion_ranges = output.deletions.iter().map(|del| {
+    let range = del.range();
+    let (begin, end) = (range.begin.as_usize(), range.end.as_usize());
+    if begin > 0 { (begin - 1, end) } else { (begin, end + 1) }
+  });
+  let insertion_ranges = insertions.keys().map(|&pos| {
+    let pos = pos.max(0) as usize;
+    (pos, pos + 1)
+  });
+
+  let mut ranges = Vec::<(usize, usize)>::new();
+  for (begin, end) in chain!(substitution_ranges, deletion_ranges, insertion_ranges)
+    .filter(|&(_, end)| end <= ref_seq.len())
+    .sorted()
+  {
+    match ranges.last_mut() {
+      Some(last) if begin < last.1 => last.1 = last.1.max(end),
+      _ => ranges.push((begin, end)),
+    }
+  }
+
+  let mut prev_end = 0;
+  ranges
+    .into_iter()
+    .map(|(begin, end)| {
+      let ref_allele = ref_seq[begin..end].to_vec();
+
+      let mut alt_allele = Vec::<Nuc>::new();
+      if begin == 0
+        && let Some(ins) = insertions.get(&-1)
+      {
+        alt_allele.extend_from_slice(ins);
+      }
+      for (pos, &ref_nuc) in ref_allele.iter().enumerate().map(|(i, nuc)| (begin + i, nuc)) {
+        if !deleted.contains(&pos) {
+          alt_allele.push(qry_nucs.get(&pos).copied().unwrap_or(ref_nuc));
+        }
+        if let Some(ins) = insertions.get(&(pos as i32)) {
+          alt_allele.extend_from_slice(ins);
+        }
+      }
+
+      let (pos, ref_allele, alt_allele) = normalize_call(ref_seq, begin, ref_allele, alt_allele, prev_end);
+      prev_end = end;
+
+      let mut call = VcfCall {
+        pos,
+        ref_allele: format_allele(&ref_allele),
+        alt_allele: format_allele(&alt_allele),
+        aa_changes: vec![],
+      };
+      call.aa_changes = find_aa_changes(output, &call.range());
+      call
+    })
+    .collect_vec()
+}
+
+/// Left-align and trim a variant, same as `bcftools norm`: nucleotides common to the ends of both alleles are removed,
+/// which shifts indels to the leftmost position in repeats, and then nucleotides common to the starts of both alleles
+/// are removed, keeping at least one nucleotide in each allele. The variant is not shifted to before `min_pos`, so that
+/// it does not overlap the previous variant of the same sequence.
+fn normalize_call(
+  ref_seq: &[Nuc],
+  mut pos: usize,
+  mut ref_allele: Vec<Nuc>,
+  mut alt_allele: Vec<Nuc>,
+  min_pos: usize,
+) -> (usize, Vec<Nuc>, Vec<Nuc>) {
+  while let (Some(ref_last), Some(alt_last)) = (ref_allele.last(), alt_allele.last()) {
+    let is_shortest = ref_allele.len() == 1 || alt_allele.len() == 1;
+    if ref_last != alt_last || (is_shortest && pos <= min_pos) {
+      break;
+    }
+    ref_allele.pop();
+    alt_allele.pop();
+    if ref_allele.is_empty() || alt_allele.is_empty() {
+      pos -= 1;
+      ref_allele.insert(0, ref_seq[pos]);
+      alt_allele.insert(0, ref_seq[pos]);
+    }
+  }
+
+  while ref_allele.len() > 1 && alt_allele.len() > 1 && ref_allele[0] == alt_allele[0] {
+    ref_allele.remove(0);
+    alt_allele.remove(0);
+    pos += 1;
+  }
+
+  (pos, ref_allele, alt_allele)
+}
+
+/// VCF only allows A, C, G, T and N in alleles. Ambiguous nucleotides are written as N.
+fn format_allele(nucs: &[Nuc]) -> String {
+  nucs
+    .iter()
+    .map(|&nuc| if nuc.is_acgt() { from_nuc(nuc) } else { 'N' })
+    .collect()
+}
+
+/// Find amino acid changes with codons overlapping a given nucleotide range
+fn find_aa_changes(output: &NextcladeOutputs, range: &NucRefGlobalRange) -> Vec<AaSub> {
+  output
+    .aa_changes_groups
+    .iter()
+    .flat_map(|group| &group.changes)
+    .filter(|change| change.ref_aa != change.qry_aa)
+    .filter(|change| change.nuc_ranges.iter().any(|r| have_intersection(r, range)))
+    .map(AaSub::from)
+    .unique()
+    .collect_vec()
+}
+
+/// Whether the query sequence has the reference allele in the given range, for a site where it has no variant call: the
+/// range is inside the alignment and does not overlap missing or ambiguous nucleotides. Deletions and substitutions
+/// overlapping the range mean that the sequence has an allele other than the reference one, which is not among the
+/// alleles of the site.
+fn is_ref_called(output: &NextcladeOutputs, range: &NucRefGlobalRange) -> bool {
+  let alignment_range = &output.alignment_range;
+  let is_aligned = alignment_range.begin <= range.begin && range.end <= alignment_range.end;
+  let substitutions = output
+    .substitutions
+    .iter()
+    .map(|sub| NucRefGlobalRange::new(sub.pos, sub.pos + 1));
+  is_aligned
+    && !output
+      .missing
+      .iter()
+      .chain(output.non_acgtns.iter())
+      .map(|missing| missing.range.clone())
+      .chain(output.deletions.iter().map(NucDelRange::range).cloned())
+      .chain(substitutions)
+      .any(|uncalled| have_intersection(&uncalled, range))
+}
+
+/// Variant site in a VCF file, merged from the calls of all samples with the same position and reference allele
+struct VcfRecord {
+  chrom_index: usize,
+  pos: usize,
+  ref_allele: String,
+  alt_alleles: Vec<String>,
+  aa_changes: BTreeSet<String>,
+  genotypes: Vec<Option<usize>>,
+}
+
+/// Writes variants of all query sequences into a multi-sample VCF file, with one haploid sample per sequence
+pub fn vcf_write_to_file(
+  filepath: impl AsRef<Path>,
+  refs: &[VcfReference],
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  let filepath = filepath.as_ref();
+  let file = create_file_or_stdout(filepath)?;
+  vcf_write_to_writer(file, refs, outputs).wrap_err_with(|| format!("When writing VCF file: {}", filepath.display()))
+}
+
+/// Writes variants of each query sequence into a separate single-sample VCF file. File paths are obtained by
+/// substituting the template variables `{seq_index}` and `{seq_name}` in the path template.
+pub fn vcf_write_per_sequence(
+  path_template: &str,
+  refs: &[VcfReference],
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  outputs.iter().try_for_each(|output| {
+    let filepath = path_template
+      .replace("{seq_index}", &output.index.to_string())
+      .replace("{seq_name}", &sanitize_filename(&output.seq_name));
+    vcf_write_to_file(filepath, refs, std::slice::from_ref(output))
+  })
+}
+
+fn sanitize_filename(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || ['.', '-', '_'].contains(&c) {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
+
+pub fn vcf_write_to_writer<W: Write>(
+  mut writer: W,
+  refs: &[VcfReference],
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  let chroms = refs.iter().map(VcfReference::chrom).collect_vec();
+
+  let sample_chrom_indices = outputs
+    .iter()
+    .map(|output| {
+      refs
+        .iter()
+        .position(|r| r.name == output.ref_name)
+        .ok_or_else(|| make_internal_report!("Reference sequence is not known: '{}'", output.ref_name))
+    })
+    .collect::<Result<Vec<_>, Report>>()?;
+
+  let mut records = BTreeMap::<(usize, usize, String), VcfRecord>::new();
+  for (sample, (output, &chrom_index)) in outputs.iter().zip(&sample_chrom_indices).enumerate() {
+    for call in find_vcf_calls(output, refs[chrom_index].seq) {
+      let record = records
+        .entry((chrom_index, call.pos, call.ref_allele.clone()))
+        .or_insert_with(|| VcfRecord {
+          chrom_index,
+          pos: call.pos,
+          ref_allele: call.ref_allele.clone(),
+          alt_alleles: vec![],
+          aa_changes: BTreeSet::new(),
+          genotypes: vec![None; outputs.len()],
+        });
+
+      let alt_index = if let Some(i) = record.alt_alleles.iter().position(|alt| alt == &call.alt_allele) {
+        i
+      } else {
+        record.alt_alleles.push(call.alt_allele.clone());
+        record.alt_alleles.len() - 1
+      };
+
+      if record.genotypes[sample].is_some() {
+        return make_internal_error!(
+          "Sequence '{}' has more than one variant call at position {} of reference '{}'",
+          output.seq_name,
+          call.pos + 1,
+          chroms[chrom_index]
+        );
+      }
+      record.genotypes[sample] = Some(alt_index + 1);
+      record
+        .aa_changes
+        .extend(call.aa_changes.iter().map(ToString::to_string));
+    }
+  }
+
+  write_header(&mut writer, refs, &chroms, outputs)?;
+
+  for record in records.values() {
+    let range = NucRefGlobalRange::from_usize(record.pos, record.pos + record.ref_allele.len());
+
+    // Genotypes of samples without the variant: reference allele if the sequence has it at the site, missing otherwise
+    let genotypes = record
+      .genotypes
+      .iter()
+      .zip(outputs)
+      .zip(&sample_chrom_indices)
+      .map(|((genotype, output), &chrom_index)| match genotype {
+        Some(allele) => Some(*allele),
+        None => (chrom_index == record.chrom_index && is_ref_called(output, &range)).then_some(0),
+      })
+      .collect_vec();
+
+    let allele_counts = (1..=record.alt_alleles.len())
+      .map(|allele| genotypes.iter().filter(|&&gt| gt == Some(allele)).count())
+      .join(",");
+    let allele_number = genotypes.iter().filter(|gt| gt.is_some()).count();
+
+    let mut info = vec![format!("AC={allele_counts}"), format!("AN={allele_number}")];
+
+    let cdses = refs[record.chrom_index]
+      .gene_map
+      .iter_cdses()
+      .filter(|cds| {
+        cds
+          .segments
+          .iter()
+          .any(|segment| have_intersection(&segment.range, &range))
+      })
+      .map(|cds| cds.name.as_str())
+      .unique()
+      .join(",");
+    if !cdses.is_empty() {
+      info.push(format!("CDS={cdses}"));
+    }
+    if !record.aa_changes.is_empty() {
+      info.push(format!("AA_CHANGE={}", record.aa_changes.iter().join(",")));
+    }
+
+    let genotypes = genotypes
+      .iter()
+      .map(|gt| gt.map_or_else(|| ".".to_owned(), |gt| gt.to_string()));
+
+    let columns = [
+      chroms[record.chrom_index].clone(),
+      (record.pos + 1).to_string(),
+      ".".to_owned(),
+      record.ref_allele.clone(),
+      record.alt_alleles.join(","),
+      ".".to_owned(),
+      ".".to_owned(),
+      info.join(";"),
+      "GT".to_owned(),
+    ];
+
+    writeln!(writer, "{}", columns.into_iter().chain(genotypes).join("\t"))?;
+  }
+
+  Ok(())
+}
+
+fn write_header<W: Write>(
+  writer: &mut W,
+  refs: &[VcfReference],
+  chroms: &[String],
+  outputs: &[NextcladeOutputs],
+) -> Result<(), Report> {
+  if let Some(dupe) = chroms.iter().duplicates().next() {
+    return make_error!("Sequence ids of reference sequences are expected to be unique, but found duplicate: '{dupe}'");
+  }
+
+  writeln!(writer, "##fileformat=VCFv4.2")?;
+  writeln!(writer, "##source=Nextclade {}", this_package_version_str())?;
+  for (r, chrom) in refs.iter().zip(chroms) {
+    writeln!(writer, "##contig=<ID={chrom},length={}>", r.seq.len())?;
+  }
+  writeln!(
+    writer,
+    r#"##INFO=<ID=AC,Number=A,Type=Integer,Description="Allele count in genotypes, for each ALT allele">"#
+  )?;
+  writeln!(
+    writer,
+    r#"##INFO=<ID=AN,Number=1,Type=Integer,Description="Total number of alleles in called genotypes">"#
+  )?;
+  writeln!(
+    writer,
+    r#"##INFO=<ID=CDS,Number=.,Type=String,Description="Names of CDSes overlapping the variant">"#
+  )?;
+  writeln!(
+    writer,
+    r#"##INFO=<ID=AA_CHANGE,Number=.,Type=String,Description="Amino acid changes in codons overlapping the variant, in format CDS:REF_AA POS QRY_AA">"#
+  )?;
+  writeln!(
+    writer,
+    r#"##FORMAT=<ID=GT,Number=1,Type=String,Description="Genotype">"#
+  )?;
+
+  let samples = outputs
+    .iter()
+    .map(|output| output.seq_name.replace(char::is_whitespace, "_"));
+  let columns = ["#CHROM", "POS", "ID", "REF", "ALT", "QUAL", "FILTER", "INFO", "FORMAT"]
+    .into_iter()
+    .map(ToOwned::to_owned)
+    .chain(samples)
+    .join("\t");
+  writeln!(writer, "{columns}")?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::align::insertions_strip::NucIns;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::analyze::letter_ranges::NucRange;
+  use crate::analyze::nuc_sub::NucSub;
+  use crate::coord::range::Range;
+  use eyre::Report;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  const REF_NAME: &str = "ref description";
+  const REF_SEQ: &str = "ACGTACGTAC";
+
+  fn create_output(seq_name: &str) -> NextcladeOutputs {
+    NextcladeOutputs {
+      seq_name: seq_name.to_owned(),
+      ref_name: REF_NAME.to_owned(),
+      alignment_range: NucRefGlobalRange::from_usize(0, REF_SEQ.len()),
+      ..NextcladeOutputs::default()
+    }
+  }
+
+  #[rstest]
+  fn normalizes_calls() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let mut output = create_output("seq");
+    output.substitutions = vec![NucSub {
+      pos: 1.into(),
+      ref_nuc: Nuc::C,
+      qry_nuc: Nuc::T,
+    }];
+    output.deletions = vec![NucDelRange::from_usize(0, 1), NucDelRange::from_usize(4, 6)];
+    output.insertions = vec![NucIns {
+      pos: 7,
+      ins: to_nuc_seq("GG")?,
+    }];
+
+    let calls = find_vcf_calls(&output, &ref_seq)
+      .into_iter()
+      .map(|call| (call.pos, call.ref_allele, call.alt_allele))
+      .collect_vec();
+
+    assert_eq!(
+      calls,
+      vec![
+        (0, "AC".to_owned(), "T".to_owned()),
+        (3, "TAC".to_owned(), "T".to_owned()),
+        (7, "T".to_owned(), "TGG".to_owned()),
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::substitution_before_deletion(&[(3, Nuc::G)], &[(4, 6)], &[], &[(3, "TAC", "G")])]
+  #[case::substitution_at_insertion_anchor(&[(7, Nuc::C)], &[], &[(7, "GG")], &[(7, "T", "CGG")])]
+  #[case::deletion_after_insertion(&[], &[(8, 10)], &[(7, "GG")], &[(8, "AC", "GG")])]
+  #[case::adjacent_substitutions(&[(3, Nuc::G), (4, Nuc::T)], &[], &[], &[(3, "T", "G"), (4, "A", "T")])]
+  fn merges_overlapping_calls(
+    #[case] substitutions: &[(usize, Nuc)],
+    #[case] deletions: &[(usize, usize)],
+    #[case] insertions: &[(i32, &str)],
+    #[case] expected: &[(usize, &str, &str)],
+  ) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let mut output = create_output("seq");
+    output.substitutions = substitutions
+      .iter()
+      .map(|&(pos, qry_nuc)| NucSub {
+        pos: pos.into(),
+        ref_nuc: ref_seq[pos],
+        qry_nuc,
+      })
+      .collect_vec();
+    output.deletions = deletions
+      .iter()
+      .map(|&(begin, end)| NucDelRange::from_usize(begin, end))
+      .collect_vec();
+    output.insertions = insertions
+      .iter()
+      .map(|&(pos, ins)| {
+        Ok(NucIns {
+          pos,
+          ins: to_nuc_seq(ins)?,
+        })
+      })
+      .collect::<Result<Vec<_>, Report>>()?;
+
+    let calls = find_vcf_calls(&output, &ref_seq)
+      .into_iter()
+      .map(|call| (call.pos, call.ref_allele, call.alt_allele))
+      .collect_vec();
+
+    let expected = expected
+      .iter()
+      .map(|&(pos, ref_allele, alt_allele)| (pos, ref_allele.to_owned(), alt_allele.to_owned()))
+      .collect_vec();
+    assert_eq!(calls, expected);
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::deletion_in_repeat(&[(5, 6)], &[], (2, "GT", "G"))]
+  #[case::long_deletion_in_repeat(&[(4, 6)], &[], (2, "GTT", "G"))]
+  #[case::insertion_in_repeat(&[], &[(5, "T")], (2, "G", "GT"))]
+  #[case::insertion_of_repeat_unit(&[], &[(9, "CG")], (7, "A", "ACG"))]
+  fn left_aligns_indels_in_repeats(
+    #[case] deletions: &[(usize, usize)],
+    #[case] insertions: &[(i32, &str)],
+    #[case] expected: (usize, &str, &str),
+  ) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACGTTTTACGCG")?;
+    let mut output = create_output("seq");
+    output.deletions = deletions
+      .iter()
+      .map(|&(begin, end)| NucDelRange::from_usize(begin, end))
+      .collect_vec();
+    output.insertions = insertions
+      .iter()
+      .map(|&(pos, ins)| {
+        Ok(NucIns {
+          pos,
+          ins: to_nuc_seq(ins)?,
+        })
+      })
+      .collect::<Result<Vec<_>, Report>>()?;
+
+    let calls = find_vcf_calls(&output, &ref_seq)
+      .into_iter()
+      .map(|call| (call.pos, call.ref_allele, call.alt_allele))
+      .collect_vec();
+
+    let (pos, ref_allele, alt_allele) = expected;
+    assert_eq!(calls, vec![(pos, ref_allele.to_owned(), alt_allele.to_owned())]);
+    Ok(())
+  }
+
+  #[rstest]
+  fn writes_one_genotype_for_substitution_and_insertion_at_same_anchor() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let gene_map = GeneMap::new();
+    let refs = [VcfReference {
+      name: REF_NAME,
+      seq: &ref_seq,
+      gene_map: &gene_map,
+    }];
+
+    let mut seq1 = create_output("seq1");
+    seq1.substitutions = vec![NucSub {
+      pos: 7.into(),
+      ref_nuc: ref_seq[7],
+      qry_nuc: Nuc::C,
+    }];
+    seq1.insertions = vec![NucIns {
+      pos: 7,
+      ins: to_nuc_seq("GG")?,
+    }];
+
+    let mut seq2 = create_output("seq2");
+    seq2.substitutions = vec![NucSub {
+      pos: 7.into(),
+      ref_nuc: ref_seq[7],
+      qry_nuc: Nuc::C,
+    }];
+
+    let mut buf = Vec::new();
+    vcf_write_to_writer(&mut buf, &refs, &[seq1, seq2])?;
+    let vcf = String::from_utf8(buf)?;
+    let lines = vcf.lines().filter(|line| !line.starts_with("##")).collect_vec();
+
+    assert_eq!(
+      lines,
+      vec![
+        "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tseq1\tseq2",
+        "ref\t8\t.\tT\tCGG,C\t.\t.\tAC=1,1;AN=2\tGT\t1\t2",
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn writes_multi_sample_vcf() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let gene_map = GeneMap::new();
+    let refs = [VcfReference {
+      name: REF_NAME,
+      seq: &ref_seq,
+      gene_map: &gene_map,
+    }];
+
+    let sub = |pos: usize, qry_nuc: Nuc| NucSub {
+      pos: pos.into(),
+      ref_nuc: ref_seq[pos],
+      qry_nuc,
+    };
+
+    let mut seq1 = create_output("seq 1");
+    seq1.substitutions = vec![sub(2, Nuc::A)];
+
+    let mut seq2 = create_output("seq2");
+    seq2.substitutions = vec![sub(2, Nuc::T), sub(5, Nuc::A)];
+
+    let mut seq3 = create_output("seq3");
+    seq3.missing = vec![NucRange {
+      range: Range::from_usize(1, 4),
+      letter: Nuc::N,
+    }];
+
+    let mut buf = Vec::new();
+    vcf_write_to_writer(&mut buf, &refs, &[seq1, seq2, seq3])?;
+    let vcf = String::from_utf8(buf)?;
+    let lines = vcf.lines().filter(|line| !line.starts_with("##")).collect_vec();
+
+    assert_eq!(
+      lines,
+      vec![
+        "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tseq_1\tseq2\tseq3",
+        "ref\t3\t.\tG\tA,T\t.\t.\tAC=1,1;AN=2\tGT\t1\t2\t.",
+        "ref\t6\t.\tC\tA\t.\t.\tAC=1;AN=3\tGT\t0\t1\t0",
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn writes_missing_genotype_for_sites_inside_deletions() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq(REF_SEQ)?;
+    let gene_map = GeneMap::new();
+    let refs = [VcfReference {
+      name: REF_NAME,
+      seq: &ref_seq,
+      gene_map: &gene_map,
+    }];
+
+    let mut seq1 = create_output("seq1");
+    seq1.deletions = vec![NucDelRange::from_usize(3, 7)];
+
+    let mut seq2 = create_output("seq2");
+    seq2.substitutions = vec![NucSub {
+      pos: 5.into(),
+      ref_nuc: ref_seq[5],
+      qry_nuc: Nuc::A,
+    }];
+
+    let mut buf = Vec::new();
+    vcf_write_to_writer(&mut buf, &refs, &[seq1, seq2])?;
+    let vcf = String::from_utf8(buf)?;
+    let lines = vcf.lines().filter(|line| !line.starts_with("##")).collect_vec();
+
+    assert_eq!(
+      lines,
+      vec![
+        "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tseq1\tseq2",
+        "ref\t1\t.\tACGTA\tA\t.\t.\tAC=1;AN=2\tGT\t1\t0",
+        "ref\t6\t.\tC\tA\t.\t.\tAC=1;AN=1\tGT\t.\t1",
+      ]
+    );
+    Ok(())
+  }
+}
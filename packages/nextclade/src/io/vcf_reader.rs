@@ -0,0 +1,461 @@
+use crate::alphabet::nuc::{Nuc, from_nuc, from_nuc_seq};
+use crate::io::fasta::FastaRecord;
+use crate::io::vcf::VcfReference;
+use crate::make_error;
+use eyre::{Report, WrapErr, eyre};
+use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::io::BufRead;
+
+/// Parameters of reconstruction of sequences from variant calls
+#[derive(Clone, Debug, Default)]
+pub struct VcfConsensusParams {
+  /// Mask sites with read depth (`DP`) below this value with `N`
+  pub min_depth: Option<usize>,
+}
+
+/// Change of the reference sequence: `[begin, end)` range of the reference is replaced with the given nucleotides
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+struct VcfEdit {
+  begin: usize,
+  end: usize,
+  replacement: String,
+}
+
+/// Reconstruct query sequences from a VCF file and reference sequences (consensus sequences).
+///
+/// Every sample in the VCF file produces one sequence per reference sequence (chromosome). For a single reference the
+/// sequence is named after the sample, otherwise `<sample>|<chromosome>` and only chromosomes present in the VCF file
+/// are produced. Sites-only VCF (without sample columns) produces a single sequence, named `default_sample_name`,
+/// with all the first ALT alleles applied.
+///
+/// Haploid as well as diploid genotypes are supported. Heterozygous genotypes resolve to the first non-reference
+/// allele. Sites with missing genotypes, failed filters or (optionally) low read depth are masked with `N`.
+pub fn vcf_read_consensus(
+  reader: impl BufRead,
+  refs: &[VcfReference],
+  params: &VcfConsensusParams,
+  default_sample_name: &str,
+) -> Result<Vec<FastaRecord>, Report> {
+  let chroms = refs.iter().map(VcfReference::chrom).collect_vec();
+
+  let mut samples: Option<Vec<String>> = None;
+  let mut edits: Vec<Vec<Vec<VcfEdit>>> = vec![];
+  let mut chroms_present = BTreeSet::<usize>::new();
+
+  for (line_index, line) in reader.lines().enumerate() {
+    let line = line?;
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() || line.starts_with("##") {
+      continue;
+    }
+
+    if let Some(header) = line.strip_prefix('#') {
+      let columns = header.split('\t').collect_vec();
+      let sample_names = if columns.len() > 9 {
+        columns[9..].iter().map(|&s| s.to_owned()).collect_vec()
+      } else {
+        vec![default_sample_name.to_owned()]
+      };
+      edits = vec![vec![vec![]; refs.len()]; sample_names.len()];
+      samples = Some(sample_names);
+      continue;
+    }
+
+    if samples.is_none() {
+      return make_error!("Expected VCF header line ('#CHROM ...') before the first record, but found none");
+    }
+
+    let record = parse_record(line, refs, &chroms, params)
+      .wrap_err_with(|| eyre!("When parsing VCF record on line {}", line_index + 1))?;
+
+    chroms_present.insert(record.chrom_index);
+    for (sample_edits, edit) in edits.iter_mut().zip(record.edits) {
+      if let Some(edit) = edit {
+        sample_edits[record.chrom_index].push(edit);
+      }
+    }
+  }
+
+  let Some(samples) = samples else {
+    return make_error!("Expected VCF header line ('#CHROM ...'), but found none");
+  };
+
+  if refs.len() == 1 {
+    chroms_present.insert(0);
+  }
+
+  let records = samples
+    .iter()
+    .zip(edits)
+    .flat_map(|(sample, sample_edits)| {
+      let chroms_present = &chroms_present;
+      let chroms = &chroms;
+      sample_edits
+        .into_iter()
+        .enumerate()
+        .filter(move |(chrom_index, _)| chroms_present.contains(chrom_index))
+        .map(move |(chrom_index, edits)| {
+          let seq_name = if refs.len() == 1 {
+            sample.clone()
+          } else {
+            format!("{sample}|{}", chroms[chrom_index])
+          };
+          let seq = apply_edits(refs[chrom_index].seq, edits)
+            .wrap_err_with(|| format!("When reconstructing sequence '{seq_name}' from VCF"))?;
+          Ok((seq_name, seq))
+        })
+    })
+    .enumerate()
+    .map(|(index, result)| result.map(|(seq_name, seq)| FastaRecord { seq_name, seq, index }))
+    .collect::<Result<Vec<_>, Report>>()?;
+
+  Ok(records)
+}
+
+struct VcfRecordEdits {
+  chrom_index: usize,
+  /// Edit for each of the samples
+  edits: Vec<Option<VcfEdit>>,
+}
+
+fn parse_record(
+  line: &str,
+  refs: &[VcfReference],
+  chroms: &[String],
+  params: &VcfConsensusParams,
+) -> Result<VcfRecordEdits, Report> {
+  let columns = line.split('\t').collect_vec();
+  if columns.len() < 8 {
+    return make_error!("Expected at least 8 tab-separated columns, but found {}", columns.len());
+  }
+
+  let chrom = columns[0];
+  let Some(chrom_index) = chroms.iter().position(|c| c == chrom) else {
+    return make_error!(
+      "Chromosome '{chrom}' does not match any of the reference sequences: {}",
+      chroms.iter().map(|c| format!("'{c}'")).join(", ")
+    );
+  };
+  let ref_seq = refs[chrom_index].seq;
+
+  let pos = columns[1]
+    .parse::<usize>()
+    .ok()
+    .filter(|&pos| pos > 0)
+    .ok_or_else(|| eyre!("Expected a positive integer in POS column, but found '{}'", columns[1]))?;
+  let begin = pos - 1;
+
+  let ref_allele = columns[3].to_uppercase();
+  let end = begin + ref_allele.len();
+  let ref_actual = ref_seq.get(begin..end).map(from_nuc_seq).unwrap_or_default();
+  if ref_actual != ref_allele {
+    return make_error!(
+      "REF allele '{ref_allele}' at {chrom}:{pos} does not match the reference sequence '{ref_actual}'"
+    );
+  }
+
+  let info = parse_info(columns[7]);
+
+  // Reference blocks of gVCF files span multiple positions
+  let mask_end = info
+    .iter()
+    .find(|(key, _)| *key == "END")
+    .and_then(|(_, value)| value.parse::<usize>().ok())
+    .map_or(end, |block_end| block_end.clamp(end, ref_seq.len()));
+  let mask = VcfEdit {
+    begin,
+    end: mask_end,
+    replacement: "N".repeat(mask_end - begin),
+  };
+
+  let alts = columns[4].split(',').map(str::to_uppercase).collect_vec();
+  let is_filter_pass = matches!(columns[6], "PASS" | ".");
+  let info_depth = info
+    .iter()
+    .find(|(key, _)| *key == "DP")
+    .and_then(|(_, value)| value.parse::<usize>().ok());
+
+  let calls = if columns.len() > 9 {
+    let format = columns[8].split(':').collect_vec();
+    let gt_index = format.iter().position(|&key| key == "GT");
+    let dp_index = format.iter().position(|&key| key == "DP");
+    columns[9..]
+      .iter()
+      .map(|sample| {
+        let fields = sample.split(':').collect_vec();
+        let allele = match gt_index.and_then(|i| fields.get(i)) {
+          Some(gt) => parse_genotype(gt),
+          // Without genotypes, the first ALT allele is assumed
+          None => Some(1),
+        };
+        let depth = dp_index
+          .and_then(|i| fields.get(i))
+          .and_then(|dp| dp.parse::<usize>().ok())
+          .or(info_depth);
+        (allele, depth)
+      })
+      .collect_vec()
+  } else {
+    vec![(Some(1), info_depth)]
+  };
+
+  let edits = calls
+    .into_iter()
+    .map(|(allele, depth)| {
+      let is_low_depth = matches!((params.min_depth, depth), (Some(min_depth), Some(depth)) if depth < min_depth);
+      match allele {
+        _ if !is_filter_pass || is_low_depth => Some(mask.clone()),
+        None => Some(mask.clone()),
+        Some(0) => None,
+        Some(allele) => {
+          let alt = alts.get(allele - 1)?;
+          // Symbolic alleles (e.g. '<DEL>') and overlapping deletions ('*') are not applied
+          alt.chars().all(|c| c.is_ascii_alphabetic()).then(|| VcfEdit {
+            begin,
+            end,
+            replacement: alt.clone(),
+          })
+        }
+      }
+    })
+    .collect_vec();
+
+  Ok(VcfRecordEdits { chrom_index, edits })
+}
+
+fn parse_info(info: &str) -> Vec<(&str, &str)> {
+  info
+    .split(';')
+    .map(|entry| entry.split_once('=').unwrap_or((entry, "")))
+    .collect_vec()
+}
+
+/// Parse genotype (e.g. `1`, `0/1`, `1|1`, `.`). Returns the first non-reference allele, or `None` if any of the
+/// alleles is missing.
+fn parse_genotype(gt: &str) -> Option<usize> {
+  let alleles = gt
+    .split(['/', '|'])
+    .map(|allele| allele.parse::<usize>().ok())
+    .collect::<Option<Vec<usize>>>()?;
+  Some(alleles.iter().copied().find(|&allele| allele != 0).unwrap_or(0))
+}
+
+/// Apply edits to the reference sequence. Overlapping edits (e.g. an SNV at the anchor nucleotide of an indel) are
+/// merged first.
+fn apply_edits(ref_seq: &[Nuc], mut edits: Vec<VcfEdit>) -> Result<String, Report> {
+  edits.sort();
+
+  let mut groups: Vec<(usize, Vec<VcfEdit>)> = vec![];
+  for edit in edits {
+    match groups.last_mut() {
+      Some((group_end, group)) if edit.begin < *group_end => {
+        *group_end = (*group_end).max(edit.end);
+        group.push(edit);
+      }
+      _ => groups.push((edit.end, vec![edit])),
+    }
+  }
+
+  let mut seq = String::with_capacity(ref_seq.len());
+  let mut cursor = 0;
+  for (_, group) in groups {
+    let edit = merge_edits(ref_seq, group)?;
+    seq.push_str(&from_nuc_seq(&ref_seq[cursor..edit.begin]));
+    seq.push_str(&edit.replacement);
+    cursor = edit.end;
+  }
+  seq.push_str(&from_nuc_seq(&ref_seq[cursor..]));
+  Ok(seq)
+}
+
+/// Result of an edit at one position of the reference: the letter replacing the reference letter (none if deleted) and
+/// the letters inserted after it
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct VcfEditSlot {
+  letter: Option<char>,
+  inserted: String,
+}
+
+/// Split an edit into slots for each position of the `[begin, end)` range of the reference. As in VCF alleles, the
+/// i-th letter of the reference allele is replaced with the i-th letter of the alternative allele, the letters past the
+/// end of the alternative allele are deleted and the rest of the alternative allele is inserted after the last letter.
+fn edit_slots(ref_seq: &[Nuc], edit: &VcfEdit, begin: usize, end: usize) -> Vec<VcfEditSlot> {
+  let replacement = edit.replacement.chars().collect_vec();
+  let last = (edit.end - edit.begin).saturating_sub(1);
+  (begin..end)
+    .map(|pos| {
+      if !(edit.begin..edit.end).contains(&pos) {
+        return VcfEditSlot {
+          letter: Some(from_nuc(ref_seq[pos])),
+          inserted: String::new(),
+        };
+      }
+      let i = pos - edit.begin;
+      VcfEditSlot {
+        letter: replacement.get(i).copied(),
+        inserted: if i == last {
+          replacement.iter().skip(last + 1).collect()
+        } else {
+          String::new()
+        },
+      }
+    })
+    .collect()
+}
+
+/// Merge overlapping edits into one. At every position, a change wins over the reference letter and a masked (`N`)
+/// position wins over a change. Different changes of the same position cannot be merged and are reported as an error.
+fn merge_edits(ref_seq: &[Nuc], mut edits: Vec<VcfEdit>) -> Result<VcfEdit, Report> {
+  if edits.len() == 1 {
+    return Ok(edits.remove(0));
+  }
+
+  let begin = edits.iter().map(|edit| edit.begin).min().unwrap_or_default();
+  let end = edits.iter().map(|edit| edit.end).max().unwrap_or_default();
+
+  let mut slots = edit_slots(ref_seq, &edits[0], begin, end);
+  for edit in &edits[1..] {
+    for ((pos, slot), other) in (begin..end).zip(&mut slots).zip(edit_slots(ref_seq, edit, begin, end)) {
+      let ref_letter = Some(from_nuc(ref_seq[pos]));
+      slot.letter = match (slot.letter, other.letter) {
+        (l, r) if l == r => l,
+        (Some('N'), _) | (_, Some('N')) => Some('N'),
+        (l, r) if l == ref_letter => r,
+        (l, r) if r == ref_letter => l,
+        _ => {
+          return make_error!(
+            "Variants at positions {} and {} change position {} differently and cannot be merged",
+            edits[0].begin + 1,
+            edit.begin + 1,
+            pos + 1
+          );
+        }
+      };
+      if slot.inserted.is_empty() {
+        slot.inserted = other.inserted;
+      } else if !other.inserted.is_empty() && slot.inserted != other.inserted {
+        return make_error!(
+          "Variants at positions {} and {} insert different nucleotides after position {} and cannot be merged",
+          edits[0].begin + 1,
+          edit.begin + 1,
+          pos + 1
+        );
+      }
+    }
+  }
+
+  let mut replacement = String::new();
+  for slot in slots {
+    replacement.extend(slot.letter);
+    replacement.push_str(&slot.inserted);
+  }
+
+  Ok(VcfEdit {
+    begin,
+    end,
+    replacement,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::gene::gene_map::GeneMap;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::haploid("1", Some(1))]
+  #[case::reference("0", Some(0))]
+  #[case::heterozygous("0/2", Some(2))]
+  #[case::phased("1|1", Some(1))]
+  #[case::missing(".", None)]
+  #[case::partially_missing("./1", None)]
+  fn parses_genotypes(#[case] gt: &str, #[case] expected: Option<usize>) {
+    assert_eq!(parse_genotype(gt), expected);
+  }
+
+  fn edit(begin: usize, end: usize, replacement: &str) -> VcfEdit {
+    VcfEdit {
+      begin,
+      end,
+      replacement: replacement.to_owned(),
+    }
+  }
+
+  #[rstest]
+  #[case::snv_at_insertion_anchor(vec![edit(7, 8, "C"), edit(7, 8, "TGG")], "ACGTACGCGGACGT")]
+  #[case::snv_at_deletion_anchor(vec![edit(3, 6, "T"), edit(3, 4, "A")], "ACGAGTACGT")]
+  #[case::mask_at_deletion_anchor(vec![edit(3, 4, "N"), edit(3, 6, "T")], "ACGNGTACGT")]
+  #[case::mnp_and_snv(vec![edit(0, 3, "TCC"), edit(1, 2, "C"), edit(2, 3, "C")], "TCCTACGTACGT")]
+  #[case::separate(vec![edit(0, 1, "T"), edit(1, 2, "A")], "TAGTACGTACGT")]
+  fn merges_overlapping_edits(#[case] edits: Vec<VcfEdit>, #[case] expected: &str) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACGTACGTACGT")?;
+    assert_eq!(apply_edits(&ref_seq, edits)?, expected);
+    Ok(())
+  }
+
+  #[rstest]
+  #[case::different_snvs(vec![edit(1, 2, "T"), edit(1, 2, "G")])]
+  #[case::snv_inside_deletion(vec![edit(3, 6, "T"), edit(4, 5, "G")])]
+  #[case::different_insertions(vec![edit(7, 8, "TGG"), edit(7, 8, "TA")])]
+  fn rejects_conflicting_edits(#[case] edits: Vec<VcfEdit>) -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACGTACGTACGT")?;
+    drop(apply_edits(&ref_seq, edits).unwrap_err());
+    Ok(())
+  }
+
+  #[rstest]
+  fn reconstructs_sequences() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACGTACGTACGT")?;
+    let gene_map = GeneMap::new();
+    let refs = [VcfReference {
+      name: "ref",
+      seq: &ref_seq,
+      gene_map: &gene_map,
+    }];
+
+    let vcf = [
+      "##fileformat=VCFv4.2",
+      "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ts2",
+      "ref\t2\t.\tC\tT\t.\t.\t.\tGT:DP\t1:30\t0:30",
+      "ref\t4\t.\tTAC\tT\t.\t.\t.\tGT:DP\t0:30\t1:30",
+      "ref\t8\t.\tT\tTGG\t.\t.\t.\tGT:DP\t1:5\t.:30",
+      "ref\t10\t.\tC\tA\t.\tLowQual\t.\tGT:DP\t1:30\t0:30",
+    ]
+    .join("\n");
+
+    let params = VcfConsensusParams { min_depth: Some(10) };
+    let records = vcf_read_consensus(vcf.as_bytes(), &refs, &params, "sample")?
+      .into_iter()
+      .map(|record| (record.index, record.seq_name, record.seq))
+      .collect_vec();
+
+    assert_eq!(
+      records,
+      vec![
+        (0, "s1".to_owned(), "ATGTACGNANGT".to_owned()),
+        (1, "s2".to_owned(), "ACGTGNANGT".to_owned()),
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_mismatched_ref_allele() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACGT")?;
+    let gene_map = GeneMap::new();
+    let refs = [VcfReference {
+      name: "ref",
+      seq: &ref_seq,
+      gene_map: &gene_map,
+    }];
+
+    let vcf = "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\nref\t2\t.\tG\tT\t.\t.\t.\n";
+    let report = vcf_read_consensus(vcf.as_bytes(), &refs, &VcfConsensusParams::default(), "sample").unwrap_err();
+    assert!(format!("{report:?}").contains("REF allele 'G' at ref:2 does not match the reference sequence 'C'"));
+    Ok(())
+  }
+}
@@ -21,9 +21,12 @@ pub fn results_to_excel_sheet(
 ) -> Result<Worksheet, Report> {
   let headers: Vec<String> = prepare_headers(
     &initial_data.clade_node_attr_key_descs,
+    &initial_data.numeric_node_attr_keys,
     &initial_data.phenotype_attr_keys,
     &initial_data.ref_nodes,
     &initial_data.aa_motif_keys,
+    &initial_data.nuc_motif_keys,
+    &initial_data.metadata_keys,
     column_config,
   );
 
@@ -34,7 +37,7 @@ pub fn results_to_excel_sheet(
     sheet.write_string(0, icol as u16, value)?;
   }
 
-  let mut row = NextcladeResultsCsvRow::new(headers)?;
+  let mut row = NextcladeResultsCsvRow::new(headers.clone())?;
   let outputs_or_errors = combine_outputs_and_errors_sorted(outputs, errors);
   for (irow, (_, output_or_error)) in outputs_or_errors.iter().enumerate() {
     let formatted_row = match output_or_error {
@@ -44,7 +47,16 @@ pub fn results_to_excel_sheet(
       }
     };
     for (icol, value) in formatted_row.values().enumerate() {
-      sheet.write_string((irow + 1) as u32, icol as u16, value)?;
+      // Numeric node attributes are written as numbers, to allow sorting and calculations in spreadsheet software
+      let number = initial_data
+        .numeric_node_attr_keys
+        .contains(&headers[icol])
+        .then(|| value.parse::<f64>().ok())
+        .flatten();
+      match number {
+        Some(number) => sheet.write_number((irow + 1) as u32, icol as u16, number)?,
+        None => sheet.write_string((irow + 1) as u32, icol as u16, value)?,
+      };
     }
     row.clear();
   }
@@ -1,8 +1,12 @@
 pub mod qc_config;
+pub mod qc_rule_amplicon_dropouts;
 pub mod qc_rule_frame_shifts;
 pub mod qc_rule_missing_data;
 pub mod qc_rule_mixed_sites;
+pub mod qc_rule_molecular_clock;
+pub mod qc_rule_mutation_signature;
 pub mod qc_rule_private_mutations;
+pub mod qc_rule_recombinants;
 pub mod qc_rule_snp_clusters;
 pub mod qc_rule_stop_codons;
 pub mod qc_run;
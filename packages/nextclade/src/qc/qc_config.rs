@@ -1,9 +1,11 @@
+use crate::analyze::virus_properties::MutationSignatureDesc;
 use crate::coord::position::AaRefPosition;
 use crate::coord::range::AaRefRange;
 use crate::io::fs::read_file_to_string;
 use crate::io::json::json_parse;
-use crate::o;
+use crate::{make_error, o};
 use eyre::{Report, WrapErr};
+use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -255,6 +257,176 @@ impl QcRulesConfigStopCodons {
   }
 }
 
+/// Configuration for QC rule "recombinants"
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+#[schemars(example = "QcRulesConfigRecombinants::example")]
+pub struct QcRulesConfigRecombinants {
+  pub enabled: bool,
+  /// Minimum reduction of the distance to the reference tree required to accept a breakpoint. The distance is reduced when genome regions on either side of the breakpoint are attributed to reference tree nodes from different clades.
+  pub min_improvement: usize,
+  /// Maximum number of breakpoints to detect in one sequence
+  pub max_breakpoints: usize,
+  /// QC score added per detected breakpoint
+  pub score_weight: OrderedFloat<f64>,
+}
+
+impl Default for QcRulesConfigRecombinants {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      min_improvement: 4,
+      max_breakpoints: 2,
+      score_weight: OrderedFloat(50.0),
+    }
+  }
+}
+
+impl QcRulesConfigRecombinants {
+  pub fn example() -> Self {
+    Self {
+      enabled: true,
+      ..Self::default()
+    }
+  }
+}
+
+/// Configuration for QC rule "mutation signature"
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+#[schemars(example = "QcRulesConfigMutationSignature::example")]
+pub struct QcRulesConfigMutationSignature {
+  pub enabled: bool,
+  /// Name of the mutation signature to evaluate, as defined in `mutationSignatures` of the pathogen config
+  pub signature: String,
+  /// Minimum number of private substitutions required to evaluate the rule. Sequences with fewer are not penalized.
+  pub min_substitutions: usize,
+  /// Minimum expected fraction of private substitutions matching the signature
+  pub min_fraction: OrderedFloat<f64>,
+  /// Maximum expected fraction of private substitutions matching the signature
+  pub max_fraction: OrderedFloat<f64>,
+  /// QC score assigned when the fraction is outside of the expected range
+  pub score_weight: OrderedFloat<f64>,
+}
+
+impl Default for QcRulesConfigMutationSignature {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      signature: String::new(),
+      min_substitutions: 10,
+      min_fraction: OrderedFloat(0.0),
+      max_fraction: OrderedFloat(1.0),
+      score_weight: OrderedFloat(50.0),
+    }
+  }
+}
+
+impl QcRulesConfigMutationSignature {
+  pub fn example() -> Self {
+    Self {
+      enabled: true,
+      signature: o!("APOBEC3"),
+      min_fraction: OrderedFloat(0.5),
+      ..Self::default()
+    }
+  }
+
+  /// Check that the signature evaluated by the enabled rule is defined in `mutationSignatures` of the pathogen config
+  pub fn validate_signature(&self, signatures: &[MutationSignatureDesc]) -> Result<(), Report> {
+    if self.enabled && !signatures.iter().any(|signature| signature.name == self.signature) {
+      return make_error!(
+        "QC rule 'mutationSignature' refers to mutation signature '{}', which is not defined in 'mutationSignatures' of the pathogen config. Defined signatures: {}",
+        self.signature,
+        if signatures.is_empty() {
+          o!("none")
+        } else {
+          signatures
+            .iter()
+            .map(|signature| format!("'{}'", signature.name))
+            .join(", ")
+        }
+      );
+    }
+    Ok(())
+  }
+}
+
+/// Configuration for QC rule "amplicon dropouts"
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+#[schemars(example = "QcRulesConfigAmpliconDropouts::example")]
+pub struct QcRulesConfigAmpliconDropouts {
+  pub enabled: bool,
+  /// Dropouts of these amplicons are known (e.g. amplicons with poor performance in the scheme) and not penalized
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub ignored_amplicons: Vec<String>,
+  /// QC score added per dropped out amplicon (excluding ignored ones)
+  pub score_weight: OrderedFloat<f64>,
+}
+
+impl Default for QcRulesConfigAmpliconDropouts {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      ignored_amplicons: vec![],
+      score_weight: OrderedFloat(25.0),
+    }
+  }
+}
+
+impl QcRulesConfigAmpliconDropouts {
+  pub fn example() -> Self {
+    Self {
+      enabled: true,
+      ignored_amplicons: vec![o!("SARS-CoV-2_64")],
+      score_weight: OrderedFloat(25.0),
+    }
+  }
+}
+
+/// Configuration for QC rule "molecular clock"
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+#[schemars(example = "QcRulesConfigMolecularClock::example")]
+pub struct QcRulesConfigMolecularClock {
+  pub enabled: bool,
+  /// Evolutionary rate, in substitutions per site per year. Used to estimate the number of mutations expected to
+  /// accumulate between the date of the nearest node and the collection date of the sample.
+  pub clock_rate: OrderedFloat<f64>,
+  /// Number of private mutations by which the query may deviate from the expected number (in either direction) before
+  /// the score starts increasing
+  pub allowed_deviation: OrderedFloat<f64>,
+  /// Deviation in excess of `allowedDeviation` at which the score reaches 100
+  pub cutoff: OrderedFloat<f64>,
+}
+
+impl Default for QcRulesConfigMolecularClock {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      clock_rate: OrderedFloat(0.0),
+      allowed_deviation: OrderedFloat(5.0),
+      cutoff: OrderedFloat(10.0),
+    }
+  }
+}
+
+impl QcRulesConfigMolecularClock {
+  pub const fn example() -> Self {
+    Self {
+      enabled: true,
+      clock_rate: OrderedFloat(0.0008),
+      allowed_deviation: OrderedFloat(5.0),
+      cutoff: OrderedFloat(10.0),
+    }
+  }
+}
+
 /// Configuration for QC rules
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate)]
 #[serde(rename_all = "camelCase")]
@@ -273,6 +445,14 @@ pub struct QcConfig {
   pub frame_shifts: QcRulesConfigFrameShifts,
   /// Configuration for the "stop codons" (S) rule
   pub stop_codons: QcRulesConfigStopCodons,
+  /// Configuration for the "recombinants" (R) rule
+  pub recombinants: QcRulesConfigRecombinants,
+  /// Configuration for the "mutation signature" (G) rule
+  pub mutation_signature: QcRulesConfigMutationSignature,
+  /// Configuration for the "amplicon dropouts" (A) rule
+  pub amplicon_dropouts: QcRulesConfigAmpliconDropouts,
+  /// Configuration for the "molecular clock" (T) rule
+  pub molecular_clock: QcRulesConfigMolecularClock,
 }
 
 impl FromStr for QcConfig {
@@ -292,6 +472,10 @@ impl QcConfig {
       snp_clusters: QcRulesConfigSnpClusters::example(),
       frame_shifts: QcRulesConfigFrameShifts::example(),
       stop_codons: QcRulesConfigStopCodons::example(),
+      recombinants: QcRulesConfigRecombinants::example(),
+      mutation_signature: QcRulesConfigMutationSignature::example(),
+      amplicon_dropouts: QcRulesConfigAmpliconDropouts::example(),
+      molecular_clock: QcRulesConfigMolecularClock::example(),
     }
   }
 
@@ -0,0 +1,60 @@
+use crate::analyze::amplicon_dropouts::AmpliconsResult;
+use crate::qc::qc_config::QcRulesConfigAmpliconDropouts;
+use crate::qc::qc_run::{QcRule, QcStatus};
+use serde::{Deserialize, Serialize};
+
+/// Result of the amplicon dropouts QC rule.
+///
+/// Amplicons which failed to amplify leave large gaps of missing data at predictable locations. Dropouts of amplicons
+/// listed in `ignoredAmplicons` in the dataset configuration are excluded from scoring. Score equals the number of
+/// non-ignored dropouts times `scoreWeight`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QcResultAmpliconDropouts {
+  /// Numeric QC score for this rule (0-100+)
+  pub score: f64,
+  /// Quality category derived from the score
+  pub status: QcStatus,
+  /// Names of dropped out amplicons not in the ignored list (penalized)
+  pub dropouts: Vec<String>,
+  /// Number of penalized dropouts
+  pub total_dropouts: usize,
+  /// Names of dropped out amplicons matching the ignored list in dataset configuration (not penalized)
+  pub dropouts_ignored: Vec<String>,
+}
+
+impl QcRule for QcResultAmpliconDropouts {
+  fn score(&self) -> f64 {
+    self.score
+  }
+}
+
+/// Runs the rule. Requires an amplicon scheme: without it, amplicon dropouts are not available.
+pub fn rule_amplicon_dropouts(
+  amplicons: Option<&AmpliconsResult>,
+  config: &QcRulesConfigAmpliconDropouts,
+) -> Option<QcResultAmpliconDropouts> {
+  if !config.enabled {
+    return None;
+  }
+
+  let amplicons = amplicons?;
+
+  let (dropouts_ignored, dropouts): (Vec<String>, Vec<String>) = amplicons
+    .dropouts
+    .iter()
+    .cloned()
+    .partition(|name| config.ignored_amplicons.contains(name));
+
+  let total_dropouts = dropouts.len();
+  let score = total_dropouts as f64 * *config.score_weight;
+  let status = QcStatus::from_score(score);
+
+  Some(QcResultAmpliconDropouts {
+    score,
+    status,
+    dropouts,
+    total_dropouts,
+    dropouts_ignored,
+  })
+}
@@ -0,0 +1,156 @@
+use crate::qc::qc_config::QcRulesConfigMolecularClock;
+use crate::qc::qc_run::{QcRule, QcStatus};
+use crate::tree::tree::DivergenceUnits;
+use num::traits::clamp_min;
+use serde::{Deserialize, Serialize};
+
+/// Dates and divergence of the query sample and of its nearest node in the reference tree
+#[derive(Clone, Debug)]
+pub struct MolecularClockInput {
+  /// Collection date of the sample, as a decimal year
+  pub sample_date: f64,
+  /// Date of the nearest node (`num_date` node attribute), as a decimal year
+  pub node_date: f64,
+  /// Divergence of the nearest node
+  pub node_divergence: f64,
+  /// Divergence of the query
+  pub divergence: f64,
+  /// Units of divergence in the reference tree
+  pub divergence_units: DivergenceUnits,
+  /// Length of the reference sequence
+  pub ref_seq_len: usize,
+}
+
+/// Result of the molecular clock QC rule.
+///
+/// Under a molecular clock, the number of mutations accumulated since the nearest node of the reference tree is
+/// proportional to the time elapsed between the date of that node and the collection date of the sample. Too many or
+/// too few private mutations for the collection date signal contamination, mislabeling of samples or dates, or unusual
+/// evolution. Score reaches 100 when the deviation from the expected number, in excess of `allowedDeviation`, equals
+/// `cutoff`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QcResultMolecularClock {
+  /// Numeric QC score for this rule (0-100+)
+  pub score: f64,
+  /// Quality category derived from the score
+  pub status: QcStatus,
+  /// Collection date of the sample, as a decimal year
+  pub sample_date: f64,
+  /// Date of the nearest node, as a decimal year
+  pub node_date: f64,
+  /// Divergence expected for the collection date, in the units of the reference tree
+  pub expected_divergence: f64,
+  /// Divergence of the query, in the units of the reference tree
+  pub divergence: f64,
+  /// Number of private mutations expected to accumulate between the date of the nearest node and the collection date.
+  /// Negative if the sample was collected before the date of the nearest node.
+  pub expected_private_mutations: f64,
+  /// Number of private mutations of the query, as counted for divergence
+  pub private_mutations: f64,
+  /// Difference between the observed and the expected numbers of private mutations
+  pub deviation: f64,
+  /// Cutoff from the dataset configuration at which the score reaches 100
+  pub cutoff: f64,
+}
+
+impl QcRule for QcResultMolecularClock {
+  fn score(&self) -> f64 {
+    self.score
+  }
+}
+
+/// Runs the rule. Requires the collection date of the sample (from sample metadata) and the date of the nearest node
+/// (from the reference tree).
+pub fn rule_molecular_clock(
+  input: Option<&MolecularClockInput>,
+  config: &QcRulesConfigMolecularClock,
+) -> Option<QcResultMolecularClock> {
+  if !config.enabled || *config.clock_rate <= 0.0 {
+    return None;
+  }
+
+  let MolecularClockInput {
+    sample_date,
+    node_date,
+    node_divergence,
+    divergence,
+    divergence_units,
+    ref_seq_len,
+  } = input?;
+
+  // Number of sites per unit of divergence: divergence is either a number of substitutions or a number of
+  // substitutions per site
+  let sites_per_unit = match divergence_units {
+    DivergenceUnits::NumSubstitutionsPerYearPerSite => *ref_seq_len as f64,
+    DivergenceUnits::NumSubstitutionsPerYear => 1.0,
+  };
+
+  let expected_private_mutations = *config.clock_rate * *ref_seq_len as f64 * (sample_date - node_date);
+  let expected_divergence = node_divergence + expected_private_mutations / sites_per_unit;
+  let private_mutations = (divergence - node_divergence) * sites_per_unit;
+  let deviation = private_mutations - expected_private_mutations;
+
+  let score = clamp_min(deviation.abs() - *config.allowed_deviation, 0.0) * 100.0 / *config.cutoff;
+  let status = QcStatus::from_score(score);
+
+  Some(QcResultMolecularClock {
+    score,
+    status,
+    sample_date: *sample_date,
+    node_date: *node_date,
+    expected_divergence,
+    divergence: *divergence,
+    expected_private_mutations,
+    private_mutations,
+    deviation,
+    cutoff: *config.cutoff,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  // Clock rate of 0.001 on 1000 sites gives 1 mutation per year
+  #[case(DivergenceUnits::NumSubstitutionsPerYear, 2022.0, 10.0 + 4.0, 0.0)]
+  #[case(DivergenceUnits::NumSubstitutionsPerYear, 2022.0, 10.0 + 12.0, 50.0)]
+  #[case(DivergenceUnits::NumSubstitutionsPerYear, 2018.0, 10.0 + 8.0, 50.0)]
+  #[case(DivergenceUnits::NumSubstitutionsPerYearPerSite, 2022.0, 0.01 + 0.022, 150.0)]
+  fn scores_deviation_from_clock(
+    #[case] divergence_units: DivergenceUnits,
+    #[case] sample_date: f64,
+    #[case] divergence: f64,
+    #[case] expected_score: f64,
+  ) {
+    let config = QcRulesConfigMolecularClock {
+      enabled: true,
+      clock_rate: OrderedFloat(0.001),
+      allowed_deviation: OrderedFloat(5.0),
+      cutoff: OrderedFloat(10.0),
+    };
+    let input = MolecularClockInput {
+      sample_date,
+      node_date: 2020.0,
+      node_divergence: if divergence_units == DivergenceUnits::NumSubstitutionsPerYear {
+        10.0
+      } else {
+        0.01
+      },
+      divergence,
+      divergence_units,
+      ref_seq_len: 1000,
+    };
+
+    let result = rule_molecular_clock(Some(&input), &config).unwrap();
+
+    assert_eq!(
+      OrderedFloat((result.score * 1e6).round() / 1e6),
+      OrderedFloat(expected_score)
+    );
+  }
+}
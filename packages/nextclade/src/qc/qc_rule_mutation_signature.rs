@@ -0,0 +1,63 @@
+use crate::analyze::mutation_spectrum::MutationSpectrum;
+use crate::qc::qc_config::QcRulesConfigMutationSignature;
+use crate::qc::qc_run::{QcRule, QcStatus};
+use serde::{Deserialize, Serialize};
+
+/// Result of the mutation signature QC rule.
+///
+/// Checks that the fraction of private substitutions matching a mutation signature (e.g. APOBEC3 editing) is within
+/// the range expected for the pathogen. Sequences with a deviating fraction may contain sequencing or assembly
+/// artifacts. Score equals `scoreWeight` if the fraction is outside of the expected range, and 0 otherwise.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QcResultMutationSignature {
+  /// Numeric QC score for this rule (0-100+)
+  pub score: f64,
+  /// Quality category derived from the score
+  pub status: QcStatus,
+  /// Name of the evaluated mutation signature
+  pub signature: String,
+  /// Total number of private substitutions between unambiguous nucleotides
+  pub total_substitutions: usize,
+  /// Number of private substitutions matching the signature
+  pub matching_substitutions: usize,
+  /// Fraction of private substitutions matching the signature
+  pub fraction: f64,
+}
+
+impl QcRule for QcResultMutationSignature {
+  fn score(&self) -> f64 {
+    self.score
+  }
+}
+
+/// Runs the rule. Requires a reference tree: without it, private mutations are not available.
+pub fn rule_mutation_signature(
+  private_mutation_spectrum: Option<&MutationSpectrum>,
+  config: &QcRulesConfigMutationSignature,
+) -> Option<QcResultMutationSignature> {
+  if !config.enabled {
+    return None;
+  }
+
+  let spectrum = private_mutation_spectrum?;
+  let signature = spectrum.signatures.iter().find(|s| s.name == config.signature)?;
+
+  let is_evaluated = spectrum.total_substitutions >= config.min_substitutions;
+  let is_expected = (*config.min_fraction..=*config.max_fraction).contains(&signature.fraction);
+  let score = if is_evaluated && !is_expected {
+    *config.score_weight
+  } else {
+    0.0
+  };
+  let status = QcStatus::from_score(score);
+
+  Some(QcResultMutationSignature {
+    score,
+    status,
+    signature: signature.name.clone(),
+    total_substitutions: spectrum.total_substitutions,
+    matching_substitutions: signature.count,
+    fraction: signature.fraction,
+  })
+}
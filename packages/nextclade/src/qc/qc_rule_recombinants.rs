@@ -0,0 +1,47 @@
+use crate::qc::qc_config::QcRulesConfigRecombinants;
+use crate::qc::qc_run::{QcRule, QcStatus};
+use crate::tree::tree_find_recombination::RecombinationResult;
+use serde::{Deserialize, Serialize};
+
+/// Result of the recombinants QC rule.
+///
+/// Detects sequences whose genome regions are closer to reference tree nodes from different clades than the whole
+/// genome is to any single node. Such sequences are putative recombinants, or contain contamination or a mixture of
+/// samples. Score equals the number of breakpoints times `scoreWeight`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QcResultRecombinants {
+  /// Numeric QC score for this rule (0-100+)
+  pub score: f64,
+  /// Quality category derived from the score
+  pub status: QcStatus,
+  /// Number of detected recombination breakpoints
+  pub total_breakpoints: usize,
+}
+
+impl QcRule for QcResultRecombinants {
+  fn score(&self) -> f64 {
+    self.score
+  }
+}
+
+/// Runs the rule. Requires a reference tree: without it, the recombination search is not performed.
+pub fn rule_recombinants(
+  recombination: Option<&RecombinationResult>,
+  config: &QcRulesConfigRecombinants,
+) -> Option<QcResultRecombinants> {
+  if !config.enabled {
+    return None;
+  }
+
+  let total_breakpoints = recombination?.breakpoints.len();
+
+  let score = total_breakpoints as f64 * *config.score_weight;
+  let status = QcStatus::from_score(score);
+
+  Some(QcResultRecombinants {
+    score,
+    status,
+    total_breakpoints,
+  })
+}
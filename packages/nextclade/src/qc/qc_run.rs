@@ -1,14 +1,21 @@
 use crate::alphabet::nuc::Nuc;
+use crate::analyze::amplicon_dropouts::AmpliconsResult;
 use crate::analyze::find_private_nuc_mutations::PrivateNucMutations;
+use crate::analyze::mutation_spectrum::MutationSpectrum;
 use crate::qc::qc_config::QcConfig;
+use crate::qc::qc_rule_amplicon_dropouts::{QcResultAmpliconDropouts, rule_amplicon_dropouts};
 use crate::qc::qc_rule_frame_shifts::{QcResultFrameShifts, rule_frame_shifts};
 use crate::qc::qc_rule_missing_data::{QcResultMissingData, rule_missing_data};
 use crate::qc::qc_rule_mixed_sites::{QcResultMixedSites, rule_mixed_sites};
+use crate::qc::qc_rule_molecular_clock::{MolecularClockInput, QcResultMolecularClock, rule_molecular_clock};
+use crate::qc::qc_rule_mutation_signature::{QcResultMutationSignature, rule_mutation_signature};
 use crate::qc::qc_rule_private_mutations::{QcResultPrivateMutations, rule_private_mutations};
+use crate::qc::qc_rule_recombinants::{QcResultRecombinants, rule_recombinants};
 use crate::qc::qc_rule_snp_clusters::{QcResultSnpClusters, rule_snp_clusters};
 use crate::qc::qc_rule_stop_codons::{QcResultStopCodons, rule_stop_codons};
 use crate::translate::frame_shifts_translate::FrameShift;
 use crate::translate::translate_genes::Translation;
+use crate::tree::tree_find_recombination::RecombinationResult;
 use num::traits::Pow;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -70,6 +77,14 @@ pub struct QcResult {
   pub frame_shifts: Option<QcResultFrameShifts>,
   /// Result of the premature stop codons (S) rule
   pub stop_codons: Option<QcResultStopCodons>,
+  /// Result of the recombinants (R) rule
+  pub recombinants: Option<QcResultRecombinants>,
+  /// Result of the mutation signature (G) rule
+  pub mutation_signature: Option<QcResultMutationSignature>,
+  /// Result of the amplicon dropouts (A) rule
+  pub amplicon_dropouts: Option<QcResultAmpliconDropouts>,
+  /// Result of the molecular clock (T) rule
+  pub molecular_clock: Option<QcResultMolecularClock>,
   /// Quadratic aggregate of all individual rule scores
   pub overall_score: f64,
   /// Quality category derived from the overall score
@@ -86,6 +101,10 @@ pub fn qc_run(
   total_missing: usize,
   translation: &Translation,
   frame_shifts: &[FrameShift],
+  recombination: Option<&RecombinationResult>,
+  private_mutation_spectrum: Option<&MutationSpectrum>,
+  amplicons: Option<&AmpliconsResult>,
+  molecular_clock: Option<&MolecularClockInput>,
   config: &QcConfig,
 ) -> QcResult {
   let mut result = QcResult {
@@ -95,6 +114,10 @@ pub fn qc_run(
     snp_clusters: rule_snp_clusters(private_nuc_mutations, &config.snp_clusters),
     frame_shifts: rule_frame_shifts(frame_shifts, &config.frame_shifts),
     stop_codons: rule_stop_codons(translation, &config.stop_codons),
+    recombinants: rule_recombinants(recombination, &config.recombinants),
+    mutation_signature: rule_mutation_signature(private_mutation_spectrum, &config.mutation_signature),
+    amplicon_dropouts: rule_amplicon_dropouts(amplicons, &config.amplicon_dropouts),
+    molecular_clock: rule_molecular_clock(molecular_clock, &config.molecular_clock),
     overall_score: 0.0,
     overall_status: QcStatus::Good,
   };
@@ -105,6 +128,10 @@ pub fn qc_run(
   result.overall_score += add_score(result.snp_clusters.as_ref());
   result.overall_score += add_score(result.frame_shifts.as_ref());
   result.overall_score += add_score(result.stop_codons.as_ref());
+  result.overall_score += add_score(result.recombinants.as_ref());
+  result.overall_score += add_score(result.mutation_signature.as_ref());
+  result.overall_score += add_score(result.amplicon_dropouts.as_ref());
+  result.overall_score += add_score(result.molecular_clock.as_ref());
 
   result.overall_status = QcStatus::from_score(result.overall_score);
 
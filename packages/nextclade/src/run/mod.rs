@@ -1,4 +1,5 @@
 pub mod nextclade_run_one;
+pub mod nextclade_segments;
 pub mod nextclade_wasm;
 pub mod params;
 pub mod params_general;
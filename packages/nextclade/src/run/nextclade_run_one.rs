@@ -1,5 +1,6 @@
-use crate::align::align::align_nuc;
+use crate::align::align_multi_ref::{AlignmentMultiRefOutput, align_nuc_multi_ref};
 use crate::align::insertions_strip::{AaIns, NucIns, get_aa_insertions, insertions_strip};
+use crate::align::soft_clip::NucSoftClips;
 use crate::alphabet::aa::Aa;
 use crate::alphabet::letter::Letter;
 use crate::alphabet::nuc::Nuc;
@@ -8,12 +9,20 @@ use crate::analyze::aa_changes_find_for_cds::FindAaChangesOutput;
 use crate::analyze::aa_changes_group::AaChangesGroup;
 use crate::analyze::aa_del::AaDel;
 use crate::analyze::aa_sub::AaSub;
+use crate::analyze::amplicon_dropouts::find_amplicon_dropouts;
+use crate::analyze::clade_confidence::{
+  CladeAttrConfidence, FindCladeConfidenceParams, apply_clade_confidence_fallbacks, find_clade_confidence,
+};
+use crate::analyze::clade_mutations::{CladeMutationsAssignment, find_clade_by_mutations};
 use crate::analyze::divergence::calculate_branch_length;
+use crate::analyze::dn_ds::calculate_dn_ds;
 use crate::analyze::find_aa_motifs::find_aa_motifs;
 use crate::analyze::find_aa_motifs_changes::find_aa_motifs_changes;
 use crate::analyze::find_clade_founder::{
   CladeNodeAttrFounderInfo, find_clade_founder, find_clade_node_attrs_founders,
 };
+use crate::analyze::find_nuc_motifs::find_nuc_motifs;
+use crate::analyze::find_nuc_motifs_changes::find_nuc_motifs_changes;
 use crate::analyze::find_private_aa_mutations::{
   FindPrivateAaMutationsParams, PrivateAaMutations, find_private_aa_mutations,
 };
@@ -26,12 +35,13 @@ use crate::analyze::letter_composition::get_letter_composition;
 use crate::analyze::letter_ranges::{
   CdsAaRange, NucRange, find_aa_letter_ranges, find_letter_ranges, find_letter_ranges_by,
 };
+use crate::analyze::mutation_spectrum::{MutationSpectrumResult, find_mutation_spectrum};
 use crate::analyze::nuc_alignment::NucAlignment;
 use crate::analyze::nuc_changes::{FindNucChangesOutput, find_nuc_changes};
 use crate::analyze::nuc_del::NucDelRange;
+use crate::analyze::nuc_sub_effects::find_nuc_sub_effects;
 use crate::analyze::pcr_primer_changes::get_pcr_primer_changes;
-use crate::analyze::phenotype::calculate_phenotype;
-use crate::analyze::virus_properties::PhenotypeData;
+use crate::analyze::phenotype::{PhenotypeMutations, calculate_phenotype};
 use crate::coord::coord_map_global::CoordMapGlobal;
 use crate::coord::position::PositionLike;
 use crate::coord::range::{AaRefRange, NucRefGlobalRange, Range, intersect};
@@ -41,15 +51,20 @@ use crate::gene::gene_map::GeneMap;
 use crate::graph::node::GraphNodeKey;
 use crate::io::fasta::parse_fasta_header;
 use crate::io::gff3_writer::GFF_ATTRIBUTES_TO_REMOVE;
+use crate::io::sample_metadata::{SampleMetadataEntry, sample_metadata_num_date, sample_metadata_select};
 use crate::o;
+use crate::qc::qc_rule_molecular_clock::MolecularClockInput;
 use crate::qc::qc_run::qc_run;
 use crate::run::nextclade_wasm::{AnalysisOutput, Nextclade};
 use crate::translate::aa_alignment_ranges::{GatherAaAlignmentRangesResult, gather_aa_alignment_ranges};
 use crate::translate::frame_shifts_flatten::frame_shifts_flatten;
 use crate::translate::frame_shifts_translate::FrameShift;
 use crate::translate::translate_genes::{Translation, translate_genes};
+use crate::tree::lineage_hierarchy::{LineageInfo, find_clade_node_attr_lineages};
 use crate::tree::tree_find_ancestors_of_interest::{AncestralSearchResult, graph_find_ancestors_of_interest};
 use crate::tree::tree_find_nearest_node::graph_find_nearest_nodes;
+use crate::tree::tree_find_numeric_node_attrs::{FindNumericNodeAttrsParams, find_numeric_node_attrs};
+use crate::tree::tree_find_recombination::{RecombinationResult, graph_find_recombination};
 use crate::types::outputs::{NextcladeOutputs, PeptideWarning, PhenotypeValue};
 use crate::utils::num::float_collapse_zero;
 use eyre::Report;
@@ -77,7 +92,6 @@ struct NextcladeResultWithAa {
   aa_alignment_ranges: BTreeMap<String, Vec<AaRefRange>>,
   aa_unsequenced_ranges: BTreeMap<String, Vec<AaRefRange>>,
   cds_coverage: BTreeMap<String, f64>,
-  phenotype_values: Option<Vec<PhenotypeValue>>,
 }
 
 #[derive(Default)]
@@ -95,6 +109,11 @@ struct NextcladeResultWithGraph {
   relative_aa_mutations: Vec<RelativeAaMutations>,
   clade_founder_info: Option<CladeNodeAttrFounderInfo>,
   clade_node_attr_founder_info: BTreeMap<String, CladeNodeAttrFounderInfo>,
+  recombination: Option<RecombinationResult>,
+  clade_confidence: BTreeMap<String, CladeAttrConfidence>,
+  clade_node_attr_lineages: BTreeMap<String, LineageInfo>,
+  numeric_node_attributes: BTreeMap<String, f64>,
+  molecular_clock: Option<MolecularClockInput>,
 }
 
 pub fn nextclade_run_one(
@@ -102,6 +121,7 @@ pub fn nextclade_run_one(
   seq_name: &str,
   qry_seq: &[Nuc],
   state: &Nextclade,
+  metadata: Option<&SampleMetadataEntry>,
 ) -> Result<AnalysisOutput, Report> {
   let Nextclade {
     dataset_name,
@@ -109,30 +129,56 @@ pub fn nextclade_run_one(
     ref_record,
     seed_index,
     gap_open_close_nuc,
+    additional_refs,
     virus_properties,
     params,
     gene_map,
     gap_open_close_aa,
     ref_translation,
+    ref_cds_sites,
     aa_motifs_ref,
+    nuc_motifs_matchers,
+    nuc_motifs_ref,
     graph,
     primers,
+    amplicon_scheme,
+    clade_mutations,
     ref_nodes,
+    segment,
     ..
   } = &state;
 
   let (seq_id, seq_desc) = parse_fasta_header(seq_name);
 
-  let alignment = align_nuc(
+  let sample_id = segment.as_ref().map(|segment| segment.get_sample_id(&seq_id));
+
+  let sample_date =
+    metadata.and_then(|metadata| sample_metadata_num_date(metadata, &params.general.metadata_date_column));
+
+  let sample_metadata = metadata
+    .map(|metadata| sample_metadata_select(metadata, &params.general.metadata_columns))
+    .unwrap_or_default();
+
+  let AlignmentMultiRefOutput {
+    alignment,
+    additional_ref_index,
+  } = align_nuc_multi_ref(
     index,
     seq_name,
     qry_seq,
     ref_seq,
     seed_index,
     gap_open_close_nuc,
+    additional_refs,
     &params.alignment,
   )?;
 
+  // Report which of the references was used, but only if there was a choice
+  let alignment_ref_name = (!additional_refs.is_empty()).then(|| match additional_ref_index {
+    Some(i) => additional_refs[i].name.clone(),
+    None => ref_record.seq_name.clone(),
+  });
+
   let stripped = insertions_strip(&alignment.qry_seq, &alignment.ref_seq);
   let alignment_score = alignment.alignment_score;
 
@@ -161,6 +207,16 @@ pub fn nextclade_run_one(
   let pcr_primer_changes = get_pcr_primer_changes(&substitutions, primers);
   let total_pcr_primer_changes = pcr_primer_changes.iter().map(|pc| pc.substitutions.len()).sum();
 
+  let amplicons = amplicon_scheme.as_ref().map(|scheme| {
+    find_amplicon_dropouts(
+      scheme,
+      &stripped.qry_seq,
+      &alignment_range,
+      &substitutions,
+      *params.general.amplicon_min_coverage,
+    )
+  });
+
   let total_aligned_nucs = alignment_range.len();
   let total_covered_nucs = total_aligned_nucs - total_missing - total_non_acgtns;
   let coverage = total_covered_nucs as f64 / ref_seq.len() as f64;
@@ -186,7 +242,6 @@ pub fn nextclade_run_one(
     aa_alignment_ranges,
     aa_unsequenced_ranges,
     cds_coverage,
-    mut phenotype_values,
   } = if !gene_map.is_empty() {
     let translation = translate_genes(
       &alignment.qry_seq,
@@ -272,21 +327,6 @@ pub fn nextclade_run_one(
       })
       .collect::<Result<BTreeMap<String, f64>, Report>>()?;
 
-    let phenotype_values = virus_properties.phenotype_data.as_ref().map(|phenotype_data| {
-      phenotype_data
-        .iter()
-        .map(|phenotype_data| {
-          let PhenotypeData { name, cds, .. } = phenotype_data;
-          let phenotype = calculate_phenotype(phenotype_data, &aa_substitutions);
-          PhenotypeValue {
-            name: name.clone(),
-            cds: cds.clone(),
-            value: float_collapse_zero(phenotype),
-          }
-        })
-        .collect_vec()
-    });
-
     NextcladeResultWithAa {
       translation,
       aa_changes_groups,
@@ -306,12 +346,35 @@ pub fn nextclade_run_one(
       aa_alignment_ranges,
       aa_unsequenced_ranges,
       cds_coverage,
-      phenotype_values,
     }
   } else {
     NextcladeResultWithAa::default()
   };
 
+  let mut phenotype_values = virus_properties.phenotype_data.as_ref().map(|phenotype_data| {
+    let mutations = PhenotypeMutations {
+      aa_substitutions: &aa_substitutions,
+      aa_deletions: &aa_deletions,
+      substitutions: &substitutions,
+      deletions: &deletions,
+    };
+    phenotype_data
+      .iter()
+      .map(|phenotype_data| {
+        let phenotype = calculate_phenotype(phenotype_data, &mutations);
+        PhenotypeValue {
+          name: phenotype_data.name.clone(),
+          cds: phenotype_data.model.cds_names().join(","),
+          value: float_collapse_zero(phenotype),
+        }
+      })
+      .collect_vec()
+  });
+
+  let clade_by_mutations = clade_mutations
+    .as_ref()
+    .map(|table| find_clade_by_mutations(table, ref_seq, &substitutions, &deletions, &missing, &alignment_range));
+
   let NextcladeResultWithGraph {
     clade,
     private_nuc_mutations,
@@ -326,6 +389,11 @@ pub fn nextclade_run_one(
     nearest_node_id,
     nearest_node_name,
     nearest_nodes,
+    recombination,
+    clade_confidence,
+    clade_node_attr_lineages,
+    numeric_node_attributes,
+    molecular_clock,
   } = if let Some(graph) = graph {
     let nearest_node_candidates =
       graph_find_nearest_nodes(graph, &substitutions, &missing, &deletions, &alignment_range)?;
@@ -344,9 +412,51 @@ pub fn nextclade_run_one(
 
     let clade = nearest_node.clade();
 
+    let recombination = virus_properties
+      .qc
+      .as_ref()
+      .filter(|qc_config| qc_config.recombinants.enabled)
+      .map(|qc_config| {
+        graph_find_recombination(
+          graph,
+          &substitutions,
+          &missing,
+          &deletions,
+          &alignment_range,
+          &qc_config.recombinants,
+        )
+      })
+      .transpose()?;
+
     let clade_node_attr_descs = graph.data.meta.clade_node_attr_descs();
     let clade_node_attrs = nearest_node.get_clade_node_attrs(clade_node_attr_descs);
 
+    let clade_confidence = find_clade_confidence(
+      graph,
+      nearest_node_id,
+      clade_node_attr_descs,
+      &FindCladeConfidenceParams {
+        missing: &missing,
+        alignment_range: &alignment_range,
+        min_confidence: *params.general.clade_min_confidence,
+        fallback: params.general.clade_low_confidence_fallback,
+      },
+    )?;
+    let (clade, clade_node_attrs) = apply_clade_confidence_fallbacks(clade, clade_node_attrs, &clade_confidence);
+    let clade_node_attr_lineages = find_clade_node_attr_lineages(&clade_node_attrs, clade_node_attr_descs);
+
+    let numeric_node_attributes = find_numeric_node_attrs(
+      graph,
+      nearest_node_id,
+      graph.data.meta.numeric_node_attr_descs(),
+      &FindNumericNodeAttrsParams {
+        substitutions: &substitutions,
+        deletions: &deletions,
+        missing: &missing,
+        alignment_range: &alignment_range,
+      },
+    )?;
+
     let nuc_params = FindPrivateNucMutationsParams {
       graph,
       substitutions: &substitutions,
@@ -386,6 +496,18 @@ pub fn nextclade_run_one(
         ref_seq.len(),
       );
 
+    let molecular_clock =
+      sample_date
+        .zip(nearest_node.get_numeric_node_attr("num_date"))
+        .map(|(sample_date, node_date)| MolecularClockInput {
+          sample_date,
+          node_date,
+          node_divergence: parent_div,
+          divergence,
+          divergence_units: graph.data.tmp.divergence_units,
+          ref_seq_len: ref_seq.len(),
+        });
+
     let clade_founder_info = find_clade_founder(graph, nearest_node_id, clade.as_ref(), &nuc_params, &aa_params)?;
 
     let clade_node_attr_founder_info =
@@ -420,14 +542,71 @@ pub fn nextclade_run_one(
       nearest_node_id,
       nearest_node_name,
       nearest_nodes,
+      recombination,
+      clade_confidence,
+      clade_node_attr_lineages,
+      numeric_node_attributes,
+      molecular_clock,
+    }
+  } else if let Some(CladeMutationsAssignment { result, attrs }) = &clade_by_mutations {
+    // Without a reference tree, clade and clade-like attributes are assigned from the clade mutations table
+    NextcladeResultWithGraph {
+      clade: result.clade.clone(),
+      custom_node_attributes: attrs.clone(),
+      ..NextcladeResultWithGraph::default()
     }
   } else {
     NextcladeResultWithGraph::default()
   };
 
+  let nuc_sub_effects = find_nuc_sub_effects(&substitutions, &substitutions, gene_map, ref_translation);
+  let private_nuc_sub_effects = graph.is_some().then(|| {
+    find_nuc_sub_effects(
+      &private_nuc_mutations.private_substitutions,
+      &substitutions,
+      gene_map,
+      ref_translation,
+    )
+  });
+  let dn_ds = calculate_dn_ds(
+    gene_map,
+    ref_cds_sites,
+    &nuc_sub_effects,
+    private_nuc_sub_effects.as_deref(),
+  );
+
+  let mutation_spectrum = MutationSpectrumResult {
+    substitutions: find_mutation_spectrum(&virus_properties.mutation_signatures, &substitutions, ref_seq)?,
+    private_substitutions: graph
+      .is_some()
+      .then(|| {
+        find_mutation_spectrum(
+          &virus_properties.mutation_signatures,
+          &private_nuc_mutations.private_substitutions,
+          ref_seq,
+        )
+      })
+      .transpose()?,
+  };
+
   let aa_motifs = find_aa_motifs(&virus_properties.aa_motifs, &translation)?;
   let aa_motifs_changes = find_aa_motifs_changes(aa_motifs_ref, &aa_motifs, ref_translation, &translation)?;
 
+  let nuc_motifs = find_nuc_motifs(
+    nuc_motifs_matchers,
+    &stripped.qry_seq,
+    &alignment.qry_seq,
+    &coord_map_global,
+    &alignment_range,
+  );
+  let nuc_motifs_changes = find_nuc_motifs_changes(
+    nuc_motifs_ref,
+    &nuc_motifs,
+    ref_seq,
+    &stripped.qry_seq,
+    &alignment_range,
+  );
+
   let qc = virus_properties
     .qc
     .as_ref()
@@ -438,12 +617,21 @@ pub fn nextclade_run_one(
         total_missing,
         &translation,
         &frame_shifts,
+        recombination.as_ref(),
+        mutation_spectrum.private_substitutions.as_ref(),
+        amplicons.as_ref(),
+        molecular_clock.as_ref(),
         qc_config,
       )
     })
     .unwrap_or_default();
 
   let is_reverse_complement = alignment.is_reverse_complement;
+  let circular_offset = params.alignment.circular.then_some(alignment.circular_offset);
+  let soft_clips = params
+    .alignment
+    .soft_clip
+    .then(|| NucSoftClips::from_alignment(&alignment));
 
   let len_unaligned = qry_seq.len();
   let len_aligned = alignment.qry_seq.len();
@@ -471,6 +659,9 @@ pub fn nextclade_run_one(
       len_aligned,
       len_stripped,
       ref_name: ref_record.seq_name.clone(),
+      alignment_ref_name,
+      segment: segment.as_ref().map(|segment| segment.name.clone()),
+      sample_id,
       dataset_name: dataset_name.clone(),
       substitutions,
       total_substitutions,
@@ -495,20 +686,28 @@ pub fn nextclade_run_one(
       total_unknown_aa,
       aa_changes_groups,
       nuc_to_aa_muts,
+      nuc_sub_effects,
+      dn_ds,
+      mutation_spectrum,
       alignment_range,
       alignment_score,
       aa_alignment_ranges,
       aa_unsequenced_ranges,
       pcr_primer_changes,
       total_pcr_primer_changes,
+      amplicons,
       warnings,
       missing_cdses: missing_genes,
       coverage,
       cds_coverage,
       aa_motifs,
       aa_motifs_changes,
+      nuc_motifs,
+      nuc_motifs_changes,
       qc,
       clade,
+      clade_by_mutations: clade_by_mutations.map(|assignment| assignment.result),
+      clade_confidence,
       private_nuc_mutations,
       private_aa_mutations,
       clade_founder_info,
@@ -520,10 +719,16 @@ pub fn nextclade_run_one(
       phenotype_values,
       divergence,
       custom_node_attributes,
+      clade_node_attr_lineages,
+      numeric_node_attributes,
+      metadata: sample_metadata,
       nearest_node_id,
       nearest_node_name,
       nearest_nodes,
+      recombination,
       is_reverse_complement,
+      circular_offset,
+      soft_clips,
       annotation,
     },
   })
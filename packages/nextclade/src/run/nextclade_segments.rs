@@ -0,0 +1,273 @@
+use crate::align::align_multi_ref::seed_match_coverage;
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::pcr_primers::PcrPrimer;
+use crate::gene::gene_map::GeneMap;
+use crate::io::fasta::{FastaRecord, parse_fasta_header};
+use crate::make_error;
+use crate::run::nextclade_wasm::{Nextclade, NextcladeParams};
+use crate::run::params::{NextcladeInputParams, NextcladeInputParamsOptional};
+use eyre::{Report, WrapErr, eyre};
+use itertools::Itertools;
+use log::warn;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Describes one segment of a segmented genome (e.g. one of the 8 segments of influenza)
+#[derive(Clone, Debug)]
+pub struct SegmentInfo {
+  /// Name of the segment. This is the identifier of the corresponding reference sequence record and is expected to
+  /// match the sequence region id in the genome annotation.
+  pub name: String,
+
+  /// Regex used to extract sample identifier from query sequence identifier
+  pub sample_id_regex: Option<Regex>,
+}
+
+impl SegmentInfo {
+  /// Extract sample identifier from sequence identifier of a query sequence assigned to this segment
+  pub fn get_sample_id(&self, seq_id: &str) -> String {
+    if let Some(sample_id_regex) = &self.sample_id_regex {
+      if let Some(captures) = sample_id_regex.captures(seq_id) {
+        let sample_id = captures.name("sample").or_else(|| captures.get(1));
+        if let Some(sample_id) = sample_id {
+          return sample_id.as_str().to_owned();
+        }
+      }
+      return seq_id.to_owned();
+    }
+
+    seq_id
+      .strip_suffix(&self.name)
+      .and_then(|prefix| {
+        let sample_id = prefix.trim_end_matches(['|', '_', '-', '/', '.', ' ']);
+        (sample_id.len() < prefix.len() && !sample_id.is_empty()).then_some(sample_id)
+      })
+      .unwrap_or(seq_id)
+      .to_owned()
+  }
+}
+
+/// Split reference records of a possibly segmented reference into the first record and the remaining segments
+pub fn split_ref_segments(mut ref_records: Vec<FastaRecord>) -> Result<(FastaRecord, Vec<FastaRecord>), Report> {
+  if ref_records.is_empty() {
+    return make_error!("Expected at least one reference sequence record, but found none");
+  }
+  if let Some(record) = ref_records.iter().find(|record| record.seq.is_empty()) {
+    return make_error!(
+      "Reference sequence '{}' is empty, but a non-empty sequence was expected",
+      record.seq_name
+    );
+  }
+  let ref_record = ref_records.remove(0);
+  Ok((ref_record, ref_records))
+}
+
+/// Name of the genome segment corresponding to a reference sequence record
+pub fn get_segment_name(ref_record: &FastaRecord) -> String {
+  let (seq_id, _) = parse_fasta_header(&ref_record.seq_name);
+  seq_id
+}
+
+/// Create Nextclade state for a segmented dataset: one state for each of the reference sequence records.
+///
+/// The returned state corresponds to the first segment and holds the states of the remaining segments in
+/// `other_segments`. The genome annotation is split into segments by sequence region ids. Each segment uses its own
+/// reference tree, if one is provided for it in `segment_trees`.
+pub fn nextclade_new_segmented(
+  inputs: NextcladeParams,
+  primers: &[PcrPrimer],
+  params: &NextcladeInputParamsOptional,
+) -> Result<Nextclade, Report> {
+  let NextcladeParams {
+    dataset_name,
+    ref_record,
+    ref_segments,
+    additional_refs,
+    gene_map,
+    tree,
+    mut segment_trees,
+    clade_mutations,
+    amplicon_scheme,
+    mut sample_metadata,
+    virus_properties,
+  } = inputs;
+
+  if !additional_refs.is_empty() {
+    return make_error!(
+      "Additional reference sequences are not supported for segmented datasets (when reference sequence file contains multiple records)"
+    );
+  }
+
+  if tree.is_some() {
+    return make_error!(
+      "A single reference tree is not supported for segmented datasets (when reference sequence file contains multiple records). Please provide one reference tree per segment, using `files.segmentTrees` in pathogen.json."
+    );
+  }
+
+  if clade_mutations.is_some() {
+    warn!(
+      "Clade mutations table is not supported for segmented datasets (when reference sequence file contains multiple records). The table will be ignored."
+    );
+  }
+
+  let ref_records = std::iter::once(ref_record).chain(ref_segments).collect_vec();
+  let segment_names = ref_records.iter().map(get_segment_name).collect_vec();
+
+  if let Some(dupe) = segment_names.iter().duplicates().next() {
+    return make_error!("Names of reference sequence records are expected to be unique, but found duplicate: '{dupe}'");
+  }
+
+  let unknown_segment_trees = segment_trees
+    .keys()
+    .filter(|segment| !segment_names.contains(segment))
+    .map(|segment| format!("'{segment}'"))
+    .join(", ");
+  if !unknown_segment_trees.is_empty() {
+    let segment_names_str = segment_names.iter().map(|name| format!("'{name}'")).join(", ");
+    return make_error!(
+      "Reference trees are provided for unknown segments: {unknown_segment_trees}. Segment names are expected to match names of the reference sequence records ({segment_names_str})."
+    );
+  }
+
+  if !gene_map.is_empty() {
+    let segment_names_str = segment_names.iter().map(|name| format!("'{name}'")).join(", ");
+
+    let seqids = gene_map.seqids();
+    if seqids.is_empty() {
+      return make_error!(
+        "When using a segmented reference sequence, genome annotation is expected to be in GFF3 format, with sequence ids (column 1) matching names of the reference sequence records ({segment_names_str}). But no sequence ids were found in the genome annotation."
+      );
+    }
+
+    let unknown_seqids = seqids
+      .into_iter()
+      .filter(|seqid| !segment_names.iter().any(|name| name == seqid))
+      .map(|seqid| format!("'{seqid}'"))
+      .join(", ");
+
+    if !unknown_seqids.is_empty() {
+      return make_error!(
+        "When using a segmented reference sequence, sequence ids in genome annotation (column 1) are expected to match names of the reference sequence records ({segment_names_str}). But found unknown sequence ids: {unknown_seqids}"
+      );
+    }
+  }
+
+  let sample_id_regex = {
+    let merged = NextcladeInputParams::from_optional(params, &virus_properties)?;
+    let regex = merged.general.segment_sample_id_regex;
+    (!regex.is_empty())
+      .then(|| Regex::new(&regex).wrap_err_with(|| eyre!("When compiling segment sample ID regex: '{regex}'")))
+      .transpose()?
+  };
+
+  let mut segments = ref_records
+    .into_iter()
+    .zip(segment_names)
+    .enumerate()
+    .map(|(i, (ref_record, name))| {
+      // PCR primers and amplicon scheme are located relative to the first reference record only
+      let primers = if i == 0 { primers.to_vec() } else { vec![] };
+      let amplicon_scheme = if i == 0 { amplicon_scheme.clone() } else { None };
+      // Sample metadata is looked up by the state of the first segment, which runs the analysis of all segments
+      let sample_metadata = if i == 0 { sample_metadata.take() } else { None };
+
+      let inputs = NextcladeParams {
+        dataset_name: dataset_name.clone(),
+        ref_record,
+        ref_segments: vec![],
+        additional_refs: vec![],
+        gene_map: gene_map.filter_by_seqid(&name),
+        tree: segment_trees.remove(&name),
+        segment_trees: BTreeMap::new(),
+        clade_mutations: None,
+        amplicon_scheme,
+        sample_metadata,
+        virus_properties: virus_properties.clone(),
+      };
+
+      let mut nextclade =
+        Nextclade::new(inputs, primers, params).wrap_err_with(|| format!("When preparing genome segment '{name}'"))?;
+
+      nextclade.segment = Some(SegmentInfo {
+        name,
+        sample_id_regex: sample_id_regex.clone(),
+      });
+
+      Ok(nextclade)
+    })
+    .collect::<Result<Vec<Nextclade>, Report>>()?;
+
+  let mut first = segments.remove(0);
+  first.other_segments = segments;
+  Ok(first)
+}
+
+/// Iterate over states of all segments. For non-segmented datasets yields only the given state itself.
+pub fn iter_segments(nextclade: &Nextclade) -> impl Iterator<Item = &Nextclade> {
+  std::iter::once(nextclade).chain(nextclade.other_segments.iter())
+}
+
+/// Names of all segments. Empty for non-segmented datasets.
+pub fn get_segment_names(nextclade: &Nextclade) -> Vec<String> {
+  iter_segments(nextclade)
+    .filter_map(|segment| segment.segment.as_ref().map(|segment| segment.name.clone()))
+    .collect_vec()
+}
+
+/// Merged genome annotation of all segments
+pub fn get_gene_map_all_segments(nextclade: &Nextclade) -> GeneMap {
+  GeneMap::from_genes(
+    iter_segments(nextclade)
+      .flat_map(|segment| segment.gene_map.genes.iter().cloned())
+      .collect_vec(),
+  )
+}
+
+/// Find the segment which the query sequence belongs to: the one with the largest seed match coverage.
+pub fn choose_segment<'a>(nextclade: &'a Nextclade, qry_seq: &[Nuc]) -> &'a Nextclade {
+  if nextclade.other_segments.is_empty() {
+    return nextclade;
+  }
+
+  iter_segments(nextclade)
+    .enumerate()
+    .max_by_key(|(i, segment)| {
+      let coverage = seed_match_coverage(
+        qry_seq,
+        &segment.ref_seq,
+        &segment.seed_index,
+        &segment.params.alignment,
+      );
+      (coverage, std::cmp::Reverse(*i))
+    })
+    .map_or(nextclade, |(_, segment)| segment)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::pipe("A/Texas/1/2024|HA", "HA", None, "A/Texas/1/2024")]
+  #[case::underscore("A/Texas/1/2024_HA", "HA", None, "A/Texas/1/2024")]
+  #[case::no_separator("A/Texas/1/2024HA", "HA", None, "A/Texas/1/2024HA")]
+  #[case::no_suffix("A/Texas/1/2024", "HA", None, "A/Texas/1/2024")]
+  #[case::only_segment("HA", "HA", None, "HA")]
+  #[case::regex_named("sample1-seg4-v2", "4", Some(r"^(?P<sample>.+?)-seg"), "sample1")]
+  #[case::regex_unnamed("sample1-seg4-v2", "4", Some(r"^(.+?)-seg"), "sample1")]
+  #[case::regex_no_match("sample1", "4", Some(r"^(.+?)-seg"), "sample1")]
+  fn segment_info_gets_sample_id(
+    #[case] seq_id: &str,
+    #[case] segment: &str,
+    #[case] regex: Option<&str>,
+    #[case] expected: &str,
+  ) {
+    let segment_info = SegmentInfo {
+      name: segment.to_owned(),
+      sample_id_regex: regex.map(|regex| Regex::new(regex).unwrap()),
+    };
+    assert_eq!(segment_info.get_sample_id(seq_id), expected);
+  }
+}
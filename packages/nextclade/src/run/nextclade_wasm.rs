@@ -1,24 +1,40 @@
+use crate::align::align_multi_ref::AdditionalRef;
 use crate::align::gap_open::{GapScoreMap, get_gap_open_close_scores_codon_aware, get_gap_open_close_scores_flat};
 use crate::align::seed_match::CodonSpacedIndex;
 use crate::alphabet::letter::{serde_deserialize_seq, serde_serialize_seq};
 use crate::alphabet::nuc::{Nuc, to_nuc_seq, to_nuc_seq_replacing};
+use crate::analyze::amplicon_scheme::AmpliconScheme;
+use crate::analyze::clade_mutations::CladeMutationsTable;
+use crate::analyze::dn_ds::{SynonymousSites, synonymous_sites_for_ref};
 use crate::analyze::find_aa_motifs::find_aa_motifs;
 use crate::analyze::find_aa_motifs_changes::AaMotifsMap;
+use crate::analyze::find_nuc_motifs::{NucMotifsMatcher, find_nuc_motifs};
+use crate::analyze::find_nuc_motifs_changes::NucMotifsMap;
 use crate::analyze::pcr_primers::PcrPrimer;
 use crate::analyze::phenotype::get_phenotype_attr_descs;
-use crate::analyze::virus_properties::{AaMotifsDesc, PhenotypeAttrDesc, VirusProperties};
+use crate::analyze::virus_properties::{AaMotifsDesc, NucMotifsDesc, PhenotypeAttrDesc, VirusProperties};
+use crate::coord::coord_map_global::CoordMapGlobal;
+use crate::coord::range::NucRefGlobalRange;
 use crate::gene::gene_map::{GeneMap, filter_gene_map};
 use crate::graph::graph::Graph;
-use crate::io::fasta::{FastaRecord, read_one_fasta_from_str};
+use crate::io::fasta::{FastaRecord, read_many_fasta_from_str, read_one_fasta_from_str};
 use crate::io::nextclade_csv_column_config::CsvColumnConfig;
 use crate::io::nwk_writer::nwk_write_to_string;
+use crate::io::sample_metadata::SampleMetadata;
+use crate::make_error;
 use crate::run::nextclade_run_one::nextclade_run_one;
+use crate::run::nextclade_segments::{
+  SegmentInfo, choose_segment, get_segment_names, nextclade_new_segmented, split_ref_segments,
+};
 use crate::run::params::{NextcladeInputParams, NextcladeInputParamsOptional};
 use crate::run::validate_ref_seq::validate_ref_seq;
 use crate::translate::translate_genes::Translation;
 use crate::translate::translate_genes_ref::translate_genes_ref;
-use crate::tree::tree::{AuspiceGraph, AuspiceRefNodesDesc, AuspiceTree, CladeNodeAttrKeyDesc, check_ref_seq_mismatch};
+use crate::tree::tree::{
+  AuspiceGraph, AuspiceRefNodesDesc, AuspiceTree, CladeNodeAttrKeyDesc, NumericNodeAttrDesc, check_ref_seq_mismatch,
+};
 use crate::tree::tree_builder::graph_attach_new_nodes_in_place;
+use crate::tree::tree_placement_priors::graph_apply_placement_priors_in_place;
 use crate::tree::tree_preprocess::graph_preprocess_in_place;
 use crate::types::outputs::NextcladeOutputs;
 use crate::utils::option::{OptionMapRefFallible, find_some};
@@ -36,8 +52,24 @@ pub struct NextcladeParams {
   pub dataset_name: String,
   #[schemars(with = "String")]
   pub ref_record: FastaRecord,
+  #[serde(default)]
+  #[schemars(with = "Vec<String>")]
+  pub ref_segments: Vec<FastaRecord>,
+  #[serde(default)]
+  #[schemars(with = "Vec<String>")]
+  pub additional_refs: Vec<FastaRecord>,
   pub gene_map: GeneMap,
   pub tree: Option<AuspiceTree>,
+  #[serde(default)]
+  pub segment_trees: BTreeMap<String, AuspiceTree>,
+  #[serde(default)]
+  pub clade_mutations: Option<CladeMutationsTable>,
+  #[serde(default)]
+  pub amplicon_scheme: Option<AmpliconScheme>,
+  /// Sample metadata table in TSV or CSV format. It is parsed once the parameters are resolved, because the column
+  /// containing sequence names is a parameter (which can be set in pathogen.json).
+  #[serde(default)]
+  pub sample_metadata: Option<String>,
   pub virus_properties: VirusProperties,
 }
 
@@ -100,11 +132,25 @@ impl NextcladeParams {
       }
     };
 
+    let additional_refs = overrides.additional_refs.clone().unwrap_or_default();
+
+    let clade_mutations = overrides.clade_mutations.clone();
+
+    let amplicon_scheme = overrides.amplicon_scheme.clone();
+
+    let sample_metadata = overrides.sample_metadata.clone();
+
     Ok(vec![Self {
       dataset_name: overrides.dataset_name.as_ref().unwrap().clone(),
       ref_record,
+      ref_segments: vec![],
+      additional_refs,
       gene_map,
       tree,
+      segment_trees: BTreeMap::new(),
+      clade_mutations,
+      amplicon_scheme,
+      sample_metadata,
       virus_properties,
     }])
   }
@@ -127,6 +173,11 @@ impl NextcladeParams {
             .map_ref_fallible(read_one_fasta_from_str)
             .wrap_err("When parsing reference sequence")?;
 
+          let additional_refs = raw
+            .additional_references
+            .map_ref_fallible(read_many_fasta_from_str)
+            .wrap_err("When parsing additional reference sequences")?;
+
           let tree = raw
             .tree_json
             .map_ref_fallible(AuspiceTree::from_str)
@@ -137,6 +188,11 @@ impl NextcladeParams {
             .map_ref_fallible(GeneMap::from_str)
             .wrap_err("When parsing genome annotation")?;
 
+          let clade_mutations = raw
+            .clade_mutations
+            .map_ref_fallible(CladeMutationsTable::from_tsv_str)
+            .wrap_err("When parsing clade mutations table")?;
+
           if let (Some(tree), Some(ref_record)) = (&tree, &ref_record)
             && let Some(tree_ref) = tree.root_sequence()
           {
@@ -146,13 +202,27 @@ impl NextcladeParams {
           NextcladeParamsOptional {
             dataset_name,
             ref_record,
+            ref_segments: None,
+            additional_refs,
             gene_map,
             tree,
+            segment_trees: None,
+            clade_mutations,
+            amplicon_scheme: None,
+            sample_metadata: raw.sample_metadata.clone(),
             virus_properties,
           }
         };
 
-        Self::from_auspice(&auspice_json, &overrides, None)
+        // Amplicon scheme is validated against the reference sequence, which is only known once the dataset is resolved
+        let mut params = Self::from_auspice(&auspice_json, &overrides, None)?;
+        for params in &mut params {
+          params.amplicon_scheme = raw
+            .amplicon_scheme
+            .map_ref_fallible(|bed| AmpliconScheme::from_bed_str(bed, params.ref_record.seq.len()))
+            .wrap_err("When parsing amplicon scheme")?;
+        }
+        Ok(params)
       }
       NextcladeParamsRaw::Dir(raw) => raw
         .into_iter()
@@ -162,19 +232,51 @@ impl NextcladeParams {
           let virus_properties =
             VirusProperties::from_str(&raw.pathogen_json).wrap_err("When parsing pathogen JSON")?;
 
-          let ref_record = read_one_fasta_from_str(&raw.reference).wrap_err("When parsing reference sequence")?;
+          let (ref_record, ref_segments) = read_many_fasta_from_str(&raw.reference)
+            .and_then(split_ref_segments)
+            .wrap_err("When parsing reference sequence")?;
+
+          let additional_refs = raw
+            .additional_references
+            .map(|additional_refs| {
+              read_many_fasta_from_str(additional_refs).wrap_err("When parsing additional reference sequences")
+            })
+            .transpose()?
+            .unwrap_or_default();
 
           let tree = raw
             .tree_json
             .map(|tree| AuspiceTree::from_str(tree).wrap_err("When parsing reference tree Auspice JSON v2"))
             .transpose()?;
 
+          let segment_trees = raw
+            .segment_trees
+            .iter()
+            .map(|(segment, tree)| {
+              let tree = AuspiceTree::from_str(tree)
+                .wrap_err_with(|| format!("When parsing reference tree Auspice JSON v2 of segment '{segment}'"))?;
+              Ok((segment.clone(), tree))
+            })
+            .collect::<Result<BTreeMap<_, _>, Report>>()?;
+
           let gene_map = raw
             .genome_annotation
             .map(|gene_map| GeneMap::from_str(gene_map).wrap_err("When parsing genome annotation"))
             .transpose()?
             .unwrap_or_default();
 
+          let clade_mutations = raw
+            .clade_mutations
+            .map(|table| CladeMutationsTable::from_tsv_str(table).wrap_err("When parsing clade mutations table"))
+            .transpose()?;
+
+          let amplicon_scheme = raw
+            .amplicon_scheme
+            .map(|bed| {
+              AmpliconScheme::from_bed_str(&bed, ref_record.seq.len()).wrap_err("When parsing amplicon scheme")
+            })
+            .transpose()?;
+
           if let Some(tree) = &tree
             && let Some(tree_ref) = tree.root_sequence()
           {
@@ -184,8 +286,14 @@ impl NextcladeParams {
           Ok(Self {
             dataset_name,
             ref_record,
+            ref_segments,
+            additional_refs,
             gene_map,
             tree,
+            segment_trees,
+            clade_mutations,
+            amplicon_scheme,
+            sample_metadata: raw.sample_metadata,
             virus_properties,
           })
         })
@@ -200,8 +308,18 @@ pub struct NextcladeParamsRawAuspice {
   pub dataset_name: String,
   pub auspice_json: String,
   pub reference: Option<String>,
+  #[serde(default)]
+  pub additional_references: Option<String>,
   pub genome_annotation: Option<String>,
   pub tree_json: Option<String>,
+  #[serde(default)]
+  pub clade_mutations: Option<String>,
+  /// Amplicon scheme in BED format
+  #[serde(default)]
+  pub amplicon_scheme: Option<String>,
+  /// Sample metadata table in TSV or CSV format
+  #[serde(default)]
+  pub sample_metadata: Option<String>,
   pub pathogen_json: Option<String>,
 }
 
@@ -210,14 +328,26 @@ pub struct NextcladeParamsRawAuspice {
 pub struct NextcladeParamsRawDir {
   pub dataset_name: String,
   pub reference: String,
+  #[serde(default)]
+  pub additional_references: Option<String>,
   pub genome_annotation: Option<String>,
   pub tree_json: Option<String>,
+  #[serde(default)]
+  pub segment_trees: BTreeMap<String, String>,
+  #[serde(default)]
+  pub clade_mutations: Option<String>,
+  /// Amplicon scheme in BED format
+  #[serde(default)]
+  pub amplicon_scheme: Option<String>,
+  /// Sample metadata table in TSV or CSV format
+  #[serde(default)]
+  pub sample_metadata: Option<String>,
   pub pathogen_json: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub enum NextcladeParamsRaw {
-  Auspice(NextcladeParamsRawAuspice),
+  Auspice(Box<NextcladeParamsRawAuspice>),
   Dir(Vec<NextcladeParamsRawDir>),
 }
 
@@ -241,12 +371,22 @@ pub struct AnalysisInitialData {
   pub cds_order_preference: Vec<String>,
   pub clade_node_attr_key_descs: Vec<CladeNodeAttrKeyDesc>,
   pub clade_node_attr_keys: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub numeric_node_attr_descs: Vec<NumericNodeAttrDesc>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub numeric_node_attr_keys: Vec<String>,
   pub phenotype_attr_descs: Vec<PhenotypeAttrDesc>,
   pub phenotype_attr_keys: Vec<String>,
   pub ref_nodes: AuspiceRefNodesDesc,
   pub aa_motifs_descs: Vec<AaMotifsDesc>,
   pub aa_motif_keys: Vec<String>,
+  pub nuc_motifs_descs: Vec<NucMotifsDesc>,
+  pub nuc_motif_keys: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub metadata_keys: Vec<String>,
   pub csv_column_config_default: CsvColumnConfig,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub segment_names: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
@@ -277,14 +417,23 @@ pub struct Nextclade {
   pub ref_seq: Vec<Nuc>,
   pub seed_index: CodonSpacedIndex,
   pub gap_open_close_nuc: Vec<i32>,
+  pub additional_refs: Vec<AdditionalRef>,
   pub virus_properties: VirusProperties,
   pub primers: Vec<PcrPrimer>,
+  pub amplicon_scheme: Option<AmpliconScheme>,
+  pub sample_metadata: Option<SampleMetadata>,
+  pub metadata_keys: Vec<String>,
   pub params: NextcladeInputParams,
+  pub nuc_motifs_matchers: Vec<NucMotifsMatcher>,
+  pub nuc_motifs_ref: NucMotifsMap,
+  pub nuc_motifs_descs: Vec<NucMotifsDesc>,
+  pub nuc_motifs_keys: Vec<String>,
 
   // If genome annotation is provided
   pub gene_map: GeneMap,
   pub gap_open_close_aa: Vec<i32>,
   pub ref_translation: Translation,
+  pub ref_cds_sites: BTreeMap<String, SynonymousSites>,
   pub aa_motifs_ref: AaMotifsMap,
   pub aa_motifs_descs: Vec<AaMotifsDesc>,
   pub aa_motifs_keys: Vec<String>,
@@ -292,14 +441,23 @@ pub struct Nextclade {
   // If ref tree is provided
   pub graph: Option<AuspiceGraph>,
   pub clade_attr_descs: Vec<CladeNodeAttrKeyDesc>,
+  pub numeric_attr_descs: Vec<NumericNodeAttrDesc>,
   pub phenotype_attr_descs: Vec<PhenotypeAttrDesc>,
   pub ref_nodes: AuspiceRefNodesDesc,
+
+  // If clade-defining mutations table is provided
+  pub clade_mutations: Option<CladeMutationsTable>,
+
+  // If reference consists of multiple segments
+  pub segment: Option<SegmentInfo>,
+  pub other_segments: Vec<Nextclade>,
 }
 
 pub struct InitialStateWithAa {
   pub gap_open_close_nuc: GapScoreMap,
   pub gap_open_close_aa: GapScoreMap,
   pub ref_translation: Translation,
+  pub ref_cds_sites: BTreeMap<String, SynonymousSites>,
   pub aa_motifs_ref: AaMotifsMap,
 }
 
@@ -315,15 +473,43 @@ impl Nextclade {
     primers: Vec<PcrPrimer>,
     params: &NextcladeInputParamsOptional,
   ) -> Result<Self, Report> {
+    if !inputs.ref_segments.is_empty() {
+      return nextclade_new_segmented(inputs, &primers, params);
+    }
+
     let NextcladeParams {
       dataset_name,
       ref_record,
-      gene_map,
+      additional_refs,
+      mut gene_map,
       tree,
+      segment_trees,
+      clade_mutations,
+      amplicon_scheme,
+      sample_metadata,
       virus_properties,
+      ..
     } = inputs;
 
+    if !segment_trees.is_empty() {
+      let segments = segment_trees.keys().map(|segment| format!("'{segment}'")).join(", ");
+      return make_error!(
+        "Reference trees of genome segments are provided ({segments}), but reference sequence contains only one record. For segmented genomes, please provide one reference sequence record per segment."
+      );
+    }
+
     let params = NextcladeInputParams::from_optional(params, &virus_properties)?;
+
+    let sample_metadata = sample_metadata
+      .map(|table| SampleMetadata::from_str(table, &params.general.metadata_id_column))
+      .transpose()
+      .wrap_err("When parsing sample metadata")?;
+
+    let metadata_keys = sample_metadata
+      .as_ref()
+      .map_ref_fallible(|sample_metadata| sample_metadata.selected_columns(&params.general.metadata_columns))?
+      .unwrap_or_default();
+
     let ref_seq = to_nuc_seq(&ref_record.seq).wrap_err("When converting reference sequence")?;
     let seed_index = CodonSpacedIndex::from_sequence(&ref_seq);
 
@@ -335,11 +521,39 @@ impl Nextclade {
 
     validate_ref_seq(&ref_record.seq_name, &ref_seq)?;
 
+    if let Some(clade_mutations) = &clade_mutations {
+      clade_mutations
+        .validate(&ref_seq)
+        .wrap_err("When validating clade mutations table")?;
+    }
+
+    if let Some(qc_config) = &virus_properties.qc {
+      qc_config
+        .mutation_signature
+        .validate_signature(&virus_properties.mutation_signatures)
+        .wrap_err("When validating QC configuration")?;
+    }
+
+    gene_map.set_genetic_codes(&virus_properties.genetic_codes);
+    gene_map
+      .set_rna_editing(&virus_properties.rna_editing)
+      .wrap_err("When applying RNA editing sites from pathogen.json")?;
+    gene_map.validate().wrap_err("When validating genome annotation")?;
+
+    let seqids = gene_map.seqids();
+    if seqids.len() > 1 {
+      return make_error!(
+        "Genome annotation contains multiple sequence regions ({}), but reference sequence contains only one record. For segmented genomes, please provide one reference sequence record per sequence region.",
+        seqids.iter().map(|seqid| format!("'{seqid}'")).join(", ")
+      );
+    }
+
     // If genome annotation is present, calculate AA-related parameters
     let InitialStateWithAa {
       gap_open_close_nuc,
       gap_open_close_aa,
       ref_translation,
+      ref_cds_sites,
       aa_motifs_ref,
     } = if !gene_map.is_empty() {
       let gap_open_close_nuc = get_gap_open_close_scores_codon_aware(&ref_seq, &gene_map, &params.alignment);
@@ -351,10 +565,13 @@ impl Nextclade {
       let aa_motifs_ref = find_aa_motifs(&virus_properties.aa_motifs, &ref_translation)
         .wrap_err("When searching AA motifs in reference translation")?;
 
+      let ref_cds_sites = synonymous_sites_for_ref(&gene_map, &ref_translation);
+
       InitialStateWithAa {
         gap_open_close_nuc,
         gap_open_close_aa,
         ref_translation,
+        ref_cds_sites,
         aa_motifs_ref,
       }
     } else {
@@ -363,10 +580,17 @@ impl Nextclade {
         gap_open_close_nuc: gap_open_close.clone(),
         gap_open_close_aa: gap_open_close,
         ref_translation: Translation::default(),
+        ref_cds_sites: BTreeMap::default(),
         aa_motifs_ref: BTreeMap::default(),
       }
     };
 
+    let additional_refs = additional_refs
+      .iter()
+      .map(|record| AdditionalRef::new(record, &ref_seq, &seed_index, &gap_open_close_nuc, &params.alignment))
+      .collect::<Result<Vec<_>, Report>>()
+      .wrap_err("When preparing additional reference sequences")?;
+
     let graph = tree
       .map(|tree| -> Result<AuspiceGraph, Report> {
         let mut graph = Graph::from_auspice_tree(tree).wrap_err("When converting Auspice tree to Nextclade graph")?;
@@ -374,13 +598,22 @@ impl Nextclade {
         graph_preprocess_in_place(&mut graph, &ref_seq, &ref_translation)
           .wrap_err("When preprocessing Nextclade graph")?;
 
+        graph_apply_placement_priors_in_place(&mut graph).wrap_err("When applying placement priors")?;
+
         Ok(graph)
       })
       .transpose()?;
 
-    let clade_attr_descs = graph
+    // Clade-like attributes come from the tree if present, otherwise from the clade mutations table
+    let clade_attr_descs = match (&graph, &clade_mutations) {
+      (Some(graph), _) => graph.data.meta.clade_node_attr_descs().to_vec(),
+      (None, Some(clade_mutations)) => clade_mutations.attr_descs(),
+      (None, None) => vec![],
+    };
+
+    let numeric_attr_descs = graph
       .as_ref()
-      .map(|graph| graph.data.meta.clade_node_attr_descs().to_vec())
+      .map(|graph| graph.data.meta.numeric_node_attr_descs().to_vec())
       .unwrap_or_default();
 
     let phenotype_attr_descs = get_phenotype_attr_descs(&virus_properties);
@@ -388,6 +621,18 @@ impl Nextclade {
     let aa_motifs_descs = virus_properties.aa_motifs.clone();
     let aa_motifs_keys = aa_motifs_descs.iter().map(|desc| desc.name.clone()).collect_vec();
 
+    let nuc_motifs_matchers = NucMotifsMatcher::from_descs(&virus_properties.nuc_motifs)
+      .wrap_err("When preparing nucleotide motifs from pathogen.json")?;
+    let nuc_motifs_ref = find_nuc_motifs(
+      &nuc_motifs_matchers,
+      &ref_seq,
+      &ref_seq,
+      &CoordMapGlobal::new(&ref_seq, &ref_seq),
+      &NucRefGlobalRange::from_usize(0, ref_seq.len()),
+    );
+    let nuc_motifs_descs = virus_properties.nuc_motifs.clone();
+    let nuc_motifs_keys = nuc_motifs_descs.iter().map(|desc| desc.name.clone()).collect_vec();
+
     let ref_nodes = graph
       .as_ref()
       .map(|graph| graph.data.meta.reference_nodes())
@@ -400,19 +645,32 @@ impl Nextclade {
       ref_seq,
       seed_index,
       gap_open_close_nuc,
+      additional_refs,
       virus_properties,
       primers,
+      amplicon_scheme,
+      sample_metadata,
+      metadata_keys,
       params,
+      nuc_motifs_matchers,
+      nuc_motifs_ref,
+      nuc_motifs_descs,
+      nuc_motifs_keys,
       gene_map,
       gap_open_close_aa,
       ref_translation,
+      ref_cds_sites,
       aa_motifs_ref,
       aa_motifs_descs,
       aa_motifs_keys,
       graph,
       clade_attr_descs,
+      numeric_attr_descs,
       phenotype_attr_descs,
       ref_nodes,
+      clade_mutations,
+      segment: None,
+      other_segments: vec![],
     })
   }
 
@@ -425,12 +683,18 @@ impl Nextclade {
       cds_order_preference: self.virus_properties.cds_order_preference.clone(),
       clade_node_attr_key_descs: self.clade_attr_descs.clone(),
       clade_node_attr_keys: self.clade_attr_descs.iter().map(|desc| desc.name.clone()).collect(),
+      numeric_node_attr_descs: self.numeric_attr_descs.clone(),
+      numeric_node_attr_keys: self.numeric_attr_descs.iter().map(|desc| desc.name.clone()).collect(),
       phenotype_attr_descs: self.phenotype_attr_descs.clone(),
       phenotype_attr_keys: self.phenotype_attr_descs.iter().map(|desc| desc.name.clone()).collect(),
       ref_nodes: self.ref_nodes.clone(),
       aa_motifs_descs: self.aa_motifs_descs.clone(),
       aa_motif_keys: self.aa_motifs_keys.clone(),
+      nuc_motifs_descs: self.nuc_motifs_descs.clone(),
+      nuc_motif_keys: self.nuc_motifs_keys.clone(),
+      metadata_keys: self.metadata_keys.clone(),
       csv_column_config_default: CsvColumnConfig::default(),
+      segment_names: get_segment_names(self),
     }
   }
 
@@ -440,12 +704,23 @@ impl Nextclade {
     } else {
       to_nuc_seq(&input.seq)
     }?;
-    nextclade_run_one(input.index, &input.seq_name, &qry_seq, self)
+    let state = choose_segment(self, &qry_seq);
+    let metadata = self
+      .sample_metadata
+      .as_ref()
+      .and_then(|sample_metadata| sample_metadata.get(&input.seq_name));
+    nextclade_run_one(input.index, &input.seq_name, &qry_seq, state, metadata)
   }
 
   pub fn get_output_trees(&mut self, results: Vec<NextcladeOutputs>) -> Result<Option<OutputTrees>, Report> {
     if let Some(graph) = &mut self.graph {
-      graph_attach_new_nodes_in_place(graph, results, self.ref_seq.len(), &self.params.tree_builder)?;
+      graph_attach_new_nodes_in_place(
+        graph,
+        results,
+        self.ref_seq.len(),
+        &self.metadata_keys,
+        &self.params.tree_builder,
+      )?;
       let auspice = Graph::to_auspice_tree(graph)?;
       let nwk = nwk_write_to_string(graph)?;
       Ok(Some(OutputTrees { auspice, nwk }))
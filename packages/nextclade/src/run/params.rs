@@ -52,6 +52,8 @@ impl NextcladeInputParams {
       general_params
     };
 
+    general.validate()?;
+
     let preset = params
       .alignment
       .as_ref()
@@ -1,7 +1,21 @@
-use clap::Parser;
+use crate::{make_error, o};
+use clap::{Parser, ValueEnum};
+use eyre::Report;
 use optfield::optfield;
+use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
+/// What to report for a clade-like attribute when the confidence of its assignment is below the threshold
+#[derive(ValueEnum, Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum CladeLowConfidenceFallback {
+  /// Report the nearest ancestral value with sufficient confidence (e.g. the parent clade). This is the default.
+  #[default]
+  Parent,
+  /// Report "unassigned"
+  Unassigned,
+}
+
 #[allow(clippy::struct_excessive_bools)]
 #[optfield(pub NextcladeGeneralParamsOptional, attrs, doc, field_attrs, field_doc, merge_fn = pub)]
 #[derive(Parser, Debug, Clone, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
@@ -39,6 +53,61 @@ pub struct NextcladeGeneralParams {
   #[clap(long)]
   #[clap(num_args=0..=1, default_missing_value = "true")]
   pub replace_unknown: bool,
+
+  /// Regular expression used to extract sample identifier from the sequence name, when analyzing segmented genomes.
+  ///
+  /// Sequences of different segments of the same sample are grouped by the sample identifier in the per-sample
+  /// segments summary. The regular expression is applied to the sequence identifier (the part of the FASTA header
+  /// before the first whitespace) and should contain a capture group named `sample`, e.g.
+  /// `^(?P<sample>.+)\|[^|]+$`. If no named group is present, the first capture group is used.
+  ///
+  /// By default (if empty), the sample identifier is the sequence identifier with the segment name suffix removed
+  /// (if present), e.g. both 'A/Texas/1/2024|HA' and 'A/Texas/1/2024_HA' become 'A/Texas/1/2024' for segment 'HA'.
+  #[clap(long)]
+  pub segment_sample_id_regex: String,
+
+  /// Minimum fraction of the unique part of an amplicon that should be covered by the query sequence, for the amplicon
+  /// not to be considered dropped out.
+  ///
+  /// Only relevant when an amplicon scheme is provided with `--input-amplicon-scheme`. The unique part of an amplicon
+  /// is the region between its primers, which does not overlap with other amplicons. A position is considered covered
+  /// if it is aligned and is not `N`.
+  #[clap(long)]
+  pub amplicon_min_coverage: OrderedFloat<f64>,
+
+  /// Minimum confidence of clade assignment, below which the assigned clade (and each clade-like attribute) is replaced
+  /// according to `--clade-low-confidence-fallback`.
+  ///
+  /// Confidence is the fraction of the mutations on the branch which defines the clade (the branch leading to the
+  /// clade's founder node in the reference tree) at positions which are sequenced in the query. Accepts values between 0
+  /// and 1. By default (0), the assigned clades are never replaced.
+  #[clap(long)]
+  pub clade_min_confidence: OrderedFloat<f64>,
+
+  /// What to report when confidence of clade assignment is below `--clade-min-confidence`.
+  #[clap(long, value_enum)]
+  pub clade_low_confidence_fallback: CladeLowConfidenceFallback,
+
+  /// Name of the column of the sample metadata table (`--input-metadata`) containing sequence names.
+  ///
+  /// By default (if empty), the first of the columns `seqName`, `strain`, `name` and `accession` which is present in
+  /// the table is used, or the first column if none of them is present.
+  #[clap(long)]
+  pub metadata_id_column: String,
+
+  /// Name of the column of the sample metadata table (`--input-metadata`) containing collection dates.
+  ///
+  /// Dates are expected in the format `YYYY-MM-DD`. Incomplete dates (`YYYY-MM`, `YYYY`, `YYYY-MM-XX`, `YYYY-XX-XX`)
+  /// and decimal years (e.g. `2021.25`) are also accepted.
+  #[clap(long)]
+  pub metadata_date_column: String,
+
+  /// Names of the columns of the sample metadata table (`--input-metadata`) to include into the outputs.
+  ///
+  /// The values are added to CSV, TSV, NDJSON, JSON and Excel outputs, and to node attributes of the new nodes in
+  /// the output tree. By default (if empty), all columns except the sequence name column are included.
+  #[clap(long, num_args=1.., use_value_delimiter = true)]
+  pub metadata_columns: Vec<String>,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -49,6 +118,25 @@ impl Default for NextcladeGeneralParams {
       include_nearest_node_info: false,
       in_order: false,
       replace_unknown: false,
+      segment_sample_id_regex: String::new(),
+      amplicon_min_coverage: OrderedFloat(0.5),
+      clade_min_confidence: OrderedFloat(0.0),
+      clade_low_confidence_fallback: CladeLowConfidenceFallback::default(),
+      metadata_id_column: String::new(),
+      metadata_date_column: o!("date"),
+      metadata_columns: vec![],
+    }
+  }
+}
+
+impl NextcladeGeneralParams {
+  pub fn validate(&self) -> Result<(), Report> {
+    if !(0.0..=1.0).contains(&*self.clade_min_confidence) {
+      return make_error!(
+        "Minimum clade confidence (`--clade-min-confidence`, `cladeMinConfidence`) should be between 0 and 1, but found: {}",
+        self.clade_min_confidence
+      );
     }
+    Ok(())
   }
 }
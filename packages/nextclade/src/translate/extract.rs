@@ -74,6 +74,7 @@ mod coord_map_tests {
   use crate::alphabet::nuc::to_nuc_seq;
   use crate::coord::position::Position;
   use crate::coord::range::{NucRefGlobalRange, Range};
+  use crate::translate::genetic_code::GeneticCode;
 
   use crate::gene::cds_segment::{CdsSegment, Truncation, WrappingPart};
   use crate::gene::frame::Frame;
@@ -127,6 +128,8 @@ mod coord_map_tests {
       proteins: vec![],
       exceptions: vec![],
       attributes: indexmap! {},
+      genetic_code: GeneticCode::default(),
+      rna_editing: vec![],
       compat_is_gene: false,
       color: None,
     }
@@ -0,0 +1,277 @@
+use crate::alphabet::aa::{Aa, to_aa};
+use crate::alphabet::nuc::Nuc;
+use crate::make_error;
+use crate::translate::translate::decode;
+use eyre::{Report, WrapErr};
+use indexmap::IndexMap;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::Schema;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Name of the GFF3 attribute which sets the genetic code of a CDS
+pub const TRANSL_TABLE_ATTRIBUTE: &str = "transl_table";
+
+/// Amino acids encoded by codons in the NCBI translation tables, in the order of the NCBI `AAs` strings: the first,
+/// second and third nucleotides of a codon vary in order `T`, `C`, `A`, `G`, with the third nucleotide varying the
+/// fastest.
+///
+/// See: https://www.ncbi.nlm.nih.gov/Taxonomy/Utils/wprintgc.cgi
+const TRANSL_TABLES: &[(u8, &str)] = &[
+  (1, "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (2, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG"),
+  (3, "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (4, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (5, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG"),
+  (6, "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (9, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG"),
+  (10, "FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (11, "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (12, "FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (13, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG"),
+  (14, "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG"),
+  (15, "FFLLSSSSYY*QCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (16, "FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (21, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG"),
+  (22, "FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (23, "FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (24, "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG"),
+  (25, "FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (26, "FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (27, "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (28, "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (29, "FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (30, "FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (31, "FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"),
+  (33, "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG"),
+];
+
+/// Genetic code used to translate a CDS, identified by its NCBI translation table number (`transl_table`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct GeneticCode {
+  table: u8,
+  codons: [Aa; 64],
+}
+
+impl GeneticCode {
+  pub fn new(table: u8) -> Result<Self, Report> {
+    let Some((_, aas)) = TRANSL_TABLES.iter().find(|(t, _)| *t == table) else {
+      let known = TRANSL_TABLES
+        .iter()
+        .map(|(t, _)| t.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+      return make_error!("Unknown genetic code (NCBI translation table): {table}. Known translation tables: {known}");
+    };
+
+    let mut codons = [Aa::X; 64];
+    for (codon, aa) in codons.iter_mut().zip(aas.chars()) {
+      *codon = to_aa(aa)?;
+    }
+
+    Ok(Self { table, codons })
+  }
+
+  /// Standard genetic code (NCBI translation table 1)
+  pub const fn standard() -> Self {
+    Self {
+      table: 1,
+      codons: [Aa::X; 64],
+    }
+  }
+
+  /// Read genetic code from the `transl_table` attribute of a GFF3 feature. Defaults to the standard code.
+  pub fn from_attributes(attributes: &IndexMap<String, Vec<String>>) -> Result<Self, Report> {
+    match attributes.get(TRANSL_TABLE_ATTRIBUTE).and_then(|values| values.first()) {
+      None => Ok(Self::standard()),
+      Some(value) => {
+        Self::from_str(value).wrap_err_with(|| format!("When reading '{TRANSL_TABLE_ATTRIBUTE}' attribute"))
+      }
+    }
+  }
+
+  pub const fn table(&self) -> u8 {
+    self.table
+  }
+
+  pub const fn is_standard(&self) -> bool {
+    self.table == 1
+  }
+
+  /// Translate a nucleotide triplet into an amino acid.
+  ///
+  /// Ambiguous nucleotides are resolved if all the codons they can represent encode the same amino acid in this code.
+  pub fn decode(&self, triplet: &[Nuc]) -> Aa {
+    if self.is_standard() {
+      return decode(triplet);
+    }
+
+    match *triplet {
+      [Nuc::Gap, Nuc::Gap, Nuc::Gap] => Aa::Gap,
+      [n1, n2, n3] => {
+        let mut aas = canonical_nucs(n1).iter().flat_map(|&n1| {
+          canonical_nucs(n2).iter().flat_map(move |&n2| {
+            canonical_nucs(n3)
+              .iter()
+              .map(move |&n3| self.codons[codon_index(n1, n2, n3)])
+          })
+        });
+        match aas.next() {
+          Some(aa) if aas.all(|other| other == aa) => aa,
+          _ => Aa::X,
+        }
+      }
+      _ => Aa::X,
+    }
+  }
+}
+
+impl Default for GeneticCode {
+  fn default() -> Self {
+    Self::standard()
+  }
+}
+
+impl schemars::JsonSchema for GeneticCode {
+  fn schema_name() -> String {
+    "GeneticCode".to_owned()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<u8>()
+  }
+}
+
+impl PartialEq for GeneticCode {
+  fn eq(&self, other: &Self) -> bool {
+    self.table == other.table
+  }
+}
+
+impl Eq for GeneticCode {}
+
+impl TryFrom<u8> for GeneticCode {
+  type Error = Report;
+
+  fn try_from(table: u8) -> Result<Self, Self::Error> {
+    Self::new(table)
+  }
+}
+
+impl From<GeneticCode> for u8 {
+  fn from(code: GeneticCode) -> Self {
+    code.table
+  }
+}
+
+impl FromStr for GeneticCode {
+  type Err = Report;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let table = s
+      .trim()
+      .parse::<u8>()
+      .wrap_err_with(|| format!("Invalid genetic code (NCBI translation table): '{s}'"))?;
+    Self::new(table)
+  }
+}
+
+impl Display for GeneticCode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.table)
+  }
+}
+
+/// Unambiguous nucleotides which can be represented by a given nucleotide (IUPAC notation)
+const fn canonical_nucs(nuc: Nuc) -> &'static [Nuc] {
+  match nuc {
+    Nuc::T => &[Nuc::T],
+    Nuc::A => &[Nuc::A],
+    Nuc::C => &[Nuc::C],
+    Nuc::G => &[Nuc::G],
+    Nuc::W => &[Nuc::A, Nuc::T],
+    Nuc::Y => &[Nuc::C, Nuc::T],
+    Nuc::M => &[Nuc::A, Nuc::C],
+    Nuc::K => &[Nuc::G, Nuc::T],
+    Nuc::R => &[Nuc::A, Nuc::G],
+    Nuc::S => &[Nuc::C, Nuc::G],
+    Nuc::H => &[Nuc::A, Nuc::C, Nuc::T],
+    Nuc::D => &[Nuc::A, Nuc::G, Nuc::T],
+    Nuc::B => &[Nuc::C, Nuc::G, Nuc::T],
+    Nuc::V => &[Nuc::A, Nuc::C, Nuc::G],
+    Nuc::N => &[Nuc::A, Nuc::C, Nuc::G, Nuc::T],
+    Nuc::Gap => &[],
+  }
+}
+
+/// Index of a codon in the NCBI translation table strings
+fn codon_index(n1: Nuc, n2: Nuc, n3: Nuc) -> usize {
+  let index = |nuc: Nuc| match nuc {
+    Nuc::T => 0,
+    Nuc::C => 1,
+    Nuc::A => 2,
+    _ => 3,
+  };
+  index(n1) * 16 + index(n2) * 4 + index(n3)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::standard_stop(1, "TGA", Aa::Stop)]
+  #[case::vertebrate_mito_tga(2, "TGA", Aa::W)]
+  #[case::vertebrate_mito_aga(2, "AGA", Aa::Stop)]
+  #[case::vertebrate_mito_ata(2, "ATA", Aa::M)]
+  #[case::yeast_mito_ctn(3, "CTN", Aa::T)]
+  #[case::mycoplasma_tga(4, "TGA", Aa::W)]
+  #[case::ciliate_tar(6, "TAR", Aa::Q)]
+  #[case::ciliate_tga(6, "TGA", Aa::Stop)]
+  #[case::standard_ambiguous(1, "TRA", Aa::Stop)]
+  #[case::mito_ambiguous_resolved(2, "TGR", Aa::W)]
+  #[case::mito_ambiguous_stop(2, "AGR", Aa::Stop)]
+  #[case::mito_ambiguous_differs(2, "ATR", Aa::M)]
+  #[case::mito_ambiguous_x(2, "MGA", Aa::X)]
+  #[case::gap(2, "---", Aa::Gap)]
+  #[case::partial_gap(2, "A-A", Aa::X)]
+  fn decodes_codons_with_genetic_code(
+    #[case] table: u8,
+    #[case] codon: &str,
+    #[case] expected: Aa,
+  ) -> Result<(), Report> {
+    let code = GeneticCode::new(table)?;
+    assert_eq!(code.decode(&to_nuc_seq(codon)?), expected);
+    Ok(())
+  }
+
+  #[rstest]
+  fn table_1_agrees_with_standard_decode() -> Result<(), Report> {
+    // Compare generic table lookup (used for non-standard codes) with the hardcoded standard code
+    let table_1 = GeneticCode {
+      table: 0,
+      ..GeneticCode::new(1)?
+    };
+    let nucs = to_nuc_seq("TAWCYMHGKRDSBVN-")?;
+    for &n1 in &nucs {
+      for &n2 in &nucs {
+        for &n3 in &nucs {
+          let triplet = [n1, n2, n3];
+          assert_eq!(table_1.decode(&triplet), decode(&triplet), "{triplet:?}");
+        }
+      }
+    }
+    Ok(())
+  }
+
+  #[rstest]
+  fn rejects_unknown_table() {
+    drop(GeneticCode::new(7).unwrap_err());
+    drop(GeneticCode::from_str("mito").unwrap_err());
+  }
+}
@@ -208,7 +208,7 @@ pub fn translate(gene_nuc_seq: &[Nuc], cds: &Cds, params: &AlignPairwiseParams)
   for i_aa in 0..peptide_length {
     let i_nuc = i_aa * 3;
     let triplet: &[Nuc] = &gene_nuc_seq[i_nuc..(i_nuc + 3)];
-    let aminoacid = decode(triplet);
+    let aminoacid = cds.genetic_code.decode(triplet);
     peptide.push(aminoacid);
     if params.no_translate_past_stop && aminoacid == Aa::Stop {
       break;
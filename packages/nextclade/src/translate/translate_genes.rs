@@ -138,6 +138,10 @@ pub struct CdsTranslation {
   pub frame_shifts: Vec<FrameShift>,
   pub alignment_ranges: Vec<AaRefRange>,
   pub unsequenced_ranges: Vec<AaRefRange>,
+
+  /// In-frame nucleotide sequence of the CDS, aligned consistently with the peptide: one codon per amino acid
+  #[serde(skip)]
+  pub nuc_seq: Vec<Nuc>,
 }
 
 /// Results of the aminoacid alignment parameters estimation
@@ -223,6 +227,43 @@ pub fn mask_peptide_frame_shifts_in_place(seq: &mut [Aa], frame_shifts: &[FrameS
   }
 }
 
+/// Masks frame-shifted codons in the codon alignment, same as in the peptide
+pub fn mask_codon_frame_shifts_in_place(seq: &mut [Nuc], frame_shifts: &[FrameShift]) {
+  let mut fill = |range: &AaRefRange, letter: Nuc| {
+    let begin = clamp_max(range.begin.as_usize() * 3, seq.len());
+    let end = clamp_max(range.end.as_usize() * 3, seq.len());
+    seq[begin..end].fill(letter);
+  };
+
+  for frame_shift in frame_shifts {
+    fill(&frame_shift.gaps_leading, Nuc::Gap);
+    fill(&frame_shift.codon, Nuc::N);
+    fill(&frame_shift.gaps_trailing, Nuc::Gap);
+  }
+}
+
+/// Builds in-frame nucleotide alignment of a CDS from the ungapped query CDS sequence and the aligned peptides, such that
+/// it is consistent with the peptide after insertions are stripped: every amino acid corresponds to its codon, gaps
+/// correspond to gapped codons, and the codons of inserted amino acids are removed.
+pub fn codon_alignment(qry_cds_seq: &[Nuc], qry_peptide_aln: &[Aa], ref_peptide_aln: &[Aa]) -> Vec<Nuc> {
+  let mut codons = Vec::with_capacity(ref_peptide_aln.len() * 3);
+  let mut i_nuc = 0;
+  for (qry, reff) in qry_peptide_aln.iter().zip(ref_peptide_aln) {
+    if qry.is_gap() {
+      codons.extend_from_slice(&[Nuc::Gap; 3]);
+      continue;
+    }
+
+    let codon = &qry_cds_seq[i_nuc..i_nuc + 3];
+    i_nuc += 3;
+
+    if !reff.is_gap() {
+      codons.extend_from_slice(codon);
+    }
+  }
+  codons
+}
+
 pub fn translate_cds(
   qry_seq: &[Nuc],
   ref_seq: &[Nuc],
@@ -289,6 +330,9 @@ pub fn translate_cds(
 
   mask_peptide_frame_shifts_in_place(&mut stripped.qry_seq, &frame_shifts);
 
+  let mut nuc_seq = codon_alignment(&qry_cds_seq, &alignment.qry_seq, &alignment.ref_seq);
+  mask_codon_frame_shifts_in_place(&mut nuc_seq, &frame_shifts);
+
   Ok(CdsTranslation {
     name: cds.name.clone(),
     seq: stripped.qry_seq,
@@ -296,6 +340,7 @@ pub fn translate_cds(
     frame_shifts,
     alignment_ranges: vec![],
     unsequenced_ranges: vec![],
+    nuc_seq,
   })
 }
 
@@ -357,3 +402,32 @@ pub fn translate_genes(
 
   Ok(translation)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::aa::to_aa_seq;
+  use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case::no_gaps("ATGAAACCC", "MKP", "MKP", "ATGAAACCC")]
+  #[case::deletion("ATGCCC", "M-P", "MKP", "ATG---CCC")]
+  #[case::insertion("ATGGGGAAACCC", "MGKP", "M-KP", "ATGAAACCC")]
+  #[case::truncated("ATGAAA", "MK-", "MKP", "ATGAAA---")]
+  fn builds_codon_alignment(
+    #[case] qry_cds_seq: &str,
+    #[case] qry_peptide_aln: &str,
+    #[case] ref_peptide_aln: &str,
+    #[case] expected: &str,
+  ) -> Result<(), Report> {
+    let actual = codon_alignment(
+      &to_nuc_seq(qry_cds_seq)?,
+      &to_aa_seq(qry_peptide_aln)?,
+      &to_aa_seq(ref_peptide_aln)?,
+    );
+    assert_eq!(from_nuc_seq(&actual), expected);
+    Ok(())
+  }
+}
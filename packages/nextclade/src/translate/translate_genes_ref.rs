@@ -20,9 +20,10 @@ pub fn translate_genes_ref(
         .cdses
         .iter()
         .map(|cds| {
-          let nucs = extract_cds_from_ref(ref_seq, cds);
+          let mut nucs = extract_cds_from_ref(ref_seq, cds);
           let tr = translate(&nucs, cds, params);
           let len = tr.seq.len();
+          nucs.truncate(len * 3);
 
           (
             cds.name.clone(),
@@ -33,6 +34,7 @@ pub fn translate_genes_ref(
               frame_shifts: vec![],
               alignment_ranges: vec![Range::from_usize(0, len)],
               unsequenced_ranges: vec![],
+              nuc_seq: nucs,
             },
           )
         })
@@ -0,0 +1,201 @@
+use crate::tree::tree::CladeNodeAttrKeyDesc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Hierarchy of hierarchically-named lineages (e.g. Pango lineages), where a dot-separated name component denotes a
+/// child lineage and name prefixes can be replaced by shorter aliases.
+///
+/// Example: with alias "BA" standing for "B.1.1.529", lineage "BA.2.75" expands to "B.1.1.529.2.75" and its
+/// ancestors are "BA.2", "B.1.1.529", "B.1.1", "B.1" and "B".
+#[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageHierarchy {
+  /// Map from an alias to the name it stands for, in the format of Pango `alias_key.json`. Empty string denotes a
+  /// top-level lineage without an alias. A list of names denotes a recombinant lineage with several parents.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub aliases: BTreeMap<String, LineageAliasTarget>,
+
+  /// Map from a lineage name to the name of its parent, for the lineages whose parent cannot be derived from the
+  /// name (e.g. top-level recombinant lineages). Takes precedence over the parent derived from the name.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub parents: BTreeMap<String, String>,
+}
+
+/// Name an alias stands for
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq, JsonSchema, Debug)]
+#[serde(untagged)]
+pub enum LineageAliasTarget {
+  Single(String),
+  Recombinant(Vec<String>),
+}
+
+/// Lineage name resolved using the hierarchy of a clade-like attribute
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageInfo {
+  /// Lineage name with all aliases expanded
+  pub expanded: String,
+  /// Ancestral lineages, from the nearest to the earliest
+  pub ancestors: Vec<String>,
+}
+
+impl LineageHierarchy {
+  /// Replace the alias in the first component of the name by the name it stands for, repeatedly
+  pub fn expand(&self, name: impl AsRef<str>) -> String {
+    let mut name = name.as_ref().to_owned();
+    let mut seen = BTreeSet::new();
+    loop {
+      let (prefix, rest) = match name.split_once('.') {
+        Some((prefix, rest)) => (prefix, Some(rest)),
+        None => (name.as_str(), None),
+      };
+      let target = match self.aliases.get(prefix) {
+        Some(LineageAliasTarget::Single(target)) if !target.is_empty() => target,
+        _ => return name,
+      };
+      if !seen.insert(prefix.to_owned()) {
+        return name;
+      }
+      name = match rest {
+        Some(rest) => format!("{target}.{rest}"),
+        None => target.clone(),
+      };
+    }
+  }
+
+  /// Shortest name of the lineage: replace the longest aliased prefix of the expanded name by its alias. The alias
+  /// alone is not a lineage name, so at least one name component has to follow it.
+  pub fn compress(&self, name: impl AsRef<str>) -> String {
+    let expanded = self.expand(name);
+    self
+      .aliases
+      .iter()
+      .filter_map(|(alias, target)| match target {
+        LineageAliasTarget::Single(target) if !target.is_empty() => expanded
+          .strip_prefix(target.as_str())
+          .and_then(|rest| rest.strip_prefix('.'))
+          .map(|rest| (target.len(), format!("{alias}.{rest}"))),
+        _ => None,
+      })
+      .max_by_key(|(len, _)| *len)
+      .map_or(expanded, |(_, compressed)| compressed)
+  }
+
+  /// Parent of the lineage, in the compressed form
+  pub fn parent(&self, name: impl AsRef<str>) -> Option<String> {
+    let name = name.as_ref();
+    let expanded = self.expand(name);
+    let explicit = [name.to_owned(), self.compress(&expanded), expanded.clone()]
+      .iter()
+      .find_map(|key| self.parents.get(key))
+      .cloned();
+    explicit
+      .or_else(|| expanded.rsplit_once('.').map(|(parent, _)| parent.to_owned()))
+      .map(|parent| self.compress(parent))
+  }
+
+  /// Ancestors of the lineage in the compressed form, from the nearest to the earliest
+  pub fn ancestors(&self, name: impl AsRef<str>) -> Vec<String> {
+    let mut ancestors = vec![];
+    let mut seen = BTreeSet::from([self.expand(&name)]);
+    let mut current = self.parent(name);
+    while let Some(parent) = current {
+      if !seen.insert(self.expand(&parent)) {
+        break;
+      }
+      current = self.parent(&parent);
+      ancestors.push(parent);
+    }
+    ancestors
+  }
+
+  /// Whether the lineage is the given ancestral lineage or its descendant
+  pub fn is_descendant_of(&self, name: impl AsRef<str>, ancestor: impl AsRef<str>) -> bool {
+    let ancestor = self.expand(ancestor);
+    self.expand(&name) == ancestor || self.ancestors(name).iter().any(|anc| self.expand(anc) == ancestor)
+  }
+
+  pub fn lineage_info(&self, name: impl AsRef<str>) -> LineageInfo {
+    LineageInfo {
+      expanded: self.expand(&name),
+      ancestors: self.ancestors(name),
+    }
+  }
+}
+
+/// Resolve values of clade-like attributes which have a lineage hierarchy defined
+pub fn find_clade_node_attr_lineages(
+  clade_node_attrs: &BTreeMap<String, String>,
+  clade_node_attr_descs: &[CladeNodeAttrKeyDesc],
+) -> BTreeMap<String, LineageInfo> {
+  clade_node_attr_descs
+    .iter()
+    .filter_map(|desc| {
+      let hierarchy = desc.lineage_hierarchy.as_ref()?;
+      let value = clade_node_attrs.get(&desc.name)?;
+      Some((desc.name.clone(), hierarchy.lineage_info(value)))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::o;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn hierarchy() -> LineageHierarchy {
+    LineageHierarchy {
+      aliases: BTreeMap::from([
+        (o!("A"), LineageAliasTarget::Single(o!(""))),
+        (o!("B"), LineageAliasTarget::Single(o!(""))),
+        (o!("BA"), LineageAliasTarget::Single(o!("B.1.1.529"))),
+        (o!("BM"), LineageAliasTarget::Single(o!("B.1.1.529.2.75.3"))),
+        (
+          o!("XBB"),
+          LineageAliasTarget::Recombinant(vec![o!("BJ.1"), o!("BM.1.1.1")]),
+        ),
+      ]),
+      parents: BTreeMap::from([(o!("XBB"), o!("BA.2"))]),
+    }
+  }
+
+  #[rstest]
+  #[case("BA.2.75", "B.1.1.529.2.75")]
+  #[case("BM.1.1", "B.1.1.529.2.75.3.1.1")]
+  #[case("B.1.1.529", "B.1.1.529")]
+  #[case("XBB.1.5", "XBB.1.5")]
+  #[case("unknown", "unknown")]
+  fn expands_aliases(#[case] name: &str, #[case] expected: &str) {
+    assert_eq!(hierarchy().expand(name), expected);
+  }
+
+  #[rstest]
+  #[case("B.1.1.529.2.75.3.1", "BM.1")]
+  #[case("B.1.1.529.2", "BA.2")]
+  #[case("B.1.1.529", "B.1.1.529")]
+  fn compresses_to_longest_alias(#[case] name: &str, #[case] expected: &str) {
+    assert_eq!(hierarchy().compress(name), expected);
+  }
+
+  #[rstest]
+  #[case("BM.1.1", &["BM.1", "BA.2.75.3", "BA.2.75", "BA.2", "B.1.1.529", "B.1.1", "B.1", "B"])]
+  #[case("XBB.1.5", &["XBB.1", "XBB", "BA.2", "B.1.1.529", "B.1.1", "B.1", "B"])]
+  #[case("A", &[])]
+  fn finds_ancestors(#[case] name: &str, #[case] expected: &[&str]) {
+    assert_eq!(hierarchy().ancestors(name), expected);
+  }
+
+  #[rstest]
+  #[case("BM.1.1", "BA.2", true)]
+  #[case("BM.1.1", "B.1.1.529.2", true)]
+  #[case("BA.2", "BA.2", true)]
+  #[case("BA.2", "BM.1", false)]
+  #[case("BA.5", "BA.2", false)]
+  #[case("A.1", "B", false)]
+  fn checks_descendants(#[case] name: &str, #[case] ancestor: &str, #[case] expected: bool) {
+    assert_eq!(hierarchy().is_descendant_of(name, ancestor), expected);
+  }
+}
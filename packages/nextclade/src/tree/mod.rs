@@ -1,10 +1,20 @@
+pub mod lineage_hierarchy;
+pub mod node_value_matcher;
 pub mod params;
 pub mod split_muts;
 pub mod split_muts2;
 pub mod tree;
+pub mod tree_ancestral_seqs;
 pub mod tree_attach_new_nodes;
 pub mod tree_builder;
+pub mod tree_check;
 pub mod tree_find_ancestors_of_interest;
 pub mod tree_find_clade_founder;
 pub mod tree_find_nearest_node;
+pub mod tree_find_numeric_node_attrs;
+pub mod tree_find_recombination;
+pub mod tree_placement_priors;
 pub mod tree_preprocess;
+
+#[cfg(test)]
+pub mod test_utils;
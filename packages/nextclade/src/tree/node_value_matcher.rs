@@ -0,0 +1,139 @@
+use crate::make_error;
+use eyre::{Report, eyre};
+use regex::Regex;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::Schema;
+use serde::{Deserialize, Serialize};
+
+/// Matcher for string values of tree nodes (name, clade or clade-like attribute), as written in the dataset.
+///
+/// A plain string matches the value exactly. An object `{ "glob": "24*" }` matches using a glob pattern, where `*`
+/// matches any sequence of characters and `?` matches any single character. An object `{ "regex": "^2[34]" }` matches
+/// using a regular expression.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum NodeValueMatcherDesc {
+  Exact(String),
+  Pattern(NodeValuePatternDesc),
+}
+
+/// Glob or regex pattern
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub enum NodeValuePatternDesc {
+  Glob(String),
+  Regex(String),
+}
+
+/// Matcher for string values of tree nodes, with patterns compiled when the dataset is loaded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "NodeValueMatcherDesc", into = "NodeValueMatcherDesc")]
+pub struct NodeValueMatcher {
+  desc: NodeValueMatcherDesc,
+  regex: Option<Regex>,
+}
+
+impl NodeValueMatcher {
+  pub fn new(desc: NodeValueMatcherDesc) -> Result<Self, Report> {
+    // NOTE: error messages include the cause, because only the message is retained when deserializing
+    let regex = match &desc {
+      NodeValueMatcherDesc::Exact(_) => None,
+      NodeValueMatcherDesc::Pattern(NodeValuePatternDesc::Glob(glob)) => Some(
+        Regex::new(&glob_to_regex(glob))
+          .map_err(|err| eyre!("Invalid glob pattern '{glob}' in node criteria: {err}"))?,
+      ),
+      NodeValueMatcherDesc::Pattern(NodeValuePatternDesc::Regex(regex)) => {
+        if regex.is_empty() {
+          return make_error!("Empty regular expression in node criteria");
+        }
+        Some(Regex::new(regex).map_err(|err| eyre!("Invalid regular expression '{regex}' in node criteria: {err}"))?)
+      }
+    };
+    Ok(Self { desc, regex })
+  }
+
+  pub fn is_match(&self, value: impl AsRef<str>) -> bool {
+    let value = value.as_ref();
+    match (&self.desc, &self.regex) {
+      (NodeValueMatcherDesc::Exact(expected), _) => expected == value,
+      (_, Some(regex)) => regex.is_match(value),
+      (_, None) => false,
+    }
+  }
+}
+
+/// Convert glob pattern into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+  let body: String = glob
+    .chars()
+    .map(|c| match c {
+      '*' => ".*".to_owned(),
+      '?' => ".".to_owned(),
+      c => regex::escape(&c.to_string()),
+    })
+    .collect();
+  format!("^{body}$")
+}
+
+impl schemars::JsonSchema for NodeValueMatcher {
+  fn schema_name() -> String {
+    "NodeValueMatcher".to_owned()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<NodeValueMatcherDesc>()
+  }
+}
+
+impl PartialEq for NodeValueMatcher {
+  fn eq(&self, other: &Self) -> bool {
+    self.desc == other.desc
+  }
+}
+
+impl Eq for NodeValueMatcher {}
+
+impl TryFrom<NodeValueMatcherDesc> for NodeValueMatcher {
+  type Error = Report;
+
+  fn try_from(desc: NodeValueMatcherDesc) -> Result<Self, Self::Error> {
+    Self::new(desc)
+  }
+}
+
+impl From<NodeValueMatcher> for NodeValueMatcherDesc {
+  fn from(matcher: NodeValueMatcher) -> Self {
+    matcher.desc
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::io::json::json_parse;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  #[rstest]
+  #[case(r#""24A""#, "24A", true)]
+  #[case(r#""24A""#, "24A.1", false)]
+  #[case(r#"{ "glob": "24*" }"#, "24B", true)]
+  #[case(r#"{ "glob": "24?" }"#, "24BB", false)]
+  #[case(r#"{ "glob": "B.1.*" }"#, "B.1.1.7", true)]
+  #[case(r#"{ "glob": "B.1.*" }"#, "BA1.7", false)]
+  #[case(r#"{ "regex": "^2[34]" }"#, "23I", true)]
+  #[case(r#"{ "regex": "^2[34]" }"#, "22F", false)]
+  fn matches_values(#[case] json: &str, #[case] value: &str, #[case] expected: bool) {
+    let matcher: NodeValueMatcher = json_parse(json).unwrap();
+    assert_eq!(matcher.is_match(value), expected);
+  }
+
+  #[rstest]
+  #[case(r#"{ "regex": "2[34" }"#, "Invalid regular expression '2[34' in node criteria")]
+  #[case(r#"{ "regex": "" }"#, "Empty regular expression in node criteria")]
+  #[case(r#"{ "wildcard": "24*" }"#, "did not match any variant")]
+  fn rejects_malformed_patterns(#[case] json: &str, #[case] expected: &str) {
+    let err = json_parse::<NodeValueMatcher>(json).unwrap_err();
+    assert!(format!("{err:#}").contains(expected), "{err:#}");
+  }
+}
@@ -0,0 +1,43 @@
+use crate::graph::node::{GraphNodeKey, Node};
+use crate::tree::tree::{
+  AuspiceGraph, AuspiceGraphEdgePayload, AuspiceGraphMeta, AuspiceGraphNodePayload, TreeNodeAttr,
+};
+use eyre::Report;
+
+/// Tree node with a given name, clade and nucleotide mutations on the branch leading to it
+pub fn test_node(name: &str, clade: Option<&str>, nuc_muts: &[&str]) -> AuspiceGraphNodePayload {
+  let mut node = AuspiceGraphNodePayload::new(name);
+  node.node_attrs.clade_membership = clade.map(TreeNodeAttr::new);
+  if !nuc_muts.is_empty() {
+    node
+      .branch_attrs
+      .mutations
+      .insert("nuc".to_owned(), nuc_muts.iter().map(ToString::to_string).collect());
+  }
+  node
+}
+
+/// Build a tree from nodes listed in pre-order, each with the index of its parent in the list (none for the root)
+pub fn make_test_graph(
+  meta: AuspiceGraphMeta,
+  nodes: Vec<(AuspiceGraphNodePayload, Option<usize>)>,
+) -> Result<AuspiceGraph, Report> {
+  let mut graph = AuspiceGraph::new(meta);
+  let mut keys = vec![];
+  for (node, parent) in nodes {
+    let key = graph.add_node(node);
+    if let Some(parent) = parent {
+      graph.add_edge(keys[parent], key, AuspiceGraphEdgePayload::new())?;
+    }
+    keys.push(key);
+  }
+  graph.build()
+}
+
+/// Key of the tree node with a given name
+pub fn test_node_key(graph: &AuspiceGraph, name: &str) -> Option<GraphNodeKey> {
+  graph
+    .iter_nodes()
+    .find(|node| node.payload().name == name)
+    .map(Node::key)
+}
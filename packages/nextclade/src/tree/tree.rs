@@ -11,8 +11,11 @@ use crate::graph::node::{GraphNode, Node};
 use crate::graph::traits::{HasDivergence, HasName};
 use crate::io::fs::read_file_to_string;
 use crate::io::json::json_parse;
+use crate::tree::lineage_hierarchy::LineageHierarchy;
+use crate::tree::node_value_matcher::NodeValueMatcher;
 use eyre::{Report, WrapErr, eyre};
 use log::warn;
+use ordered_float::OrderedFloat;
 use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::json;
@@ -235,6 +238,8 @@ pub struct TreeNodeTempData {
   pub private_mutations: BranchMutations,
   pub aa_substitutions: BTreeMap<String, BTreeMap<AaRefPosition, Aa>>,
   pub aa_mutations: BTreeMap<String, BTreeMap<AaRefPosition, Aa>>,
+  /// Adjustment of the log10 placement prior, from the dataset-level placement prior configuration
+  pub placement_prior_adjustment: f64,
 }
 
 /// Data payload for a node in the phylogenetic reference tree graph.
@@ -309,6 +314,21 @@ impl AuspiceGraphNodePayload {
     val.as_str()
   }
 
+  /// Extracts numeric node attribute, given its name
+  pub fn get_numeric_node_attr(&self, key: impl AsRef<str>) -> Option<f64> {
+    self
+      .node_attrs
+      .other
+      .get(key.as_ref())
+      .and_then(|val| val.get("value"))
+      .and_then(serde_json::Value::as_f64)
+  }
+
+  /// Sets numeric node attribute
+  pub fn set_numeric_node_attr(&mut self, key: impl AsRef<str>, value: f64) {
+    self.node_attrs.other[key.as_ref()] = json!({ "value": value });
+  }
+
   /// Sets clade-like node attribute
   pub fn set_clade_node_attr(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) {
     self.node_attrs.other[key.as_ref()] = json!({ "value": value.as_ref() });
@@ -393,6 +413,29 @@ pub struct CladeNodeAttrKeyDesc {
   /// Exclude this attribute from clade founder node search and relative mutation calculation
   #[serde(default)]
   pub skip_as_reference: bool,
+  /// Aliases and hierarchy of lineage names, for attributes with hierarchically-named values (e.g. Pango lineages)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub lineage_hierarchy: Option<LineageHierarchy>,
+  #[serde(flatten)]
+  pub other: serde_json::Value,
+}
+
+/// Description of a numeric node attribute (e.g. fitness, growth advantage or `num_date`), transferred to query
+/// sequences from the nearest node
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NumericNodeAttrDesc {
+  /// Machine-readable identifier, must match the key used in node_attrs on tree nodes
+  pub name: String,
+  /// Human-readable label displayed in the UI
+  pub display_name: String,
+  /// Tooltip text describing the attribute
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  /// Interpolate the value between the parent of the nearest node and the nearest node, according to the fraction of
+  /// mutations on the branch leading to the nearest node which are present in the query
+  #[serde(default)]
+  pub interpolate: bool,
   #[serde(flatten)]
   pub other: serde_json::Value,
 }
@@ -430,20 +473,36 @@ impl AuspiceRefNodeCriterion {
 }
 
 /// Conditions for matching a tree node by name, clade, or clade-like attributes.
+///
+/// Values are matched by exact string, glob or regex pattern (see `NodeValueMatcher`).
 #[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AuspiceNodeCriterion {
   /// Node names to match (at least one must match)
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
-  pub name: Vec<String>,
+  pub name: Vec<NodeValueMatcher>,
 
   /// Clade values to match (at least one must match)
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
-  pub clade: Vec<String>,
+  pub clade: Vec<NodeValueMatcher>,
 
   /// Clade-like attribute values to match, keyed by attribute name. Each key requires at least one value match.
   #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
-  pub clade_node_attrs: BTreeMap<String, Vec<String>>,
+  pub clade_node_attrs: BTreeMap<String, Vec<NodeValueMatcher>>,
+
+  /// Clade-like attribute lineages to match, keyed by attribute name. A value matches if it is one of the given
+  /// lineages or their descendant, according to the lineage hierarchy of the attribute. Each key requires a match.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub descendant_of: BTreeMap<String, Vec<String>>,
+
+  /// Names of tree nodes. A node matches if it is one of these nodes or their descendant in the tree.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub descendant_of_node: Vec<String>,
+
+  /// Negated conditions: a node does not match if it matches any of these. If no other conditions are given, every
+  /// node which does not match any of these matches.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub not: Vec<AuspiceNodeCriterion>,
 
   #[serde(flatten)]
   pub other: serde_json::Value,
@@ -561,6 +620,68 @@ impl AuspiceRefNodesDesc {
   }
 }
 
+/// Dataset-level adjustments of placement priors of reference tree nodes. Priors are used to break ties between
+/// nodes at equal distance from a query sequence during phylogenetic placement.
+///
+/// Adjustments are in log10 scale and are added to the `placement_prior` node attribute (or to its default value, if
+/// the attribute is not present).
+#[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema, Validate, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementPriorsDesc {
+  /// Weights of nodes matching given conditions. Weights of all matching rules are added up.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub rules: Vec<PlacementPriorRule>,
+
+  /// Weight of nodes depending on their date
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub recency: Option<PlacementPriorRecency>,
+
+  #[serde(flatten)]
+  pub other: serde_json::Value,
+}
+
+impl PlacementPriorsDesc {
+  pub fn is_empty(&self) -> bool {
+    self == &Self::default()
+  }
+}
+
+/// Placement prior weight of nodes matching given conditions (name, clade, clade-like attributes)
+#[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementPriorRule {
+  /// Node matching conditions
+  pub node: AuspiceNodeCriterion,
+
+  /// Value added to the log10 placement prior of matching nodes. Negative values down-weight the nodes.
+  pub log_weight: OrderedFloat<f64>,
+
+  #[serde(flatten)]
+  pub other: serde_json::Value,
+}
+
+/// Placement prior weight of nodes depending on how old they are, compared to the most recent node of the tree
+#[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementPriorRecency {
+  /// Name of the numeric node attribute containing the date of the node, in decimal years
+  #[serde(default = "PlacementPriorRecency::default_attribute")]
+  pub attribute: String,
+
+  /// Value added to the log10 placement prior of a node per year of difference between the date of the most recent
+  /// node and the date of this node. Negative values down-weight older nodes. Nodes without a date are not adjusted.
+  pub log_weight_per_year: OrderedFloat<f64>,
+
+  #[serde(flatten)]
+  pub other: serde_json::Value,
+}
+
+impl PlacementPriorRecency {
+  fn default_attribute() -> String {
+    "num_date".to_owned()
+  }
+}
+
 /// Nextclade extensions to Auspice JSON format.
 /// See also: https://github.com/nextstrain/augur/blob/master/augur/data/schema-export-v2.json
 #[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize, schemars::JsonSchema, Validate, Debug)]
@@ -569,10 +690,18 @@ pub struct AuspiceMetaExtensionsNextclade {
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub clade_node_attrs: Vec<CladeNodeAttrKeyDesc>,
 
+  /// Descriptions of numeric node attributes present on tree nodes, to be transferred to query sequences
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub numeric_node_attrs: Vec<NumericNodeAttrDesc>,
+
   /// Genomic ranges excluded from phylogenetic placement scoring
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub placement_mask_ranges: Vec<NucRefGlobalRange>,
 
+  /// Adjustments of placement priors of reference tree nodes
+  #[serde(default, skip_serializing_if = "PlacementPriorsDesc::is_empty")]
+  pub placement_priors: PlacementPriorsDesc,
+
   /// Configuration for the "Relative to" reference node search feature
   #[serde(default, skip_serializing_if = "AuspiceRefNodesDesc::is_empty")]
   pub ref_nodes: AuspiceRefNodesDesc,
@@ -782,12 +911,22 @@ impl AuspiceTreeMeta {
     self.extensions_nextclade().placement_mask_ranges.as_slice()
   }
 
+  /// Extract placement prior adjustments
+  pub const fn placement_priors(&self) -> &PlacementPriorsDesc {
+    &self.extensions_nextclade().placement_priors
+  }
+
   /// Extract a list of descriptions of clade-like node attributes.
   /// These tell what additional entries to expect in node attributes (`node_attr`) of nodes.
   pub const fn clade_node_attr_descs(&self) -> &[CladeNodeAttrKeyDesc] {
     self.extensions_nextclade().clade_node_attrs.as_slice()
   }
 
+  /// Extract a list of descriptions of numeric node attributes
+  pub const fn numeric_node_attr_descs(&self) -> &[NumericNodeAttrDesc] {
+    self.extensions_nextclade().numeric_node_attrs.as_slice()
+  }
+
   pub const fn reference_nodes(&self) -> &AuspiceRefNodesDesc {
     &self.extensions_nextclade().ref_nodes
   }
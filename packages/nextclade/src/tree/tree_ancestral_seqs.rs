@@ -0,0 +1,152 @@
+use crate::alphabet::nuc::Nuc;
+use crate::coord::position::PositionLike;
+use crate::graph::node::{GraphNodeKey, Node};
+use crate::tree::tree::{AuspiceGraph, AuspiceGraphNodePayload};
+use crate::types::outputs::NextcladeOutputs;
+use clap::ValueEnum;
+use eyre::Report;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Which nodes of the reference tree to reconstruct ancestral sequences for
+#[derive(ValueEnum, Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum AncestralSeqsNodes {
+  /// All nodes of the reference tree, including leaves
+  All,
+  /// Nodes where the clade changes compared to the parent node, and the root
+  CladeFounders,
+  /// Nearest nodes and clade founder nodes of the query sequences. This is the default.
+  #[default]
+  AttachmentPoints,
+}
+
+/// Reconstruct the nucleotide sequence of a reference tree node, by applying the mutations accumulated on the path from
+/// the root to the node to the reference sequence. Requires the graph to be preprocessed.
+pub fn graph_node_nuc_seq(node: &AuspiceGraphNodePayload, ref_seq: &[Nuc]) -> Vec<Nuc> {
+  let mut seq = ref_seq.to_vec();
+  for (pos, nuc) in &node.tmp.mutations {
+    if let Some(letter) = seq.get_mut(pos.as_usize()) {
+      *letter = *nuc;
+    }
+  }
+  seq
+}
+
+/// Find keys of the reference tree nodes to reconstruct ancestral sequences for, in the order of the nodes in the tree
+pub fn graph_find_ancestral_seq_nodes(
+  graph: &AuspiceGraph,
+  selection: AncestralSeqsNodes,
+  outputs: &[NextcladeOutputs],
+) -> Result<Vec<GraphNodeKey>, Report> {
+  let keys = match selection {
+    AncestralSeqsNodes::All => graph.iter_nodes().map(Node::key).collect_vec(),
+    AncestralSeqsNodes::CladeFounders => graph
+      .iter_nodes()
+      .filter(|node| {
+        let parent_clade = graph.parent_of(node).map(|parent| parent.payload().clade());
+        parent_clade.is_none_or(|parent_clade| parent_clade != node.payload().clade())
+      })
+      .map(Node::key)
+      .collect_vec(),
+    AncestralSeqsNodes::AttachmentPoints => outputs
+      .iter()
+      .flat_map(|output| {
+        let founder_key = output.clade_founder_info.as_ref().map(|founder| founder.node_key);
+        [Some(output.nearest_node_id), founder_key]
+      })
+      .flatten()
+      .collect::<BTreeSet<_>>()
+      .into_iter()
+      .collect_vec(),
+  };
+
+  // Make sure that all the keys refer to the existing nodes
+  keys.iter().try_for_each(|key| graph.get_node(*key).map(|_| ()))?;
+  Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::{from_nuc_seq, to_nuc_seq};
+  use crate::analyze::find_clade_founder::CladeNodeAttrFounderInfo;
+  use crate::analyze::find_private_nuc_mutations::PrivateNucMutations;
+  use crate::o;
+  use crate::translate::translate_genes::Translation;
+  use crate::tree::test_utils::{make_test_graph, test_node, test_node_key};
+  use crate::tree::tree::AuspiceGraphMeta;
+  use crate::tree::tree_preprocess::graph_preprocess_in_place;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+  use std::collections::BTreeMap;
+
+  fn make_graph(ref_seq: &[Nuc]) -> Result<AuspiceGraph, Report> {
+    let mut graph = make_test_graph(
+      AuspiceGraphMeta::default(),
+      vec![
+        (test_node("root", Some("A"), &[]), None),
+        (test_node("inner", Some("B"), &["A1G", "C3-"]), Some(0)),
+        (test_node("leaf1", Some("B"), &["G1T"]), Some(1)),
+        (test_node("leaf2", Some("B"), &["G1A"]), Some(1)),
+      ],
+    )?;
+    graph_preprocess_in_place(&mut graph, ref_seq, &Translation::default())?;
+    Ok(graph)
+  }
+
+  fn node_names(graph: &AuspiceGraph, keys: &[GraphNodeKey]) -> Result<Vec<String>, Report> {
+    keys
+      .iter()
+      .map(|key| Ok(graph.get_node(*key)?.payload().name.clone()))
+      .collect()
+  }
+
+  #[rstest]
+  fn reconstructs_node_sequences() -> Result<(), Report> {
+    let ref_seq = to_nuc_seq("ACCT")?;
+    let graph = make_graph(&ref_seq)?;
+    let seqs = graph
+      .iter_node_payloads()
+      .map(|node| (node.name.clone(), from_nuc_seq(&graph_node_nuc_seq(node, &ref_seq))))
+      .collect_vec();
+    assert_eq!(
+      seqs,
+      vec![
+        ("root".to_owned(), "ACCT".to_owned()),
+        ("inner".to_owned(), "GC-T".to_owned()),
+        ("leaf1".to_owned(), "TC-T".to_owned()),
+        ("leaf2".to_owned(), "AC-T".to_owned()),
+      ]
+    );
+    Ok(())
+  }
+
+  #[rstest]
+  #[case(AncestralSeqsNodes::All, &["root", "inner", "leaf1", "leaf2"])]
+  #[case(AncestralSeqsNodes::CladeFounders, &["root", "inner"])]
+  #[case(AncestralSeqsNodes::AttachmentPoints, &["inner", "leaf2"])]
+  fn selects_nodes(#[case] selection: AncestralSeqsNodes, #[case] expected: &[&str]) -> Result<(), Report> {
+    let graph = make_graph(&to_nuc_seq("ACCT")?)?;
+    let key = |name: &str| test_node_key(&graph, name);
+    let output = |nearest: &str| NextcladeOutputs {
+      nearest_node_id: key(nearest).unwrap(),
+      clade_founder_info: Some(CladeNodeAttrFounderInfo {
+        key: o!("clade"),
+        value: o!("B"),
+        node_key: key("inner").unwrap(),
+        node_name: o!("inner"),
+        nuc_mutations: PrivateNucMutations::default(),
+        aa_mutations: BTreeMap::new(),
+      }),
+      ..NextcladeOutputs::default()
+    };
+    let outputs = [output("leaf2"), output("leaf2"), output("inner")];
+
+    let keys = graph_find_ancestral_seq_nodes(&graph, selection, &outputs)?;
+
+    assert_eq!(node_names(&graph, &keys)?, expected);
+    Ok(())
+  }
+}
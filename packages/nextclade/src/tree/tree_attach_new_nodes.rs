@@ -12,6 +12,19 @@ use crate::types::outputs::NextcladeOutputs;
 use itertools::{Itertools, chain};
 use serde_json::json;
 
+/// Sample metadata columns which have dedicated node attributes in Auspice
+pub const METADATA_GEO_NODE_ATTRS: &[&str] = &["region", "country", "division"];
+
+/// Name of the node attribute holding the value of a sample metadata column. Prefixed, such that metadata cannot
+/// collide with the attributes set by Nextclade or with the attributes of the reference tree.
+pub fn metadata_node_attr_key(column: &str) -> String {
+  if METADATA_GEO_NODE_ATTRS.contains(&column) {
+    column.to_owned()
+  } else {
+    format!("metadata_{column}")
+  }
+}
+
 pub fn create_new_auspice_node(
   result: &NextcladeOutputs,
   new_private_mutations: &BranchMutations,
@@ -43,6 +56,12 @@ pub fn create_new_auspice_node(
     .map(|(key, val)| (key, json!({ "value": val })))
     .collect_vec();
 
+  let numeric_node_attributes_json = result
+    .numeric_node_attributes
+    .iter()
+    .map(|(key, val)| (key.clone(), json!({ "value": val })))
+    .collect_vec();
+
   let phenotype_values_json = result.phenotype_values.as_ref().map_or(vec![], |phenotype_values| {
     phenotype_values
       .iter()
@@ -50,7 +69,22 @@ pub fn create_new_auspice_node(
       .collect_vec()
   });
 
-  let other: serde_json::Value = chain!(phenotype_values_json, custom_node_attributes_json).collect();
+  // Geographic columns have dedicated fields and the rest go into the other attributes
+  let metadata_attr = |key: &str| result.metadata.get(key).map(|val| TreeNodeAttr::new(val));
+  let metadata_json = result
+    .metadata
+    .iter()
+    .filter(|(key, _)| !METADATA_GEO_NODE_ATTRS.contains(&key.as_str()))
+    .map(|(key, val)| (metadata_node_attr_key(key), json!({ "value": val })))
+    .collect_vec();
+
+  let other: serde_json::Value = chain!(
+    metadata_json,
+    phenotype_values_json,
+    custom_node_attributes_json,
+    numeric_node_attributes_json
+  )
+  .collect();
 
   AuspiceGraphNodePayload {
     name: result.seq_name.clone(),
@@ -69,9 +103,9 @@ pub fn create_new_auspice_node(
       div: Some(new_divergence),
       clade_membership: result.clade.as_ref().map(|clade| TreeNodeAttr::new(clade)),
       node_type: Some(TreeNodeAttr::new("New")),
-      region: None,
-      country: None,
-      division: None,
+      region: metadata_attr("region"),
+      country: metadata_attr("country"),
+      division: metadata_attr("division"),
       placement_prior: None,
       alignment: Some(TreeNodeAttr::new(&alignment)),
       missing: Some(TreeNodeAttr::new(&format_missings(&result.missing, ", "))),
@@ -24,6 +24,7 @@ pub fn graph_attach_new_nodes_in_place(
   graph: &mut AuspiceGraph,
   mut results: Vec<NextcladeOutputs>,
   ref_seq_len: usize,
+  metadata_keys: &[String],
   params: &TreeBuilderParams,
 ) -> Result<(), Report> {
   // Add sequences with less private mutations first to avoid un-treelike behavior in the graph.
@@ -44,7 +45,7 @@ pub fn graph_attach_new_nodes_in_place(
   graph.ladderize().wrap_err("When ladderizing the resulting tree")?;
 
   let has_pcr_primers = results.iter().any(|result| !result.pcr_primer_changes.is_empty());
-  add_auspice_metadata_in_place(&mut graph.data.meta, has_pcr_primers);
+  add_auspice_metadata_in_place(&mut graph.data.meta, has_pcr_primers, metadata_keys);
 
   Ok(())
 }
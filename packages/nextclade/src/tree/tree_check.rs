@@ -0,0 +1,305 @@
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::graph::node::GraphNodeKey;
+use crate::tree::tree::AuspiceGraph;
+use eyre::Report;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// Kind of inconsistency found in the reference tree
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TreeCheckIssueKind {
+  /// Branch mutation cannot be parsed
+  InvalidMutation,
+  /// Branch mutation is at a position outside of the reference sequence
+  MutationOutsideReference,
+  /// Origin state of a branch mutation disagrees with the state of the parent node
+  MutationParentStateMismatch,
+  /// Branch has more than one mutation at the same position
+  DuplicateMutationPosition,
+  /// Node has no `clade_membership` attribute
+  MissingClade,
+  /// Nodes with the same clade do not descend from a single node of that clade
+  NonMonophyleticClade,
+  /// Several nodes have the same name
+  DuplicateNodeName,
+  /// Node name is the same as the name of a query sequence
+  QueryNameCollision,
+}
+
+/// Inconsistency found in the reference tree
+#[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeCheckIssue {
+  pub kind: TreeCheckIssueKind,
+  /// Name of the node where the issue is found
+  pub node_name: String,
+  pub message: String,
+}
+
+/// Summary statistics of the reference tree
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeCheckStats {
+  pub num_nodes: usize,
+  pub num_leaves: usize,
+  /// Number of edges on the longest path from the root to a leaf
+  pub max_depth: usize,
+  /// Number of nucleotide mutations on all branches
+  pub num_nuc_mutations: usize,
+  /// Number of nodes for each clade
+  pub clades: BTreeMap<String, usize>,
+  pub max_divergence: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeCheckReport {
+  pub issues: Vec<TreeCheckIssue>,
+  pub stats: TreeCheckStats,
+}
+
+/// Check the reference tree for inconsistencies with itself, with the reference sequence and with the names of the
+/// query sequences, and collect summary statistics.
+///
+/// Unlike the preprocessing done before placement, all the issues are reported rather than only the first one. The
+/// graph does not need to be preprocessed.
+pub fn graph_check(graph: &AuspiceGraph, ref_seq: &[Nuc], query_names: &[String]) -> Result<TreeCheckReport, Report> {
+  let mut report = TreeCheckReport::default();
+
+  let root_key = graph.get_exactly_one_root()?.key();
+  check_mutations_recursive(graph, root_key, &BTreeMap::new(), ref_seq, 0, &mut report)?;
+
+  check_clades(graph, &mut report);
+  check_names(graph, query_names, &mut report);
+
+  let stats = &mut report.stats;
+  stats.num_nodes = graph.iter_nodes().count();
+  stats.num_leaves = graph.iter_leaves().count();
+  stats.max_divergence = graph
+    .iter_node_payloads()
+    .filter_map(|node| node.node_attrs.div)
+    .fold(0.0, f64::max);
+
+  Ok(report)
+}
+
+/// Follow nucleotide mutations from the root, keeping track of the state of each node relative to the reference
+fn check_mutations_recursive(
+  graph: &AuspiceGraph,
+  node_key: GraphNodeKey,
+  parent_muts: &BTreeMap<NucRefGlobalPosition, Nuc>,
+  ref_seq: &[Nuc],
+  depth: usize,
+  report: &mut TreeCheckReport,
+) -> Result<(), Report> {
+  let node = graph.get_node(node_key)?.payload();
+  let mut muts = parent_muts.clone();
+  let mut positions = BTreeSet::new();
+
+  let mut add_issue = |kind: TreeCheckIssueKind, message: String| {
+    report.issues.push(TreeCheckIssue {
+      kind,
+      node_name: node.name.clone(),
+      message,
+    });
+  };
+
+  for mutation_str in node.branch_attrs.mutations.get("nuc").into_iter().flatten() {
+    let Ok(mutation) = NucSub::from_str(mutation_str) else {
+      add_issue(
+        TreeCheckIssueKind::InvalidMutation,
+        format!("Unable to parse nucleotide mutation '{mutation_str}'"),
+      );
+      continue;
+    };
+
+    let Some(&ref_nuc) = ref_seq.get(mutation.pos.as_usize()) else {
+      add_issue(
+        TreeCheckIssueKind::MutationOutsideReference,
+        format!(
+          "Mutation '{mutation_str}' is at position {}, which is outside of the reference sequence of length {}",
+          mutation.pos.as_usize() + 1,
+          ref_seq.len()
+        ),
+      );
+      continue;
+    };
+
+    if !positions.insert(mutation.pos) {
+      add_issue(
+        TreeCheckIssueKind::DuplicateMutationPosition,
+        format!("Mutation '{mutation_str}' is at the same position as another mutation on the same branch"),
+      );
+    }
+
+    let parent_nuc = muts.get(&mutation.pos).copied().unwrap_or(ref_nuc);
+    if mutation.ref_nuc != parent_nuc {
+      add_issue(
+        TreeCheckIssueKind::MutationParentStateMismatch,
+        format!(
+          "Origin state of mutation '{mutation_str}' is '{}', but the parent node has '{}' at this position",
+          mutation.ref_nuc, parent_nuc
+        ),
+      );
+    }
+
+    if mutation.qry_nuc == ref_nuc {
+      muts.remove(&mutation.pos);
+    } else {
+      muts.insert(mutation.pos, mutation.qry_nuc);
+    }
+    report.stats.num_nuc_mutations += 1;
+  }
+
+  report.stats.max_depth = report.stats.max_depth.max(depth);
+
+  for child_key in graph.iter_child_keys_of_by_key(node_key) {
+    check_mutations_recursive(graph, child_key, &muts, ref_seq, depth + 1, report)?;
+  }
+
+  Ok(())
+}
+
+/// Every node should have a clade, and all nodes of a clade should descend from a single founder node of that clade
+fn check_clades(graph: &AuspiceGraph, report: &mut TreeCheckReport) {
+  let mut founders = BTreeMap::<String, Vec<String>>::new();
+
+  for node in graph.iter_nodes() {
+    let payload = node.payload();
+    let Some(clade) = payload.clade() else {
+      report.issues.push(TreeCheckIssue {
+        kind: TreeCheckIssueKind::MissingClade,
+        node_name: payload.name.clone(),
+        message: "Node has no 'clade_membership' attribute".to_owned(),
+      });
+      continue;
+    };
+
+    *report.stats.clades.entry(clade.clone()).or_default() += 1;
+
+    let parent_clade = graph.parent_of(node).and_then(|parent| parent.payload().clade());
+    if parent_clade.as_ref() != Some(&clade) {
+      founders.entry(clade).or_default().push(payload.name.clone());
+    }
+  }
+
+  for (clade, founder_names) in founders {
+    if let [first, rest @ ..] = founder_names.as_slice()
+      && !rest.is_empty()
+    {
+      report.issues.push(TreeCheckIssue {
+        kind: TreeCheckIssueKind::NonMonophyleticClade,
+        node_name: first.clone(),
+        message: format!(
+          "Clade '{clade}' emerges {} times in the tree, at nodes: {}",
+          founder_names.len(),
+          founder_names.iter().map(|name| format!("'{name}'")).join(", ")
+        ),
+      });
+    }
+  }
+}
+
+/// Node names should be unique and should not collide with the names of the query sequences
+fn check_names(graph: &AuspiceGraph, query_names: &[String], report: &mut TreeCheckReport) {
+  let name_counts = graph.iter_node_payloads().map(|node| node.name.as_str()).counts();
+
+  for (name, count) in name_counts.iter().sorted() {
+    if *count > 1 {
+      report.issues.push(TreeCheckIssue {
+        kind: TreeCheckIssueKind::DuplicateNodeName,
+        node_name: (*name).to_owned(),
+        message: format!("Node name is used by {count} nodes"),
+      });
+    }
+  }
+
+  for name in query_names.iter().unique() {
+    if name_counts.contains_key(name.as_str()) {
+      report.issues.push(TreeCheckIssue {
+        kind: TreeCheckIssueKind::QueryNameCollision,
+        node_name: name.clone(),
+        message: "Node name is the same as the name of a query sequence".to_owned(),
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::alphabet::nuc::to_nuc_seq;
+  use crate::o;
+  use crate::tree::test_utils::{make_test_graph, test_node as node};
+  use crate::tree::tree::{AuspiceGraphMeta, AuspiceGraphNodePayload};
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn make_graph(nodes: Vec<(AuspiceGraphNodePayload, Option<usize>)>) -> Result<AuspiceGraph, Report> {
+    make_test_graph(AuspiceGraphMeta::default(), nodes)
+  }
+
+  fn issue_kinds(report: &TreeCheckReport) -> Vec<(TreeCheckIssueKind, String)> {
+    report
+      .issues
+      .iter()
+      .map(|issue| (issue.kind, issue.node_name.clone()))
+      .collect()
+  }
+
+  #[rstest]
+  fn accepts_consistent_tree() -> Result<(), Report> {
+    let graph = make_graph(vec![
+      (node("root", Some("A"), &[]), None),
+      (node("b", Some("B"), &["A1G"]), Some(0)),
+      (node("b1", Some("B"), &["G1T", "C2-"]), Some(1)),
+      (node("a1", Some("A"), &["A1C"]), Some(0)),
+    ])?;
+
+    let report = graph_check(&graph, &to_nuc_seq("ACGT")?, &[o!("query")])?;
+
+    assert_eq!(issue_kinds(&report), vec![]);
+    assert_eq!(report.stats.num_nodes, 4);
+    assert_eq!(report.stats.num_leaves, 2);
+    assert_eq!(report.stats.max_depth, 2);
+    assert_eq!(report.stats.num_nuc_mutations, 4);
+    assert_eq!(report.stats.clades, BTreeMap::from([(o!("A"), 2), (o!("B"), 2)]));
+    Ok(())
+  }
+
+  #[rstest]
+  fn reports_all_issues() -> Result<(), Report> {
+    let graph = make_graph(vec![
+      (node("root", Some("A"), &["foo"]), None),
+      (node("b", Some("B"), &["A1G", "C9T"]), Some(0)),
+      (node("b1", Some("B"), &["A1T", "C2T", "C2G"]), Some(1)),
+      (node("b", None, &[]), Some(0)),
+      (node("c", Some("A"), &[]), Some(1)),
+      (node("c1", Some("B"), &[]), Some(4)),
+    ])?;
+
+    let report = graph_check(&graph, &to_nuc_seq("ACGT")?, &[o!("c1"), o!("d")])?;
+
+    assert_eq!(
+      issue_kinds(&report),
+      vec![
+        (TreeCheckIssueKind::InvalidMutation, o!("root")),
+        (TreeCheckIssueKind::MutationOutsideReference, o!("b")),
+        (TreeCheckIssueKind::MutationParentStateMismatch, o!("b1")),
+        (TreeCheckIssueKind::DuplicateMutationPosition, o!("b1")),
+        (TreeCheckIssueKind::MutationParentStateMismatch, o!("b1")),
+        (TreeCheckIssueKind::MissingClade, o!("b")),
+        (TreeCheckIssueKind::NonMonophyleticClade, o!("root")),
+        (TreeCheckIssueKind::NonMonophyleticClade, o!("b")),
+        (TreeCheckIssueKind::DuplicateNodeName, o!("b")),
+        (TreeCheckIssueKind::QueryNameCollision, o!("c1")),
+      ]
+    );
+    Ok(())
+  }
+}
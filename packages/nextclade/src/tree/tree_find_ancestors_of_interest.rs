@@ -1,9 +1,10 @@
 use crate::graph::node::{GraphNodeKey, Node};
 use crate::graph::search::{graph_find_backwards_first, graph_find_backwards_last};
 use crate::io::json::{JsonPretty, json_stringify};
+use crate::tree::node_value_matcher::NodeValueMatcher;
 use crate::tree::tree::{
   AuspiceGraph, AuspiceGraphNodePayload, AuspiceNodeCriterion, AuspiceNodeSearchAlgo, AuspiceRefNodeCriterion,
-  AuspiceRefNodeSearchCriteria, AuspiceRefNodeSearchDesc, AuspiceRefNodesDesc,
+  AuspiceRefNodeSearchCriteria, AuspiceRefNodeSearchDesc, AuspiceRefNodesDesc, CladeNodeAttrKeyDesc,
 };
 use crate::utils::string::{Indent, format_list};
 use eyre::Report;
@@ -53,6 +54,12 @@ pub struct AncestralSearchMatch {
   pub clade: Option<String>,
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub clade_like_attrs: Option<Vec<AttrPair>>,
+  /// Clade-like attributes matched by the "descendant of" criterion, with the matched ancestral lineage as the value
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub descendant_of: Option<Vec<AttrPair>>,
+  /// Name of the node matched by the "descendant of node" criterion: the node itself or its ancestor
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub descendant_of_node: Option<String>,
 }
 
 /// For a given query sample, find ancestral nodes of interest, as specified by the config
@@ -61,6 +68,7 @@ pub fn graph_find_ancestors_of_interest(
   nearest_node_key: GraphNodeKey,
   ancestral_search_descs: &AuspiceRefNodesDesc,
 ) -> Result<Vec<AncestralSearchResult>, Report> {
+  let attr_descs = graph.data.meta.clade_node_attr_descs();
   ancestral_search_descs
     .search
     .iter()
@@ -76,7 +84,8 @@ pub fn graph_find_ancestors_of_interest(
             || criterion
               .qry
               .iter()
-              .any(|criteria_qry| is_qry_match(qry_node, criteria_qry));
+              .map(|criteria_qry| is_qry_match(graph, qry_node, criteria_qry, attr_descs))
+              .process_results(|mut matches| matches.any(|is_match| is_match))?;
 
           if !qry_is_ok {
             return Ok(AncestralSearchResultForCriteria {
@@ -88,7 +97,7 @@ pub fn graph_find_ancestors_of_interest(
           let results = criterion
             .node
             .iter()
-            .filter_map(|criteria_node| find_node(graph, nearest_node_key, criteria_node).transpose())
+            .filter_map(|criteria_node| find_node(graph, nearest_node_key, criteria_node, attr_descs).transpose())
             .collect::<Result<Vec<AncestralSearchMatch>, Report>>()?;
 
           if results.len() > 1 {
@@ -155,75 +164,123 @@ fn find_node(
   graph: &AuspiceGraph,
   nearest_node_key: GraphNodeKey,
   ref_criterion: &AuspiceRefNodeCriterion,
+  attr_descs: &[CladeNodeAttrKeyDesc],
 ) -> Result<Option<AncestralSearchMatch>, Report> {
   let criterion = &ref_criterion.criterion;
+  let matches =
+    |node: &Node<AuspiceGraphNodePayload>| node_matches(graph, node, criterion, attr_descs, true).transpose();
   match ref_criterion.search_algo {
-    AuspiceNodeSearchAlgo::AncestorNearest => {
-      graph_find_backwards_first(graph, nearest_node_key, |node| node_matches(node, criterion))
-    }
-    AuspiceNodeSearchAlgo::AncestorEarliest => {
-      graph_find_backwards_last(graph, nearest_node_key, |node| node_matches(node, criterion))
-    }
-    AuspiceNodeSearchAlgo::Full => Ok(graph.iter_nodes().find_map(|node| node_matches(node, criterion))),
+    AuspiceNodeSearchAlgo::AncestorNearest => graph_find_backwards_first(graph, nearest_node_key, matches)?.transpose(),
+    AuspiceNodeSearchAlgo::AncestorEarliest => graph_find_backwards_last(graph, nearest_node_key, matches)?.transpose(),
+    AuspiceNodeSearchAlgo::Full => graph.iter_nodes().find_map(matches).transpose(),
   }
 }
 
-fn is_qry_match(node: &Node<AuspiceGraphNodePayload>, criteria: &AuspiceNodeCriterion) -> bool {
-  let node = node.payload();
-  [
-    find_matching_clade(node.clade().as_ref(), &criteria.clade).is_some(),
-    find_matching_clade_like_attrs(node, &criteria.clade_node_attrs).is_some(),
-  ]
-  .iter()
-  .any(|c| *c)
+fn is_qry_match(
+  graph: &AuspiceGraph,
+  node: &Node<AuspiceGraphNodePayload>,
+  criteria: &AuspiceNodeCriterion,
+  attr_descs: &[CladeNodeAttrKeyDesc],
+) -> Result<bool, Report> {
+  // Names are not considered for the query
+  Ok(node_matches(graph, node, criteria, attr_descs, false)?.is_some())
 }
 
-fn node_matches(node: &Node<AuspiceGraphNodePayload>, criteria: &AuspiceNodeCriterion) -> Option<AncestralSearchMatch> {
+/// Checks whether a tree node matches the given conditions, including node names
+pub fn is_node_match(
+  graph: &AuspiceGraph,
+  node: &Node<AuspiceGraphNodePayload>,
+  criteria: &AuspiceNodeCriterion,
+  attr_descs: &[CladeNodeAttrKeyDesc],
+) -> Result<bool, Report> {
+  Ok(node_matches(graph, node, criteria, attr_descs, true)?.is_some())
+}
+
+fn node_matches(
+  graph: &AuspiceGraph,
+  node: &Node<AuspiceGraphNodePayload>,
+  criteria: &AuspiceNodeCriterion,
+  attr_descs: &[CladeNodeAttrKeyDesc],
+  match_names: bool,
+) -> Result<Option<AncestralSearchMatch>, Report> {
   let node_key = node.key();
-  let node = node.payload();
-  let node_name = node.name.clone();
+  let payload = node.payload();
+  let node_name = payload.name.clone();
 
-  // In order to be "found", the ancestral node need to fulfill ONE OR MORE of the following requirements:
+  // In order to be "found", the node need to fulfill ONE OR MORE of the following requirements:
 
   // 1. Name of the candidate node matches at least ONE OF criteria names
-  let name = criteria.name.iter().find(|&name| name == &node_name).cloned();
+  let name = if match_names {
+    find_matching_value(Some(&node_name), &criteria.name)
+  } else {
+    None
+  };
 
   // 2. Candidate clade matches at least ONE OF the clades in the criteria
-  let clade = find_matching_clade(node.clade().as_ref(), &criteria.clade);
+  let clade = find_matching_value(payload.clade().as_ref(), &criteria.clade);
 
   // 3. ALL clade-like attribute keys in the criteria are also defined in the candidate node,
   //    AND
   //    for each key, query value matches at least ONE OF the values in the criteria
-  let clade_like_attrs = find_matching_clade_like_attrs(node, &criteria.clade_node_attrs);
-
-  match (name, clade, clade_like_attrs) {
-    // Nothing matched
-    (None, None, None) => None,
-
-    // At least one matched
-    (name, clade, clade_like_attrs) => Some(AncestralSearchMatch {
-      node_key,
-      node_name,
-      name,
-      clade,
-      clade_like_attrs,
-    }),
+  let clade_like_attrs = find_matching_clade_like_attrs(payload, &criteria.clade_node_attrs);
+
+  // 4. ALL clade-like attribute keys in the "descendant of" criteria are also defined in the candidate node,
+  //    AND
+  //    for each key, candidate value is ONE OF the lineages in the criteria or their descendant
+  let descendant_of = find_matching_descendant_of(payload, &criteria.descendant_of, attr_descs);
+
+  // 5. Candidate node is ONE OF the nodes in the criteria or their descendant
+  let descendant_of_node = find_matching_node_ancestor(graph, node, &criteria.descendant_of_node)?;
+
+  let is_match = [
+    name.is_some(),
+    clade.is_some(),
+    clade_like_attrs.is_some(),
+    descendant_of.is_some(),
+    descendant_of_node.is_some(),
+  ]
+  .iter()
+  .any(|c| *c);
+
+  // If only negated requirements are given, then any node matches, unless negated
+  let has_only_negated = criteria.name.is_empty()
+    && criteria.clade.is_empty()
+    && criteria.clade_node_attrs.is_empty()
+    && criteria.descendant_of.is_empty()
+    && criteria.descendant_of_node.is_empty()
+    && !criteria.not.is_empty();
+
+  if !is_match && !has_only_negated {
+    return Ok(None);
   }
-}
 
-fn find_matching_clade(qry_clade: Option<&String>, anc_clades: &[String]) -> Option<String> {
-  if anc_clades.is_empty() {
-    return None;
+  // AND the candidate node should fulfill NONE OF the negated requirements
+  for negated in &criteria.not {
+    if node_matches(graph, node, negated, attr_descs, match_names)?.is_some() {
+      return Ok(None);
+    }
   }
 
-  qry_clade
-    .and_then(|clade| anc_clades.iter().find(|anc_clade| *anc_clade == clade))
+  Ok(Some(AncestralSearchMatch {
+    node_key,
+    node_name,
+    name,
+    clade,
+    clade_like_attrs,
+    descendant_of,
+    descendant_of_node,
+  }))
+}
+
+fn find_matching_value(qry_value: Option<&String>, matchers: &[NodeValueMatcher]) -> Option<String> {
+  qry_value
+    .filter(|value| matchers.iter().any(|matcher| matcher.is_match(value)))
     .map(String::to_owned)
 }
 
 fn find_matching_clade_like_attrs(
   node: &AuspiceGraphNodePayload,
-  anc_attrs: &BTreeMap<String, Vec<String>>,
+  anc_attrs: &BTreeMap<String, Vec<NodeValueMatcher>>,
 ) -> Option<Vec<AttrPair>> {
   // ALL given ancestral attribute keys must have...
   let result = anc_attrs
@@ -232,7 +289,7 @@ fn find_matching_clade_like_attrs(
       // ... at least ONE OF the values matching in query.
       node
         .get_clade_node_attr(key)
-        .and_then(|qry_val| anc_vals.iter().find(|anc_val| *anc_val == qry_val))
+        .filter(|qry_val| anc_vals.iter().any(|anc_val| anc_val.is_match(qry_val)))
         .map(|value| AttrPair {
           key: key.to_owned(),
           value: value.to_owned(),
@@ -247,3 +304,152 @@ fn find_matching_clade_like_attrs(
   // NOTE: Here we chose to treat any missing attributes as a complete mismatch
   (result.len() == anc_attrs.len()).then_some(result)
 }
+
+fn find_matching_descendant_of(
+  node: &AuspiceGraphNodePayload,
+  anc_lineages: &BTreeMap<String, Vec<String>>,
+  attr_descs: &[CladeNodeAttrKeyDesc],
+) -> Option<Vec<AttrPair>> {
+  // ALL given attribute keys must have...
+  let result = anc_lineages
+    .iter()
+    .filter_map(|(key, anc_vals)| {
+      let qry_val = node.get_clade_node_attr(key)?;
+      let hierarchy = attr_descs
+        .iter()
+        .find(|desc| &desc.name == key)
+        .and_then(|desc| desc.lineage_hierarchy.as_ref());
+
+      // ... the query value being at least ONE OF the lineages or their descendant.
+      // Without a lineage hierarchy, only the lineage itself matches.
+      anc_vals
+        .iter()
+        .find(|anc_val| match hierarchy {
+          Some(hierarchy) => hierarchy.is_descendant_of(qry_val, anc_val),
+          None => *anc_val == qry_val,
+        })
+        .map(|value| AttrPair {
+          key: key.to_owned(),
+          value: value.to_owned(),
+        })
+    })
+    .collect_vec();
+
+  if result.is_empty() {
+    return None;
+  }
+
+  (result.len() == anc_lineages.len()).then_some(result)
+}
+
+/// Find the name of the node itself or of its nearest ancestor, which is ONE OF the given node names
+fn find_matching_node_ancestor(
+  graph: &AuspiceGraph,
+  node: &Node<AuspiceGraphNodePayload>,
+  anc_names: &[String],
+) -> Result<Option<String>, Report> {
+  if anc_names.is_empty() {
+    return Ok(None);
+  }
+
+  let find_name =
+    |node: &Node<AuspiceGraphNodePayload>| anc_names.iter().find(|name| **name == node.payload().name).cloned();
+
+  match find_name(node) {
+    Some(name) => Ok(Some(name)),
+    None => graph_find_backwards_first(graph, node.key(), find_name),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::io::json::json_parse;
+  use crate::o;
+  use crate::tree::lineage_hierarchy::{LineageAliasTarget, LineageHierarchy};
+  use crate::tree::test_utils::{make_test_graph, test_node};
+  use crate::tree::tree::AuspiceGraphMeta;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn attr_descs() -> Vec<CladeNodeAttrKeyDesc> {
+    vec![CladeNodeAttrKeyDesc {
+      name: o!("Nextclade_pango"),
+      display_name: o!("Pango lineage"),
+      description: None,
+      hide_in_web: false,
+      skip_as_reference: false,
+      lineage_hierarchy: Some(LineageHierarchy {
+        aliases: BTreeMap::from([(o!("BA"), LineageAliasTarget::Single(o!("B.1.1.529")))]),
+        parents: BTreeMap::new(),
+      }),
+      other: serde_json::Value::default(),
+    }]
+  }
+
+  fn node_with_lineage(lineage: &str) -> AuspiceGraphNodePayload {
+    let mut node = AuspiceGraphNodePayload::default();
+    node.set_clade_node_attr("Nextclade_pango", lineage);
+    node
+  }
+
+  #[rstest]
+  #[case("BA.2.75", &["BA.2"], Some("BA.2"))]
+  #[case("B.1.1.529.2", &["BA.5", "BA.2"], Some("BA.2"))]
+  #[case("BA.5.1", &["BA.2"], None)]
+  fn matches_descendants(#[case] lineage: &str, #[case] ancestors: &[&str], #[case] expected: Option<&str>) {
+    let anc_lineages = BTreeMap::from([(o!("Nextclade_pango"), ancestors.iter().map(|a| o!(*a)).collect_vec())]);
+    let result = find_matching_descendant_of(&node_with_lineage(lineage), &anc_lineages, &attr_descs());
+    assert_eq!(
+      result.map(|pairs| pairs.into_iter().map(|pair| pair.value).collect_vec()),
+      expected.map(|value| vec![o!(value)])
+    );
+  }
+
+  //        root (A)
+  //        /     \
+  //    b (24A)   d (recombinant)
+  //      |
+  //    c (24B)
+  fn make_graph() -> Result<AuspiceGraph, Report> {
+    make_test_graph(
+      AuspiceGraphMeta::default(),
+      vec![
+        (test_node("root", Some("A"), &[]), None),
+        (test_node("b", Some("24A"), &[]), Some(0)),
+        (test_node("c", Some("24B"), &[]), Some(1)),
+        (test_node("d", Some("recombinant"), &[]), Some(0)),
+      ],
+    )
+  }
+
+  #[rstest]
+  #[case(r#"{ "clade": [{ "glob": "24*" }] }"#, &["b", "c"])]
+  #[case(r#"{ "clade": [{ "regex": "^(A|24B)$" }] }"#, &["root", "c"])]
+  #[case(r#"{ "name": ["d"], "clade": [{ "glob": "24?" }] }"#, &["b", "c", "d"])]
+  #[case(r#"{ "descendantOfNode": ["b"] }"#, &["b", "c"])]
+  #[case(r#"{ "not": [{ "clade": ["recombinant"] }] }"#, &["root", "b", "c"])]
+  #[case(r#"{ "descendantOfNode": ["root"], "not": [{ "name": ["c"] }, { "clade": ["A"] }] }"#, &["b", "d"])]
+  fn matches_nodes(#[case] criterion: &str, #[case] expected: &[&str]) -> Result<(), Report> {
+    let graph = make_graph()?;
+    let criterion: AuspiceNodeCriterion = json_parse(criterion)?;
+    let matched = graph
+      .iter_nodes()
+      .map(|node| Ok(node_matches(&graph, node, &criterion, &[], true)?.map(|m| m.node_name)))
+      .collect::<Result<Vec<Option<String>>, Report>>()?
+      .into_iter()
+      .flatten()
+      .collect_vec();
+    assert_eq!(matched, expected);
+    Ok(())
+  }
+
+  #[test]
+  fn rejects_malformed_pattern() {
+    let err = json_parse::<AuspiceNodeCriterion>(r#"{ "clade": [{ "regex": "(24" }] }"#).unwrap_err();
+    assert!(
+      format!("{err:#}").contains("Invalid regular expression '(24'"),
+      "{err:#}"
+    );
+  }
+}
@@ -61,16 +61,15 @@ pub fn graph_find_nearest_nodes(
   })
 }
 
-/// Gets non-log scale prior from node attributes
+/// Gets non-log scale prior from node attributes, adjusted by the dataset-level placement priors
 fn get_prior(node: &AuspiceGraphNodePayload) -> f64 {
-  10.0_f64.powf(
-    node
-      .node_attrs
-      .placement_prior
-      .as_ref()
-      // Hard coded -10.0 is small but not zero
-      .map_or(-10.0, |attr| attr.value),
-  )
+  let log_prior = node
+    .node_attrs
+    .placement_prior
+    .as_ref()
+    // Hard coded -10.0 is small but not zero
+    .map_or(-10.0, |attr| attr.value);
+  10.0_f64.powf(log_prior + node.tmp.placement_prior_adjustment)
 }
 
 /// Calculates distance metric between a given query sample and a tree node
@@ -138,12 +137,13 @@ mod tests {
   use std::collections::BTreeMap;
 
   use crate::alphabet::nuc::Nuc;
-  use crate::tree::tree::{TreeBranchAttrs, TreeNodeAttr, TreeNodeAttrs, TreeNodeTempData};
+  use crate::tree::tree::{TreeBranchAttrs, TreeNodeAttr, TreeNodeAttrF64, TreeNodeAttrs, TreeNodeTempData};
 
   use super::*;
   use crate::coord::position::NucRefGlobalPosition;
   use crate::coord::range::Range;
   use eyre::Report;
+  use ordered_float::OrderedFloat;
   use pretty_assertions::assert_eq;
   use rstest::{fixture, rstest};
 
@@ -481,4 +481,26 @@ mod tests {
 
     Ok(())
   }
+
+  #[rstest]
+  #[case(None, 0.0, -10.0)]
+  #[case(Some(-2.0), 0.0, -2.0)]
+  #[case(Some(-2.0), -3.0, -5.0)]
+  #[case(None, 4.0, -6.0)]
+  fn prior_includes_dataset_adjustment(
+    mut default_node: AuspiceGraphNodePayload,
+    #[case] placement_prior: Option<f64>,
+    #[case] adjustment: f64,
+    #[case] expected_log_prior: f64,
+  ) {
+    default_node.node_attrs.placement_prior = placement_prior.map(|value| TreeNodeAttrF64 {
+      value,
+      other: serde_json::Value::default(),
+    });
+    default_node.tmp.placement_prior_adjustment = adjustment;
+    assert_eq!(
+      OrderedFloat(get_prior(&default_node)),
+      OrderedFloat(10.0_f64.powf(expected_log_prior))
+    );
+  }
 }
@@ -0,0 +1,148 @@
+use crate::alphabet::letter::Letter;
+use crate::analyze::is_sequenced::is_nuc_sequenced;
+use crate::analyze::letter_ranges::NucRange;
+use crate::analyze::nuc_del::NucDelRange;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::range::NucRefGlobalRange;
+use crate::graph::node::{GraphNodeKey, Node};
+use crate::tree::tree::{AuspiceGraph, AuspiceGraphNodePayload, NumericNodeAttrDesc};
+use eyre::Report;
+use std::collections::BTreeMap;
+
+pub struct FindNumericNodeAttrsParams<'a> {
+  pub substitutions: &'a [NucSub],
+  pub deletions: &'a [NucDelRange],
+  pub missing: &'a [NucRange],
+  pub alignment_range: &'a NucRefGlobalRange,
+}
+
+/// Find values of numeric node attributes for the query, inherited from the nearest node.
+///
+/// For attributes with interpolation enabled, the value is interpolated between the parent of the nearest node and the
+/// nearest node itself, proportionally to the fraction of mutations on the branch leading to the nearest node, which
+/// are also present in the query. Mutations at positions not sequenced in the query are not taken into account.
+pub fn find_numeric_node_attrs(
+  graph: &AuspiceGraph,
+  nearest_node_id: GraphNodeKey,
+  numeric_node_attr_descs: &[NumericNodeAttrDesc],
+  params: &FindNumericNodeAttrsParams,
+) -> Result<BTreeMap<String, f64>, Report> {
+  let nearest_node = graph.get_node(nearest_node_id)?.payload();
+  let parent_node = graph.parent_of_by_key(nearest_node_id).map(Node::payload);
+
+  Ok(
+    numeric_node_attr_descs
+      .iter()
+      .filter_map(|desc| {
+        let value = nearest_node.get_numeric_node_attr(&desc.name)?;
+        let parent_value = parent_node.and_then(|parent| parent.get_numeric_node_attr(&desc.name));
+        let value = match parent_value {
+          Some(parent_value) if desc.interpolate => {
+            parent_value + (value - parent_value) * branch_fraction(nearest_node, params)
+          }
+          _ => value,
+        };
+        Some((desc.name.clone(), value))
+      })
+      .collect(),
+  )
+}
+
+/// Fraction of sequenced mutations on the branch leading to the node, which are also present in the query.
+/// Equals 1 if there are no such mutations.
+fn branch_fraction(node: &AuspiceGraphNodePayload, params: &FindNumericNodeAttrsParams) -> f64 {
+  let sequenced = node
+    .tmp
+    .private_mutations
+    .nuc_muts
+    .iter()
+    .filter(|sub| is_nuc_sequenced(sub.pos, params.missing, params.alignment_range))
+    .collect::<Vec<_>>();
+
+  if sequenced.is_empty() {
+    return 1.0;
+  }
+
+  let shared = sequenced
+    .iter()
+    .filter(|sub| {
+      if sub.qry_nuc.is_gap() {
+        params.deletions.iter().any(|del| del.range().contains(sub.pos))
+      } else {
+        params
+          .substitutions
+          .iter()
+          .any(|qry_sub| qry_sub.pos == sub.pos && qry_sub.qry_nuc == sub.qry_nuc)
+      }
+    })
+    .count();
+
+  shared as f64 / sequenced.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analyze::find_private_nuc_mutations::BranchMutations;
+  use crate::o;
+  use crate::tree::test_utils::{make_test_graph, test_node, test_node_key};
+  use crate::tree::tree::AuspiceGraphMeta;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+  use std::str::FromStr;
+
+  fn make_graph() -> Result<(AuspiceGraph, GraphNodeKey), Report> {
+    let mut parent = test_node("parent", None, &[]);
+    parent.set_numeric_node_attr("fitness", 1.0);
+
+    let mut nearest = test_node("nearest", None, &[]);
+    nearest.set_numeric_node_attr("fitness", 2.0);
+    nearest.tmp.private_mutations = BranchMutations {
+      nuc_muts: ["C101T", "G201A", "A301-", "T401C"]
+        .iter()
+        .map(|m| NucSub::from_str(m))
+        .collect::<Result<Vec<_>, Report>>()?,
+      aa_muts: BTreeMap::new(),
+    };
+
+    let graph = make_test_graph(AuspiceGraphMeta::default(), vec![(parent, None), (nearest, Some(0))])?;
+    let nearest = test_node_key(&graph, "nearest").unwrap();
+    Ok((graph, nearest))
+  }
+
+  fn desc(interpolate: bool) -> NumericNodeAttrDesc {
+    NumericNodeAttrDesc {
+      name: o!("fitness"),
+      display_name: o!("Fitness"),
+      description: None,
+      interpolate,
+      other: serde_json::Value::default(),
+    }
+  }
+
+  #[rstest]
+  // Query has "C101T" and deletion at 301; position 401 is not sequenced: 2 of 3 sequenced mutations are shared
+  #[case(true, 1.0 + 2.0 / 3.0)]
+  #[case(false, 2.0)]
+  fn finds_numeric_node_attrs(#[case] interpolate: bool, #[case] expected: f64) -> Result<(), Report> {
+    let (graph, nearest) = make_graph()?;
+    let params = FindNumericNodeAttrsParams {
+      substitutions: &[NucSub::from_str("C101T")?, NucSub::from_str("G201C")?],
+      deletions: &[NucDelRange::from_usize(299, 303)],
+      missing: &[],
+      alignment_range: &NucRefGlobalRange::from_usize(0, 350),
+    };
+
+    let result = find_numeric_node_attrs(&graph, nearest, &[desc(interpolate)], &params)?;
+
+    assert_eq!(
+      result
+        .into_iter()
+        .map(|(k, v)| (k, OrderedFloat(v)))
+        .collect::<Vec<_>>(),
+      vec![(o!("fitness"), OrderedFloat(expected))]
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,480 @@
+use crate::alphabet::nuc::Nuc;
+use crate::analyze::is_sequenced::is_nuc_sequenced;
+use crate::analyze::letter_ranges::NucRange;
+use crate::analyze::nuc_del::NucDelRange;
+use crate::analyze::nuc_sub::NucSub;
+use crate::coord::position::{NucRefGlobalPosition, PositionLike};
+use crate::coord::range::NucRefGlobalRange;
+use crate::qc::qc_config::QcRulesConfigRecombinants;
+use crate::tree::tree::{AuspiceGraph, AuspiceGraphNodePayload};
+use eyre::Report;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use traversal::DftPre;
+
+/// Genome region of a putative recombinant, attributed to the nearest reference tree node within that region
+#[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecombinationDonor {
+  /// Genome region (in reference coordinates) attributed to this donor
+  pub range: NucRefGlobalRange,
+  /// Name of the nearest reference tree node within the region
+  pub node_name: String,
+  /// Clade of the nearest reference tree node within the region
+  pub clade: Option<String>,
+  /// Distance between the query and the donor node, counting only sites within the region
+  pub distance: i64,
+}
+
+/// Candidate recombination breakpoint between two adjacent donor regions
+#[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecombinationBreakpoint {
+  /// Interval containing the first position of the right donor region. The breakpoint is only known up to the
+  /// nearest informative sites on either side, or wider, if several placements explain the sequence equally well.
+  pub range: NucRefGlobalRange,
+  /// Clade of the donor of the region to the left of the breakpoint
+  pub left_clade: Option<String>,
+  /// Clade of the donor of the region to the right of the breakpoint
+  pub right_clade: Option<String>,
+  /// Reduction in distance to the reference tree gained by splitting the genome at this breakpoint
+  pub improvement: i64,
+}
+
+/// Result of the search for recombination breakpoints.
+///
+/// The genome is split into regions, each attributed to its own nearest reference tree node (donor), such that the
+/// total distance between the query and the donors is smaller than the distance to the nearest node overall.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecombinationResult {
+  /// Candidate breakpoints, ordered by position. Empty if the sequence is not a putative recombinant.
+  pub breakpoints: Vec<RecombinationBreakpoint>,
+  /// Donor regions, ordered by position. Contains one more entry than `breakpoints`.
+  pub donors: Vec<RecombinationDonor>,
+  /// Distance to the nearest reference tree node, without splitting the genome
+  pub distance_without_recombination: i64,
+  /// Sum of distances to donor nodes over all donor regions
+  pub distance_with_recombination: i64,
+}
+
+/// For a given query sample, finds genome regions which are closer to different nodes of the reference tree
+pub fn graph_find_recombination(
+  graph: &AuspiceGraph,
+  qry_nuc_subs: &[NucSub],
+  qry_missing: &[NucRange],
+  qry_deletions: &[NucDelRange],
+  aln_range: &NucRefGlobalRange,
+  config: &QcRulesConfigRecombinants,
+) -> Result<RecombinationResult, Report> {
+  let masked_ranges = graph.data.meta.placement_mask_ranges();
+
+  let nodes = DftPre::new(graph.get_exactly_one_root()?, |node| graph.iter_children_of(node))
+    .map(|(_, node)| node.payload())
+    .collect_vec();
+
+  let masked_qry_nuc_subs = qry_nuc_subs
+    .iter()
+    .filter(|sub| !masked_ranges.iter().any(|range| range.contains(sub.pos)))
+    .collect_vec();
+
+  // Candidate breakpoints are at the positions of query substitutions, such that each region boundary is at an
+  // informative site
+  let boundaries = {
+    let begin = aln_range.begin.as_usize();
+    let end = aln_range.end.as_usize();
+    let inner = masked_qry_nuc_subs
+      .iter()
+      .map(|sub| sub.pos.as_usize())
+      .filter(|pos| *pos > begin && *pos < end);
+    std::iter::once(begin)
+      .chain(inner)
+      .chain(std::iter::once(end))
+      .sorted_unstable()
+      .dedup()
+      .collect_vec()
+  };
+
+  let sites = nodes
+    .iter()
+    .map(|node| {
+      node_distance_sites(
+        &node.tmp.substitutions,
+        &masked_qry_nuc_subs,
+        qry_missing,
+        qry_deletions,
+        aln_range,
+        masked_ranges,
+      )
+    })
+    .collect_vec();
+
+  let cumulative_distances = sites
+    .iter()
+    .map(|sites| cumulative_distance(sites, &boundaries))
+    .collect_vec();
+
+  let labels = nodes.iter().map(|node| donor_label(node)).collect_vec();
+
+  let regions = split_into_regions(
+    &cumulative_distances,
+    &labels,
+    config.min_improvement as i64,
+    config.max_breakpoints,
+  );
+
+  let whole = (0, boundaries.len() - 1);
+  let distance_without_recombination = nearest_in_region(&cumulative_distances, whole).map_or(0, |(_, d)| d);
+
+  let donor_nodes = regions
+    .iter()
+    .filter_map(|region| nearest_in_region(&cumulative_distances, *region).map(|(node_index, _)| node_index))
+    .collect_vec();
+
+  // Region boundaries are at query substitutions so far. Refine them, given the donors, taking into account all
+  // informative sites of the donors, including the sites where the donors are mutated, but the query is not.
+  let mut positions = regions
+    .iter()
+    .map(|region| boundaries[region.0])
+    .chain(regions.last().map(|region| boundaries[region.1]))
+    .collect_vec();
+  let mut uncertainty_ranges = vec![];
+  for (b, (left, right)) in donor_nodes.iter().tuple_windows().enumerate() {
+    let (boundary, uncertainty) = refine_boundary(
+      &sites[*left],
+      &sites[*right],
+      positions[b],
+      positions[b + 1],
+      positions[b + 2],
+    );
+    positions[b + 1] = boundary;
+    uncertainty_ranges.push(uncertainty);
+  }
+
+  let donors = donor_nodes
+    .iter()
+    .zip(positions.iter().tuple_windows())
+    .map(|(node_index, (begin, end))| {
+      let node = nodes[*node_index];
+      RecombinationDonor {
+        range: NucRefGlobalRange::from_usize(*begin, *end),
+        node_name: node.name.clone(),
+        clade: node.clade(),
+        distance: region_distance(&sites[*node_index], *begin, *end),
+      }
+    })
+    .collect_vec();
+
+  let breakpoints = donors
+    .iter()
+    .tuple_windows()
+    .zip(uncertainty_ranges)
+    .map(|((left, right), range)| {
+      let begin = left.range.begin.as_usize();
+      let end = right.range.end.as_usize();
+      let whole = sites
+        .iter()
+        .map(|sites| region_distance(sites, begin, end))
+        .min()
+        .unwrap_or_default();
+      RecombinationBreakpoint {
+        range,
+        left_clade: left.clade.clone(),
+        right_clade: right.clade.clone(),
+        improvement: whole - left.distance - right.distance,
+      }
+    })
+    .collect_vec();
+
+  let distance_with_recombination = donors.iter().map(|donor| donor.distance).sum();
+
+  Ok(RecombinationResult {
+    breakpoints,
+    donors,
+    distance_without_recombination,
+    distance_with_recombination,
+  })
+}
+
+/// Donors of adjacent regions are required to have different labels: the clade, if available, or the node name
+fn donor_label(node: &AuspiceGraphNodePayload) -> String {
+  node.clade().unwrap_or_else(|| node.name.clone())
+}
+
+/// Calculates contributions of individual sites to the distance metric between a query sample and a tree node
+/// (see `tree_calculate_node_distance`). The distance within a genome region is the sum of contributions of the
+/// sites within that region. Only sites with non-zero contributions are returned.
+///
+/// Query substitutions are expected to be already filtered by the masked ranges.
+fn node_distance_sites(
+  node_subs: &BTreeMap<NucRefGlobalPosition, Nuc>,
+  qry_nuc_subs: &[&NucSub],
+  qry_missing: &[NucRange],
+  qry_deletions: &[NucDelRange],
+  aln_range: &NucRefGlobalRange,
+  masked_ranges: &[NucRefGlobalRange],
+) -> BTreeMap<usize, i64> {
+  let mut sites = BTreeMap::new();
+
+  for qmut in qry_nuc_subs {
+    // A mutation shared with the node does not contribute. Differing states at the same site contribute once.
+    let contribution = match node_subs.get(&qmut.pos) {
+      Some(node_nuc) if *node_nuc == qmut.qry_nuc => 0,
+      _ => 1,
+    };
+    sites.insert(qmut.pos.as_usize(), contribution);
+  }
+
+  for pos in node_subs.keys() {
+    if sites.contains_key(&pos.as_usize()) {
+      continue;
+    }
+    // Node mutations on sites which are missing, deleted or masked in the query are undetermined and don't contribute
+    let is_determined = is_nuc_sequenced(*pos, qry_missing, aln_range)
+      && !masked_ranges.iter().any(|range| range.contains(*pos))
+      && !qry_deletions.iter().any(|del| del.range().contains(*pos));
+    sites.insert(pos.as_usize(), i64::from(is_determined));
+  }
+
+  sites.retain(|_, contribution| *contribution != 0);
+  sites
+}
+
+/// Sum of contributions of sites within the region
+fn region_distance(sites: &BTreeMap<usize, i64>, begin: usize, end: usize) -> i64 {
+  sites.range(begin..end).map(|(_, contribution)| contribution).sum()
+}
+
+/// Finds the position of the boundary between two adjacent donor regions `[begin, boundary)` and `[boundary, end)`,
+/// which minimizes the sum of the distances to the two donors. Returns the new boundary and the range of positions
+/// where the boundary can be placed equally well.
+fn refine_boundary(
+  left_sites: &BTreeMap<usize, i64>,
+  right_sites: &BTreeMap<usize, i64>,
+  begin: usize,
+  boundary: usize,
+  end: usize,
+) -> (usize, NucRefGlobalRange) {
+  let candidates = left_sites
+    .keys()
+    .chain(right_sites.keys())
+    .copied()
+    .chain(std::iter::once(boundary))
+    .filter(|pos| *pos > begin && *pos < end)
+    .sorted_unstable()
+    .dedup()
+    .collect_vec();
+
+  let costs = candidates
+    .iter()
+    .map(|pos| region_distance(left_sites, begin, *pos) + region_distance(right_sites, *pos, end))
+    .collect_vec();
+
+  let Some(&min_cost) = costs.iter().min() else {
+    return (boundary, NucRefGlobalRange::from_usize(boundary, boundary + 1));
+  };
+
+  // The boundary can be moved freely between informative sites. The first position of the right region is
+  // anywhere after the informative site preceding the candidate, up to and including the candidate itself.
+  let optimal = costs.iter().positions(|cost| *cost == min_cost).collect_vec();
+  let first = optimal[0];
+  let last = optimal[optimal.len() - 1];
+  let uncertainty_begin = if first > 0 { candidates[first - 1] + 1 } else { begin };
+
+  (
+    candidates[first],
+    NucRefGlobalRange::from_usize(uncertainty_begin, candidates[last] + 1),
+  )
+}
+
+/// For each of the boundaries, calculates total contribution of sites before it
+fn cumulative_distance(sites: &BTreeMap<usize, i64>, boundaries: &[usize]) -> Vec<i64> {
+  boundaries
+    .iter()
+    .map(|boundary| region_distance(sites, 0, *boundary))
+    .collect_vec()
+}
+
+/// Finds the node with the smallest distance within the region between the given boundary indices
+fn nearest_in_region(cumulative_distances: &[Vec<i64>], (begin, end): (usize, usize)) -> Option<(usize, i64)> {
+  cumulative_distances
+    .iter()
+    .map(|cumulative| cumulative[end] - cumulative[begin])
+    .enumerate()
+    .min_by_key(|(i, distance)| (*distance, *i))
+}
+
+/// Finds the boundary which splits the region into two regions with the largest reduction of the total distance,
+/// such that the nearest nodes of the two regions have different labels
+fn find_best_split(
+  cumulative_distances: &[Vec<i64>],
+  labels: &[String],
+  (begin, end): (usize, usize),
+) -> Option<(usize, i64)> {
+  let (_, whole) = nearest_in_region(cumulative_distances, (begin, end))?;
+  ((begin + 1)..end)
+    .filter_map(|k| {
+      let (left, left_distance) = nearest_in_region(cumulative_distances, (begin, k))?;
+      let (right, right_distance) = nearest_in_region(cumulative_distances, (k, end))?;
+      (labels[left] != labels[right]).then_some((k, whole - left_distance - right_distance))
+    })
+    .max_by_key(|(k, improvement)| (*improvement, Reverse(*k)))
+}
+
+/// Greedily splits the genome into regions, one breakpoint at a time, for as long as every additional breakpoint
+/// reduces the total distance by at least `min_improvement`. Regions are returned as pairs of boundary indices.
+fn split_into_regions(
+  cumulative_distances: &[Vec<i64>],
+  labels: &[String],
+  min_improvement: i64,
+  max_breakpoints: usize,
+) -> Vec<(usize, usize)> {
+  let n_boundaries = cumulative_distances.first().map_or(0, Vec::len);
+  if n_boundaries < 2 {
+    return vec![];
+  }
+
+  let mut regions = vec![(0, n_boundaries - 1)];
+  while regions.len() <= max_breakpoints {
+    let best = regions
+      .iter()
+      .enumerate()
+      .filter_map(|(r, region)| {
+        let (k, improvement) = find_best_split(cumulative_distances, labels, *region)?;
+        Some((r, k, improvement))
+      })
+      .max_by_key(|(r, _, improvement)| (*improvement, Reverse(*r)));
+
+    match best {
+      Some((r, k, improvement)) if improvement >= min_improvement.max(1) => {
+        let (begin, end) = regions[r];
+        regions.splice(r..=r, [(begin, k), (k, end)]);
+      }
+      _ => break,
+    }
+  }
+
+  regions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::coord::range::Range;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn sub(pos: usize, qry_nuc: Nuc) -> NucSub {
+    NucSub {
+      ref_nuc: Nuc::A,
+      pos: pos.into(),
+      qry_nuc,
+    }
+  }
+
+  fn node_subs(subs: &[(usize, Nuc)]) -> BTreeMap<NucRefGlobalPosition, Nuc> {
+    subs.iter().map(|(pos, nuc)| ((*pos).into(), *nuc)).collect()
+  }
+
+  // Same inputs as in the tests of `tree_calculate_node_distance`, such that the sums are expected to be equal
+  #[rstest]
+  #[case::shared_mutations(100, vec![], vec![], 5)]
+  #[case::shared_mutations_with_missing(100, vec![(8, 10), (20, 30)], vec![], 4)]
+  #[case::shared_mutations_clipped_range(20, vec![], vec![], 3)]
+  #[case::shared_mutations_some_masked(100, vec![], vec![(0, 5), (30, 50)], 3)]
+  #[case::shared_mutations_all_combinations(30, vec![(8, 10), (20, 30)], vec![(12, 13)], 3)]
+  fn node_distance_sites_sum_to_node_distance(
+    #[case] aln_end: usize,
+    #[case] missing: Vec<(usize, usize)>,
+    #[case] masked: Vec<(usize, usize)>,
+    #[case] expected: i64,
+  ) {
+    let node = node_subs(&[(3, Nuc::T), (12, Nuc::C), (15, Nuc::T), (23, Nuc::G), (35, Nuc::G)]);
+    let qry_subs = [sub(3, Nuc::C), sub(7, Nuc::C), sub(12, Nuc::C)];
+    let qry_missing = missing
+      .iter()
+      .map(|(begin, end)| NucRange {
+        range: Range::from_usize(*begin, *end),
+        letter: Nuc::N,
+      })
+      .collect_vec();
+    let masked_ranges = masked
+      .iter()
+      .map(|(begin, end)| NucRefGlobalRange::from_usize(*begin, *end))
+      .collect_vec();
+    let masked_qry_subs = qry_subs
+      .iter()
+      .filter(|sub| !masked_ranges.iter().any(|range| range.contains(sub.pos)))
+      .collect_vec();
+
+    let sites = node_distance_sites(
+      &node,
+      &masked_qry_subs,
+      &qry_missing,
+      &[],
+      &NucRefGlobalRange::from_usize(0, aln_end),
+      &masked_ranges,
+    );
+
+    assert_eq!(sites.values().sum::<i64>(), expected);
+  }
+
+  #[rstest]
+  fn splits_recombinant_into_regions() {
+    // Query has mutations at 10, 20 (shared with node "A") and at 30, 40 (shared with node "B")
+    let qry_subs = [sub(10, Nuc::C), sub(20, Nuc::C), sub(30, Nuc::C), sub(40, Nuc::C)];
+    let qry_subs = qry_subs.iter().collect_vec();
+    let aln_range = NucRefGlobalRange::from_usize(0, 50);
+    let nodes = [
+      node_subs(&[]),
+      node_subs(&[(10, Nuc::C), (20, Nuc::C), (35, Nuc::C), (45, Nuc::C)]),
+      node_subs(&[(5, Nuc::C), (15, Nuc::C), (30, Nuc::C), (40, Nuc::C)]),
+    ];
+    let labels = ["root", "A", "B"].map(ToOwned::to_owned);
+
+    let boundaries = vec![0, 10, 20, 30, 40, 50];
+    let cumulative_distances = nodes
+      .iter()
+      .map(|node| {
+        let sites = node_distance_sites(node, &qry_subs, &[], &[], &aln_range, &[]);
+        cumulative_distance(&sites, &boundaries)
+      })
+      .collect_vec();
+
+    let regions = split_into_regions(&cumulative_distances, &labels, 3, 2);
+    assert_eq!(regions, vec![(0, 3), (3, 5)]);
+
+    let regions = split_into_regions(&cumulative_distances, &labels, 5, 2);
+    assert_eq!(regions, vec![(0, 5)]);
+  }
+
+  #[rstest]
+  #[case::at_query_substitution(30, 30, 21, 31)]
+  #[case::from_coarse_boundary(40, 30, 21, 31)]
+  fn refines_boundary_between_donors(
+    #[case] coarse: usize,
+    #[case] expected_boundary: usize,
+    #[case] expected_begin: usize,
+    #[case] expected_end: usize,
+  ) {
+    // Query has mutations at 10, 20 (shared with the left donor) and at 30, 40 (shared with the right donor)
+    let qry_subs = [sub(10, Nuc::C), sub(20, Nuc::C), sub(30, Nuc::C), sub(40, Nuc::C)];
+    let qry_subs = qry_subs.iter().collect_vec();
+    let aln_range = NucRefGlobalRange::from_usize(0, 50);
+    let left = node_subs(&[(10, Nuc::C), (20, Nuc::C), (35, Nuc::C), (45, Nuc::C)]);
+    let right = node_subs(&[(5, Nuc::C), (15, Nuc::C), (30, Nuc::C), (40, Nuc::C)]);
+    let left = node_distance_sites(&left, &qry_subs, &[], &[], &aln_range, &[]);
+    let right = node_distance_sites(&right, &qry_subs, &[], &[], &aln_range, &[]);
+
+    let (boundary, range) = refine_boundary(&left, &right, 0, coarse, 50);
+    assert_eq!(
+      (boundary, range),
+      (
+        expected_boundary,
+        NucRefGlobalRange::from_usize(expected_begin, expected_end)
+      )
+    );
+  }
+}
@@ -0,0 +1,119 @@
+use crate::tree::tree::{AuspiceGraph, PlacementPriorRecency};
+use crate::tree::tree_find_ancestors_of_interest::is_node_match;
+use eyre::{Report, WrapErr};
+
+/// Computes adjustments of placement priors of reference tree nodes from the dataset-level placement prior
+/// configuration (`meta.extensions.nextclade.placement_priors`) and stores them in the temporary node data
+pub fn graph_apply_placement_priors_in_place(graph: &mut AuspiceGraph) -> Result<(), Report> {
+  let priors = graph.data.meta.placement_priors();
+  if priors.is_empty() {
+    return Ok(());
+  }
+
+  let attr_descs = graph.data.meta.clade_node_attr_descs();
+  let max_date = priors
+    .recency
+    .as_ref()
+    .and_then(|recency| find_max_date(graph, recency));
+
+  let adjustments = graph
+    .iter_nodes()
+    .map(|node| {
+      let mut adjustment = 0.0;
+
+      for (i, rule) in priors.rules.iter().enumerate() {
+        if is_node_match(graph, node, &rule.node, attr_descs)
+          .wrap_err_with(|| format!("When matching placement prior rule #{i}"))?
+        {
+          adjustment += rule.log_weight.0;
+        }
+      }
+
+      if let Some(recency) = &priors.recency
+        && let Some(max_date) = max_date
+        && let Some(date) = node.payload().get_numeric_node_attr(&recency.attribute)
+      {
+        adjustment += recency.log_weight_per_year.0 * (max_date - date);
+      }
+
+      Ok((node.key(), adjustment))
+    })
+    .collect::<Result<Vec<_>, Report>>()?;
+
+  for (key, adjustment) in adjustments {
+    graph.get_node_mut(key)?.payload_mut().tmp.placement_prior_adjustment = adjustment;
+  }
+
+  Ok(())
+}
+
+fn find_max_date(graph: &AuspiceGraph, recency: &PlacementPriorRecency) -> Option<f64> {
+  graph
+    .iter_node_payloads()
+    .filter_map(|node| node.get_numeric_node_attr(&recency.attribute))
+    .max_by(f64::total_cmp)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::io::json::json_parse;
+  use crate::tree::test_utils::{make_test_graph, test_node};
+  use crate::tree::tree::{AuspiceGraphMeta, AuspiceGraphNodePayload};
+  use itertools::Itertools;
+  use ordered_float::OrderedFloat;
+  use pretty_assertions::assert_eq;
+  use rstest::rstest;
+
+  fn node(name: &str, clade: &str, date: Option<f64>) -> AuspiceGraphNodePayload {
+    let mut node = test_node(name, Some(clade), &[]);
+    if let Some(date) = date {
+      node.set_numeric_node_attr("num_date", date);
+    }
+    node
+  }
+
+  //        root (A)
+  //           |
+  //     inner (B, 2020.0)
+  //        /          \
+  //  leaf1 (B, 2022.5)  leaf2 (C, 2021.0)
+  fn make_graph(priors: &str) -> Result<AuspiceGraph, Report> {
+    let mut meta = AuspiceGraphMeta::default();
+    meta.meta.extensions.nextclade.placement_priors = json_parse(priors)?;
+    make_test_graph(
+      meta,
+      vec![
+        (node("root", "A", None), None),
+        (node("inner", "B", Some(2020.0)), Some(0)),
+        (node("leaf1", "B", Some(2022.5)), Some(1)),
+        (node("leaf2", "C", Some(2021.0)), Some(1)),
+      ],
+    )
+  }
+
+  #[rstest]
+  #[case(r#"{}"#, &[0.0, 0.0, 0.0, 0.0])]
+  #[case(r#"{ "rules": [{ "node": { "clade": ["B"] }, "logWeight": -2.0 }] }"#, &[0.0, -2.0, -2.0, 0.0])]
+  #[case(
+    r#"{ "rules": [{ "node": { "clade": ["B"] }, "logWeight": -2.0 }, { "node": { "name": ["leaf1"] }, "logWeight": 1.0 }] }"#,
+    &[0.0, -2.0, -1.0, 0.0]
+  )]
+  #[case(r#"{ "recency": { "logWeightPerYear": -1.0 } }"#, &[0.0, -2.5, 0.0, -1.5])]
+  #[case(
+    r#"{ "rules": [{ "node": { "name": ["root"] }, "logWeight": -5.0 }], "recency": { "logWeightPerYear": -2.0 } }"#,
+    &[-5.0, -5.0, 0.0, -3.0]
+  )]
+  fn applies_placement_priors(#[case] priors: &str, #[case] expected: &[f64]) -> Result<(), Report> {
+    let mut graph = make_graph(priors)?;
+
+    graph_apply_placement_priors_in_place(&mut graph)?;
+
+    let adjustments = graph
+      .iter_node_payloads()
+      .map(|node| OrderedFloat(node.tmp.placement_prior_adjustment))
+      .collect_vec();
+    assert_eq!(adjustments, expected.iter().copied().map(OrderedFloat).collect_vec());
+    Ok(())
+  }
+}
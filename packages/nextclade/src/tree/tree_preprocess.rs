@@ -10,6 +10,7 @@ use crate::graph::node::GraphNodeKey;
 use crate::make_error;
 use crate::translate::translate_genes::Translation;
 use crate::tree::tree::{AuspiceColoring, AuspiceGraph, AuspiceGraphNodePayload, AuspiceTreeMeta, ScaleKey};
+use crate::tree::tree_attach_new_nodes::metadata_node_attr_key;
 use crate::utils::collections::concat_to_vec;
 use eyre::{Report, WrapErr};
 use itertools::Itertools;
@@ -251,7 +252,7 @@ fn pair(key: &str, val: &str) -> (ScaleKey, String) {
   (ScaleKey::Str(key.to_owned()), val.to_owned())
 }
 
-pub fn add_auspice_metadata_in_place(meta: &mut AuspiceTreeMeta, has_pcr_primers: bool) {
+pub fn add_auspice_metadata_in_place(meta: &mut AuspiceTreeMeta, has_pcr_primers: bool, metadata_keys: &[String]) {
   let mut new_colorings: Vec<AuspiceColoring> = vec![
     AuspiceColoring {
       key: "Node type".to_owned(),
@@ -285,6 +286,21 @@ pub fn add_auspice_metadata_in_place(meta: &mut AuspiceTreeMeta, has_pcr_primers
 
   meta.colorings = concat_to_vec(&new_colorings, &meta.colorings);
 
+  // Sample metadata columns become colorings, unless the reference tree already defines them
+  let metadata_colorings = metadata_keys
+    .iter()
+    .map(|column| (metadata_node_attr_key(column), column))
+    .filter(|(key, _)| !meta.colorings.iter().any(|coloring| &coloring.key == key))
+    .map(|(key, column)| AuspiceColoring {
+      key,
+      title: column.clone(),
+      type_: "categorical".to_owned(),
+      scale: vec![],
+      other: serde_json::Value::default(),
+    })
+    .collect_vec();
+  meta.colorings.extend(metadata_colorings);
+
   meta.display_defaults.branch_label = Some("clade".to_owned());
   meta.display_defaults.color_by = Some("clade_membership".to_owned());
   meta.display_defaults.distance_measure = Some("div".to_owned());
@@ -1,17 +1,25 @@
 use crate::align::insertions_strip::{AaIns, Insertion};
+use crate::align::soft_clip::NucSoftClips;
 use crate::alphabet::nuc::Nuc;
 use crate::analyze::aa_changes_group::AaChangesGroup;
 use crate::analyze::aa_del::AaDel;
 use crate::analyze::aa_sub::AaSub;
+use crate::analyze::amplicon_dropouts::AmpliconsResult;
+use crate::analyze::clade_confidence::CladeAttrConfidence;
+use crate::analyze::clade_mutations::CladeMutationsResult;
+use crate::analyze::dn_ds::CdsDnDs;
 use crate::analyze::find_aa_motifs_changes::{AaMotifsChangesMap, AaMotifsMap};
 use crate::analyze::find_clade_founder::CladeNodeAttrFounderInfo;
+use crate::analyze::find_nuc_motifs_changes::{NucMotifsChangesMap, NucMotifsMap};
 use crate::analyze::find_private_aa_mutations::PrivateAaMutations;
 use crate::analyze::find_private_nuc_mutations::PrivateNucMutations;
 use crate::analyze::find_relative_aa_mutations::RelativeAaMutations;
 use crate::analyze::find_relative_nuc_mutations::RelativeNucMutations;
 use crate::analyze::letter_ranges::{CdsAaRange, NucRange};
+use crate::analyze::mutation_spectrum::MutationSpectrumResult;
 use crate::analyze::nuc_del::NucDelRange;
 use crate::analyze::nuc_sub::NucSub;
+use crate::analyze::nuc_sub_effects::NucSubEffect;
 use crate::analyze::pcr_primer_changes::PcrPrimerChange;
 use crate::coord::range::{AaRefRange, NucRefGlobalRange};
 use crate::gene::gene_map::GeneMap;
@@ -19,8 +27,10 @@ use crate::graph::node::GraphNodeKey;
 use crate::io::json::json_parse;
 use crate::qc::qc_run::QcResult;
 use crate::translate::frame_shifts_translate::FrameShift;
+use crate::tree::lineage_hierarchy::LineageInfo;
 use crate::tree::tree::AuspiceRefNodesDesc;
 use crate::tree::tree_find_ancestors_of_interest::AncestralSearchResult;
+use crate::tree::tree_find_recombination::RecombinationResult;
 use eyre::{Report, WrapErr};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -41,14 +51,14 @@ pub struct PeptideWarning {
 pub struct PhenotypeValue {
   /// Phenotype name as defined in the dataset configuration
   pub name: String,
-  /// CDS used for phenotype score calculation
+  /// CDS used for phenotype score calculation (comma-separated if several, empty for nucleotide-based models)
   pub cds: String,
   /// Computed phenotype score
   pub value: f64,
 }
 
 /// Single element in `.results` array in nextclade.json file, produced by `nextclade run --output-json`. This corresponds to a single sequence in the inputs.
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[schemars(title = "ResultJson")]
 pub struct NextcladeOutputs {
@@ -68,6 +78,17 @@ pub struct NextcladeOutputs {
   pub len_stripped: usize,
   /// Name of the reference sequence used for alignment
   pub ref_name: String,
+  /// Name of the closest reference sequence the query was aligned to, when the dataset provides additional
+  /// references. Results are always reported in the coordinates of the primary reference.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub alignment_ref_name: Option<String>,
+  /// Name of the genome segment the query was assigned to, for segmented datasets
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub segment: Option<String>,
+  /// Sample identifier parsed from the sequence name, used to group segments of the same sample, for segmented
+  /// datasets
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sample_id: Option<String>,
   /// Name of the dataset used for analysis
   pub dataset_name: String,
   /// Nucleotide substitutions relative to the reference
@@ -118,6 +139,15 @@ pub struct NextcladeOutputs {
   pub aa_changes_groups: Vec<AaChangesGroup>,
   /// Amino acid substitutions caused by each nucleotide mutation, keyed by CDS name
   pub nuc_to_aa_muts: BTreeMap<String, Vec<AaSub>>,
+  /// Effect of each nucleotide substitution on every overlapping CDS (synonymous, nonsynonymous, etc.)
+  #[serde(default)]
+  pub nuc_sub_effects: Vec<NucSubEffect>,
+  /// Per-CDS counts of synonymous and nonsynonymous substitutions and dN/dS estimates
+  #[serde(default)]
+  pub dn_ds: Vec<CdsDnDs>,
+  /// Counts of nucleotide substitutions by class and by mutation signatures defined in the dataset
+  #[serde(default)]
+  pub mutation_spectrum: MutationSpectrumResult,
   /// Reference coordinate range of the aligned region
   pub alignment_range: NucRefGlobalRange,
   /// Smith-Waterman alignment score
@@ -130,9 +160,19 @@ pub struct NextcladeOutputs {
   pub pcr_primer_changes: Vec<PcrPrimerChange>,
   /// Total count of mutations in PCR primer regions
   pub total_pcr_primer_changes: usize,
-  /// Assigned clade label from the nearest reference tree node
+  /// Per-amplicon coverage, dropouts and primer binding site mutations. Only present when an amplicon scheme is provided.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub amplicons: Option<AmpliconsResult>,
+  /// Assigned clade label from the nearest reference tree node, or from the clade mutations table if there is no tree
   #[serde(skip_serializing_if = "Option::is_none")]
   pub clade: Option<String>,
+  /// Clade assignment using the clade-defining mutations table. Only present when the table is provided.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub clade_by_mutations: Option<CladeMutationsResult>,
+  /// Confidence of assignment of clade (key "clade") and of each clade-like attribute, based on whether the mutations
+  /// defining the assigned values are at sequenced positions. Only present when a reference tree is provided.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub clade_confidence: BTreeMap<String, CladeAttrConfidence>,
   /// Nucleotide mutations not shared with the nearest reference tree node, subdivided into reversions, labeled, and unlabeled
   pub private_nuc_mutations: PrivateNucMutations,
   /// Per-CDS amino acid mutations not shared with the nearest reference tree node
@@ -161,8 +201,19 @@ pub struct NextcladeOutputs {
   pub cds_coverage: BTreeMap<String, f64>,
   /// Quality control results including overall score, status, and per-rule results
   pub qc: QcResult,
-  /// Clade-like attributes from the nearest tree node, keyed by attribute name
+  /// Clade-like attributes from the nearest tree node (or from the clade mutations table), keyed by attribute name
   pub custom_node_attributes: BTreeMap<String, String>,
+  /// Expanded names and ancestors of clade-like attribute values, for the attributes with a lineage hierarchy defined
+  /// in the reference tree, keyed by attribute name
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub clade_node_attr_lineages: BTreeMap<String, LineageInfo>,
+  /// Numeric node attributes inherited from the nearest tree node, keyed by attribute name
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub numeric_node_attributes: BTreeMap<String, f64>,
+  /// Values from the sample metadata table (`--input-metadata`) for this sequence, keyed by column name. Only the
+  /// columns selected for outputs and the non-empty values are included.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub metadata: BTreeMap<String, String>,
   /// Internal graph key of the nearest reference tree node
   pub nearest_node_id: GraphNodeKey,
   /// Name of the nearest reference tree node
@@ -170,14 +221,30 @@ pub struct NextcladeOutputs {
   /// Names of equidistant nearest tree nodes when multiple candidates exist
   #[serde(skip_serializing_if = "Option::is_none")]
   pub nearest_nodes: Option<Vec<String>>,
+  /// Candidate recombination breakpoints and donor regions, when the "recombinants" QC rule is enabled
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub recombination: Option<RecombinationResult>,
   /// Whether the sequence was reverse-complemented before analysis
   pub is_reverse_complement: bool,
+  /// Position in the query sequence which was rotated to the origin of the circular reference genome before
+  /// alignment, when circular alignment mode is enabled
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub circular_offset: Option<usize>,
+  /// Query sequence fragments soft-clipped from the ends of the alignment, when local alignment mode is enabled
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub soft_clips: Option<NucSoftClips>,
   /// Computed phenotype scores as defined in the dataset configuration
   pub phenotype_values: Option<Vec<PhenotypeValue>>,
   /// Amino acid motifs detected in the query sequence, keyed by motif name
   pub aa_motifs: AaMotifsMap,
   /// Changes in amino acid motifs relative to the reference, keyed by motif name
   pub aa_motifs_changes: AaMotifsChangesMap,
+  /// Nucleotide motifs detected in the query sequence, keyed by motif name
+  #[serde(default)]
+  pub nuc_motifs: NucMotifsMap,
+  /// Changes in nucleotide motifs relative to the reference, keyed by motif name
+  #[serde(default)]
+  pub nuc_motifs_changes: NucMotifsChangesMap,
 
   /// Genome annotation in query sequence coordinates
   #[serde(default, skip_serializing_if = "GeneMap::is_empty")]
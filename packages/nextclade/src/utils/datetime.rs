@@ -1,5 +1,6 @@
-use chrono::{DateTime, FixedOffset, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Months, NaiveDate, Utc};
 use eyre::Report;
+use itertools::Itertools;
 use std::time::{Duration, UNIX_EPOCH};
 
 pub fn date_now() -> DateTime<Utc> {
@@ -61,3 +62,45 @@ pub fn date_format_safe(datetime: &DateTime<Utc>) -> String {
 pub fn timestamp_format_safe(timestamp: i64) -> String {
   date_format_safe(&timestamp_to_date(timestamp))
 }
+
+/// Convert a calendar date into a decimal year, as used in `num_date` node attributes of Auspice trees.
+/// The day is represented by its midpoint, e.g. 2021-01-01 is 2021.0014.
+pub fn date_to_num_date(date: NaiveDate) -> f64 {
+  let days_in_year = if NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some() {
+    366.0
+  } else {
+    365.0
+  };
+  f64::from(date.year()) + (f64::from(date.ordinal()) - 0.5) / days_in_year
+}
+
+/// Parse a collection date into a decimal year.
+///
+/// Accepts full dates (`2021-03-15`), incomplete dates (`2021-03`, `2021`, `2021-03-XX`, `2021-XX-XX`), for which the
+/// midpoint of the month or year is used, and decimal years (`2021.2`). Returns `None` if the date cannot be parsed.
+pub fn parse_num_date(date: &str) -> Option<f64> {
+  let date = date.trim();
+  let parts = date
+    .split('-')
+    .take_while(|part| !part.is_empty() && !part.chars().all(|c| c.eq_ignore_ascii_case(&'x')))
+    .collect_vec();
+
+  match parts.as_slice() {
+    [year] => {
+      if let Ok(year) = year.parse::<i32>() {
+        return Some(f64::from(year) + 0.5);
+      }
+      year.parse::<f64>().ok().filter(|num_date| num_date.is_finite())
+    }
+    [year, month] => {
+      let first = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)?;
+      let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+      Some(f64::midpoint(date_to_num_date(first), date_to_num_date(last)))
+    }
+    [year, month, day] => {
+      let date = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)?;
+      Some(date_to_num_date(date))
+    }
+    _ => None,
+  }
+}